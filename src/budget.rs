@@ -0,0 +1,101 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use crate::error::MemoryBudgetExceeded;
+
+#[derive(Debug)]
+struct Shared {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+/// A limit on the memory used by the buffers of many decoders together, e.g. all those running
+/// in a server at once.
+///
+/// This is a cheaply cloneable handle, decoders given one with their `with_memory_budget` method
+/// reserve the size of their internal buffers from it before they take their first step, and
+/// release it when they are dropped. A decoder that cannot reserve what it needs, to start or to
+/// grow its buffers, fails with an [`io::Error`](std::io::Error) wrapping a
+/// [`MemoryBudgetExceeded`](crate::MemoryBudgetExceeded) instead.
+///
+/// Only the buffers the decoders allocate themselves are counted, not the state kept by the
+/// library implementing the format, which depends on the format and the stream being decoded.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    shared: Arc<Shared>,
+}
+
+impl MemoryBudget {
+    /// Creates a budget allowing `limit` bytes to be reserved at once.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                limit,
+                used: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// The number of bytes that may be reserved at once.
+    pub fn limit(&self) -> usize {
+        self.shared.limit
+    }
+
+    /// The number of bytes currently reserved by decoders.
+    pub fn used(&self) -> usize {
+        self.shared.used.load(Ordering::Acquire)
+    }
+
+    fn reserve(&self, bytes: usize) -> Result<(), MemoryBudgetExceeded> {
+        let limit = self.shared.limit;
+        self.shared
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|&total| total <= limit)
+            })
+            .map(drop)
+            .map_err(|_| MemoryBudgetExceeded::new(limit, bytes))
+    }
+
+    fn release(&self, bytes: usize) {
+        self.shared.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
+
+/// The part of a [`MemoryBudget`] held by one decoder, given back when it is dropped.
+#[derive(Debug)]
+pub(crate) struct Reservation {
+    budget: MemoryBudget,
+    bytes: usize,
+}
+
+impl Reservation {
+    /// Creates an empty reservation, nothing is taken from the budget until it is resized.
+    pub(crate) fn new(budget: MemoryBudget) -> Self {
+        Self { budget, bytes: 0 }
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Grows or shrinks the reservation to `bytes`, leaving it unchanged if growing it would go
+    /// over the budget.
+    pub(crate) fn resize(&mut self, bytes: usize) -> Result<(), MemoryBudgetExceeded> {
+        if bytes > self.bytes {
+            self.budget.reserve(bytes - self.bytes)?;
+        } else {
+            self.budget.release(self.bytes - bytes);
+        }
+        self.bytes = bytes;
+        Ok(())
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
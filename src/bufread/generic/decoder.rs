@@ -2,63 +2,251 @@ use core::{
     pin::Pin,
     task::{Context, Poll},
 };
-use std::io::Result;
+use std::{io::Result, mem};
 
-use crate::{codec::Decode, util::PartialBuffer};
+use crate::{
+    codec::{Decode, Limited},
+    util::PartialBuffer,
+    MemoryBudget, Stats,
+};
 use futures_core::ready;
 use futures_io::{AsyncBufRead, AsyncRead};
 use pin_project::pin_project;
 
+/// The size of the buffer output is decoded into for [`AsyncBufRead`], the same as the default of
+/// `BufReader`.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// How many bytes are read and decoded in one poll by default before yielding.
+const POLL_BUDGET: usize = 256 * 1024;
+
+/// The memory to reserve from a budget for a decoder's buffers.
+///
+/// A decoder used only through [`AsyncRead`] never allocates its own buffer, it decodes straight
+/// into the caller's, so the one used for [`AsyncBufRead`] is reserved up front whether or not it
+/// is ever allocated, otherwise it would take nothing from the budget at all. When it is wrapped
+/// by a decoder owning the reader that wrapper only reads from it, so just the reader's buffer is
+/// counted.
+fn memory(reader_memory: Option<usize>, buffer_capacity: usize) -> usize {
+    match reader_memory {
+        Some(reader_memory) => reader_memory + buffer_capacity,
+        None => std::cmp::max(BUFFER_SIZE, buffer_capacity),
+    }
+}
+
 #[derive(Debug)]
 enum State {
+    Sniffing(&'static [u8], usize),
+    Prefix(PartialBuffer<&'static [u8]>),
+    Passthrough(PartialBuffer<&'static [u8]>),
     Decoding,
     Flushing,
     Done,
 }
 
+/// The state machine behind the [`bufread`](crate::bufread) decoders, generic over the format's
+/// [`Decode`](crate::codec::Decode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Decoder<R: AsyncBufRead, D: Decode> {
     #[pin]
     reader: R,
-    decoder: D,
+    decoder: Limited<D>,
     state: State,
+    total_in: u64,
+    total_out: u64,
+    /// Output decoded by `poll_fill_buf`, only allocated once that is first called.
+    buffer: Vec<u8>,
+    pos: usize,
+    filled: usize,
+    /// The size of the reader's buffer, when it is owned by a wrapping decoder.
+    reader_memory: Option<usize>,
+    /// How much of the reader's buffer was left unconsumed when the stream ended.
+    trailing: usize,
+    /// How many bytes of input and output one poll may process.
+    poll_budget: usize,
 }
 
 impl<R: AsyncBufRead, D: Decode> Decoder<R, D> {
+    /// Creates a new decoder wrapping the reader, using `decoder` for the format.
     pub fn new(reader: R, decoder: D) -> Self {
+        let mut decoder = Limited::new(decoder);
+        decoder.set_memory(memory(None, 0));
         Self {
             reader,
             decoder,
             state: State::Decoding,
+            total_in: 0,
+            total_out: 0,
+            buffer: Vec::new(),
+            pos: 0,
+            filled: 0,
+            reader_memory: None,
+            trailing: 0,
+            poll_budget: POLL_BUDGET,
         }
     }
 
+    /// Acquires a reference to the underlying reader.
     pub fn get_ref(&self) -> &R {
         &self.reader
     }
 
+    /// Acquires a mutable reference to the underlying reader.
     pub fn get_mut(&mut self) -> &mut R {
         &mut self.reader
     }
 
+    /// Acquires a pinned mutable reference to the underlying reader.
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
         self.project().reader
     }
 
+    /// Consumes this decoder returning the underlying reader.
     pub fn into_inner(self) -> R {
         self.reader
     }
 
+    /// Acquires a reference to the format's decoder.
+    pub fn get_decoder_ref(&self) -> &D {
+        self.decoder.get_ref()
+    }
+
+    /// Acquires a mutable reference to the format's decoder.
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.decoder.get_mut()
+    }
+
+    /// Limits the total number of decompressed bytes produced, see the `with_limit` method of
+    /// the format decoders.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.decoder.set_limit(limit);
+    }
+
+    /// Limits the number of calls into the format's decoder, see the `with_step_limit` method of
+    /// the format decoders.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.decoder.set_step_limit(limit);
+    }
+
+    /// Bounds how many bytes are processed in one poll before yielding, see the
+    /// `with_poll_budget` method of the format decoders.
+    pub fn set_poll_budget(&mut self, bytes: usize) {
+        // Without room for any work a poll could never make progress
+        self.poll_budget = bytes.max(1);
+    }
+
+    /// Reserves the memory of the internal buffer from `budget`, see the `with_memory_budget`
+    /// method of the format decoders.
+    pub fn set_memory_budget(&mut self, budget: &MemoryBudget) {
+        self.decoder.set_memory_budget(budget);
+    }
+
+    /// Counts the buffer of a reader owned by a wrapping decoder against the memory budget too.
+    #[allow(dead_code)] // unused without the `read` feature
+    pub(crate) fn set_reader_memory(&mut self, bytes: usize) {
+        self.reader_memory = Some(bytes);
+        self.decoder
+            .set_memory(memory(self.reader_memory, self.buffer.capacity()));
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.decoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns how many bytes following the stream were already buffered in the reader when it
+    /// ended, or `None` until it has ended, see the `trailing_len` method of the format decoders.
+    pub fn trailing_len(&self) -> Option<usize> {
+        match self.state {
+            State::Done => Some(self.trailing),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// When given a `magic` prefix the input is only decoded if it starts with it, otherwise it is
+    /// passed through unchanged. Must be set before any data has been read.
+    pub(crate) fn set_passthrough(&mut self, magic: Option<&'static [u8]>) {
+        self.state = match magic {
+            Some(magic) => State::Sniffing(magic, 0),
+            None => State::Decoding,
+        };
+    }
+
     fn do_poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Poll<Result<()>> {
         let mut this = self.project();
+        let start_in = *this.total_in;
 
         loop {
             *this.state = match this.state {
+                State::Sniffing(magic, seen) => {
+                    let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+                    let len = std::cmp::min(magic.len() - *seen, input.len());
+                    if input.is_empty() || input[..len] != magic[*seen..*seen + len] {
+                        // The bytes matched so far are exactly the start of the magic, so they
+                        // don't need to be buffered separately to be output.
+                        State::Passthrough(PartialBuffer::new(&magic[..*seen]))
+                    } else {
+                        this.reader.as_mut().consume(len);
+                        *this.total_in += len as u64;
+                        if *seen + len == magic.len() {
+                            State::Prefix(PartialBuffer::new(magic))
+                        } else {
+                            State::Sniffing(magic, *seen + len)
+                        }
+                    }
+                }
+
+                State::Prefix(prefix) => {
+                    let done = this.decoder.decode(prefix, output)?;
+                    if done {
+                        State::Flushing
+                    } else if prefix.unwritten().is_empty() {
+                        State::Decoding
+                    } else {
+                        State::Prefix(prefix.take())
+                    }
+                }
+
+                State::Passthrough(prefix) => {
+                    if prefix.unwritten().is_empty() {
+                        let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+                        if input.is_empty() {
+                            State::Done
+                        } else {
+                            let mut input = PartialBuffer::new(input);
+                            output.copy_unwritten_from(&mut input);
+                            let len = input.written().len();
+                            this.reader.as_mut().consume(len);
+                            *this.total_in += len as u64;
+                            State::Passthrough(prefix.take())
+                        }
+                    } else {
+                        output.copy_unwritten_from(prefix);
+                        State::Passthrough(prefix.take())
+                    }
+                }
+
                 State::Decoding => {
                     let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
                     if input.is_empty() {
@@ -67,8 +255,11 @@ impl<R: AsyncBufRead, D: Decode> Decoder<R, D> {
                         let mut input = PartialBuffer::new(input);
                         let done = this.decoder.decode(&mut input, output)?;
                         let len = input.written().len();
+                        let trailing = input.unwritten().len();
                         this.reader.as_mut().consume(len);
+                        *this.total_in += len as u64;
                         if done {
+                            *this.trailing = trailing;
                             State::Flushing
                         } else {
                             State::Decoding
@@ -93,24 +284,86 @@ impl<R: AsyncBufRead, D: Decode> Decoder<R, D> {
             if output.unwritten().is_empty() {
                 return Poll::Ready(Ok(()));
             }
+
+            // Give other tasks a turn rather than decoding an unbounded amount of input in one
+            // poll, e.g. one producing no output at all
+            let work = (*this.total_in - start_in) as usize + output.written().len();
+            if work >= *this.poll_budget {
+                if output.written().is_empty() {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                return Poll::Ready(Ok(()));
+            }
         }
     }
 }
 
 impl<R: AsyncBufRead, D: Decode> AsyncRead for Decoder<R, D> {
     fn poll_read(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<Result<usize>> {
+        // A zero length read must not run a coding step, the output would have nowhere to go
         if buf.is_empty() {
             return Poll::Ready(Ok(0));
         }
 
-        let mut output = PartialBuffer::new(buf);
-        match self.do_poll_read(cx, &mut output)? {
+        // Output already decoded by `poll_fill_buf` goes first, and was counted then
+        let this = self.as_mut().project();
+        if *this.pos < *this.filled {
+            let mut buffered = PartialBuffer::new(&this.buffer[*this.pos..*this.filled]);
+            let mut output = PartialBuffer::new(buf);
+            output.copy_unwritten_from(&mut buffered);
+            *this.pos += buffered.written().len();
+            return Poll::Ready(Ok(buffered.written().len()));
+        }
+
+        let len = std::cmp::min(buf.len(), self.poll_budget);
+        let mut output = PartialBuffer::new(&mut buf[..len]);
+        match self.as_mut().do_poll_read(cx, &mut output)? {
             Poll::Pending if output.written().is_empty() => Poll::Pending,
-            _ => Poll::Ready(Ok(output.written().len())),
+            _ => {
+                let len = output.written().len();
+                *self.project().total_out += len as u64;
+                Poll::Ready(Ok(len))
+            }
         }
     }
 }
+
+impl<R: AsyncBufRead, D: Decode> AsyncBufRead for Decoder<R, D> {
+    fn poll_fill_buf(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        if self.pos >= self.filled {
+            let mut buffer = mem::take(self.as_mut().project().buffer);
+            buffer.resize(BUFFER_SIZE, 0);
+            let this = self.as_mut().project();
+            this.decoder
+                .set_memory(memory(*this.reader_memory, buffer.capacity()));
+
+            let mut output = PartialBuffer::new(&mut buffer[..]);
+            let result = self.as_mut().do_poll_read(cx, &mut output);
+            let len = output.written().len();
+
+            let this = self.as_mut().project();
+            *this.buffer = buffer;
+            match result? {
+                Poll::Pending if len == 0 => return Poll::Pending,
+                _ => {
+                    *this.pos = 0;
+                    *this.filled = len;
+                    *this.total_out += len as u64;
+                }
+            }
+        }
+
+        let this = self.project();
+        Poll::Ready(Ok(&this.buffer[*this.pos..*this.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        *this.pos = std::cmp::min(*this.pos + amt, *this.filled);
+    }
+}
@@ -4,7 +4,7 @@ use core::{
 };
 use std::io::Result;
 
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{codec::Encode, util::PartialBuffer, warning::InputSniffer, Stats, Warning};
 use futures_core::ready;
 use futures_io::{AsyncBufRead, AsyncRead};
 use pin_project::pin_project;
@@ -16,6 +16,8 @@ enum State {
     Done,
 }
 
+/// The state machine behind the [`bufread`](crate::bufread) encoders, generic over the format's
+/// [`Encode`](crate::codec::Encode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Encoder<R: AsyncBufRead, E: Encode> {
@@ -23,33 +25,101 @@ pub struct Encoder<R: AsyncBufRead, E: Encode> {
     reader: R,
     encoder: E,
     state: State,
+    total_in: u64,
+    total_out: u64,
+    sniffer: InputSniffer,
 }
 
 impl<R: AsyncBufRead, E: Encode> Encoder<R, E> {
+    /// Creates a new encoder wrapping the reader, using `encoder` for the format.
     pub fn new(reader: R, encoder: E) -> Self {
         Self {
             reader,
             encoder,
             state: State::Encoding,
+            total_in: 0,
+            total_out: 0,
+            sniffer: InputSniffer::default(),
         }
     }
 
+    /// Calls `hook` with warnings about the input, see the `with_warning_hook` method of the
+    /// format types.
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.sniffer.set_hook(hook);
+    }
+
+    /// Acquires a reference to the underlying reader.
     pub fn get_ref(&self) -> &R {
         &self.reader
     }
 
+    /// Acquires a mutable reference to the underlying reader.
     pub fn get_mut(&mut self) -> &mut R {
         &mut self.reader
     }
 
+    /// Acquires a pinned mutable reference to the underlying reader.
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
         self.project().reader
     }
 
+    /// Consumes this encoder returning the underlying reader.
     pub fn into_inner(self) -> R {
         self.reader
     }
 
+    /// Acquires a reference to the format's encoder.
+    pub fn get_encoder_ref(&self) -> &E {
+        &self.encoder
+    }
+
+    /// Acquires a mutable reference to the format's encoder.
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// Resets the encoder to start a new stream, the reader is left as is.
+    pub fn reset(&mut self) -> Result<()> {
+        self.encoder.reset()?;
+        self.state = State::Encoding;
+        self.total_in = 0;
+        self.total_out = 0;
+        self.sniffer.reset();
+        Ok(())
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.encoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so far, or
+    /// `None` until there has been some of both.
+    pub fn current_ratio(&self) -> Option<f32> {
+        if self.total_in == 0 || self.total_out == 0 {
+            None
+        } else {
+            Some(self.total_out as f32 / self.total_in as f32)
+        }
+    }
+
     fn do_poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -66,8 +136,10 @@ impl<R: AsyncBufRead, E: Encode> Encoder<R, E> {
                     } else {
                         let mut input = PartialBuffer::new(input);
                         this.encoder.encode(&mut input, output)?;
+                        this.sniffer.sniff(input.written());
                         let len = input.written().len();
                         this.reader.as_mut().consume(len);
+                        *this.total_in += len as u64;
                         State::Encoding
                     }
                 }
@@ -95,18 +167,23 @@ impl<R: AsyncBufRead, E: Encode> Encoder<R, E> {
 
 impl<R: AsyncBufRead, E: Encode> AsyncRead for Encoder<R, E> {
     fn poll_read(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<Result<usize>> {
+        // A zero length read must not run a coding step, the output would have nowhere to go
         if buf.is_empty() {
             return Poll::Ready(Ok(0));
         }
 
         let mut output = PartialBuffer::new(buf);
-        match self.do_poll_read(cx, &mut output)? {
+        match self.as_mut().do_poll_read(cx, &mut output)? {
             Poll::Pending if output.written().is_empty() => Poll::Pending,
-            _ => Poll::Ready(Ok(output.written().len())),
+            _ => {
+                let len = output.written().len();
+                *self.project().total_out += len as u64;
+                Poll::Ready(Ok(len))
+            }
         }
     }
 }
@@ -0,0 +1,200 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::{Error, ErrorKind, Result, SeekFrom};
+
+use crate::{
+    codec::{Decode, FlateDecoder, GzipHeader, GzipHeaderParser},
+    util::PartialBuffer,
+    CompressionError, Format,
+};
+use futures_core::{ready, stream::Stream};
+use futures_io::{AsyncBufRead, AsyncSeek};
+use pin_project::pin_project;
+
+const FOOTER_LEN: u64 = 8;
+
+/// The location and header of a single member in a gzip file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GzipMember {
+    /// The offset of the start of the member, relative to where the reader was positioned when
+    /// the [`GzipMembers`] was created.
+    pub offset: u64,
+    /// The length of the whole member, including its header and footer.
+    pub len: u64,
+    /// The member's header.
+    pub header: GzipHeader,
+}
+
+#[derive(Debug)]
+enum State {
+    Header(GzipHeaderParser),
+    Body(Option<GzipHeader>, Box<FlateDecoder>),
+    Footer(Option<GzipHeader>, u64),
+    Seeking(Option<GzipHeader>, i64),
+    Done,
+}
+
+/// A stream over the members of a concatenated gzip file, such as a BGZF file, that yields each
+/// member's header and location without decompressing it.
+///
+/// Members with a BGZF `BC` extra subfield recording their size are skipped over by seeking the
+/// reader, others have to be inflated (into a scratch buffer that is discarded) to find where
+/// they end. Neither the contents nor the footers of the members are checked, so a file that is
+/// truncated part way through a BGZF block is not detected.
+#[pin_project]
+#[derive(Debug)]
+pub struct GzipMembers<R: AsyncBufRead + AsyncSeek> {
+    #[pin]
+    reader: R,
+    state: State,
+    /// The offset of the start of the current member.
+    start: u64,
+    /// How many bytes have been consumed from the reader.
+    pos: u64,
+    scratch: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + AsyncSeek> GzipMembers<R> {
+    /// Creates a new stream over the members of the gzip file starting at the current position
+    /// of `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            state: State::Header(GzipHeaderParser::default()),
+            start: 0,
+            pos: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Acquires a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Acquires a mutable reference to the underlying reader.
+    ///
+    /// Note that care must be taken to avoid tampering with the state of the reader which may
+    /// otherwise confuse this stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consumes this stream returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead + AsyncSeek> Stream for GzipMembers<R> {
+    type Item = Result<GzipMember>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<GzipMember>>> {
+        let mut this = self.project();
+
+        loop {
+            *this.state = match this.state {
+                State::Header(parser) => {
+                    let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+                    if input.is_empty() {
+                        *this.state = State::Done;
+                        if *this.pos == *this.start {
+                            return Poll::Ready(None);
+                        }
+                        return Poll::Ready(Some(Err(CompressionError::UnexpectedEof {
+                            format: Format::Gzip,
+                        }
+                        .into())));
+                    }
+
+                    let mut input = PartialBuffer::new(input);
+                    let header = parser.input(&mut input)?;
+                    let len = input.written().len();
+                    this.reader.as_mut().consume(len);
+                    *this.pos += len as u64;
+
+                    match header.map(|header| header.info) {
+                        None => continue,
+                        Some(header) => match header.bgzf_block_size() {
+                            Some(size) => {
+                                let end = *this.start + size;
+                                if end < *this.pos + FOOTER_LEN {
+                                    return Poll::Ready(Some(Err(Error::new(
+                                        ErrorKind::InvalidData,
+                                        "BGZF block size is smaller than the gzip member header",
+                                    ))));
+                                }
+                                State::Seeking(Some(header), (end - *this.pos) as i64)
+                            }
+                            None => State::Body(Some(header), Box::new(FlateDecoder::new(false))),
+                        },
+                    }
+                }
+
+                State::Body(header, decoder) => {
+                    let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+                    if input.is_empty() {
+                        return Poll::Ready(Some(Err(CompressionError::UnexpectedEof {
+                            format: Format::Gzip,
+                        }
+                        .into())));
+                    }
+
+                    this.scratch.resize(8_000, 0);
+                    let mut input = PartialBuffer::new(input);
+                    let done = decoder
+                        .decode(&mut input, &mut PartialBuffer::new(&mut this.scratch[..]))?;
+                    let len = input.written().len();
+                    this.reader.as_mut().consume(len);
+                    *this.pos += len as u64;
+
+                    if done {
+                        State::Footer(header.take(), FOOTER_LEN)
+                    } else {
+                        continue;
+                    }
+                }
+
+                State::Footer(header, remaining) => {
+                    if *remaining > 0 {
+                        let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+                        if input.is_empty() {
+                            return Poll::Ready(Some(Err(CompressionError::UnexpectedEof {
+                                format: Format::Gzip,
+                            }
+                            .into())));
+                        }
+
+                        let len = std::cmp::min(*remaining, input.len() as u64);
+                        this.reader.as_mut().consume(len as usize);
+                        *this.pos += len;
+                        *remaining -= len;
+                        continue;
+                    }
+
+                    let member = GzipMember {
+                        offset: *this.start,
+                        len: *this.pos - *this.start,
+                        header: header.take().expect("member yielded twice"),
+                    };
+                    *this.start = *this.pos;
+                    *this.state = State::Header(GzipHeaderParser::default());
+                    return Poll::Ready(Some(Ok(member)));
+                }
+
+                State::Seeking(header, distance) => {
+                    ready!(this
+                        .reader
+                        .as_mut()
+                        .poll_seek(cx, SeekFrom::Current(*distance)))?;
+                    *this.pos += *distance as u64;
+                    State::Footer(header.take(), 0)
+                }
+
+                State::Done => return Poll::Ready(None),
+            };
+        }
+    }
+}
@@ -1,25 +1,108 @@
 macro_rules! decoder {
-    ($(#[$attr:meta])* $name:ident) => {
+    ($(#[$attr:meta])* $name:ident $(($($arg:ident: $ty:ty),*))? $({ $($methods:tt)* })?) => {
         $(#[$attr])*
         #[pin_project::pin_project]
-        #[derive(Debug)]
         ///
         /// This structure implements an [`AsyncRead`](futures_io::AsyncRead) interface and will
         /// read compressed data from an underlying stream and emit a stream of uncompressed data.
+        ///
+        /// Decoding stops at the end of the first compressed stream, any data following it is left
+        /// unread in the underlying reader rather than being guessed to be another stream. The
+        /// exceptions are gzip, where a file is defined as a series of members, and zstd, where a
+        /// stream can be a series of frames, so every member or frame up to the end of the
+        /// underlying reader is decoded, unless turned off with the decoder's
+        /// `with_multiple_members(false)` or `with_multiple_frames(false)` respectively.
+        ///
+        /// Data is decompressed straight into the buffer given to
+        /// [`poll_read`](futures_io::AsyncRead::poll_read), there is no intermediate output buffer,
+        /// so a large read is filled with as much as the available input decodes to in one call.
+        ///
+        /// It also implements [`AsyncBufRead`](futures_io::AsyncBufRead), so the output can be
+        /// read line by line without wrapping it in another buffered reader. Only then is output
+        /// decoded into an internal 8 KiB buffer, allocated on the first call to
+        /// [`poll_fill_buf`](futures_io::AsyncBufRead::poll_fill_buf), and reads drain that before
+        /// decoding any more.
         pub struct $name<R: futures_io::AsyncBufRead> {
             #[pin]
             inner: crate::bufread::Decoder<R, crate::codec::$name>,
         }
 
+        impl<R: futures_io::AsyncBufRead> core::fmt::Debug for $name<R> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The reader is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("reader", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
         impl<R: futures_io::AsyncBufRead> $name<R> {
             /// Creates a new decoder which will read compressed data from the given stream and
             /// emit a uncompressed stream.
-            pub fn new(read: R) -> $name<R> {
+            pub fn new(read: R $($(, $arg: $ty)*)?) -> $name<R> {
                 $name {
-                    inner: crate::bufread::Decoder::new(read, crate::codec::$name::new()),
+                    inner: crate::bufread::Decoder::new(read, crate::codec::$name::new($($($arg),*)?)),
                 }
             }
 
+            /// Limits the total number of decompressed bytes this decoder will produce, to guard
+            /// against small inputs that decompress to huge outputs.
+            ///
+            /// Once the data would exceed `limit` the decoder fails with an
+            /// [`io::Error`](std::io::Error) wrapping a [`LimitExceeded`](crate::LimitExceeded),
+            /// having produced exactly `limit` bytes before it.
+            pub fn with_limit(mut self, limit: u64) -> Self {
+                self.inner.set_limit(limit);
+                self
+            }
+
+            /// Limits the number of steps this decoder will take, to guard against inputs crafted
+            /// to be slow to decompress even if their output is small.
+            ///
+            /// A step is one call into the library implementing the format, the work done in each
+            /// is bounded by the size of the input chunk and output buffer it is given, so this
+            /// caps the CPU time spent on the stream. The number taken so far is reported by
+            /// [`stats`](Self::stats). Once `limit` steps have been
+            /// taken the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`StepLimitExceeded`](crate::StepLimitExceeded).
+            pub fn with_step_limit(mut self, limit: u64) -> Self {
+                self.inner.set_step_limit(limit);
+                self
+            }
+
+            /// Bounds how many bytes of input and output are processed in one poll, by default
+            /// 256 KiB.
+            ///
+            /// Highly compressed input can otherwise keep a single read busy for a long time,
+            /// starving the other tasks of the executor. Once the budget is used up the read
+            /// returns what has been decoded so far, or if that is nothing wakes the task and
+            /// returns [`Poll::Pending`](std::task::Poll::Pending) to be polled again later.
+            pub fn with_poll_budget(mut self, bytes: usize) -> Self {
+                self.inner.set_poll_budget(bytes);
+                self
+            }
+
+            /// Counts the memory of this decoder's buffers against `budget`, which bounds the memory
+            /// used by many decoders together.
+            ///
+            /// The output is decompressed straight into the caller's buffer and the input read from
+            /// the reader's, so what is counted is the 8 KiB buffer used for the
+            /// [`AsyncBufRead`](futures_io::AsyncBufRead) implementation. It is counted even if the
+            /// decoder is only read with `poll_read` and never allocates it, so that every decoder
+            /// takes a share of the budget.
+            ///
+            /// It is reserved before the decoder first calls into the format, and again if the
+            /// buffers grow, and released when the decoder is dropped. If the budget does not have
+            /// room the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`MemoryBudgetExceeded`](crate::MemoryBudgetExceeded) without decoding anything,
+            /// it can be retried once other decoders have finished.
+            pub fn with_memory_budget(mut self, budget: &crate::MemoryBudget) -> Self {
+                self.inner.set_memory_budget(budget);
+                self
+            }
+
             /// Acquires a reference to the underlying reader that this decoder is wrapping.
             pub fn get_ref(&self) -> &R {
                 self.inner.get_ref()
@@ -47,9 +130,43 @@ macro_rules! decoder {
             ///
             /// Note that this may discard internal state of this decoder, so care should be taken
             /// to avoid losing resources when this is called.
+            ///
+            /// The decoder only ever consumes input it has decoded, it never reads ahead past the
+            /// end of the compressed stream, so once the decoder has returned EOF any data following
+            /// the stream is still available from the returned reader. For gzip and zstd that is
+            /// only the case once decoding the following members or frames is turned off,
+            /// otherwise decoding carries on to the end of the reader.
             pub fn into_inner(self) -> R {
                 self.inner.into_inner()
             }
+
+            /// Returns a snapshot of the statistics of this decoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of compressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of decompressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            /// Returns how many bytes following the end of the compressed stream had already been
+            /// read into the underlying reader's buffer, or `None` until the stream has ended.
+            ///
+            /// Those bytes are left unconsumed, so they are the start of what
+            /// [`poll_fill_buf`](futures_io::AsyncBufRead::poll_fill_buf) on the underlying reader returns next, e.g. for a caller
+            /// that needs to rewind over them or process them itself. Any data after them has not
+            /// been read from the source yet.
+            pub fn trailing_len(&self) -> Option<usize> {
+                self.inner.trailing_len()
+            }
+
+            $($($methods)*)?
         }
 
         impl<R: futures_io::AsyncBufRead> futures_io::AsyncRead for $name<R> {
@@ -62,6 +179,19 @@ macro_rules! decoder {
             }
         }
 
+        impl<R: futures_io::AsyncBufRead> futures_io::AsyncBufRead for $name<R> {
+            fn poll_fill_buf(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<&[u8]>> {
+                self.project().inner.poll_fill_buf(cx)
+            }
+
+            fn consume(self: std::pin::Pin<&mut Self>, amt: usize) {
+                self.project().inner.consume(amt)
+            }
+        }
+
         const _: () = {
             fn _assert() {
                 use crate::util::{_assert_send, _assert_sync};
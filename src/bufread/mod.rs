@@ -4,7 +4,56 @@
 #[macro_use]
 mod macros;
 mod generic;
+#[cfg(feature = "gzip")]
+mod gzip_members;
 
-pub(crate) use generic::{Decoder, Encoder};
+pub use generic::{Decoder, Encoder};
+#[cfg(feature = "gzip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+pub use gzip_members::{GzipMember, GzipMembers};
 
 algos!(bufread<R>);
+formats!(R: [futures_io::AsyncBufRead] => [futures_io::AsyncRead]);
+default_algos!();
+
+#[cfg(feature = "deflate64")]
+decoder! {
+    /// A deflate64 decoder, or decompressor, for the "Enhanced Deflate" method 9 of ZIP archives.
+    /// There is no deflate64 encoder.
+    #[cfg_attr(docsrs, doc(cfg(feature = "deflate64")))]
+    Deflate64Decoder
+}
+
+#[cfg(feature = "gzip")]
+impl<R: futures_io::AsyncBufRead> GzipDecoder<R> {
+    /// Sets whether input that does not start with the gzip magic bytes is passed through
+    /// unchanged instead of failing to decode, for sources such as HTTP servers that may label
+    /// uncompressed content as gzip.
+    ///
+    /// This must be set before anything has been read from the decoder.
+    pub fn with_passthrough(mut self, enabled: bool) -> Self {
+        self.inner.set_passthrough(if enabled {
+            Some(crate::codec::GzipDecoder::MAGIC)
+        } else {
+            None
+        });
+        self
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: futures_io::AsyncBufRead> ZstdDecoder<R> {
+    /// Sets whether input that does not start with the zstd magic number is passed through
+    /// unchanged instead of failing to decode, for sources that may label uncompressed content as
+    /// zstd.
+    ///
+    /// This must be set before anything has been read from the decoder.
+    pub fn with_passthrough(mut self, enabled: bool) -> Self {
+        self.inner.set_passthrough(if enabled {
+            Some(crate::codec::ZstdDecoder::MAGIC)
+        } else {
+            None
+        });
+        self
+    }
+}
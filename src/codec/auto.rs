@@ -0,0 +1,217 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
+
+use crate::{
+    codec::Decode,
+    format::{Detection, MAX_MAGIC_LEN},
+    util::PartialBuffer,
+    Format, Stats,
+};
+
+type BoxDecoder = Box<dyn Decode + Send + Sync>;
+
+/// A decoder for whichever format the input's magic number identifies, input that isn't
+/// recognised is passed through unchanged.
+pub struct AutoDecoder {
+    /// The start of the input, kept until the format is known and then replayed to the decoder.
+    header: PartialBuffer<[u8; MAX_MAGIC_LEN]>,
+    /// How much of the header has been replayed.
+    replayed: usize,
+    detected: Option<Format>,
+    /// The only format the input is allowed to be, if any.
+    expected: Option<Format>,
+    /// `None` while sniffing, or when passing the input through.
+    decoder: Option<BoxDecoder>,
+    done: bool,
+}
+
+impl AutoDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            header: PartialBuffer::default(),
+            replayed: 0,
+            detected: None,
+            expected: None,
+            decoder: None,
+            done: false,
+        }
+    }
+
+    pub(crate) fn detected_format(&self) -> Option<Format> {
+        self.detected
+    }
+
+    pub(crate) fn set_expected_format(&mut self, format: Format) -> Result<()> {
+        if let Format::Brotli | Format::Deflate | Format::Lzma = format {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} has no magic number, so the input can't be checked to be in that format",
+                    format
+                ),
+            ));
+        }
+
+        self.expected = Some(format);
+        Ok(())
+    }
+
+    #[allow(unreachable_patterns)]
+    fn create(format: Format) -> Result<Option<BoxDecoder>> {
+        match format {
+            Format::Identity => Ok(None),
+            #[cfg(feature = "bzip")]
+            Format::Bzip => Ok(Some(Box::new(crate::codec::BzDecoder::new()))),
+            #[cfg(feature = "gzip")]
+            Format::Gzip => Ok(Some(Box::new(crate::codec::GzipDecoder::new()))),
+            #[cfg(feature = "lz4")]
+            Format::Lz4 => Ok(Some(Box::new(crate::codec::Lz4Decoder::new()))),
+            #[cfg(feature = "snappy")]
+            Format::Snappy => Ok(Some(Box::new(crate::codec::SnappyDecoder::new()))),
+            #[cfg(feature = "xz")]
+            Format::Xz => Ok(Some(Box::new(crate::codec::XzDecoder::new()))),
+            #[cfg(feature = "zlib")]
+            Format::Zlib => Ok(Some(Box::new(crate::codec::ZlibDecoder::new()))),
+            #[cfg(feature = "zstd")]
+            Format::Zstd => Ok(Some(Box::new(crate::codec::ZstdDecoder::new()))),
+            _ => Err(format.unavailable()),
+        }
+    }
+
+    /// Decides on the format once enough of the input has been seen, or at the end of the input,
+    /// returning whether it is known.
+    fn detect(&mut self, eof: bool) -> Result<bool> {
+        if self.detected.is_some() {
+            return Ok(true);
+        }
+
+        let format = match Format::detect(self.header.written()) {
+            Detection::Format(format) => format,
+            Detection::Incomplete if !eof && !self.header.unwritten().is_empty() => {
+                return Ok(false)
+            }
+            Detection::Incomplete | Detection::Unknown => Format::Identity,
+        };
+
+        // Checked before creating the decoder, so an unexpected format is reported as such even
+        // when its feature isn't enabled
+        match self.expected {
+            Some(expected) if expected != format => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "expected {} input, but it was detected as {}",
+                        expected, format
+                    ),
+                ))
+            }
+            _ => {}
+        }
+
+        self.decoder = Self::create(format)?;
+        self.detected = Some(format);
+        Ok(true)
+    }
+
+    /// Passes the sniffed bytes on, returning whether they have all been used.
+    fn replay(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        let mut header = PartialBuffer::new(&self.header.written()[self.replayed..]);
+        if !header.unwritten().is_empty() {
+            match &mut self.decoder {
+                Some(decoder) => self.done = decoder.decode(&mut header, output)?,
+                None => output.copy_unwritten_from(&mut header),
+            }
+        }
+        self.replayed += header.written().len();
+
+        // A tiny stream may end within the sniffed bytes, what followed it can't be given back
+        Ok(self.done || self.replayed == self.header.written().len())
+    }
+}
+
+impl Decode for AutoDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        if self.detected.is_none() {
+            self.header.copy_unwritten_from(input);
+            if !self.detect(false)? {
+                return Ok(false);
+            }
+        }
+
+        if !self.replay(output)? {
+            return Ok(false);
+        }
+        if self.done {
+            return Ok(true);
+        }
+        // The sniffing may have used up all the input, and decoders don't expect to be given none
+        if input.unwritten().is_empty() {
+            return Ok(false);
+        }
+
+        match &mut self.decoder {
+            Some(decoder) => decoder.decode(input, output),
+            None => {
+                output.copy_unwritten_from(input);
+                Ok(false)
+            }
+        }
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        // Until the format is known there is no output to flush
+        if self.detected.is_none() {
+            return Ok(true);
+        }
+        if !self.replay(output)? {
+            return Ok(false);
+        }
+
+        match &mut self.decoder {
+            Some(decoder) => decoder.flush(output),
+            None => Ok(true),
+        }
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.detect(true)?;
+
+        if !self.replay(output)? {
+            return Ok(false);
+        }
+
+        match &mut self.decoder {
+            Some(decoder) => decoder.finish(output),
+            None => Ok(true),
+        }
+    }
+
+    fn between_frames(&self) -> bool {
+        match &self.decoder {
+            Some(decoder) => {
+                self.replayed == self.header.written().len() && decoder.between_frames()
+            }
+            None => false,
+        }
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        if let Some(decoder) = &self.decoder {
+            decoder.stats(stats);
+        }
+    }
+}
+
+impl fmt::Debug for AutoDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AutoDecoder")
+            .field("detected", &self.detected)
+            .finish()
+    }
+}
@@ -1,42 +1,123 @@
-use crate::{codec::Decode, util::PartialBuffer};
+use crate::{
+    codec::Decode, unshared::Unshared, util::PartialBuffer, BackendError, CompressionError, Format,
+};
 use std::{
+    ffi::CStr,
     fmt,
     io::{Error, ErrorKind, Result},
 };
 
-use brotli2::raw::{DeStatus, Decompress};
+use brotli2::raw::DeStatus;
+use brotli_sys::{
+    BrotliDecoderCreateInstance, BrotliDecoderDecompressStream, BrotliDecoderDestroyInstance,
+    BrotliDecoderErrorString, BrotliDecoderGetErrorCode, BrotliDecoderSetCustomDictionary,
+    BrotliDecoderState, BROTLI_DECODER_RESULT_ERROR, BROTLI_DECODER_RESULT_NEEDS_MORE_INPUT,
+    BROTLI_DECODER_RESULT_NEEDS_MORE_OUTPUT, BROTLI_DECODER_RESULT_SUCCESS,
+};
+
+/// The decoder state, used directly rather than through `brotli2` as that discards the error
+/// codes.
+struct State(*mut BrotliDecoderState);
+
+// The state is only ever used through a unique reference
+unsafe impl Send for State {}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        unsafe { BrotliDecoderDestroyInstance(self.0) };
+    }
+}
+
+/// The largest custom dictionary brotli accepts, larger ones are silently ignored.
+const MAX_DICTIONARY_LEN: usize = 1 << 24;
 
 pub struct BrotliDecoder {
-    decompress: Decompress,
+    state: Unshared<State>,
+    // The state refers to the custom dictionary rather than copying it, so it is kept alive here
+    // for as long as the state is.
+    dictionary: Option<Box<[u8]>>,
 }
 
 impl BrotliDecoder {
     pub(crate) fn new() -> Self {
+        let state = unsafe { BrotliDecoderCreateInstance(None, None, std::ptr::null_mut()) };
+        assert!(!state.is_null());
+
         Self {
-            decompress: Decompress::new(),
+            state: Unshared::new(State(state)),
+            dictionary: None,
         }
     }
 
+    pub(crate) fn with_dictionary(dictionary: &[u8]) -> Result<Self> {
+        if dictionary.len() > MAX_DICTIONARY_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "brotli dictionary of {} bytes is too large, it must be at most {} bytes",
+                    dictionary.len(),
+                    MAX_DICTIONARY_LEN
+                ),
+            ));
+        }
+
+        let mut decoder = Self::new();
+        let dictionary: Box<[u8]> = dictionary.into();
+        unsafe {
+            BrotliDecoderSetCustomDictionary(
+                decoder.state.get_mut().0,
+                dictionary.len(),
+                dictionary.as_ptr(),
+            )
+        };
+        decoder.dictionary = Some(dictionary);
+        Ok(decoder)
+    }
+
     fn decode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<DeStatus> {
-        let mut in_buf = input.unwritten();
-        let mut out_buf = output.unwritten_mut();
-
-        let original_input_len = in_buf.len();
-        let original_output_len = out_buf.len();
+        let state = self.state.get_mut().0;
 
-        let status = self.decompress.decompress(&mut in_buf, &mut out_buf)?;
+        let mut available_in = input.unwritten().len();
+        let mut next_in = input.unwritten().as_ptr();
+        let mut available_out = output.unwritten().len();
+        let mut next_out = output.unwritten_mut().as_mut_ptr();
 
-        let input_len = original_input_len - in_buf.len();
-        let output_len = original_output_len - out_buf.len();
+        let result = unsafe {
+            BrotliDecoderDecompressStream(
+                state,
+                &mut available_in,
+                &mut next_in,
+                &mut available_out,
+                &mut next_out,
+                std::ptr::null_mut(),
+            )
+        };
 
-        input.advance(input_len);
-        output.advance(output_len);
+        input.advance(input.unwritten().len() - available_in);
+        output.advance(output.unwritten().len() - available_out);
 
-        Ok(status)
+        match result {
+            BROTLI_DECODER_RESULT_SUCCESS => Ok(DeStatus::Finished),
+            BROTLI_DECODER_RESULT_NEEDS_MORE_INPUT => Ok(DeStatus::NeedInput),
+            // Output that didn't fit, however little room there was, is kept in the state and
+            // written before anything else on the next call
+            BROTLI_DECODER_RESULT_NEEDS_MORE_OUTPUT => Ok(DeStatus::NeedOutput),
+            BROTLI_DECODER_RESULT_ERROR => {
+                let code = unsafe { BrotliDecoderGetErrorCode(state) };
+                let message = unsafe { CStr::from_ptr(BrotliDecoderErrorString(code)) };
+                Err(BackendError::new(
+                    Format::Brotli,
+                    Some(code),
+                    message.to_string_lossy().into_owned(),
+                )
+                .into())
+            }
+            result => unreachable!("unknown brotli result {}", result),
+        }
     }
 }
 
@@ -63,10 +144,10 @@ impl Decode for BrotliDecoder {
         match self.decode(&mut PartialBuffer::new(&[][..]), output)? {
             DeStatus::Finished => Ok(true),
             DeStatus::NeedOutput => Ok(false),
-            DeStatus::NeedInput => Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "reached unexpected EOF",
-            )),
+            DeStatus::NeedInput => Err(CompressionError::UnexpectedEof {
+                format: Format::Brotli,
+            }
+            .into()),
         }
     }
 }
@@ -74,7 +155,11 @@ impl Decode for BrotliDecoder {
 impl fmt::Debug for BrotliDecoder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BrotliDecoder")
-            .field("decompress", &"<no debug>")
+            .field("state", &"<no debug>")
+            .field(
+                "dictionary",
+                &self.dictionary.as_ref().map(|dictionary| dictionary.len()),
+            )
             .finish()
     }
 }
@@ -1,20 +1,147 @@
-use crate::{codec::Encode, util::PartialBuffer};
-use std::{fmt, io::Result};
+use crate::{codec::Encode, unshared::Unshared, util::PartialBuffer};
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
 
 use brotli2::{
     raw::{CoStatus, Compress, CompressOp},
-    CompressParams,
+    CompressMode, CompressParams,
+};
+use brotli_sys::{
+    BrotliEncoderCompressStream, BrotliEncoderCreateInstance, BrotliEncoderDestroyInstance,
+    BrotliEncoderHasMoreOutput, BrotliEncoderIsFinished, BrotliEncoderSetCustomDictionary,
+    BrotliEncoderSetParameter, BrotliEncoderState, BROTLI_PARAM_QUALITY,
 };
 
+/// Checks a parameter is within the bounds brotli allows for it, `CompressParams` passes them on
+/// unchecked and brotli silently clamps them.
+fn check_range(name: &str, value: u32, min: u32, max: u32) -> Result<()> {
+    if value < min || value > max {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "brotli {} of {} is out of range, it must be from {} to {}",
+                name, value, min, max
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// The encoder state, used directly rather than through `brotli2` for custom dictionaries as that
+/// has no way to set one.
+struct State(*mut BrotliEncoderState);
+
+// The state is only ever used through a unique reference
+unsafe impl Send for State {}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        unsafe { BrotliEncoderDestroyInstance(self.0) };
+    }
+}
+
+impl State {
+    fn with_dictionary(quality: u32, dictionary: &[u8]) -> Self {
+        let state = unsafe { BrotliEncoderCreateInstance(None, None, std::ptr::null_mut()) };
+        assert!(!state.is_null());
+        unsafe {
+            BrotliEncoderSetParameter(state, BROTLI_PARAM_QUALITY, quality);
+            BrotliEncoderSetCustomDictionary(state, dictionary.len(), dictionary.as_ptr());
+        }
+        Self(state)
+    }
+
+    /// The same as `Compress::compress`.
+    fn compress(
+        &mut self,
+        op: CompressOp,
+        input: &mut &[u8],
+        output: &mut &mut [u8],
+    ) -> Result<CoStatus> {
+        let mut available_in = input.len();
+        let mut next_in = input.as_ptr();
+        let mut available_out = output.len();
+        let mut next_out = output.as_mut_ptr();
+
+        let result = unsafe {
+            BrotliEncoderCompressStream(
+                self.0,
+                op as _,
+                &mut available_in,
+                &mut next_in,
+                &mut available_out,
+                &mut next_out,
+                std::ptr::null_mut(),
+            )
+        };
+
+        *input = &input[input.len() - available_in..];
+        let output_len = output.len();
+        *output = &mut std::mem::take(output)[output_len - available_out..];
+
+        if result == 0 {
+            return Err(Error::other("brotli error"));
+        }
+
+        let unfinished = op != CompressOp::Process
+            && (available_in != 0
+                || unsafe { BrotliEncoderHasMoreOutput(self.0) } == 1
+                || (op == CompressOp::Finish && unsafe { BrotliEncoderIsFinished(self.0) } == 0));
+        Ok(if unfinished {
+            CoStatus::Unfinished
+        } else {
+            CoStatus::Finished
+        })
+    }
+}
+
+enum Compressor {
+    Params(Compress),
+    Dictionary(Unshared<State>),
+}
+
 pub struct BrotliEncoder {
-    compress: Compress,
+    compress: Compressor,
+    params: CompressParams,
+    /// The quality and custom dictionary the encoder was created with, kept to recreate it.
+    dictionary: Option<(u32, Box<[u8]>)>,
 }
 
 impl BrotliEncoder {
     pub(crate) fn new(params: &CompressParams) -> Self {
         let mut compress = Compress::new();
         compress.set_params(params);
-        Self { compress }
+        Self {
+            compress: Compressor::Params(compress),
+            params: params.clone(),
+            dictionary: None,
+        }
+    }
+
+    pub(crate) fn with_dictionary(quality: u32, dictionary: &[u8]) -> Result<Self> {
+        check_range("quality", quality, 0, 11)?;
+
+        let mut params = CompressParams::new();
+        params.quality(quality);
+        Ok(Self {
+            compress: Compressor::Dictionary(Unshared::new(State::with_dictionary(
+                quality, dictionary,
+            ))),
+            params,
+            dictionary: Some((quality, dictionary.into())),
+        })
+    }
+
+    pub(crate) fn with_params(quality: u32, lgwin: u32, mode: CompressMode) -> Result<Self> {
+        check_range("quality", quality, 0, 11)?;
+        // The large window extension, allowing up to 30, isn't supported by the bundled brotli
+        check_range("lgwin", lgwin, 10, 24)?;
+
+        let mut params = CompressParams::new();
+        params.quality(quality).lgwin(lgwin).mode(mode);
+        Ok(Self::new(&params))
     }
 
     fn encode(
@@ -29,7 +156,12 @@ impl BrotliEncoder {
         let original_input_len = in_buf.len();
         let original_output_len = out_buf.len();
 
-        let status = self.compress.compress(op, &mut in_buf, &mut out_buf)?;
+        let status = match &mut self.compress {
+            Compressor::Params(compress) => compress.compress(op, &mut in_buf, &mut out_buf)?,
+            Compressor::Dictionary(state) => {
+                state.get_mut().compress(op, &mut in_buf, &mut out_buf)?
+            }
+        };
 
         let input_len = original_input_len - in_buf.len();
         let output_len = original_output_len - out_buf.len();
@@ -63,12 +195,35 @@ impl Encode for BrotliEncoder {
             CoStatus::Finished => Ok(true),
         }
     }
+
+    fn reset(&mut self) -> Result<()> {
+        // The raw brotli encoder can't be reset, so it's recreated with the same parameters
+        self.compress = match &self.dictionary {
+            Some((quality, dictionary)) => {
+                Compressor::Dictionary(Unshared::new(State::with_dictionary(*quality, dictionary)))
+            }
+            None => {
+                let mut compress = Compress::new();
+                compress.set_params(&self.params);
+                Compressor::Params(compress)
+            }
+        };
+        Ok(())
+    }
 }
 
 impl fmt::Debug for BrotliEncoder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BrotliEncoder")
             .field("compress", &"<no debug>")
+            .field("params", &self.params)
+            .field(
+                "dictionary",
+                &self
+                    .dictionary
+                    .as_ref()
+                    .map(|(_, dictionary)| dictionary.len()),
+            )
             .finish()
     }
 }
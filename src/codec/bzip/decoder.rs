@@ -1,4 +1,4 @@
-use crate::{codec::Decode, util::PartialBuffer};
+use crate::{codec::Decode, util::PartialBuffer, BackendError, Format};
 use std::fmt;
 use std::io::{Error, ErrorKind, Result};
 
@@ -21,8 +21,12 @@ impl fmt::Debug for BzDecoder {
 
 impl BzDecoder {
     pub(crate) fn new() -> Self {
+        Self::with_small(false)
+    }
+
+    pub(crate) fn with_small(small: bool) -> Self {
         Self {
-            decompress: Decompress::new(false),
+            decompress: Decompress::new(small),
         }
     }
 
@@ -37,7 +41,16 @@ impl BzDecoder {
         let status = self
             .decompress
             .decompress(input.unwritten(), output.unwritten_mut())
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(|err| {
+                // bzip2 describes all of these the same way, so use the libbzip2 descriptions
+                let (code, message) = match err {
+                    bzip2::Error::Sequence => (-1, "functions called out of sequence"),
+                    bzip2::Error::Param => (-2, "invalid parameter"),
+                    bzip2::Error::Data => (-4, "data integrity error"),
+                    bzip2::Error::DataMagic => (-5, "data does not start with the bzip2 magic"),
+                };
+                BackendError::new(Format::Bzip, Some(code), message)
+            })?;
 
         input.advance((self.decompress.total_in() - prior_in) as usize);
         output.advance((self.decompress.total_out() - prior_out) as usize);
@@ -6,6 +6,8 @@ use bzip2::{Action, Compress, Compression, Status};
 
 pub struct BzEncoder {
     compress: Compress,
+    level: Compression,
+    work_factor: u32,
 }
 
 impl fmt::Debug for BzEncoder {
@@ -43,9 +45,28 @@ impl BzEncoder {
     pub(crate) fn new(level: Compression, work_factor: u32) -> Self {
         Self {
             compress: Compress::new(level, work_factor),
+            level,
+            work_factor,
         }
     }
 
+    /// Like [`new`](Self::new), but returns an error of kind
+    /// [`InvalidInput`](ErrorKind::InvalidInput) rather than panicking if the `work_factor` is
+    /// out of range.
+    pub(crate) fn with_work_factor(level: Compression, work_factor: u32) -> Result<Self> {
+        if work_factor > 250 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "bzip2 work factor of {} is out of range, it must be from 0 to 250",
+                    work_factor
+                ),
+            ));
+        }
+
+        Ok(Self::new(level, work_factor))
+    }
+
     fn encode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
@@ -118,6 +139,11 @@ impl Encode for BzEncoder {
         }
     }
 
+    fn can_sync_flush(&self) -> bool {
+        // A flush ends the block, but the last bits of it are kept until the next byte is full
+        false
+    }
+
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
         match self.encode(&mut PartialBuffer::new(&[][..]), output, Action::Finish)? {
             // Decompression went fine, nothing much to report.
@@ -140,4 +166,10 @@ impl Encode for BzEncoder {
             Status::MemNeeded => Err(Error::new(ErrorKind::Other, "out of memory")),
         }
     }
+
+    fn reset(&mut self) -> Result<()> {
+        // bzip2 has no way to reset a stream, so it's recreated with the same parameters
+        self.compress = Compress::new(self.level, self.work_factor);
+        Ok(())
+    }
 }
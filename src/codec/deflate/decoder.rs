@@ -1,17 +1,53 @@
-use crate::util::PartialBuffer;
+use crate::{
+    codec::deflate::{Termination, SYNC_FLUSH_TRAILER},
+    util::PartialBuffer,
+    Stats,
+};
 use std::io::Result;
 
 #[derive(Debug)]
 pub struct DeflateDecoder {
     inner: crate::codec::FlateDecoder,
+    termination: Termination,
+    trailer: PartialBuffer<&'static [u8]>,
+    context_takeover: bool,
 }
 
 impl DeflateDecoder {
     pub(crate) fn new() -> Self {
         Self {
             inner: crate::codec::FlateDecoder::new(false),
+            termination: Termination::Final,
+            trailer: PartialBuffer::new(SYNC_FLUSH_TRAILER),
+            context_takeover: true,
         }
     }
+
+    pub(crate) fn with_dictionary(dictionary: &[u8]) -> Result<Self> {
+        Ok(Self {
+            inner: crate::codec::FlateDecoder::with_dictionary(false, dictionary)?,
+            ..Self::new()
+        })
+    }
+
+    pub(crate) fn with_window_bits(window_bits: u8) -> Result<Self> {
+        Ok(Self {
+            inner: crate::codec::FlateDecoder::with_window_bits(false, window_bits)?,
+            ..Self::new()
+        })
+    }
+
+    pub(crate) fn stored(&self) -> Option<bool> {
+        self.inner.stored()
+    }
+
+    pub(crate) fn set_termination(&mut self, termination: Termination) {
+        self.termination = termination;
+    }
+
+    pub(crate) fn set_context_takeover(&mut self, enabled: bool) {
+        self.context_takeover = enabled;
+    }
 }
 
 impl crate::codec::Decode for DeflateDecoder {
@@ -24,10 +60,35 @@ impl crate::codec::Decode for DeflateDecoder {
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.flush(output)
+        let done = self.inner.flush(output)?;
+        if done && !self.context_takeover {
+            self.inner.reset_context()?;
+        }
+        Ok(done)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.finish(output)
+        match self.termination {
+            Termination::Final => self.inner.finish(output),
+
+            Termination::SyncFlush => self.inner.flush(output),
+
+            Termination::StrippedSyncFlush => {
+                // Restore the stripped trailer so the last block is complete
+                while !self.trailer.unwritten().is_empty() {
+                    if self.inner.decode(&mut self.trailer, output)? {
+                        return Ok(true);
+                    }
+                    if output.unwritten().is_empty() {
+                        return Ok(false);
+                    }
+                }
+                self.inner.flush(output)
+            }
+        }
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        self.inner.stats(stats)
     }
 }
@@ -1,4 +1,10 @@
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{
+    codec::{
+        deflate::{Termination, SYNC_FLUSH_TRAILER},
+        Encode,
+    },
+    util::PartialBuffer,
+};
 use std::io::Result;
 
 use flate2::Compression;
@@ -6,14 +12,51 @@ use flate2::Compression;
 #[derive(Debug)]
 pub struct DeflateEncoder {
     inner: crate::codec::FlateEncoder,
+    termination: Termination,
+    trailer: Option<PartialBuffer<Vec<u8>>>,
+    context_takeover: bool,
 }
 
 impl DeflateEncoder {
     pub(crate) fn new(level: Compression) -> Self {
         Self {
             inner: crate::codec::FlateEncoder::new(level, false),
+            termination: Termination::Final,
+            trailer: None,
+            context_takeover: true,
         }
     }
+
+    pub(crate) fn with_dictionary(level: Compression, dictionary: &[u8]) -> Result<Self> {
+        Ok(Self {
+            inner: crate::codec::FlateEncoder::with_dictionary(level, false, dictionary)?,
+            ..Self::new(level)
+        })
+    }
+
+    pub(crate) fn with_window_bits(level: Compression, window_bits: u8) -> Result<Self> {
+        Ok(Self {
+            inner: crate::codec::FlateEncoder::with_window_bits(level, false, window_bits)?,
+            ..Self::new(level)
+        })
+    }
+
+    pub(crate) fn set_termination(&mut self, termination: Termination) {
+        self.termination = termination;
+    }
+
+    pub(crate) fn set_context_takeover(&mut self, enabled: bool) {
+        self.context_takeover = enabled;
+    }
+
+    /// Ends a message once a flush is done, starting the next with an empty window if the
+    /// context isn't taken over.
+    fn flushed(&mut self, done: bool) -> Result<bool> {
+        if done && !self.context_takeover {
+            self.inner.reset_context()?;
+        }
+        Ok(done)
+    }
 }
 
 impl Encode for DeflateEncoder {
@@ -26,10 +69,43 @@ impl Encode for DeflateEncoder {
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.flush(output)
+        let done = self.inner.flush(output)?;
+        self.flushed(done)
+    }
+
+    fn full_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        let done = self.inner.full_flush(output)?;
+        self.flushed(done)
+    }
+
+    fn partial_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        let done = self.inner.partial_flush(output)?;
+        self.flushed(done)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.finish(output)
+        if let Termination::Final = self.termination {
+            return self.inner.finish(output);
+        }
+
+        // The trailer can only be stripped once it's known to be the end of the output, so the
+        // final sync flush is collected before any of it is written out.
+        if self.trailer.is_none() {
+            let mut trailer = self.inner.sync_flush()?;
+            if let Termination::StrippedSyncFlush = self.termination {
+                debug_assert!(trailer.ends_with(SYNC_FLUSH_TRAILER));
+                trailer.truncate(trailer.len() - SYNC_FLUSH_TRAILER.len());
+            }
+            self.trailer = Some(trailer.into());
+        }
+
+        let trailer = self.trailer.as_mut().unwrap();
+        output.copy_unwritten_from(trailer);
+        Ok(trailer.unwritten().is_empty())
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.trailer = None;
+        self.inner.reset()
     }
 }
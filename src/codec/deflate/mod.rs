@@ -2,3 +2,23 @@ mod decoder;
 mod encoder;
 
 pub(crate) use self::{decoder::DeflateDecoder, encoder::DeflateEncoder};
+
+/// How the end of a raw deflate stream is marked, for interoperating with protocols that don't
+/// use a final block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The stream ends with a block that has the `BFINAL` bit set, as specified by the deflate
+    /// format. This is the default.
+    Final,
+
+    /// The stream ends with a sync flush (an empty stored block, ending in the bytes
+    /// `00 00 FF FF`) and never has a block with the `BFINAL` bit set.
+    SyncFlush,
+
+    /// Like `SyncFlush`, but the trailing `00 00 FF FF` bytes are stripped, as done by
+    /// permessage-deflate (RFC 7692).
+    StrippedSyncFlush,
+}
+
+/// The bytes that end every sync flush.
+const SYNC_FLUSH_TRAILER: &[u8] = &[0x00, 0x00, 0xff, 0xff];
@@ -0,0 +1,470 @@
+use crate::{
+    codec::{deflate64::invalid_data, huffman::Huffman, Decode},
+    util::PartialBuffer,
+    Stats,
+};
+use std::io::{Error, ErrorKind, Result};
+
+/// The size of the sliding window, matches can refer back this far.
+const WINDOW_SIZE: usize = 1 << 16;
+
+/// The number of literal/length and distance codes that may be used.
+const MAX_LENGTH_CODES: usize = 286;
+const MAX_DISTANCE_CODES: usize = 32;
+
+/// The base lengths and number of extra bits of the length codes 257 to 285, deflate64 differs
+/// from deflate in the last, which takes 16 extra bits instead of meaning a length of 258.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 3,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 16,
+];
+
+/// The base distances and number of extra bits of the distance codes, deflate64 adds the last
+/// two to reach back 64 KiB.
+const DISTANCE_BASE: [u16; 32] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577, 32769, 49153,
+];
+const DISTANCE_EXTRA: [u8; 32] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13, 14, 14,
+];
+
+/// The order the code length code lengths are sent in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+#[derive(Debug)]
+enum State {
+    Header,
+    StoredHeader,
+    Stored(usize),
+    DynamicHeader,
+    CodeLengthCodes {
+        lengths: usize,
+        distances: usize,
+        count: usize,
+        code_lengths: [u8; 19],
+        read: usize,
+    },
+    CodeLengths {
+        lengths: usize,
+        distances: usize,
+        code: Huffman,
+        read: usize,
+    },
+    Codes,
+    Distance(usize),
+    Copy(usize, usize),
+    Done,
+}
+
+/// A decoder for deflate64, the "Enhanced Deflate" method 9 of ZIP archives, which extends
+/// deflate with a 64 KiB window, two more distance codes and lengths of up to 65538.
+///
+/// No library implements it, so this is a complete inflater. Input bytes are only taken as the
+/// bits in them are needed, so nothing after the end of the stream is consumed.
+#[derive(Debug)]
+pub struct Deflate64Decoder {
+    state: State,
+    /// Whether the current block is the last.
+    last: bool,
+    /// Bits read from the input but not used yet, the next one lowest.
+    bits: u64,
+    bit_count: u32,
+    window: Vec<u8>,
+    /// Where the next byte goes in the window.
+    position: usize,
+    /// Whether the window has been filled, so matches can refer back all the way.
+    wrapped: bool,
+    lengths: [u8; MAX_LENGTH_CODES + MAX_DISTANCE_CODES],
+    literal_code: Option<Huffman>,
+    distance_code: Option<Huffman>,
+    /// The number of blocks completed.
+    blocks: u64,
+}
+
+impl Deflate64Decoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: State::Header,
+            last: false,
+            bits: 0,
+            bit_count: 0,
+            window: vec![0; WINDOW_SIZE],
+            position: 0,
+            wrapped: false,
+            lengths: [0; MAX_LENGTH_CODES + MAX_DISTANCE_CODES],
+            literal_code: None,
+            distance_code: None,
+            blocks: 0,
+        }
+    }
+
+    /// Takes input bytes until there are at least `count` bits, returns whether there are.
+    fn need(&mut self, input: &mut PartialBuffer<&[u8]>, count: u32) -> bool {
+        while self.bit_count < count {
+            match input.unwritten().first() {
+                Some(&byte) => {
+                    input.advance(1);
+                    self.bits |= u64::from(byte) << self.bit_count;
+                    self.bit_count += 8;
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn consume(&mut self, count: u32) -> u64 {
+        let value = self.bits & ((1 << count) - 1);
+        self.bits >>= count;
+        self.bit_count -= count;
+        value
+    }
+
+    /// Decodes a symbol and the extra bits `extra` says it is followed by, only consuming them
+    /// once all are available.
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        code: &Huffman,
+        extra: impl Fn(u16) -> u32,
+    ) -> Result<Option<(u16, u64)>> {
+        loop {
+            if let Some((symbol, len)) = code.decode(self.bits, self.bit_count)? {
+                let extra = extra(symbol);
+                if !self.need(input, len + extra) {
+                    return Ok(None);
+                }
+                self.consume(len);
+                return Ok(Some((symbol, self.consume(extra))));
+            }
+            if !self.need(input, self.bit_count + 1) {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn write(&mut self, output: &mut PartialBuffer<&mut [u8]>, byte: u8) {
+        output.unwritten_mut()[0] = byte;
+        output.advance(1);
+        self.window[self.position] = byte;
+        self.position += 1;
+        if self.position == WINDOW_SIZE {
+            self.position = 0;
+            self.wrapped = true;
+        }
+    }
+
+    fn end_block(&mut self) -> State {
+        self.blocks += 1;
+        if self.last {
+            State::Done
+        } else {
+            State::Header
+        }
+    }
+
+    fn fixed_codes(&mut self) -> Result<State> {
+        let mut lengths = [0; 288];
+        lengths[..144].iter_mut().for_each(|len| *len = 8);
+        lengths[144..256].iter_mut().for_each(|len| *len = 9);
+        lengths[256..280].iter_mut().for_each(|len| *len = 7);
+        lengths[280..].iter_mut().for_each(|len| *len = 8);
+        self.literal_code = Some(Huffman::new(&lengths, false)?);
+        self.distance_code = Some(Huffman::new(&[5; 32], false)?);
+        Ok(State::Codes)
+    }
+
+    fn dynamic_codes(&mut self, lengths: usize, distances: usize) -> Result<State> {
+        if self.lengths[256] == 0 {
+            return Err(invalid_data("deflate64 block has no end of block code"));
+        }
+        self.literal_code = Some(Huffman::new(&self.lengths[..lengths], true)?);
+        self.distance_code = Some(Huffman::new(
+            &self.lengths[MAX_LENGTH_CODES..MAX_LENGTH_CODES + distances],
+            true,
+        )?);
+        Ok(State::Codes)
+    }
+
+    /// Runs until the input runs out, the output is full or the stream ends, returns whether it
+    /// ended.
+    fn run(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        loop {
+            self.state = match std::mem::replace(&mut self.state, State::Done) {
+                State::Header => {
+                    if !self.need(input, 3) {
+                        self.state = State::Header;
+                        return Ok(false);
+                    }
+                    self.last = self.consume(1) == 1;
+                    match self.consume(2) {
+                        0 => State::StoredHeader,
+                        1 => self.fixed_codes()?,
+                        2 => State::DynamicHeader,
+                        _ => return Err(invalid_data("invalid deflate64 block type")),
+                    }
+                }
+
+                State::StoredHeader => {
+                    // Stored blocks start on a byte boundary, fewer than 8 bits are ever left over
+                    self.consume(self.bit_count % 8);
+                    if !self.need(input, 32) {
+                        self.state = State::StoredHeader;
+                        return Ok(false);
+                    }
+                    let len = self.consume(16) as u16;
+                    if self.consume(16) as u16 != !len {
+                        return Err(invalid_data("invalid deflate64 stored block length"));
+                    }
+                    State::Stored(len.into())
+                }
+
+                State::Stored(0) => self.end_block(),
+
+                State::Stored(remaining) => {
+                    let len = remaining
+                        .min(input.unwritten().len())
+                        .min(output.unwritten().len());
+                    for i in 0..len {
+                        let byte = input.unwritten()[i];
+                        self.write(output, byte);
+                    }
+                    input.advance(len);
+                    self.state = State::Stored(remaining - len);
+                    if len == 0 {
+                        return Ok(false);
+                    }
+                    continue;
+                }
+
+                State::DynamicHeader => {
+                    if !self.need(input, 14) {
+                        self.state = State::DynamicHeader;
+                        return Ok(false);
+                    }
+                    let lengths = self.consume(5) as usize + 257;
+                    let distances = self.consume(5) as usize + 1;
+                    let count = self.consume(4) as usize + 4;
+                    if lengths > MAX_LENGTH_CODES {
+                        return Err(invalid_data("too many deflate64 length codes"));
+                    }
+                    self.lengths = [0; MAX_LENGTH_CODES + MAX_DISTANCE_CODES];
+                    State::CodeLengthCodes {
+                        lengths,
+                        distances,
+                        count,
+                        code_lengths: [0; 19],
+                        read: 0,
+                    }
+                }
+
+                State::CodeLengthCodes {
+                    lengths,
+                    distances,
+                    count,
+                    mut code_lengths,
+                    read,
+                } => {
+                    if read == count {
+                        State::CodeLengths {
+                            lengths,
+                            distances,
+                            code: Huffman::new(&code_lengths, false)?,
+                            read: 0,
+                        }
+                    } else if self.need(input, 3) {
+                        code_lengths[CODE_LENGTH_ORDER[read]] = self.consume(3) as u8;
+                        State::CodeLengthCodes {
+                            lengths,
+                            distances,
+                            count,
+                            code_lengths,
+                            read: read + 1,
+                        }
+                    } else {
+                        self.state = State::CodeLengthCodes {
+                            lengths,
+                            distances,
+                            count,
+                            code_lengths,
+                            read,
+                        };
+                        return Ok(false);
+                    }
+                }
+
+                State::CodeLengths {
+                    lengths,
+                    distances,
+                    code,
+                    read,
+                } => {
+                    if read == lengths + distances {
+                        self.dynamic_codes(lengths, distances)?
+                    } else {
+                        let extra = |symbol| match symbol {
+                            16 => 2,
+                            17 => 3,
+                            18 => 7,
+                            _ => 0,
+                        };
+                        let (symbol, extra) = match self.decode(input, &code, extra)? {
+                            Some(decoded) => decoded,
+                            None => {
+                                self.state = State::CodeLengths {
+                                    lengths,
+                                    distances,
+                                    code,
+                                    read,
+                                };
+                                return Ok(false);
+                            }
+                        };
+                        // The distance code lengths follow straight after the used length codes
+                        let index = |i| {
+                            if i < lengths {
+                                i
+                            } else {
+                                MAX_LENGTH_CODES + i - lengths
+                            }
+                        };
+                        let (len, repeat) = match symbol {
+                            0..=15 => (symbol as u8, 1),
+                            16 if read == 0 => {
+                                return Err(invalid_data("deflate64 repeat with no first length"))
+                            }
+                            16 => (self.lengths[index(read - 1)], 3 + extra as usize),
+                            17 => (0, 3 + extra as usize),
+                            _ => (0, 11 + extra as usize),
+                        };
+                        if read + repeat > lengths + distances {
+                            return Err(invalid_data("too many deflate64 code lengths"));
+                        }
+                        for i in read..read + repeat {
+                            self.lengths[index(i)] = len;
+                        }
+                        State::CodeLengths {
+                            lengths,
+                            distances,
+                            code,
+                            read: read + repeat,
+                        }
+                    }
+                }
+
+                State::Codes => {
+                    if output.unwritten().is_empty() {
+                        self.state = State::Codes;
+                        return Ok(false);
+                    }
+                    let code = self.literal_code.take().unwrap();
+                    let extra = |symbol: u16| match symbol {
+                        257..=285 => LENGTH_EXTRA[symbol as usize - 257].into(),
+                        _ => 0,
+                    };
+                    let decoded = self.decode(input, &code, extra);
+                    self.literal_code = Some(code);
+                    match decoded? {
+                        None => {
+                            self.state = State::Codes;
+                            return Ok(false);
+                        }
+                        Some((symbol @ 0..=255, _)) => {
+                            self.write(output, symbol as u8);
+                            State::Codes
+                        }
+                        Some((256, _)) => self.end_block(),
+                        Some((symbol @ 257..=285, extra)) => State::Distance(
+                            usize::from(LENGTH_BASE[symbol as usize - 257]) + extra as usize,
+                        ),
+                        Some(_) => return Err(invalid_data("invalid deflate64 length code")),
+                    }
+                }
+
+                State::Distance(len) => {
+                    let code = self.distance_code.take().unwrap();
+                    let extra = |symbol: u16| match symbol {
+                        0..=31 => DISTANCE_EXTRA[symbol as usize].into(),
+                        _ => 0,
+                    };
+                    let decoded = self.decode(input, &code, extra);
+                    self.distance_code = Some(code);
+                    match decoded? {
+                        None => {
+                            self.state = State::Distance(len);
+                            return Ok(false);
+                        }
+                        Some((symbol, extra)) => {
+                            let distance =
+                                usize::from(DISTANCE_BASE[symbol as usize]) + extra as usize;
+                            if !self.wrapped && distance > self.position {
+                                return Err(invalid_data("deflate64 distance too far back"));
+                            }
+                            State::Copy(len, distance)
+                        }
+                    }
+                }
+
+                State::Copy(len, distance) => {
+                    let count = len.min(output.unwritten().len());
+                    for _ in 0..count {
+                        let byte =
+                            self.window[(self.position + WINDOW_SIZE - distance) % WINDOW_SIZE];
+                        self.write(output, byte);
+                    }
+                    if count < len {
+                        self.state = State::Copy(len - count, distance);
+                        return Ok(false);
+                    }
+                    State::Codes
+                }
+
+                State::Done => return Ok(true),
+            };
+        }
+    }
+}
+
+impl Decode for Deflate64Decoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        self.run(input, output)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.run(&mut PartialBuffer::new(&[]), output)?;
+        Ok(!matches!(self.state, State::Copy(..)))
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if self.run(&mut PartialBuffer::new(&[]), output)? {
+            Ok(true)
+        } else if output.unwritten().is_empty() {
+            Ok(false)
+        } else {
+            Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "deflate64 stream ended unexpectedly",
+            ))
+        }
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.blocks = Some(self.blocks);
+        stats.window_size = Some(WINDOW_SIZE as u64);
+    }
+}
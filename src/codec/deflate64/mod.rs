@@ -0,0 +1,9 @@
+use std::io::{Error, ErrorKind};
+
+mod decoder;
+
+pub(crate) use self::decoder::Deflate64Decoder;
+
+fn invalid_data(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
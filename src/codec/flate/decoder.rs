@@ -1,4 +1,11 @@
-use crate::{codec::Decode, util::PartialBuffer};
+use crate::{
+    codec::{
+        flate::{check_window_bits, scanner::BlockScanner, MAX_WINDOW_BITS},
+        Decode,
+    },
+    util::PartialBuffer,
+    CompressionError, Format, Stats,
+};
 use std::io::{Error, ErrorKind, Result};
 
 use flate2::{Decompress, FlushDecompress, Status};
@@ -6,15 +13,68 @@ use flate2::{Decompress, FlushDecompress, Status};
 #[derive(Debug)]
 pub struct FlateDecoder {
     decompress: Decompress,
+    scanner: BlockScanner,
+    zlib_header: bool,
+    window_bits: u8,
+    /// For zlib a dictionary that still has to be loaded once the header asks for it, for raw
+    /// deflate the dictionary kept to load again when the context is reset.
+    dictionary: Option<Vec<u8>>,
 }
 
 impl FlateDecoder {
     pub(crate) fn new(zlib_header: bool) -> Self {
         Self {
             decompress: Decompress::new(zlib_header),
+            scanner: BlockScanner::new(zlib_header),
+            zlib_header,
+            window_bits: MAX_WINDOW_BITS,
+            dictionary: None,
         }
     }
 
+    pub(crate) fn with_window_bits(zlib_header: bool, window_bits: u8) -> Result<Self> {
+        check_window_bits(window_bits)?;
+        Ok(Self {
+            decompress: Decompress::new_with_window_bits(zlib_header, window_bits),
+            window_bits,
+            ..Self::new(zlib_header)
+        })
+    }
+
+    pub(crate) fn with_dictionary(zlib_header: bool, dictionary: &[u8]) -> Result<Self> {
+        let mut this = Self::new(zlib_header);
+        // zlib only accepts the dictionary once the header has identified it
+        if !zlib_header {
+            this.decompress.set_dictionary(dictionary)?;
+        }
+        this.dictionary = Some(dictionary.to_vec());
+        Ok(this)
+    }
+
+    /// Starts over with an empty window at a block boundary of a raw deflate stream, keeping the
+    /// allocations, see `FlateEncoder::reset_context`.
+    #[cfg(feature = "deflate")]
+    pub(crate) fn reset_context(&mut self) -> Result<()> {
+        debug_assert!(!self.zlib_header);
+        self.decompress.reset(false);
+        if let Some(dictionary) = &self.dictionary {
+            self.decompress.set_dictionary(dictionary)?;
+        }
+        Ok(())
+    }
+
+    fn format(&self) -> Format {
+        if self.zlib_header {
+            Format::Zlib
+        } else {
+            Format::Deflate
+        }
+    }
+
+    pub(crate) fn stored(&self) -> Option<bool> {
+        self.scanner.stored()
+    }
+
     fn decode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
@@ -24,14 +84,68 @@ impl FlateDecoder {
         let prior_in = self.decompress.total_in();
         let prior_out = self.decompress.total_out();
 
-        let status =
-            self.decompress
-                .decompress(input.unwritten(), output.unwritten_mut(), flush)?;
+        if self.zlib_header && self.dictionary.is_some() {
+            // The FDICT flag is in the second byte of the zlib header
+            let flags = 1u64
+                .checked_sub(prior_in)
+                .and_then(|offset| input.unwritten().get(offset as usize));
+            if let Some(flags) = flags {
+                if flags & 0b0010_0000 == 0 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "a dictionary was given but the zlib stream does not use one",
+                    ));
+                }
+            }
+        }
+
+        // zlib's own error for a header asking for a larger window doesn't say what the window is
+        if self.zlib_header && self.window_bits < MAX_WINDOW_BITS && prior_in == 0 {
+            if let Some(&cmf) = input.unwritten().first() {
+                let window_bits = (cmf >> 4) + 8;
+                if cmf & 0x0f == 8 && window_bits > self.window_bits {
+                    return Err(CompressionError::invalid_header(
+                        Format::Zlib,
+                        format!(
+                            "the stream uses a window of {} bits, more than the decoder's {}",
+                            window_bits, self.window_bits
+                        ),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let result = self
+            .decompress
+            .decompress(input.unwritten(), output.unwritten_mut(), flush);
 
-        input.advance((self.decompress.total_in() - prior_in) as usize);
+        let consumed = (self.decompress.total_in() - prior_in) as usize;
+        self.scanner
+            .input(&mut PartialBuffer::new(&input.unwritten()[..consumed]));
+        input.advance(consumed);
         output.advance((self.decompress.total_out() - prior_out) as usize);
 
-        Ok(status)
+        match result {
+            Ok(status) => Ok(status),
+            Err(err) if self.zlib_header && err.needs_dictionary().is_some() => {
+                let dictionary = self.dictionary.take().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "the zlib stream requires a dictionary but none was given",
+                    )
+                })?;
+                // This checks the dictionary against the id in the header
+                self.decompress.set_dictionary(&dictionary).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "the zlib stream was compressed with a different dictionary",
+                    )
+                })?;
+                Ok(Status::Ok)
+            }
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
@@ -78,7 +192,17 @@ impl Decode for FlateDecoder {
         )? {
             Status::Ok => Ok(false),
             Status::StreamEnd => Ok(true),
-            Status::BufError => Err(Error::new(ErrorKind::Other, "unexpected BufError")),
+            // With room for output, no progress can only mean the input ended before the stream
+            Status::BufError if output.unwritten().is_empty() => Ok(false),
+            Status::BufError => Err(CompressionError::UnexpectedEof {
+                format: self.format(),
+            }
+            .into()),
         }
     }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.blocks = Some(self.scanner.blocks());
+        stats.window_size = self.scanner.window_size();
+    }
 }
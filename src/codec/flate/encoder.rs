@@ -1,4 +1,10 @@
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{
+    codec::{
+        flate::{check_window_bits, MAX_WINDOW_BITS},
+        Encode,
+    },
+    util::PartialBuffer,
+};
 use std::io::{Error, ErrorKind, Result};
 
 use flate2::{Compress, Compression, FlushCompress, Status};
@@ -7,16 +13,55 @@ use flate2::{Compress, Compression, FlushCompress, Status};
 pub struct FlateEncoder {
     compress: Compress,
     flushed: bool,
+    level: Compression,
+    zlib_header: bool,
+    window_bits: u8,
+    dictionary: Option<Vec<u8>>,
+    dictionary_adler32: Option<u32>,
 }
 
 impl FlateEncoder {
     pub(crate) fn new(level: Compression, zlib_header: bool) -> Self {
         Self {
-            compress: Compress::new(level, zlib_header),
+            compress: Compress::new_with_window_bits(level, zlib_header, MAX_WINDOW_BITS),
             flushed: true,
+            level,
+            zlib_header,
+            window_bits: MAX_WINDOW_BITS,
+            dictionary: None,
+            dictionary_adler32: None,
         }
     }
 
+    pub(crate) fn with_window_bits(
+        level: Compression,
+        zlib_header: bool,
+        window_bits: u8,
+    ) -> Result<Self> {
+        check_window_bits(window_bits)?;
+        Ok(Self {
+            compress: Compress::new_with_window_bits(level, zlib_header, window_bits),
+            window_bits,
+            ..Self::new(level, zlib_header)
+        })
+    }
+
+    pub(crate) fn with_dictionary(
+        level: Compression,
+        zlib_header: bool,
+        dictionary: &[u8],
+    ) -> Result<Self> {
+        let mut this = Self::new(level, zlib_header);
+        this.dictionary_adler32 = Some(this.compress.set_dictionary(dictionary)?);
+        this.dictionary = Some(dictionary.to_vec());
+        Ok(this)
+    }
+
+    #[cfg(feature = "zlib")]
+    pub(crate) fn dictionary_adler32(&self) -> Option<u32> {
+        self.dictionary_adler32
+    }
+
     fn encode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
@@ -35,34 +80,19 @@ impl FlateEncoder {
 
         Ok(status)
     }
-}
 
-impl Encode for FlateEncoder {
-    fn encode(
+    fn flush_with(
         &mut self,
-        input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
-    ) -> Result<()> {
-        self.flushed = false;
-        match self.encode(input, output, FlushCompress::None)? {
-            Status::Ok => Ok(()),
-            Status::StreamEnd => unreachable!(),
-            Status::BufError => Err(Error::new(ErrorKind::Other, "unexpected BufError")),
-        }
-    }
-
-    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        flush: FlushCompress,
+    ) -> Result<bool> {
         // We need to keep track of whether we've already flushed otherwise we'll just keep writing
         // out sync blocks continuously and probably never complete flushing.
         if self.flushed {
             return Ok(true);
         }
 
-        self.encode(
-            &mut PartialBuffer::new(&[][..]),
-            output,
-            FlushCompress::Sync,
-        )?;
+        self.encode(&mut PartialBuffer::new(&[][..]), output, flush)?;
 
         loop {
             let old_len = output.written().len();
@@ -76,8 +106,79 @@ impl Encode for FlateEncoder {
             }
         }
 
+        // A flush that ran out of output space is repeated with the same mode next time, as zlib
+        // requires, a stored block at level 0 is otherwise left unfinished until more input
+        let done = !output.unwritten().is_empty();
+        self.flushed = done;
+        Ok(done)
+    }
+
+    /// Starts over with an empty window at a block boundary, after a flush, keeping the
+    /// allocations. The output carries on as the same stream, the dictionary is loaded again if
+    /// there is one.
+    #[cfg(feature = "deflate")]
+    pub(crate) fn reset_context(&mut self) -> Result<()> {
+        self.compress.reset();
+        if let Some(dictionary) = &self.dictionary {
+            self.compress.set_dictionary(dictionary)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a sync flush even if nothing has been written since the last one, returning all of
+    /// the output produced up to and including it.
+    pub(crate) fn sync_flush(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+
+        // As with zlib, the flush is complete once it doesn't fill the whole output buffer
+        loop {
+            let old_len = output.len();
+            output.resize(old_len + 1024, 0);
+
+            let mut buffer = PartialBuffer::new(&mut output[old_len..]);
+            self.encode(
+                &mut PartialBuffer::new(&[][..]),
+                &mut buffer,
+                FlushCompress::Sync,
+            )?;
+            let done = !buffer.unwritten().is_empty();
+            let written = buffer.written().len();
+            output.truncate(old_len + written);
+
+            if done {
+                break;
+            }
+        }
+
         self.flushed = true;
-        Ok(!output.unwritten().is_empty())
+        Ok(output)
+    }
+}
+
+impl Encode for FlateEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        self.flushed = false;
+        match self.encode(input, output, FlushCompress::None)? {
+            Status::Ok => Ok(()),
+            Status::StreamEnd => unreachable!(),
+            Status::BufError => Err(Error::new(ErrorKind::Other, "unexpected BufError")),
+        }
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush_with(output, FlushCompress::Sync)
+    }
+
+    fn full_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush_with(output, FlushCompress::Full)
+    }
+
+    fn partial_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush_with(output, FlushCompress::Partial)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
@@ -92,4 +193,16 @@ impl Encode for FlateEncoder {
             Status::BufError => Err(Error::new(ErrorKind::Other, "unexpected BufError")),
         }
     }
+
+    fn reset(&mut self) -> Result<()> {
+        // zlib-rs doesn't fully reset its fastest level part way through a block, so start over
+        // with a fresh stream, which then needs the dictionary loaded again
+        self.compress =
+            Compress::new_with_window_bits(self.level, self.zlib_header, self.window_bits);
+        self.flushed = true;
+        if let Some(dictionary) = &self.dictionary {
+            self.compress.set_dictionary(dictionary)?;
+        }
+        Ok(())
+    }
 }
@@ -1,4 +1,21 @@
 mod decoder;
 mod encoder;
+mod scanner;
 
 pub(crate) use self::{decoder::FlateDecoder, encoder::FlateEncoder};
+
+use std::io::{Error, ErrorKind, Result};
+
+/// The largest window, and the default, as the base-2 logarithm of its size.
+const MAX_WINDOW_BITS: u8 = 15;
+
+fn check_window_bits(window_bits: u8) -> Result<()> {
+    if (9..=MAX_WINDOW_BITS).contains(&window_bits) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("window bits must be within 9-15, got {}", window_bits),
+        ))
+    }
+}
@@ -0,0 +1,476 @@
+use crate::{codec::huffman::Huffman, util::PartialBuffer};
+
+/// The number of literal/length and distance codes that may be used.
+const MAX_LENGTH_CODES: usize = 286;
+const MAX_DISTANCE_CODES: usize = 30;
+
+/// The number of extra bits of the length codes 257 to 285 and of the distance codes.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order the code length code lengths are sent in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The window deflate allows, which raw deflate and gzip streams don't declare.
+const WINDOW_SIZE: u64 = 1 << 15;
+
+#[derive(Debug)]
+enum State {
+    ZlibHeader(PartialBuffer<[u8; 2]>),
+    DictId(PartialBuffer<[u8; 4]>),
+    BlockHeader,
+    StoredHeader,
+    Stored(usize),
+    DynamicHeader,
+    CodeLengthCodes {
+        lengths: usize,
+        distances: usize,
+        count: usize,
+        code_lengths: [u8; 19],
+        read: usize,
+    },
+    CodeLengths {
+        lengths: usize,
+        distances: usize,
+        code: Huffman,
+        read: usize,
+    },
+    Codes,
+    Distance,
+    Done,
+    /// Not something we understand, the decoder will report the error.
+    Invalid,
+}
+
+/// Follows the deflate blocks in the input given to a decoder to count them and track whether
+/// the stream consists only of stored blocks.
+///
+/// Stored blocks carry their length so they can be skipped over, compressed blocks are walked
+/// through symbol by symbol to find their end, without keeping a window to produce the output.
+#[derive(Debug)]
+pub(crate) struct BlockScanner {
+    state: State,
+    /// Whether the current block is the last.
+    last: bool,
+    /// Bits read from the input but not used yet, the next one lowest.
+    bits: u64,
+    bit_count: u32,
+    lengths: [u8; MAX_LENGTH_CODES + MAX_DISTANCE_CODES],
+    literal_code: Option<Huffman>,
+    distance_code: Option<Huffman>,
+    blocks: u64,
+    compressed_seen: bool,
+    window_size: Option<u64>,
+}
+
+impl BlockScanner {
+    pub(crate) fn new(zlib_header: bool) -> Self {
+        Self {
+            state: if zlib_header {
+                State::ZlibHeader(<_>::default())
+            } else {
+                State::BlockHeader
+            },
+            last: false,
+            bits: 0,
+            bit_count: 0,
+            lengths: [0; MAX_LENGTH_CODES + MAX_DISTANCE_CODES],
+            literal_code: None,
+            distance_code: None,
+            blocks: 0,
+            compressed_seen: false,
+            window_size: if zlib_header { None } else { Some(WINDOW_SIZE) },
+        }
+    }
+
+    /// Returns `Some(true)` once the final block has been seen and every block was stored,
+    /// `Some(false)` once any compressed block has been seen, and `None` before either is known.
+    pub(crate) fn stored(&self) -> Option<bool> {
+        if self.compressed_seen {
+            Some(false)
+        } else if let State::Done = self.state {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of complete blocks seen.
+    pub(crate) fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the size of the window, for zlib once its header has been seen.
+    pub(crate) fn window_size(&self) -> Option<u64> {
+        self.window_size
+    }
+
+    /// Takes input bytes until there are at least `count` bits, returns whether there are.
+    fn need(&mut self, input: &mut PartialBuffer<&[u8]>, count: u32) -> bool {
+        while self.bit_count < count {
+            match input.unwritten().first() {
+                Some(&byte) => {
+                    input.advance(1);
+                    self.bits |= u64::from(byte) << self.bit_count;
+                    self.bit_count += 8;
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Takes as many input bytes as fit in the bits, at least 57 bits unless the input runs out,
+    /// more than any symbol and its extra bits need.
+    fn refill(&mut self, input: &mut PartialBuffer<&[u8]>) {
+        let len = std::cmp::min(input.unwritten().len(), (64 - self.bit_count) as usize / 8);
+        for &byte in &input.unwritten()[..len] {
+            self.bits |= u64::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+        input.advance(len);
+    }
+
+    fn consume(&mut self, count: u32) -> u64 {
+        let value = self.bits & ((1 << count) - 1);
+        self.bits >>= count;
+        self.bit_count -= count;
+        value
+    }
+
+    /// Decodes a symbol and the extra bits `extra` says it is followed by, only consuming them
+    /// once all are available. Returns `Err` for an invalid code or symbol.
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        code: &Huffman,
+        extra: impl Fn(u16) -> Option<u32>,
+    ) -> Result<Option<(u16, u64)>, ()> {
+        // No symbol and its extra bits take more than 28 bits
+        if self.bit_count < 28 {
+            self.refill(input);
+        }
+        match code.decode(self.bits, self.bit_count).map_err(drop)? {
+            Some((symbol, len)) => {
+                let extra = extra(symbol).ok_or(())?;
+                if len + extra > self.bit_count {
+                    return Ok(None);
+                }
+                self.consume(len);
+                Ok(Some((symbol, self.consume(extra))))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Walks through the symbols of a compressed block, starting with a distance if `distance`
+    /// is set, until the block ends or the input runs out.
+    fn codes(&mut self, input: &mut PartialBuffer<&[u8]>, mut distance: bool) -> State {
+        let literal_code = self.literal_code.take().unwrap();
+        let distance_code = self.distance_code.take().unwrap();
+        let length_extra = |symbol: u16| match symbol {
+            0..=256 => Some(0),
+            257..=285 => Some(LENGTH_EXTRA[symbol as usize - 257].into()),
+            _ => None,
+        };
+        let distance_extra = |symbol: u16| DISTANCE_EXTRA.get(symbol as usize).map(|&e| e.into());
+
+        let state = loop {
+            if !distance {
+                match self.decode(input, &literal_code, length_extra) {
+                    Ok(Some((0..=255, _))) => continue,
+                    Ok(Some((256, _))) => break self.end_block(),
+                    Ok(Some(_)) => {}
+                    Ok(None) => break State::Codes,
+                    Err(()) => break State::Invalid,
+                }
+            }
+            distance = false;
+            match self.decode(input, &distance_code, distance_extra) {
+                Ok(Some(_)) => {}
+                Ok(None) => break State::Distance,
+                Err(()) => break State::Invalid,
+            }
+        };
+
+        self.literal_code = Some(literal_code);
+        self.distance_code = Some(distance_code);
+        state
+    }
+
+    fn end_block(&mut self) -> State {
+        self.blocks += 1;
+        if self.last {
+            State::Done
+        } else {
+            State::BlockHeader
+        }
+    }
+
+    fn fixed_codes(&mut self) -> State {
+        let mut lengths = [0; 288];
+        lengths[..144].iter_mut().for_each(|len| *len = 8);
+        lengths[144..256].iter_mut().for_each(|len| *len = 9);
+        lengths[256..280].iter_mut().for_each(|len| *len = 7);
+        lengths[280..].iter_mut().for_each(|len| *len = 8);
+        self.literal_code = Huffman::new(&lengths, false).ok();
+        self.distance_code = Huffman::new(&[5; 32], false).ok();
+        State::Codes
+    }
+
+    fn dynamic_codes(&mut self, lengths: usize, distances: usize) -> State {
+        if self.lengths[256] == 0 {
+            return State::Invalid;
+        }
+        self.literal_code = Huffman::new(&self.lengths[..lengths], true).ok();
+        self.distance_code = Huffman::new(
+            &self.lengths[MAX_LENGTH_CODES..MAX_LENGTH_CODES + distances],
+            true,
+        )
+        .ok();
+        if self.literal_code.is_some() && self.distance_code.is_some() {
+            State::Codes
+        } else {
+            State::Invalid
+        }
+    }
+
+    pub(crate) fn input(&mut self, input: &mut PartialBuffer<&[u8]>) {
+        loop {
+            self.state = match std::mem::replace(&mut self.state, State::Invalid) {
+                State::ZlibHeader(mut data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        self.state = State::ZlibHeader(data);
+                        return;
+                    }
+
+                    // The CINFO field is the base-2 logarithm of the window size, less 8
+                    let [cmf, flags] = data.into_inner();
+                    self.window_size = Some(1 << ((cmf >> 4) + 8));
+
+                    // The FDICT flag means the dictionary id follows the header
+                    if flags & 0b0010_0000 != 0 {
+                        State::DictId(<_>::default())
+                    } else {
+                        State::BlockHeader
+                    }
+                }
+
+                State::DictId(mut data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        self.state = State::DictId(data);
+                        return;
+                    }
+
+                    State::BlockHeader
+                }
+
+                State::BlockHeader => {
+                    if !self.need(input, 3) {
+                        self.state = State::BlockHeader;
+                        return;
+                    }
+
+                    self.last = self.consume(1) == 1;
+                    match self.consume(2) {
+                        0 => State::StoredHeader,
+                        1 => {
+                            self.compressed_seen = true;
+                            self.fixed_codes()
+                        }
+                        2 => {
+                            self.compressed_seen = true;
+                            State::DynamicHeader
+                        }
+                        _ => State::Invalid,
+                    }
+                }
+
+                State::StoredHeader => {
+                    // Stored blocks start on a byte boundary, the bits we still hold up to it are
+                    // padding
+                    self.consume(self.bit_count % 8);
+                    if !self.need(input, 32) {
+                        self.state = State::StoredHeader;
+                        return;
+                    }
+
+                    let len = self.consume(16);
+                    self.consume(16);
+                    State::Stored(len as usize)
+                }
+
+                State::Stored(remaining) => {
+                    // Whole bytes may already have been taken into the bits while reading the
+                    // header
+                    let buffered = std::cmp::min(remaining, self.bit_count as usize / 8);
+                    for _ in 0..buffered {
+                        self.consume(8);
+                    }
+                    let len = std::cmp::min(remaining - buffered, input.unwritten().len());
+                    input.advance(len);
+                    let remaining = remaining - buffered - len;
+
+                    if remaining > 0 {
+                        self.state = State::Stored(remaining);
+                        return;
+                    }
+
+                    self.end_block()
+                }
+
+                State::DynamicHeader => {
+                    if !self.need(input, 14) {
+                        self.state = State::DynamicHeader;
+                        return;
+                    }
+
+                    let lengths = self.consume(5) as usize + 257;
+                    let distances = self.consume(5) as usize + 1;
+                    let count = self.consume(4) as usize + 4;
+                    if lengths > MAX_LENGTH_CODES || distances > MAX_DISTANCE_CODES {
+                        State::Invalid
+                    } else {
+                        self.lengths = [0; MAX_LENGTH_CODES + MAX_DISTANCE_CODES];
+                        State::CodeLengthCodes {
+                            lengths,
+                            distances,
+                            count,
+                            code_lengths: [0; 19],
+                            read: 0,
+                        }
+                    }
+                }
+
+                State::CodeLengthCodes {
+                    lengths,
+                    distances,
+                    count,
+                    mut code_lengths,
+                    read,
+                } => {
+                    if read == count {
+                        match Huffman::new(&code_lengths, false) {
+                            Ok(code) => State::CodeLengths {
+                                lengths,
+                                distances,
+                                code,
+                                read: 0,
+                            },
+                            Err(_) => State::Invalid,
+                        }
+                    } else if self.need(input, 3) {
+                        code_lengths[CODE_LENGTH_ORDER[read]] = self.consume(3) as u8;
+                        State::CodeLengthCodes {
+                            lengths,
+                            distances,
+                            count,
+                            code_lengths,
+                            read: read + 1,
+                        }
+                    } else {
+                        self.state = State::CodeLengthCodes {
+                            lengths,
+                            distances,
+                            count,
+                            code_lengths,
+                            read,
+                        };
+                        return;
+                    }
+                }
+
+                State::CodeLengths {
+                    lengths,
+                    distances,
+                    code,
+                    read,
+                } => {
+                    if read == lengths + distances {
+                        self.dynamic_codes(lengths, distances)
+                    } else {
+                        let extra = |symbol| match symbol {
+                            0..=15 => Some(0),
+                            16 => Some(2),
+                            17 => Some(3),
+                            18 => Some(7),
+                            _ => None,
+                        };
+                        let (symbol, extra) = match self.decode(input, &code, extra) {
+                            Ok(Some(decoded)) => decoded,
+                            Ok(None) => {
+                                self.state = State::CodeLengths {
+                                    lengths,
+                                    distances,
+                                    code,
+                                    read,
+                                };
+                                return;
+                            }
+                            Err(()) => {
+                                self.state = State::Invalid;
+                                return;
+                            }
+                        };
+                        // The distance code lengths follow straight after the used length codes
+                        let index = |i| {
+                            if i < lengths {
+                                i
+                            } else {
+                                MAX_LENGTH_CODES + i - lengths
+                            }
+                        };
+                        let (len, repeat) = match symbol {
+                            0..=15 => (symbol as u8, 1),
+                            16 if read > 0 => (self.lengths[index(read - 1)], 3 + extra as usize),
+                            17 => (0, 3 + extra as usize),
+                            18 => (0, 11 + extra as usize),
+                            // A repeat with no first length
+                            _ => (0, usize::MAX),
+                        };
+                        if repeat > lengths + distances - read {
+                            State::Invalid
+                        } else {
+                            for i in read..read + repeat {
+                                self.lengths[index(i)] = len;
+                            }
+                            State::CodeLengths {
+                                lengths,
+                                distances,
+                                code,
+                                read: read + repeat,
+                            }
+                        }
+                    }
+                }
+
+                state @ State::Codes | state @ State::Distance => {
+                    match self.codes(input, matches!(state, State::Distance)) {
+                        // The input ran out part way through the block
+                        state @ State::Codes | state @ State::Distance => {
+                            self.state = state;
+                            return;
+                        }
+                        state => state,
+                    }
+                }
+
+                state @ State::Done | state @ State::Invalid => {
+                    self.state = state;
+                    return;
+                }
+            };
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
     codec::{
-        gzip::header::{self, Header},
+        gzip::header::{self, GzipHeader},
         Decode,
     },
     util::PartialBuffer,
+    CompressionError, Format, Stats,
 };
 use std::io::{Error, ErrorKind, Result};
 
@@ -14,7 +15,8 @@ enum State {
     Header(header::Parser),
     Decoding,
     Footer(PartialBuffer<Vec<u8>>),
-    Done,
+    /// Between members, the stream can end cleanly here or another member can follow.
+    Next,
     Invalid,
 }
 
@@ -23,19 +25,87 @@ pub struct GzipDecoder {
     inner: crate::codec::FlateDecoder,
     crc: Crc,
     state: State,
-    header: Header,
+    header: Option<GzipHeader>,
+    members: u64,
+    /// When set the member has no trailer, and this is the length it must decompress to instead.
+    trailerless_len: Option<u64>,
+    /// Whether members following the first are decoded too.
+    multiple_members: bool,
+    /// The length of the member decompressed so far, the CRC only tracks it modulo 2^32.
+    len: u64,
+    /// The CRC recorded in the member's trailer, once it has been read.
+    declared_crc: Option<u32>,
+    /// Whether the first member was made up of only stored blocks, kept once `inner` has been
+    /// replaced to decode the following members.
+    first_stored: Option<bool>,
+    /// The blocks of the members before the one `inner` is decoding.
+    blocks: u64,
 }
 
 impl GzipDecoder {
+    pub(crate) const MAGIC: &'static [u8] = &[0x1f, 0x8b];
+
     pub(crate) fn new() -> Self {
         Self {
             inner: crate::codec::FlateDecoder::new(false),
             crc: Crc::new(),
             state: State::Header(header::Parser::default()),
-            header: Header::default(),
+            header: None,
+            members: 0,
+            trailerless_len: None,
+            multiple_members: true,
+            len: 0,
+            declared_crc: None,
+            first_stored: None,
+            blocks: 0,
         }
     }
 
+    pub(crate) fn set_trailerless(&mut self, len: u64) {
+        self.trailerless_len = Some(len);
+    }
+
+    pub(crate) fn set_multiple_members(&mut self, enabled: bool) {
+        self.multiple_members = enabled;
+    }
+
+    /// Whether the stream ends with the first member, instead of only at the end of the input.
+    fn single_member(&self) -> bool {
+        self.trailerless_len.is_some() || !self.multiple_members
+    }
+
+    /// Whether the first member was made up of only stored blocks.
+    pub(crate) fn stored(&self) -> Option<bool> {
+        self.first_stored.or_else(|| self.inner.stored())
+    }
+
+    /// The header of the member being decoded, or of the last one once the stream has ended.
+    pub(crate) fn header(&self) -> Option<&GzipHeader> {
+        self.header.as_ref()
+    }
+
+    /// The CRC of the member decompressed so far.
+    pub(crate) fn checksum(&self) -> u32 {
+        self.crc.sum()
+    }
+
+    pub(crate) fn declared_checksum(&self) -> Option<u32> {
+        self.declared_crc
+    }
+
+    /// Finishes the deflate stream, reporting it ending early as the gzip member doing so.
+    fn finish_inner(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.finish(output).map_err(|err| {
+            match err.get_ref().and_then(|err| err.downcast_ref()) {
+                Some(CompressionError::UnexpectedEof { .. }) => CompressionError::UnexpectedEof {
+                    format: Format::Gzip,
+                }
+                .into(),
+                _ => err,
+            }
+        })
+    }
+
     fn check_footer(&mut self, input: &[u8]) -> Result<()> {
         if input.len() < 8 {
             return Err(Error::new(
@@ -44,14 +114,18 @@ impl GzipDecoder {
             ));
         }
 
-        let crc = self.crc.sum().to_le_bytes();
+        let declared = u32::from_le_bytes([input[0], input[1], input[2], input[3]]);
+        self.declared_crc = Some(declared);
+
         let bytes_read = self.crc.amount().to_le_bytes();
 
-        if crc != input[0..4] {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "CRC computed does not match",
-            ));
+        if self.crc.sum() != declared {
+            return Err(CompressionError::ChecksumMismatch {
+                format: Format::Gzip,
+                expected: declared,
+                found: self.crc.sum(),
+            }
+            .into());
         }
 
         if bytes_read != input[4..8] {
@@ -73,12 +147,12 @@ impl GzipDecoder {
             &mut PartialBuffer<&[u8]>,
             &mut PartialBuffer<&mut [u8]>,
         ) -> Result<bool>,
-    ) -> Result<bool> {
+    ) -> Result<()> {
         loop {
             self.state = match std::mem::replace(&mut self.state, State::Invalid) {
                 State::Header(mut parser) => {
                     if let Some(header) = parser.input(input)? {
-                        self.header = header;
+                        self.header = Some(header.info);
                         State::Decoding
                     } else {
                         State::Header(parser)
@@ -89,10 +163,23 @@ impl GzipDecoder {
                     let prior = output.written().len();
                     let done = inner(self, input, output)?;
                     self.crc.update(&output.written()[prior..]);
-                    if done {
-                        State::Footer(vec![0; 8].into())
-                    } else {
-                        State::Decoding
+                    self.len += (output.written().len() - prior) as u64;
+                    match (done, self.trailerless_len) {
+                        (false, _) => State::Decoding,
+                        (true, None) => State::Footer(vec![0; 8].into()),
+                        (true, Some(len)) => {
+                            if self.len != len {
+                                return Err(Error::new(
+                                    ErrorKind::InvalidData,
+                                    format!(
+                                        "decompressed {} bytes but the expected length is {}",
+                                        self.len, len
+                                    ),
+                                ));
+                            }
+                            self.members += 1;
+                            State::Next
+                        }
                     }
                 }
 
@@ -101,22 +188,44 @@ impl GzipDecoder {
 
                     if footer.unwritten().is_empty() {
                         self.check_footer(footer.written())?;
-                        State::Done
+                        self.members += 1;
+                        State::Next
                     } else {
                         State::Footer(footer.take())
                     }
                 }
 
-                State::Done => State::Done,
+                State::Next => {
+                    if self.single_member() {
+                        // The member is the whole stream, what follows it is left for the caller
+                        self.state = State::Next;
+                        return Ok(());
+                    } else if input.unwritten().is_empty() {
+                        State::Next
+                    } else {
+                        if self.first_stored.is_none() {
+                            self.first_stored = self.inner.stored();
+                        }
+                        let mut stats = Stats::default();
+                        self.inner.stats(&mut stats);
+                        self.blocks += stats.blocks.unwrap_or(0);
+                        self.inner = crate::codec::FlateDecoder::new(false);
+                        self.crc = Crc::new();
+                        self.len = 0;
+                        self.declared_crc = None;
+                        State::Header(header::Parser::default())
+                    }
+                }
+
                 State::Invalid => panic!("Reached invalid state"),
             };
 
-            if let State::Done = self.state {
-                return Ok(true);
-            }
-
-            if input.unwritten().is_empty() || output.unwritten().is_empty() {
-                return Ok(false);
+            // Stopping at the end of each member lets the caller see where the next one starts
+            if input.unwritten().is_empty()
+                || output.unwritten().is_empty()
+                || matches!(self.state, State::Next)
+            {
+                return Ok(());
             }
         }
     }
@@ -130,13 +239,17 @@ impl Decode for GzipDecoder {
     ) -> Result<bool> {
         self.process(input, output, |this, input, output| {
             this.inner.decode(input, output)
-        })
+        })?;
+
+        // Another member may follow, so the end of the stream is only known once the input ends,
+        // unless only one member is expected
+        Ok(self.single_member() && matches!(self.state, State::Next))
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
         loop {
             match self.state {
-                State::Header(_) | State::Footer(_) | State::Done => return Ok(true),
+                State::Header(_) | State::Footer(_) | State::Next => return Ok(true),
 
                 State::Decoding => {
                     let prior = output.written().len();
@@ -159,7 +272,27 @@ impl Decode for GzipDecoder {
         self.process(
             &mut PartialBuffer::new(&[][..]),
             output,
-            |this, _, output| this.inner.finish(output),
-        )
+            |this, _, output| this.finish_inner(output),
+        )?;
+
+        match self.state {
+            State::Next => Ok(true),
+            State::Header(_) | State::Footer(_) => Err(CompressionError::UnexpectedEof {
+                format: Format::Gzip,
+            }
+            .into()),
+            _ => Ok(false),
+        }
+    }
+
+    fn between_frames(&self) -> bool {
+        matches!(self.state, State::Next)
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        self.inner.stats(stats);
+        stats.blocks = stats.blocks.map(|blocks| self.blocks + blocks);
+        stats.frames = Some(self.members);
+        stats.checksum = Some(self.crc.sum());
     }
 }
@@ -1,7 +1,7 @@
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{codec::Encode, util::PartialBuffer, Stats};
 use std::io::Result;
 
-use flate2::{Compression, Crc};
+use flate2::{Compression, Crc, FlushCompress};
 
 #[derive(Debug)]
 enum State {
@@ -17,6 +17,8 @@ pub struct GzipEncoder {
     inner: crate::codec::FlateEncoder,
     crc: Crc,
     state: State,
+    level: Compression,
+    trailer: bool,
 }
 
 fn header(level: Compression) -> Vec<u8> {
@@ -37,6 +39,64 @@ impl GzipEncoder {
             inner: crate::codec::FlateEncoder::new(level, false),
             crc: Crc::new(),
             state: State::Header(header(level).into()),
+            level,
+            trailer: true,
+        }
+    }
+
+    pub(crate) fn set_trailer(&mut self, trailer: bool) {
+        self.trailer = trailer;
+    }
+
+    fn flush_with(
+        &mut self,
+        output: &mut PartialBuffer<&mut [u8]>,
+        flush: FlushCompress,
+    ) -> Result<bool> {
+        loop {
+            let (done, state) = match std::mem::replace(&mut self.state, State::Invalid) {
+                State::Header(mut header) => {
+                    output.copy_unwritten_from(&mut header);
+
+                    if header.unwritten().is_empty() {
+                        (false, State::Encoding)
+                    } else {
+                        (false, State::Header(header))
+                    }
+                }
+
+                State::Encoding => {
+                    let done = match flush {
+                        FlushCompress::Full => self.inner.full_flush(output)?,
+                        FlushCompress::Partial => self.inner.partial_flush(output)?,
+                        _ => self.inner.flush(output)?,
+                    };
+                    (done, State::Encoding)
+                }
+
+                State::Footer(mut footer) => {
+                    output.copy_unwritten_from(&mut footer);
+
+                    if footer.unwritten().is_empty() {
+                        (true, State::Done)
+                    } else {
+                        (false, State::Footer(footer))
+                    }
+                }
+
+                State::Done => (true, State::Done),
+                State::Invalid => panic!("Reached invalid state"),
+            };
+
+            self.state = state;
+
+            if done {
+                return Ok(true);
+            }
+
+            if output.unwritten().is_empty() {
+                return Ok(false);
+            }
         }
     }
 
@@ -87,44 +147,15 @@ impl Encode for GzipEncoder {
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        loop {
-            let (done, state) = match std::mem::replace(&mut self.state, State::Invalid) {
-                State::Header(mut header) => {
-                    output.copy_unwritten_from(&mut header);
-
-                    if header.unwritten().is_empty() {
-                        (false, State::Encoding)
-                    } else {
-                        (false, State::Header(header))
-                    }
-                }
-
-                State::Encoding => (self.inner.flush(output)?, State::Encoding),
-
-                State::Footer(mut footer) => {
-                    output.copy_unwritten_from(&mut footer);
-
-                    if footer.unwritten().is_empty() {
-                        (true, State::Done)
-                    } else {
-                        (false, State::Footer(footer))
-                    }
-                }
-
-                State::Done => (true, State::Done),
-                State::Invalid => panic!("Reached invalid state"),
-            };
-
-            self.state = state;
+        self.flush_with(output, FlushCompress::Sync)
+    }
 
-            if done {
-                return Ok(true);
-            }
+    fn full_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush_with(output, FlushCompress::Full)
+    }
 
-            if output.unwritten().is_empty() {
-                return Ok(false);
-            }
-        }
+    fn partial_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush_with(output, FlushCompress::Partial)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
@@ -141,10 +172,12 @@ impl Encode for GzipEncoder {
                 }
 
                 State::Encoding => {
-                    if self.inner.finish(output)? {
+                    if !self.inner.finish(output)? {
+                        State::Encoding
+                    } else if self.trailer {
                         State::Footer(self.footer().into())
                     } else {
-                        State::Encoding
+                        State::Done
                     }
                 }
 
@@ -171,4 +204,15 @@ impl Encode for GzipEncoder {
             }
         }
     }
+
+    fn reset(&mut self) -> Result<()> {
+        self.inner.reset()?;
+        self.crc.reset();
+        self.state = State::Header(header(self.level).into());
+        Ok(())
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.checksum = Some(self.crc.sum());
+    }
 }
@@ -1,5 +1,7 @@
-use crate::util::PartialBuffer;
-use std::io::{Error, ErrorKind, Result};
+use crate::{util::PartialBuffer, CompressionError, Format};
+use std::io::Result;
+
+use flate2::Crc;
 
 #[derive(Debug, Default)]
 struct Flags {
@@ -10,9 +12,44 @@ struct Flags {
     comment: bool,
 }
 
+/// The metadata stored in the header of a gzip member.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GzipHeader {
+    /// The original filename, if one was stored (`FNAME`).
+    pub filename: Option<Vec<u8>>,
+    /// A free-form comment, if one was stored (`FCOMMENT`).
+    pub comment: Option<Vec<u8>>,
+    /// The modification time of the original file as a unix timestamp, or `0` if unknown.
+    pub mtime: u32,
+    /// The extra field, if one was stored (`FEXTRA`).
+    pub extra: Option<Vec<u8>>,
+    /// The filesystem the member was created on, `255` if unknown.
+    pub operating_system: u8,
+}
+
+impl GzipHeader {
+    /// Returns the block size from a BGZF `BC` extra subfield, the length of the whole member
+    /// including its header and footer.
+    pub(crate) fn bgzf_block_size(&self) -> Option<u64> {
+        let mut extra = self.extra.as_deref()?;
+
+        while extra.len() >= 4 {
+            let len = usize::from(u16::from_le_bytes([extra[2], extra[3]]));
+            let data = extra.get(4..4 + len)?;
+            if extra[0..2] == *b"BC" && len == 2 {
+                return Some(u64::from(u16::from_le_bytes([data[0], data[1]])) + 1);
+            }
+            extra = &extra[4 + len..];
+        }
+
+        None
+    }
+}
+
 #[derive(Debug, Default)]
-pub(super) struct Header {
+pub(crate) struct Header {
     flags: Flags,
+    pub(crate) info: GzipHeader,
 }
 
 #[derive(Debug)]
@@ -33,15 +70,19 @@ impl Default for State {
 }
 
 #[derive(Debug, Default)]
-pub(super) struct Parser {
+pub(crate) struct Parser {
     state: State,
     header: Header,
+    /// Covers every header byte before the optional header CRC16 (`FHCRC`).
+    crc: Crc,
 }
 
 impl Header {
     fn parse(input: &[u8; 10]) -> Result<Self> {
         if input[0..3] != [0x1f, 0x8b, 0x08] {
-            return Err(Error::new(ErrorKind::InvalidData, "Invalid gzip header"));
+            return Err(
+                CompressionError::invalid_header(Format::Gzip, "wrong magic number").into(),
+            );
         }
 
         let flag = input[3];
@@ -54,19 +95,28 @@ impl Header {
             comment: (flag & 0b0001_0000) != 0,
         };
 
-        Ok(Header { flags })
+        Ok(Header {
+            flags,
+            info: GzipHeader {
+                mtime: u32::from_le_bytes([input[4], input[5], input[6], input[7]]),
+                operating_system: input[9],
+                ..GzipHeader::default()
+            },
+        })
     }
 }
 
 impl Parser {
-    pub(super) fn input(&mut self, input: &mut PartialBuffer<&[u8]>) -> Result<Option<Header>> {
+    pub(crate) fn input(&mut self, input: &mut PartialBuffer<&[u8]>) -> Result<Option<Header>> {
         loop {
             match &mut self.state {
                 State::Fixed(data) => {
                     data.copy_unwritten_from(input);
 
                     if data.unwritten().is_empty() {
-                        self.header = Header::parse(&data.take().into_inner())?;
+                        let data = data.take().into_inner();
+                        self.crc.update(&data);
+                        self.header = Header::parse(&data)?;
                         self.state = State::ExtraLen(<_>::default());
                     } else {
                         return Ok(None);
@@ -82,7 +132,9 @@ impl Parser {
                     data.copy_unwritten_from(input);
 
                     if data.unwritten().is_empty() {
-                        let len = u16::from_be_bytes(data.take().into_inner());
+                        let data = data.take().into_inner();
+                        self.crc.update(&data);
+                        let len = u16::from_le_bytes(data);
                         self.state = State::Extra(vec![0; usize::from(len)].into());
                     } else {
                         return Ok(None);
@@ -93,6 +145,9 @@ impl Parser {
                     data.copy_unwritten_from(input);
 
                     if data.unwritten().is_empty() {
+                        let data = data.take().into_inner();
+                        self.crc.update(&data);
+                        self.header.info.extra = Some(data);
                         self.state = State::Filename(<_>::default());
                     } else {
                         return Ok(None);
@@ -107,10 +162,13 @@ impl Parser {
 
                     if let Some(len) = memchr::memchr(0, input.unwritten()) {
                         data.extend_from_slice(&input.unwritten()[..len]);
+                        self.crc.update(&input.unwritten()[..=len]);
                         input.advance(len + 1);
+                        self.header.info.filename = Some(std::mem::take(data));
                         self.state = State::Comment(<_>::default());
                     } else {
                         data.extend_from_slice(input.unwritten());
+                        self.crc.update(input.unwritten());
                         input.advance(input.unwritten().len());
                         return Ok(None);
                     }
@@ -124,10 +182,13 @@ impl Parser {
 
                     if let Some(len) = memchr::memchr(0, input.unwritten()) {
                         data.extend_from_slice(&input.unwritten()[..len]);
+                        self.crc.update(&input.unwritten()[..=len]);
                         input.advance(len + 1);
+                        self.header.info.comment = Some(std::mem::take(data));
                         self.state = State::Crc(<_>::default());
                     } else {
                         data.extend_from_slice(input.unwritten());
+                        self.crc.update(input.unwritten());
                         input.advance(input.unwritten().len());
                         return Ok(None);
                     }
@@ -142,6 +203,15 @@ impl Parser {
                     data.copy_unwritten_from(input);
 
                     if data.unwritten().is_empty() {
+                        // The CRC16 is the low half of the CRC32 of the preceding header bytes
+                        let expected = u16::from_le_bytes(data.take().into_inner());
+                        if expected != self.crc.sum() as u16 {
+                            return Err(CompressionError::invalid_header(
+                                Format::Gzip,
+                                "header CRC16 does not match",
+                            )
+                            .into());
+                        }
                         self.state = State::Done;
                         return Ok(Some(std::mem::replace(&mut self.header, Header::default())));
                     } else {
@@ -2,4 +2,7 @@ mod decoder;
 mod encoder;
 mod header;
 
-pub(crate) use self::{decoder::GzipDecoder, encoder::GzipEncoder};
+pub use self::header::GzipHeader;
+pub(crate) use self::{
+    decoder::GzipDecoder, encoder::GzipEncoder, header::Parser as GzipHeaderParser,
+};
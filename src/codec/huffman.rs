@@ -0,0 +1,125 @@
+use std::io::{Error, ErrorKind, Result};
+
+/// The longest code deflate and deflate64 allow.
+const MAX_BITS: usize = 15;
+
+/// Codes up to this long are decoded with a single lookup.
+const FAST_BITS: u32 = 10;
+
+fn invalid_data(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// A canonical Huffman code, decoded a bit at a time from the counts of codes of each length and
+/// the symbols in code order, as in zlib's `puff`.
+///
+/// The codes of up to `FAST_BITS` bits, which most symbols have, are also kept in a table indexed
+/// by the next `FAST_BITS` bits of input.
+#[derive(Debug)]
+pub(crate) struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+    /// The symbol and code length of each code up to `FAST_BITS` long, as `length << 9 | symbol`,
+    /// at every index whose low bits are the code in the order it is read. Zero for no code.
+    fast: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code from the code length of each symbol, zero for unused symbols.
+    ///
+    /// An over-subscribed set of lengths is always an error, an incomplete one is only allowed if
+    /// `incomplete` is set, for the literal/length and distance codes which may have a single code,
+    /// or no codes at all for a block without any matches.
+    pub(crate) fn new(lengths: &[u8], incomplete: bool) -> Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid_data("over-subscribed Huffman code"));
+            }
+        }
+        let used = lengths.len() - counts[0] as usize;
+        if left > 0 && !(incomplete && (used == 0 || (used == 1 && counts[1] == 1))) {
+            return Err(invalid_data("incomplete Huffman code"));
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; used];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        // The codes of each length are consecutive, starting after the shorter ones
+        let mut next = [0u32; MAX_BITS + 1];
+        for len in 2..=MAX_BITS {
+            next[len] = (next[len - 1] + u32::from(counts[len - 1])) << 1;
+        }
+        let mut fast = vec![0; 1 << FAST_BITS];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            let len = u32::from(len);
+            if len == 0 || len > FAST_BITS {
+                continue;
+            }
+            let code = next[len as usize];
+            next[len as usize] += 1;
+            // The code is sent most significant bit first, so it is reversed in the input bits
+            let reversed = code.reverse_bits() >> (32 - len);
+            for index in (reversed..1 << FAST_BITS).step_by(1 << len) {
+                fast[index as usize] = (len << 9) as u16 | symbol as u16;
+            }
+        }
+
+        Ok(Self {
+            counts,
+            symbols,
+            fast,
+        })
+    }
+
+    /// Decodes a symbol from the first `len` bits of `bits`, in the order they were read, returning
+    /// it and the length of its code, or `None` if the code is longer than `len` bits.
+    pub(crate) fn decode(&self, bits: u64, len: u32) -> Result<Option<(u16, u32)>> {
+        let entry = self.fast[(bits & ((1 << FAST_BITS) - 1)) as usize];
+        if entry != 0 {
+            // As no code is the prefix of another, a code whose bits haven't all been read yet
+            // means no shorter code matches either
+            let code_len = u32::from(entry >> 9);
+            return Ok(if code_len <= len {
+                Some((entry & 0x1ff, code_len))
+            } else {
+                None
+            });
+        }
+
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for (bit, &count) in self.counts.iter().enumerate().skip(1) {
+            let bit = bit as u32;
+            if bit > len {
+                return Ok(None);
+            }
+            code |= ((bits >> (bit - 1)) & 1) as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                let symbol = self.symbols[(index + code - first) as usize];
+                return Ok(Some((symbol, bit)));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid Huffman code"))
+    }
+}
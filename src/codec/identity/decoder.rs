@@ -0,0 +1,32 @@
+use crate::{codec::Decode, util::PartialBuffer};
+use std::io::Result;
+
+#[derive(Debug)]
+pub struct IdentityDecoder;
+
+impl IdentityDecoder {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Decode for IdentityDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        output.copy_unwritten_from(input);
+
+        // There is no end marker, the stream only ends with the end of the input
+        Ok(false)
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn finish(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+}
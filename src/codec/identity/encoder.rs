@@ -0,0 +1,34 @@
+use crate::{codec::Encode, util::PartialBuffer};
+use std::io::Result;
+
+#[derive(Debug)]
+pub struct IdentityEncoder;
+
+impl IdentityEncoder {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl Encode for IdentityEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        output.copy_unwritten_from(input);
+        Ok(())
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn finish(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{codec::Decode, util::PartialBuffer, BackendError, CompressionError, Format};
+use std::{fmt, io::Result};
+
+use xz2::stream::{Action, Status, Stream};
+
+pub struct LiblzmaDecoder {
+    stream: Stream,
+    /// The format the stream decodes, for reporting errors.
+    format: Format,
+    done: bool,
+}
+
+impl fmt::Debug for LiblzmaDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LiblzmaDecoder {{format: {}, total_in: {}, total_out: {}}}",
+            self.format,
+            self.stream.total_in(),
+            self.stream.total_out()
+        )
+    }
+}
+
+impl LiblzmaDecoder {
+    pub(crate) fn new(stream: Stream, format: Format) -> Self {
+        Self {
+            stream,
+            format,
+            done: false,
+        }
+    }
+
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+        action: Action,
+    ) -> Result<Status> {
+        let prior_in = self.stream.total_in();
+        let prior_out = self.stream.total_out();
+
+        let status = self
+            .stream
+            .process(input.unwritten(), output.unwritten_mut(), action)
+            .map_err(|err| {
+                // xz2 drops liblzma's return codes, so they are recovered from the variants
+                let code = match err {
+                    xz2::stream::Error::NoCheck => 2,
+                    xz2::stream::Error::UnsupportedCheck => 3,
+                    xz2::stream::Error::Mem => 5,
+                    xz2::stream::Error::MemLimit => 6,
+                    xz2::stream::Error::Format => 7,
+                    xz2::stream::Error::Options => 8,
+                    xz2::stream::Error::Data => 9,
+                    xz2::stream::Error::Program => 11,
+                };
+                BackendError::new(self.format, Some(code), err.to_string())
+            })?;
+
+        input.advance((self.stream.total_in() - prior_in) as usize);
+        output.advance((self.stream.total_out() - prior_out) as usize);
+
+        if status == Status::StreamEnd {
+            self.done = true;
+        }
+
+        Ok(status)
+    }
+}
+
+impl Decode for LiblzmaDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        Ok(self.decode(input, output, Action::Run)? == Status::StreamEnd)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if self.done {
+            return Ok(true);
+        }
+
+        self.decode(&mut PartialBuffer::new(&[][..]), output, Action::Run)?;
+
+        Ok(!output.unwritten().is_empty())
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if self.done {
+            return Ok(true);
+        }
+
+        match self.decode(&mut PartialBuffer::new(&[][..]), output, Action::Finish)? {
+            Status::StreamEnd => Ok(true),
+            Status::Ok | Status::GetCheck => Ok(false),
+            // Returned once no more progress can be made without more input
+            Status::MemNeeded => Err(CompressionError::UnexpectedEof {
+                format: self.format,
+            }
+            .into()),
+        }
+    }
+}
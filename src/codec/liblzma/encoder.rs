@@ -0,0 +1,102 @@
+use crate::{codec::Encode, util::PartialBuffer};
+use std::{fmt, io::Result};
+
+use xz2::stream::{Action, Status, Stream};
+
+pub struct LiblzmaEncoder {
+    stream: Stream,
+    /// Creates the stream, kept to recreate it as liblzma streams can't be reset.
+    create: Box<dyn Fn() -> Stream + Send + Sync>,
+    preset: u32,
+    /// Whether a flush ends the block with `LZMA_FULL_FLUSH`, for the multithreaded encoder which
+    /// doesn't support `LZMA_SYNC_FLUSH`.
+    full_flush: bool,
+}
+
+impl fmt::Debug for LiblzmaEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LiblzmaEncoder {{preset: {}, total_in: {}, total_out: {}}}",
+            self.preset,
+            self.stream.total_in(),
+            self.stream.total_out()
+        )
+    }
+}
+
+impl LiblzmaEncoder {
+    pub(crate) fn new(create: fn(u32) -> Stream, preset: u32) -> Self {
+        Self::with_create(move || create(preset), preset, false)
+    }
+
+    /// Creates the encoder with a stream configured beyond its preset, `preset` is only recorded
+    /// for `Debug`.
+    pub(crate) fn with_create(
+        create: impl Fn() -> Stream + Send + Sync + 'static,
+        preset: u32,
+        full_flush: bool,
+    ) -> Self {
+        Self {
+            stream: create(),
+            create: Box::new(create),
+            preset,
+            full_flush,
+        }
+    }
+
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+        action: Action,
+    ) -> Result<Status> {
+        let prior_in = self.stream.total_in();
+        let prior_out = self.stream.total_out();
+
+        let status = self
+            .stream
+            .process(input.unwritten(), output.unwritten_mut(), action)?;
+
+        input.advance((self.stream.total_in() - prior_in) as usize);
+        output.advance((self.stream.total_out() - prior_out) as usize);
+
+        Ok(status)
+    }
+}
+
+impl Encode for LiblzmaEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        self.encode(input, output, Action::Run).map(drop)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        let action = if self.full_flush {
+            Action::FullFlush
+        } else {
+            Action::SyncFlush
+        };
+
+        // liblzma reports the end of a flush as the end of the stream
+        match self.encode(&mut PartialBuffer::new(&[][..]), output, action)? {
+            Status::StreamEnd => Ok(true),
+            Status::Ok | Status::GetCheck | Status::MemNeeded => Ok(false),
+        }
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        match self.encode(&mut PartialBuffer::new(&[][..]), output, Action::Finish)? {
+            Status::StreamEnd => Ok(true),
+            Status::Ok | Status::GetCheck | Status::MemNeeded => Ok(false),
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.stream = (self.create)();
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::io::Result;
+
+use crate::{
+    budget::{MemoryBudget, Reservation},
+    codec::Decode,
+    error::{LimitExceeded, StepLimitExceeded},
+    util::PartialBuffer,
+    Stats,
+};
+
+/// Wraps a decoder to cap the total amount of output it may produce, and the number of steps it
+/// may take to produce it.
+///
+/// The output the inner decoder is given is cut down to what remains of the limit, so it can never
+/// write past it. Once the limit is reached it is given a scratch byte instead, if the stream has
+/// more data to produce it lands there and the limit is reported as exceeded, without any of it
+/// reaching the caller.
+///
+/// Each call into the inner decoder is a step. The work done in one is bounded by the size of the
+/// input and output buffers it is given, so the number of them is a proxy for the CPU time spent,
+/// which can be large for crafted inputs even when they produce little output.
+///
+/// It also holds the decoder's share of a [`MemoryBudget`], the owner tells it how much memory its
+/// buffers take and it is reserved before the next step.
+#[derive(Debug)]
+pub struct Limited<D> {
+    inner: D,
+    limit: Option<u64>,
+    produced: u64,
+    step_limit: Option<u64>,
+    steps: u64,
+    reservation: Option<Reservation>,
+    memory: usize,
+}
+
+impl<D: Decode> Limited<D> {
+    pub(crate) fn new(inner: D) -> Self {
+        Self {
+            inner,
+            limit: None,
+            produced: 0,
+            step_limit: None,
+            steps: 0,
+            reservation: None,
+            memory: 0,
+        }
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: u64) {
+        self.limit = Some(limit);
+    }
+
+    pub(crate) fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = Some(limit);
+    }
+
+    pub(crate) fn set_memory_budget(&mut self, budget: &MemoryBudget) {
+        self.reservation = Some(Reservation::new(budget.clone()));
+    }
+
+    /// Sets the number of bytes the owner's buffers take, to reserve from the budget.
+    pub(crate) fn set_memory(&mut self, bytes: usize) {
+        self.memory = bytes;
+    }
+
+    pub(crate) fn get_ref(&self) -> &D {
+        &self.inner
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    fn run(
+        &mut self,
+        output: &mut PartialBuffer<&mut [u8]>,
+        f: impl FnOnce(&mut D, &mut PartialBuffer<&mut [u8]>) -> Result<bool>,
+    ) -> Result<bool> {
+        if let Some(reservation) = &mut self.reservation {
+            if reservation.bytes() != self.memory {
+                reservation.resize(self.memory)?;
+            }
+        }
+
+        if let Some(step_limit) = self.step_limit {
+            if self.steps >= step_limit {
+                return Err(StepLimitExceeded::new(step_limit).into());
+            }
+        }
+        self.steps += 1;
+
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return f(&mut self.inner, output),
+        };
+
+        let remaining = limit - self.produced;
+        if remaining == 0 {
+            let mut scratch = [0; 1];
+            let mut scratch = PartialBuffer::new(&mut scratch[..]);
+            let done = f(&mut self.inner, &mut scratch)?;
+            if !scratch.written().is_empty() {
+                return Err(LimitExceeded::new(limit).into());
+            }
+            return Ok(done);
+        }
+
+        let len = std::cmp::min(output.unwritten().len() as u64, remaining) as usize;
+        let mut window = PartialBuffer::new(&mut output.unwritten_mut()[..len]);
+        let done = f(&mut self.inner, &mut window)?;
+        let written = window.written().len();
+        output.advance(written);
+        self.produced += written as u64;
+        Ok(done)
+    }
+}
+
+impl<D: Decode> Decode for Limited<D> {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        self.run(output, |inner, output| inner.decode(input, output))
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.run(output, |inner, output| inner.flush(output))
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.run(output, |inner, output| inner.finish(output))
+    }
+
+    fn between_frames(&self) -> bool {
+        self.inner.between_frames()
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.steps = Some(self.steps);
+        self.inner.stats(stats)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{codec::Decode, util::PartialBuffer};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+};
+
+#[derive(Debug)]
+enum State {
+    /// Collecting the input, as a block has no end marker so it ends with the input.
+    Reading(Vec<u8>),
+    Writing(PartialBuffer<Vec<u8>>),
+}
+
+#[derive(Debug)]
+pub struct Lz4BlockDecoder {
+    decompressed_size: usize,
+    state: State,
+}
+
+impl Lz4BlockDecoder {
+    pub(crate) fn new(decompressed_size: usize) -> Self {
+        Self {
+            decompressed_size,
+            state: State::Reading(Vec::new()),
+        }
+    }
+
+    /// The longest a block decompressing to `decompressed_size` bytes can be, lz4's
+    /// `LZ4_compressBound`, for incompressible input.
+    fn max_compressed_size(&self) -> usize {
+        let size = self.decompressed_size;
+        size.saturating_add(size / 255).saturating_add(16)
+    }
+}
+
+impl Decode for Lz4BlockDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        _output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        let max = self.max_compressed_size();
+        if let State::Reading(buffer) = &mut self.state {
+            // Don't collect an unbounded amount of input that could never decompress to the size
+            if input.unwritten().len() > max - buffer.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "the lz4 block is longer than {} bytes, the most that can decompress to {} \
+                         bytes",
+                        max, self.decompressed_size
+                    ),
+                ));
+            }
+            buffer.extend_from_slice(input.unwritten());
+            input.advance(input.unwritten().len());
+        }
+        Ok(false)
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        // Nothing is decoded before the end of the block
+        Ok(true)
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if let State::Reading(input) = &self.state {
+            let size = i32::try_from(self.decompressed_size).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "the decompressed size of an lz4 block must be less than 2 GiB",
+                )
+            })?;
+            let block = liblz4::block::decompress(input, Some(size)).map_err(|err| {
+                if err.kind() == ErrorKind::InvalidData {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "the lz4 block is invalid or decompresses to more than {} bytes",
+                            self.decompressed_size
+                        ),
+                    )
+                } else {
+                    err
+                }
+            })?;
+            self.state = State::Writing(PartialBuffer::new(block));
+        }
+
+        match &mut self.state {
+            State::Writing(block) => {
+                output.copy_unwritten_from(block);
+                Ok(block.unwritten().is_empty())
+            }
+            State::Reading(_) => unreachable!(),
+        }
+    }
+}
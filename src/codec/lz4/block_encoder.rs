@@ -0,0 +1,88 @@
+use crate::{codec::Encode, util::PartialBuffer};
+use std::{io::Result, mem};
+
+use liblz4::block::CompressionMode;
+
+#[derive(Debug)]
+enum State {
+    /// Collecting the input, as a block can only be compressed all at once.
+    Reading(Vec<u8>),
+    Writing(PartialBuffer<Vec<u8>>),
+}
+
+#[derive(Debug)]
+pub struct Lz4BlockEncoder {
+    level: u32,
+    state: State,
+}
+
+impl Lz4BlockEncoder {
+    pub(crate) fn new(level: u32) -> Self {
+        Self {
+            level,
+            state: State::Reading(Vec::new()),
+        }
+    }
+
+    fn mode(&self) -> Option<CompressionMode> {
+        // The same split as the frame format's levels, lz4 itself treats anything above 12 as 12
+        // but the level has to fit an `i32` to get there
+        if self.level > 2 {
+            let level = std::cmp::min(self.level, 12);
+            Some(CompressionMode::HIGHCOMPRESSION(level as i32))
+        } else {
+            None
+        }
+    }
+}
+
+impl Encode for Lz4BlockEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        _output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        match &mut self.state {
+            State::Reading(buffer) => {
+                buffer.extend_from_slice(input.unwritten());
+                input.advance(input.unwritten().len());
+                Ok(())
+            }
+            State::Writing(_) => panic!("encode after complete"),
+        }
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        // Nothing can be written out before the whole block is known
+        Ok(true)
+    }
+
+    fn can_sync_flush(&self) -> bool {
+        false
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if let State::Reading(input) = &self.state {
+            let block = liblz4::block::compress(input, self.mode(), false)?;
+            self.state = State::Writing(PartialBuffer::new(block));
+        }
+
+        match &mut self.state {
+            State::Writing(block) => {
+                output.copy_unwritten_from(block);
+                Ok(block.unwritten().is_empty())
+            }
+            State::Reading(_) => unreachable!(),
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        let mut buffer = match mem::replace(&mut self.state, State::Reading(Vec::new())) {
+            State::Reading(buffer) => buffer,
+            State::Writing(block) => block.into_inner(),
+        };
+        buffer.clear();
+        self.state = State::Reading(buffer);
+        Ok(())
+    }
+}
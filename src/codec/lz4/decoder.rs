@@ -0,0 +1,124 @@
+use crate::{
+    codec::{
+        lz4::scanner::{FrameScanner, WINDOW_SIZE},
+        Decode,
+    },
+    unshared::Unshared,
+    util::PartialBuffer,
+    BackendError, CompressionError, Format, Stats,
+};
+use std::io::{Error, Result};
+
+use liblz4::liblz4::{
+    check_error, LZ4FDecompressionContext, LZ4F_createDecompressionContext, LZ4F_decompress,
+    LZ4F_freeDecompressionContext, LZ4F_VERSION,
+};
+
+#[derive(Debug)]
+struct Context(LZ4FDecompressionContext);
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { LZ4F_freeDecompressionContext(self.0) };
+    }
+}
+
+#[derive(Debug)]
+pub struct Lz4Decoder {
+    context: Unshared<Context>,
+    scanner: FrameScanner,
+    done: bool,
+}
+
+impl Lz4Decoder {
+    pub(crate) fn new() -> Self {
+        let mut context = LZ4FDecompressionContext(std::ptr::null_mut());
+        check_error(unsafe { LZ4F_createDecompressionContext(&mut context, LZ4F_VERSION) })
+            .unwrap();
+
+        Self {
+            context: Unshared::new(Context(context)),
+            scanner: FrameScanner::new(),
+            done: false,
+        }
+    }
+
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        let mut input_len = input.unwritten().len();
+        let mut output_len = output.unwritten().len();
+
+        let result = unsafe {
+            LZ4F_decompress(
+                self.context.get_mut().0,
+                output.unwritten_mut().as_mut_ptr(),
+                &mut output_len,
+                input.unwritten().as_ptr(),
+                &mut input_len,
+                std::ptr::null(),
+            )
+        };
+        let hint = check_error(result).map_err(|err| {
+            // `check_error` only keeps the name, the code is the negated result
+            let code = (result as isize).wrapping_neg() as i32;
+            Error::from(BackendError::new(Format::Lz4, Some(code), err.to_string()))
+        })?;
+
+        self.scanner
+            .input(&mut PartialBuffer::new(&input.unwritten()[..input_len]));
+        input.advance(input_len);
+        output.advance(output_len);
+
+        // A hint of zero means the whole frame has been decoded and written out
+        if hint == 0 {
+            self.done = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Lz4Decoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        self.decode(input, output)?;
+        Ok(self.done)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if self.done {
+            return Ok(true);
+        }
+
+        // Decoded data may still be held in the context if the last output buffer was too small
+        self.decode(&mut PartialBuffer::new(&[][..]), output)?;
+        Ok(!output.unwritten().is_empty())
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if !self.flush(output)? {
+            return Ok(false);
+        }
+
+        if self.done {
+            Ok(true)
+        } else {
+            Err(CompressionError::UnexpectedEof {
+                format: Format::Lz4,
+            }
+            .into())
+        }
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.blocks = Some(self.scanner.blocks());
+        stats.window_size = Some(WINDOW_SIZE);
+        stats.checksum = self.scanner.checksum();
+    }
+}
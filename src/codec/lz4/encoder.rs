@@ -0,0 +1,201 @@
+use crate::{codec::Encode, unshared::Unshared, util::PartialBuffer};
+use std::io::Result;
+
+use liblz4::liblz4::{
+    check_error, ContentChecksum, LZ4FCompressionContext, LZ4FPreferences, LZ4F_compressBegin,
+    LZ4F_compressBound, LZ4F_compressEnd, LZ4F_compressUpdate, LZ4F_createCompressionContext,
+    LZ4F_flush, LZ4F_freeCompressionContext, LZ4F_VERSION,
+};
+
+/// The largest amount of input compressed in one call, keeping the output buffer bounded.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The largest frame header `LZ4F_compressBegin` can write.
+const HEADER_SIZE: usize = 19;
+
+#[derive(Debug)]
+struct Context(LZ4FCompressionContext);
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { LZ4F_freeCompressionContext(self.0) };
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Header,
+    Encoding,
+    Footer,
+    Done,
+}
+
+#[derive(Debug)]
+pub struct Lz4Encoder {
+    context: Unshared<Context>,
+    preferences: Unshared<LZ4FPreferences>,
+    state: State,
+    /// Compressed data that hasn't fit into the output yet, `LZ4F` always needs enough output
+    /// space for the worst case so it can't write directly into the caller's buffer.
+    buffer: PartialBuffer<Vec<u8>>,
+}
+
+impl Lz4Encoder {
+    pub(crate) fn new(level: u32) -> Self {
+        let mut context = LZ4FCompressionContext(std::ptr::null_mut());
+        check_error(unsafe { LZ4F_createCompressionContext(&mut context, LZ4F_VERSION) }).unwrap();
+
+        // The preferences have grown fields across versions of liblz4, all of them default to 0
+        let mut preferences: LZ4FPreferences = unsafe { std::mem::zeroed() };
+        preferences.compression_level = level;
+        preferences.frame_info.content_checksum_flag = ContentChecksum::ChecksumEnabled;
+
+        Self {
+            context: Unshared::new(Context(context)),
+            preferences: Unshared::new(preferences),
+            state: State::Header,
+            buffer: PartialBuffer::new(Vec::new()),
+        }
+    }
+
+    /// Runs an `LZ4F` operation writing into the internal buffer, which must have been fully
+    /// written out beforehand.
+    fn write_buffer(
+        &mut self,
+        capacity: usize,
+        f: impl FnOnce(LZ4FCompressionContext, &mut [u8]) -> usize,
+    ) -> Result<()> {
+        debug_assert!(self.buffer.unwritten().is_empty());
+
+        let mut buffer =
+            std::mem::replace(&mut self.buffer, PartialBuffer::new(Vec::new())).into_inner();
+        buffer.clear();
+        buffer.resize(capacity, 0);
+
+        let len = check_error(f(self.context.get_mut().0, &mut buffer))?;
+        buffer.truncate(len);
+
+        self.buffer = PartialBuffer::new(buffer);
+        Ok(())
+    }
+
+    fn bound(&mut self, len: usize) -> usize {
+        unsafe { LZ4F_compressBound(len, &*self.preferences.get_mut()) }
+    }
+}
+
+impl Encode for Lz4Encoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        loop {
+            output.copy_unwritten_from(&mut self.buffer);
+            if !self.buffer.unwritten().is_empty() {
+                return Ok(());
+            }
+
+            match self.state {
+                State::Header => {
+                    let preferences = &*self.preferences.get_mut() as *const _;
+                    self.write_buffer(HEADER_SIZE, |context, buffer| unsafe {
+                        LZ4F_compressBegin(context, buffer.as_mut_ptr(), buffer.len(), preferences)
+                    })?;
+                    self.state = State::Encoding;
+                }
+
+                State::Encoding => {
+                    if input.unwritten().is_empty() {
+                        return Ok(());
+                    }
+
+                    let chunk =
+                        &input.unwritten()[..std::cmp::min(input.unwritten().len(), CHUNK_SIZE)];
+                    let bound = self.bound(chunk.len());
+                    self.write_buffer(bound, |context, buffer| unsafe {
+                        LZ4F_compressUpdate(
+                            context,
+                            buffer.as_mut_ptr(),
+                            buffer.len(),
+                            chunk.as_ptr(),
+                            chunk.len(),
+                            std::ptr::null(),
+                        )
+                    })?;
+                    input.advance(chunk.len());
+                }
+
+                State::Footer | State::Done => panic!("encode after complete"),
+            }
+        }
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        output.copy_unwritten_from(&mut self.buffer);
+        if !self.buffer.unwritten().is_empty() {
+            return Ok(false);
+        }
+
+        match self.state {
+            State::Header => {
+                // Write out the header so there's always a valid frame start after a flush
+                self.encode(&mut PartialBuffer::new(&[][..]), output)?;
+                Ok(self.buffer.unwritten().is_empty())
+            }
+
+            State::Encoding => {
+                let bound = self.bound(0);
+                self.write_buffer(bound, |context, buffer| unsafe {
+                    LZ4F_flush(context, buffer.as_mut_ptr(), buffer.len(), std::ptr::null())
+                })?;
+                output.copy_unwritten_from(&mut self.buffer);
+                Ok(self.buffer.unwritten().is_empty())
+            }
+
+            State::Footer | State::Done => Ok(true),
+        }
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        loop {
+            output.copy_unwritten_from(&mut self.buffer);
+            if !self.buffer.unwritten().is_empty() {
+                return Ok(false);
+            }
+
+            match self.state {
+                State::Header => {
+                    self.encode(&mut PartialBuffer::new(&[][..]), output)?;
+                }
+
+                State::Encoding => {
+                    let bound = self.bound(0);
+                    self.write_buffer(bound, |context, buffer| unsafe {
+                        LZ4F_compressEnd(
+                            context,
+                            buffer.as_mut_ptr(),
+                            buffer.len(),
+                            std::ptr::null(),
+                        )
+                    })?;
+                    self.state = State::Footer;
+                }
+
+                State::Footer => {
+                    self.state = State::Done;
+                }
+
+                State::Done => return Ok(true),
+            }
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.state = State::Header;
+        let mut buffer = self.buffer.take().into_inner();
+        buffer.clear();
+        self.buffer = PartialBuffer::new(buffer);
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+mod block_decoder;
+mod block_encoder;
+mod decoder;
+mod encoder;
+mod scanner;
+
+pub(crate) use self::{
+    block_decoder::Lz4BlockDecoder, block_encoder::Lz4BlockEncoder, decoder::Lz4Decoder,
+    encoder::Lz4Encoder,
+};
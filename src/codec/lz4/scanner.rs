@@ -0,0 +1,178 @@
+use crate::util::PartialBuffer;
+
+const MAGIC: u32 = 0x184D_2204;
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFF_FFF0;
+
+/// Matches can refer back this far in every lz4 frame.
+pub(crate) const WINDOW_SIZE: u64 = 1 << 16;
+
+#[derive(Debug)]
+enum State {
+    Magic(PartialBuffer<[u8; 4]>),
+    SkippableLen(PartialBuffer<[u8; 4]>),
+    Descriptor,
+    /// The block descriptor, optional content size and dictionary ID, and header checksum to skip.
+    Header(usize),
+    BlockSize(PartialBuffer<[u8; 4]>),
+    Block(usize),
+    Checksum(PartialBuffer<[u8; 4]>),
+    Skip(usize),
+    /// Not something we understand, the decoder will report the error.
+    Invalid,
+}
+
+/// Follows the frame and block headers in the input given to a decoder to count the blocks and
+/// find the content checksum at the end of each frame.
+///
+/// Every block starts with its size, so the scanner can skip over the blocks without decoding
+/// them.
+#[derive(Debug)]
+pub(crate) struct FrameScanner {
+    state: State,
+    block_checksum: bool,
+    content_checksum: bool,
+    blocks: u64,
+    checksum: Option<u32>,
+}
+
+impl FrameScanner {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: State::Magic(<_>::default()),
+            block_checksum: false,
+            content_checksum: false,
+            blocks: 0,
+            checksum: None,
+        }
+    }
+
+    /// Returns the number of complete blocks seen, across all frames.
+    pub(crate) fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the content checksum stored at the end of the last frame which had one.
+    pub(crate) fn checksum(&self) -> Option<u32> {
+        self.checksum
+    }
+
+    pub(crate) fn input(&mut self, input: &mut PartialBuffer<&[u8]>) {
+        loop {
+            match &mut self.state {
+                State::Magic(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let magic = u32::from_le_bytes(data.take().into_inner());
+                    self.state = if magic == MAGIC {
+                        State::Descriptor
+                    } else if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+                        State::SkippableLen(<_>::default())
+                    } else {
+                        State::Invalid
+                    };
+                }
+
+                State::SkippableLen(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let len = u32::from_le_bytes(data.take().into_inner());
+                    self.state = State::Skip(len as usize);
+                }
+
+                State::Descriptor => {
+                    let flags = match input.unwritten().first() {
+                        Some(&flags) => flags,
+                        None => return,
+                    };
+                    input.advance(1);
+
+                    self.block_checksum = flags & 0b0001_0000 != 0;
+                    self.content_checksum = flags & 0b0000_0100 != 0;
+
+                    let content_size_len = if flags & 0b0000_1000 != 0 { 8 } else { 0 };
+                    let dict_id_len = if flags & 0b0000_0001 != 0 { 4 } else { 0 };
+
+                    self.state = State::Header(1 + content_size_len + dict_id_len + 1);
+                }
+
+                State::Header(remaining) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.state = State::BlockSize(<_>::default());
+                }
+
+                State::BlockSize(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    // The high bit marks an uncompressed block, a size of zero ends the frame
+                    let size = u32::from_le_bytes(data.take().into_inner()) & 0x7FFF_FFFF;
+                    self.state = if size != 0 {
+                        let checksum_len = if self.block_checksum { 4 } else { 0 };
+                        State::Block(size as usize + checksum_len)
+                    } else if self.content_checksum {
+                        State::Checksum(<_>::default())
+                    } else {
+                        State::Magic(<_>::default())
+                    };
+                }
+
+                State::Block(remaining) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.blocks += 1;
+                    self.state = State::BlockSize(<_>::default());
+                }
+
+                State::Checksum(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    self.checksum = Some(u32::from_le_bytes(data.take().into_inner()));
+                    self.state = State::Magic(<_>::default());
+                }
+
+                State::Skip(remaining) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.state = State::Magic(<_>::default());
+                }
+
+                State::Invalid => return,
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{
+    codec::{lzma::LzmaHeader, Decode, LiblzmaDecoder},
+    util::PartialBuffer,
+    Format,
+};
+use std::io::Result;
+
+use xz2::stream::Stream;
+
+#[derive(Debug)]
+pub struct LzmaDecoder {
+    inner: LiblzmaDecoder,
+    /// The start of the stream, collected from the input liblzma consumes until the whole header
+    /// has been seen.
+    prefix: PartialBuffer<[u8; LzmaHeader::LEN]>,
+    header: Option<LzmaHeader>,
+}
+
+impl LzmaDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: LiblzmaDecoder::new(Stream::new_lzma_decoder(u64::MAX).unwrap(), Format::Lzma),
+            prefix: PartialBuffer::default(),
+            header: None,
+        }
+    }
+
+    pub(crate) fn header(&self) -> Option<&LzmaHeader> {
+        self.header.as_ref()
+    }
+}
+
+impl Decode for LzmaDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        let prior = input.written().len();
+        let done = self.inner.decode(input, output)?;
+
+        // liblzma checks the header as it reads it, so it only needs to be picked out here
+        if self.header.is_none() {
+            self.prefix
+                .copy_unwritten_from(&mut PartialBuffer::new(&input.written()[prior..]));
+            if self.prefix.unwritten().is_empty() {
+                self.header = Some(LzmaHeader::parse(&self.prefix.take().into_inner()));
+            }
+        }
+
+        Ok(done)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.flush(output)
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.finish(output)
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    codec::{Encode, LiblzmaEncoder},
+    util::PartialBuffer,
+};
+use std::io::Result;
+
+use xz2::stream::{LzmaOptions, Stream};
+
+#[derive(Debug)]
+pub struct LzmaEncoder {
+    inner: LiblzmaEncoder,
+}
+
+impl LzmaEncoder {
+    /// Creates a new stream compressing at the preset `level`, from 0 to 9. The size of the input
+    /// isn't known up front, so the stream is always written with an end marker.
+    pub(crate) fn new(level: u32) -> Self {
+        // Only invalid presets or allocation failures are reported, and the preset was checked
+        let create =
+            |preset| Stream::new_lzma_encoder(&LzmaOptions::new_preset(preset).unwrap()).unwrap();
+
+        Self {
+            inner: LiblzmaEncoder::new(create, level.min(9)),
+        }
+    }
+}
+
+impl Encode for LzmaEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        self.inner.encode(input, output)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        // liblzma can't sync flush this format, so only the output it already has is passed on
+        self.inner
+            .encode(&mut PartialBuffer::new(&[][..]), output)?;
+        Ok(!output.unwritten().is_empty())
+    }
+
+    fn can_sync_flush(&self) -> bool {
+        false
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.finish(output)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.inner.reset()
+    }
+}
@@ -0,0 +1,32 @@
+/// The header of a stream in the legacy `.lzma` ("LZMA alone") format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzmaHeader {
+    /// The properties byte, encoding the `lc`, `lp` and `pb` parameters of the stream as
+    /// `(pb * 5 + lp) * 9 + lc`.
+    pub properties: u8,
+    /// The dictionary size the stream was compressed with.
+    pub dictionary_size: u32,
+    /// The size of the decompressed data, or `None` if it was unknown when the stream was
+    /// written, in which case the stream ends with an end marker instead.
+    pub uncompressed_size: Option<u64>,
+}
+
+impl LzmaHeader {
+    pub(crate) const LEN: usize = 13;
+
+    pub(crate) fn parse(header: &[u8; Self::LEN]) -> Self {
+        let mut dictionary_size = [0; 4];
+        dictionary_size.copy_from_slice(&header[1..5]);
+        let mut uncompressed_size = [0; 8];
+        uncompressed_size.copy_from_slice(&header[5..13]);
+
+        Self {
+            properties: header[0],
+            dictionary_size: u32::from_le_bytes(dictionary_size),
+            uncompressed_size: match u64::from_le_bytes(uncompressed_size) {
+                u64::MAX => None,
+                size => Some(size),
+            },
+        }
+    }
+}
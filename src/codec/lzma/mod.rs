@@ -0,0 +1,6 @@
+mod decoder;
+mod encoder;
+mod header;
+
+pub use self::header::LzmaHeader;
+pub(crate) use self::{decoder::LzmaDecoder, encoder::LzmaEncoder};
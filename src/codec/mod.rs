@@ -1,61 +1,176 @@
-use crate::util::PartialBuffer;
+//! The traits the formats implement, for implementing a custom format.
+//!
+//! A type implementing [`Encode`] or [`Decode`] can be wrapped in the generic `Encoder` and
+//! `Decoder` types of the [`bufread`](crate::bufread), [`read`](crate::read),
+//! [`write`](crate::write) and [`stream`](crate::stream) modules, which provide the same
+//! interfaces as the built in formats.
+
+use crate::Stats;
 use std::io::Result;
 
+pub use crate::util::PartialBuffer;
+
+mod auto;
 #[cfg(feature = "brotli")]
 mod brotli;
 #[cfg(feature = "bzip")]
 mod bzip;
 #[cfg(feature = "deflate")]
 mod deflate;
+#[cfg(feature = "deflate64")]
+mod deflate64;
 #[cfg(feature = "flate2")]
 mod flate;
 #[cfg(feature = "gzip")]
 mod gzip;
+#[cfg(any(feature = "flate2", feature = "deflate64"))]
+mod huffman;
+#[cfg(feature = "identity")]
+mod identity;
+#[cfg(feature = "xz2")]
+mod liblzma;
+mod limit;
+#[cfg(feature = "lz4")]
+mod lz4;
+#[cfg(feature = "lzma")]
+mod lzma;
+#[cfg(feature = "snappy")]
+mod snappy;
+#[cfg(feature = "xz")]
+mod xz;
 #[cfg(feature = "zlib")]
 mod zlib;
 #[cfg(feature = "zstd")]
 mod zstd;
 
+pub(crate) use self::auto::AutoDecoder;
 #[cfg(feature = "brotli")]
 pub(crate) use self::brotli::{BrotliDecoder, BrotliEncoder};
 #[cfg(feature = "bzip")]
 pub(crate) use self::bzip::{BzDecoder, BzEncoder};
 #[cfg(feature = "deflate")]
+#[doc(hidden)]
+pub use self::deflate::Termination;
+#[cfg(feature = "deflate")]
 pub(crate) use self::deflate::{DeflateDecoder, DeflateEncoder};
+#[cfg(feature = "deflate64")]
+pub(crate) use self::deflate64::Deflate64Decoder;
 #[cfg(feature = "flate2")]
 pub(crate) use self::flate::{FlateDecoder, FlateEncoder};
 #[cfg(feature = "gzip")]
-pub(crate) use self::gzip::{GzipDecoder, GzipEncoder};
+#[doc(hidden)]
+pub use self::gzip::GzipHeader;
+#[cfg(feature = "gzip")]
+pub(crate) use self::gzip::{GzipDecoder, GzipEncoder, GzipHeaderParser};
+#[cfg(feature = "identity")]
+pub(crate) use self::identity::{IdentityDecoder, IdentityEncoder};
+#[cfg(feature = "xz2")]
+pub(crate) use self::liblzma::{LiblzmaDecoder, LiblzmaEncoder};
+pub(crate) use self::limit::Limited;
+#[cfg(feature = "lz4")]
+pub(crate) use self::lz4::{Lz4BlockDecoder, Lz4BlockEncoder, Lz4Decoder, Lz4Encoder};
+#[cfg(feature = "lzma")]
+#[doc(hidden)]
+pub use self::lzma::LzmaHeader;
+#[cfg(feature = "lzma")]
+pub(crate) use self::lzma::{LzmaDecoder, LzmaEncoder};
+#[cfg(feature = "snappy")]
+pub(crate) use self::snappy::{SnappyDecoder, SnappyEncoder};
+#[cfg(feature = "xz")]
+pub(crate) use self::xz::{XzDecoder, XzEncoder};
+#[cfg(feature = "zlib")]
+#[doc(hidden)]
+pub use self::zlib::Wrap;
 #[cfg(feature = "zlib")]
 pub(crate) use self::zlib::{ZlibDecoder, ZlibEncoder};
 #[cfg(feature = "zstd")]
+#[doc(hidden)]
+pub use self::zstd::{
+    PreparedPrefix, ZstdDictionaries, ZstdEncoderBuilder, ZstdParam, ZstdStrategy,
+};
+#[cfg(feature = "zstd")]
 pub(crate) use self::zstd::{ZstdDecoder, ZstdEncoder};
 
+/// A compression format's encoder, driven by the generic `Encoder` types in each of the IO
+/// modules, which do all the polling and buffering.
+///
+/// Each method is given the part of the output buffer that is still free, and advances it past
+/// what it writes. The methods are called again while they return `false`, or leave input
+/// unconsumed, with more room in the output, so an implementation only needs to make some
+/// progress on each call.
 pub trait Encode {
+    /// Compresses some of `input` into `output`, advancing both past what was used.
     fn encode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<()>;
 
-    /// Returns whether the internal buffers are flushed
+    /// Writes out anything held internally so that all the input so far can be decoded, returns
+    /// whether that is done.
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool>;
 
-    /// Returns whether the internal buffers are flushed and the end of the stream is written
+    /// Like [`flush`](Self::flush), but also discards the history so decoding can restart from
+    /// this point, for formats that support it. Defaults to `flush`.
+    fn full_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush(output)
+    }
+
+    /// Like [`flush`](Self::flush), but for formats that support it uses a lighter flush which
+    /// doesn't byte align the output, such as zlib's `Z_PARTIAL_FLUSH`. Defaults to `flush`.
+    fn partial_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.flush(output)
+    }
+
+    /// Returns whether [`flush`](Self::flush) writes out all the input so far, so the output up
+    /// to it decodes to exactly that input. Formats that can only pass on the output they already
+    /// have return `false`, and `poll_flush_at_input_offset` fails for them. Defaults to `true`.
+    fn can_sync_flush(&self) -> bool {
+        true
+    }
+
+    /// Writes out anything held internally and the end of the stream, returns whether that is
+    /// done.
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool>;
+
+    /// Prepares to encode a new stream with the same parameters, reusing the allocated state
+    /// where possible.
+    fn reset(&mut self) -> Result<()>;
+
+    /// Fills in the statistics this format tracks, by default none.
+    fn stats(&self, _stats: &mut Stats) {}
 }
 
+/// A compression format's decoder, driven by the generic `Decoder` types in each of the IO
+/// modules, which do all the polling and buffering.
+///
+/// As with [`Encode`], each method is given the part of the output buffer that is still free and
+/// advances it past what it writes, and is called again until it reports it is done.
 pub trait Decode {
-    /// Returns whether the end of the stream has been read
+    /// Decompresses some of `input` into `output`, advancing both past what was used, returns
+    /// whether the end of the compressed stream has been reached.
+    ///
+    /// Once it has, the decoder is not called again and any input after the end is left unread.
     fn decode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<bool>;
 
-    /// Returns whether the internal buffers are flushed
+    /// Writes out any decompressed data held internally, returns whether that is done.
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool>;
 
-    /// Returns whether the internal buffers are flushed
+    /// Called at the end of the input to write out any decompressed data held internally,
+    /// returns whether that is done. Should fail if the input ended part way through the stream.
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool>;
+
+    /// Returns whether all the input so far has been decoded and its output written, up to the
+    /// end of a self-contained unit such as a gzip member or zstd frame, so a new decoder could
+    /// carry on from the next byte. By default `false`.
+    fn between_frames(&self) -> bool {
+        false
+    }
+
+    /// Fills in the statistics this format tracks, by default none.
+    fn stats(&self, _stats: &mut Stats) {}
 }
@@ -0,0 +1,194 @@
+use crate::{
+    codec::{
+        snappy::{
+            masked_crc32c, CHUNK_COMPRESSED, CHUNK_STREAM_IDENTIFIER, CHUNK_UNCOMPRESSED,
+            MAX_BLOCK_SIZE, STREAM_IDENTIFIER,
+        },
+        Decode,
+    },
+    util::PartialBuffer,
+    CompressionError, Format,
+};
+use std::io::{Error, ErrorKind, Result};
+
+use snap::raw::{decompress_len, max_compress_len, Decoder};
+
+#[derive(Debug)]
+enum State {
+    Header(PartialBuffer<[u8; 4]>),
+    Body(u8, usize),
+    Skipping(usize),
+}
+
+#[derive(Debug)]
+pub struct SnappyDecoder {
+    decoder: Decoder,
+    state: State,
+    read_identifier: bool,
+    /// The body of the current chunk.
+    chunk: Vec<u8>,
+    /// Decoded data that hasn't fit into the output yet.
+    output: PartialBuffer<Vec<u8>>,
+}
+
+fn invalid_data(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl SnappyDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            decoder: Decoder::new(),
+            state: State::Header([0; 4].into()),
+            read_identifier: false,
+            chunk: Vec::new(),
+            output: PartialBuffer::new(Vec::new()),
+        }
+    }
+
+    fn start_chunk(&mut self, header: [u8; 4]) -> Result<State> {
+        let kind = header[0];
+        let len = u32::from_le_bytes([header[1], header[2], header[3], 0]) as usize;
+
+        if !self.read_identifier && kind != CHUNK_STREAM_IDENTIFIER {
+            return Err(CompressionError::invalid_header(
+                Format::Snappy,
+                "the stream does not start with a stream identifier",
+            )
+            .into());
+        }
+
+        let max = match kind {
+            CHUNK_STREAM_IDENTIFIER => STREAM_IDENTIFIER.len() - 4,
+            CHUNK_COMPRESSED => 4 + max_compress_len(MAX_BLOCK_SIZE),
+            CHUNK_UNCOMPRESSED => 4 + MAX_BLOCK_SIZE,
+            0x02..=0x7f => return Err(invalid_data("unsupported unskippable snappy chunk")),
+            // Padding and other skippable chunks
+            _ => return Ok(State::Skipping(len)),
+        };
+
+        if len > max || (kind != CHUNK_STREAM_IDENTIFIER && len < 4) {
+            return Err(invalid_data("invalid snappy chunk length"));
+        }
+
+        self.chunk.clear();
+        Ok(State::Body(kind, len))
+    }
+
+    /// Handles a complete chunk body, leaving any data it contains in the output buffer.
+    fn finish_chunk(&mut self, kind: u8) -> Result<()> {
+        if kind == CHUNK_STREAM_IDENTIFIER {
+            if self.chunk[..] != STREAM_IDENTIFIER[4..] {
+                return Err(CompressionError::invalid_header(
+                    Format::Snappy,
+                    "invalid stream identifier",
+                )
+                .into());
+            }
+            self.read_identifier = true;
+            return Ok(());
+        }
+
+        let expected =
+            u32::from_le_bytes([self.chunk[0], self.chunk[1], self.chunk[2], self.chunk[3]]);
+        let data = &self.chunk[4..];
+
+        let mut output = self.output.take().into_inner();
+        output.clear();
+
+        if kind == CHUNK_COMPRESSED {
+            let len =
+                decompress_len(data).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            if len > MAX_BLOCK_SIZE {
+                return Err(invalid_data("snappy chunk exceeds maximum block size"));
+            }
+            output.resize(len, 0);
+            self.decoder
+                .decompress(data, &mut output)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        } else {
+            output.extend_from_slice(data);
+        }
+
+        let found = masked_crc32c(&output);
+        if found != expected {
+            return Err(CompressionError::ChecksumMismatch {
+                format: Format::Snappy,
+                expected,
+                found,
+            }
+            .into());
+        }
+
+        self.output = PartialBuffer::new(output);
+        Ok(())
+    }
+}
+
+impl Decode for SnappyDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        loop {
+            output.copy_unwritten_from(&mut self.output);
+            if !self.output.unwritten().is_empty() || input.unwritten().is_empty() {
+                // A framed stream has no end marker, it only ends with the end of the input
+                return Ok(false);
+            }
+
+            self.state = match &mut self.state {
+                State::Header(header) => {
+                    header.copy_unwritten_from(input);
+                    if !header.unwritten().is_empty() {
+                        continue;
+                    }
+                    let header = header.take().into_inner();
+                    self.start_chunk(header)?
+                }
+
+                State::Body(kind, len) => {
+                    let (kind, len) = (*kind, *len);
+                    let needed = std::cmp::min(len - self.chunk.len(), input.unwritten().len());
+                    self.chunk.extend_from_slice(&input.unwritten()[..needed]);
+                    input.advance(needed);
+                    if self.chunk.len() < len {
+                        continue;
+                    }
+                    self.finish_chunk(kind)?;
+                    State::Header([0; 4].into())
+                }
+
+                State::Skipping(remaining) => {
+                    let skipped = std::cmp::min(*remaining, input.unwritten().len());
+                    input.advance(skipped);
+                    if skipped < *remaining {
+                        State::Skipping(*remaining - skipped)
+                    } else {
+                        State::Header([0; 4].into())
+                    }
+                }
+            };
+        }
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        output.copy_unwritten_from(&mut self.output);
+        Ok(self.output.unwritten().is_empty())
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if !self.flush(output)? {
+            return Ok(false);
+        }
+
+        match &self.state {
+            State::Header(header) if header.written().is_empty() => Ok(true),
+            _ => Err(CompressionError::UnexpectedEof {
+                format: Format::Snappy,
+            }
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    codec::{
+        snappy::{
+            masked_crc32c, CHUNK_COMPRESSED, CHUNK_UNCOMPRESSED, MAX_BLOCK_SIZE, STREAM_IDENTIFIER,
+        },
+        Encode,
+    },
+    util::PartialBuffer,
+};
+use std::io::Result;
+
+use snap::raw::{max_compress_len, Encoder};
+
+#[derive(Debug)]
+pub struct SnappyEncoder {
+    encoder: Encoder,
+    wrote_identifier: bool,
+    /// Uncompressed data waiting to fill a whole chunk.
+    input: Vec<u8>,
+    /// Encoded chunks that haven't fit into the output yet.
+    output: PartialBuffer<Vec<u8>>,
+}
+
+impl SnappyEncoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            encoder: Encoder::new(),
+            wrote_identifier: false,
+            input: Vec::with_capacity(MAX_BLOCK_SIZE),
+            output: PartialBuffer::new(Vec::new()),
+        }
+    }
+
+    /// Encodes the buffered input as a chunk, preceded by the stream identifier if this is the
+    /// start of the stream. The previous output must have been fully written out.
+    fn write_chunk(&mut self) -> Result<()> {
+        debug_assert!(self.output.unwritten().is_empty());
+
+        let mut output = self.output.take().into_inner();
+        output.clear();
+
+        if !self.wrote_identifier {
+            output.extend_from_slice(STREAM_IDENTIFIER);
+            self.wrote_identifier = true;
+        }
+
+        if !self.input.is_empty() {
+            let start = output.len();
+            output.resize(start + 8 + max_compress_len(self.input.len()), 0);
+
+            let len = self
+                .encoder
+                .compress(&self.input, &mut output[start + 8..])?;
+
+            // Same threshold as the reference implementation, below 12.5% savings the data is
+            // stored as is to save the decoder work
+            let (kind, len) = if len >= self.input.len() - self.input.len() / 8 {
+                output[start + 8..][..self.input.len()].copy_from_slice(&self.input);
+                (CHUNK_UNCOMPRESSED, self.input.len())
+            } else {
+                (CHUNK_COMPRESSED, len)
+            };
+
+            output.truncate(start + 8 + len);
+            output[start] = kind;
+            output[start + 1..start + 4].copy_from_slice(&(len as u32 + 4).to_le_bytes()[..3]);
+            output[start + 4..start + 8].copy_from_slice(&masked_crc32c(&self.input).to_le_bytes());
+
+            self.input.clear();
+        }
+
+        self.output = PartialBuffer::new(output);
+        Ok(())
+    }
+}
+
+impl Encode for SnappyEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        loop {
+            output.copy_unwritten_from(&mut self.output);
+            if !self.output.unwritten().is_empty() || input.unwritten().is_empty() {
+                return Ok(());
+            }
+
+            let len = std::cmp::min(MAX_BLOCK_SIZE - self.input.len(), input.unwritten().len());
+            self.input.extend_from_slice(&input.unwritten()[..len]);
+            input.advance(len);
+
+            if self.input.len() == MAX_BLOCK_SIZE {
+                self.write_chunk()?;
+            }
+        }
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        loop {
+            output.copy_unwritten_from(&mut self.output);
+            if !self.output.unwritten().is_empty() {
+                return Ok(false);
+            }
+
+            if self.input.is_empty() {
+                return Ok(true);
+            }
+
+            self.write_chunk()?;
+        }
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        loop {
+            output.copy_unwritten_from(&mut self.output);
+            if !self.output.unwritten().is_empty() {
+                return Ok(false);
+            }
+
+            // Even an empty stream gets the identifier so it's recognizable as a snappy stream
+            if self.input.is_empty() && self.wrote_identifier {
+                return Ok(true);
+            }
+
+            self.write_chunk()?;
+        }
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.wrote_identifier = false;
+        self.input.clear();
+        let mut output = self.output.take().into_inner();
+        output.clear();
+        self.output = PartialBuffer::new(output);
+        Ok(())
+    }
+}
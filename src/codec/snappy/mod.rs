@@ -0,0 +1,21 @@
+mod decoder;
+mod encoder;
+
+pub(crate) use self::{decoder::SnappyDecoder, encoder::SnappyEncoder};
+
+/// The chunk every framed stream starts with.
+const STREAM_IDENTIFIER: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+
+/// The maximum amount of uncompressed data in a single chunk.
+const MAX_BLOCK_SIZE: usize = 65_536;
+
+const CHUNK_COMPRESSED: u8 = 0x00;
+const CHUNK_UNCOMPRESSED: u8 = 0x01;
+const CHUNK_STREAM_IDENTIFIER: u8 = 0xff;
+
+/// The checksum stored in each data chunk, CRC-32C of the uncompressed data rotated and offset
+/// so that checksums of data containing embedded checksums are still well distributed.
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c::crc32c(data);
+    crc.rotate_right(15).wrapping_add(0xa282_ead8)
+}
@@ -0,0 +1,43 @@
+use crate::{
+    codec::{Decode, LiblzmaDecoder},
+    util::PartialBuffer,
+    Format,
+};
+use std::io::Result;
+
+use xz2::stream::Stream;
+
+#[derive(Debug)]
+pub struct XzDecoder {
+    inner: LiblzmaDecoder,
+}
+
+impl XzDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            // Decodes a single stream, any that follow it are left unread
+            inner: LiblzmaDecoder::new(
+                Stream::new_stream_decoder(u64::MAX, 0).unwrap(),
+                Format::Xz,
+            ),
+        }
+    }
+}
+
+impl Decode for XzDecoder {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        self.inner.decode(input, output)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.flush(output)
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.finish(output)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    codec::{Encode, LiblzmaEncoder},
+    util::PartialBuffer,
+};
+use std::io::{Error, ErrorKind, Result};
+
+use xz2::stream::{Check, MtStreamBuilder, Stream};
+
+/// `LZMA_PRESET_EXTREME`, which xz2 doesn't re-export.
+const PRESET_EXTREME: u32 = 0x8000_0000;
+
+/// `LZMA_THREADS_MAX`, the most threads liblzma's multithreaded encoder accepts.
+const THREADS_MAX: u32 = 16384;
+
+#[derive(Debug)]
+pub struct XzEncoder {
+    inner: LiblzmaEncoder,
+}
+
+impl XzEncoder {
+    /// Creates a new stream compressing at the preset `level`, from 0 to 9, optionally in its
+    /// slower extreme variant.
+    pub(crate) fn new(level: u32, extreme: bool) -> Self {
+        let mut preset = level.min(9);
+        if extreme {
+            preset |= PRESET_EXTREME;
+        }
+
+        // Only invalid presets or allocation failures are reported, and the preset was checked
+        let create = |preset| Stream::new_easy_encoder(preset, Check::Crc64).unwrap();
+
+        Self {
+            inner: LiblzmaEncoder::new(create, preset),
+        }
+    }
+
+    /// Creates a new stream compressing at the preset `level` with `threads` worker threads, each
+    /// compressing blocks of `block_size` bytes of input, or a size chosen from the preset if `0`.
+    pub(crate) fn with_threads(level: u32, threads: u32, block_size: u64) -> Result<Self> {
+        if !(1..=THREADS_MAX).contains(&threads) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "xz thread count of {} is out of range, it must be from 1 to {}",
+                    threads, THREADS_MAX
+                ),
+            ));
+        }
+
+        let preset = level.min(9);
+        let create = move || {
+            MtStreamBuilder::new()
+                .preset(preset)
+                .threads(threads)
+                .block_size(block_size)
+                .check(Check::Crc64)
+                .encoder()
+        };
+
+        // Any failure, such as running out of memory for the blocks, is reported here, the stream
+        // is only recreated with the same options
+        create()?;
+        let create = move || create().unwrap();
+
+        Ok(Self {
+            inner: LiblzmaEncoder::with_create(create, preset, true),
+        })
+    }
+}
+
+impl Encode for XzEncoder {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        self.inner.encode(input, output)
+    }
+
+    fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.flush(output)
+    }
+
+    fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.inner.finish(output)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.inner.reset()
+    }
+}
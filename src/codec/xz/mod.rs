@@ -0,0 +1,4 @@
+mod decoder;
+mod encoder;
+
+pub(crate) use self::{decoder::XzDecoder, encoder::XzEncoder};
@@ -0,0 +1,33 @@
+/// The largest prime below 2^16.
+const BASE: u32 = 65521;
+
+/// The most bytes that can be summed before `b` could overflow and has to be reduced.
+const NMAX: usize = 5552;
+
+/// A running Adler-32 checksum, as recorded in the zlib trailer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub(crate) fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(NMAX) {
+            for &byte in chunk {
+                self.a += u32::from(byte);
+                self.b += self.a;
+            }
+            self.a %= BASE;
+            self.b %= BASE;
+        }
+    }
+
+    pub(crate) fn sum(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
@@ -1,33 +1,200 @@
-use crate::util::PartialBuffer;
-use std::io::Result;
+use crate::{
+    codec::{
+        zlib::{adler32::Adler32, Wrap},
+        Decode, FlateDecoder,
+    },
+    util::PartialBuffer,
+    CompressionError, Format, Stats,
+};
+use std::io::{Error, Result};
+
+use flate2::DecompressError;
+
+#[derive(Debug)]
+enum Inner {
+    Flate(Box<FlateDecoder>),
+    #[cfg(feature = "gzip")]
+    Gzip(Box<crate::codec::GzipDecoder>),
+}
 
 #[derive(Debug)]
 pub struct ZlibDecoder {
-    inner: crate::codec::FlateDecoder,
+    inner: Inner,
+    zlib_header: bool,
+    adler: Adler32,
+    /// The last four bytes of input consumed, which hold the trailer once the stream has ended.
+    tail: u32,
+    declared_adler: Option<u32>,
 }
 
 impl ZlibDecoder {
     pub(crate) fn new() -> Self {
+        Self::with_wrap(Wrap::Zlib)
+    }
+
+    fn from_inner(inner: Inner, zlib_header: bool) -> Self {
         Self {
-            inner: crate::codec::FlateDecoder::new(true),
+            inner,
+            zlib_header,
+            adler: Adler32::new(),
+            tail: 0,
+            declared_adler: None,
+        }
+    }
+
+    pub(crate) fn with_wrap(wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Raw => Self::from_inner(Inner::Flate(Box::new(FlateDecoder::new(false))), false),
+            Wrap::Zlib => Self::new_zlib(FlateDecoder::new(true)),
+            #[cfg(feature = "gzip")]
+            Wrap::Gzip => Self::from_inner(
+                Inner::Gzip(Box::new(crate::codec::GzipDecoder::new())),
+                false,
+            ),
+        }
+    }
+
+    fn new_zlib(inner: FlateDecoder) -> Self {
+        Self::from_inner(Inner::Flate(Box::new(inner)), true)
+    }
+
+    pub(crate) fn with_dictionary(dictionary: &[u8]) -> Result<Self> {
+        Ok(Self::new_zlib(FlateDecoder::with_dictionary(
+            true, dictionary,
+        )?))
+    }
+
+    pub(crate) fn with_window_bits(window_bits: u8) -> Result<Self> {
+        Ok(Self::new_zlib(FlateDecoder::with_window_bits(
+            true,
+            window_bits,
+        )?))
+    }
+
+    pub(crate) fn stored(&self) -> Option<bool> {
+        match &self.inner {
+            Inner::Flate(inner) => inner.stored(),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => inner.stored(),
+        }
+    }
+
+    /// The Adler-32 of the output so far, or for a gzip stream the CRC-32 of the member.
+    pub(crate) fn checksum(&self) -> u32 {
+        match &self.inner {
+            Inner::Flate(_) => self.adler.sum(),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => inner.checksum(),
+        }
+    }
+
+    pub(crate) fn declared_checksum(&self) -> Option<u32> {
+        match &self.inner {
+            Inner::Flate(_) => self.declared_adler,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => inner.declared_checksum(),
+        }
+    }
+
+    /// Runs a step of the inner decoder, following the checksum of its output and the trailer in
+    /// its input.
+    fn track(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+        step: impl FnOnce(
+            &mut dyn Decode,
+            &mut PartialBuffer<&[u8]>,
+            &mut PartialBuffer<&mut [u8]>,
+        ) -> Result<bool>,
+    ) -> Result<bool> {
+        let prior_in = input.written().len();
+        let prior_out = output.written().len();
+        let mut result = step(self.get_mut(), input, output);
+
+        if let Inner::Flate(_) = self.inner {
+            self.adler.update(&output.written()[prior_out..]);
+            for &byte in &input.written()[prior_in..] {
+                self.tail = (self.tail << 8) | u32::from(byte);
+            }
+
+            // zlib reports a mismatch once it has consumed the trailer
+            let mismatch = matches!(&result, Err(err) if is_data_check_error(err));
+            if self.zlib_header && (mismatch || matches!(result, Ok(true))) {
+                self.declared_adler = Some(self.tail);
+            }
+            if self.zlib_header && mismatch {
+                result = Err(CompressionError::ChecksumMismatch {
+                    format: Format::Zlib,
+                    expected: self.tail,
+                    found: self.adler.sum(),
+                }
+                .into());
+            }
+        }
+
+        result
+    }
+
+    fn get_ref(&self) -> &dyn Decode {
+        match &self.inner {
+            Inner::Flate(inner) => &**inner,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => &**inner,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn Decode {
+        match &mut self.inner {
+            Inner::Flate(inner) => &mut **inner,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => &mut **inner,
         }
     }
 }
 
-impl crate::codec::Decode for ZlibDecoder {
+impl Decode for ZlibDecoder {
     fn decode(
         &mut self,
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<bool> {
-        self.inner.decode(input, output)
+        self.track(input, output, |inner, input, output| {
+            inner.decode(input, output)
+        })
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.flush(output)
+        // Whether a flush has written everything out says nothing about the stream having ended
+        let mut flushed = false;
+        self.track(&mut PartialBuffer::new(&[]), output, |inner, _, output| {
+            flushed = inner.flush(output)?;
+            Ok(false)
+        })?;
+        Ok(flushed)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.finish(output)
+        self.track(&mut PartialBuffer::new(&[]), output, |inner, _, output| {
+            inner.finish(output)
+        })
+    }
+
+    fn between_frames(&self) -> bool {
+        self.get_ref().between_frames()
     }
+
+    fn stats(&self, stats: &mut Stats) {
+        self.get_ref().stats(stats);
+        if self.zlib_header {
+            stats.checksum = Some(self.adler.sum());
+        }
+    }
+}
+
+fn is_data_check_error(err: &Error) -> bool {
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<DecompressError>())
+        .and_then(DecompressError::message)
+        == Some("incorrect data check")
 }
@@ -1,17 +1,91 @@
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{
+    codec::{
+        zlib::{adler32::Adler32, Wrap},
+        Encode, FlateEncoder,
+    },
+    util::PartialBuffer,
+    Stats,
+};
 use std::io::Result;
 
 use flate2::Compression;
 
+#[derive(Debug)]
+enum Inner {
+    Flate(FlateEncoder),
+    #[cfg(feature = "gzip")]
+    Gzip(crate::codec::GzipEncoder),
+}
+
 #[derive(Debug)]
 pub struct ZlibEncoder {
-    inner: crate::codec::FlateEncoder,
+    inner: Inner,
+    /// The Adler-32 of the input so far, for a stream with a zlib header.
+    adler: Option<Adler32>,
 }
 
 impl ZlibEncoder {
     pub(crate) fn new(level: Compression) -> Self {
+        Self::with_wrap(level, Wrap::Zlib)
+    }
+
+    pub(crate) fn with_wrap(level: Compression, wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Raw => Self::from_inner(Inner::Flate(FlateEncoder::new(level, false)), false),
+            Wrap::Zlib => Self::from_inner(Inner::Flate(FlateEncoder::new(level, true)), true),
+            #[cfg(feature = "gzip")]
+            Wrap::Gzip => {
+                Self::from_inner(Inner::Gzip(crate::codec::GzipEncoder::new(level)), false)
+            }
+        }
+    }
+
+    fn from_inner(inner: Inner, zlib_header: bool) -> Self {
         Self {
-            inner: crate::codec::FlateEncoder::new(level, true),
+            inner,
+            adler: if zlib_header {
+                Some(Adler32::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn with_dictionary(level: Compression, dictionary: &[u8]) -> Result<Self> {
+        Ok(Self::from_inner(
+            Inner::Flate(FlateEncoder::with_dictionary(level, true, dictionary)?),
+            true,
+        ))
+    }
+
+    pub(crate) fn with_window_bits(level: Compression, window_bits: u8) -> Result<Self> {
+        Ok(Self::from_inner(
+            Inner::Flate(FlateEncoder::with_window_bits(level, true, window_bits)?),
+            true,
+        ))
+    }
+
+    pub(crate) fn dictionary_adler32(&self) -> Option<u32> {
+        match &self.inner {
+            Inner::Flate(inner) => inner.dictionary_adler32(),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(_) => None,
+        }
+    }
+
+    fn get_ref(&self) -> &dyn Encode {
+        match &self.inner {
+            Inner::Flate(inner) => inner,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => inner,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn Encode {
+        match &mut self.inner {
+            Inner::Flate(inner) => inner,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(inner) => inner,
         }
     }
 }
@@ -22,14 +96,41 @@ impl Encode for ZlibEncoder {
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<()> {
-        self.inner.encode(input, output)
+        let prior = input.written().len();
+        self.get_mut().encode(input, output)?;
+        if let Some(adler) = &mut self.adler {
+            adler.update(&input.written()[prior..]);
+        }
+        Ok(())
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.flush(output)
+        self.get_mut().flush(output)
+    }
+
+    fn full_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.get_mut().full_flush(output)
+    }
+
+    fn partial_flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        self.get_mut().partial_flush(output)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
-        self.inner.finish(output)
+        self.get_mut().finish(output)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        if let Some(adler) = &mut self.adler {
+            *adler = Adler32::new();
+        }
+        self.get_mut().reset()
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        self.get_ref().stats(stats);
+        if let Some(adler) = &self.adler {
+            stats.checksum = Some(adler.sum());
+        }
     }
 }
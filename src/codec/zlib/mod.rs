@@ -1,4 +1,23 @@
+mod adler32;
 mod decoder;
 mod encoder;
 
 pub(crate) use self::{decoder::ZlibDecoder, encoder::ZlibEncoder};
+
+/// What is wrapped around the deflate stream, choosing between the formats zlib can produce like
+/// its `windowBits` parameter does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Wrap {
+    /// No header or trailer, a bare deflate stream, like a negative `windowBits`.
+    Raw,
+
+    /// A zlib header and Adler-32 trailer. This is the default.
+    #[default]
+    Zlib,
+
+    /// A gzip header and CRC-32 trailer, like adding 16 to `windowBits`.
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    Gzip,
+}
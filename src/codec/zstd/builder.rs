@@ -0,0 +1,159 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
+
+use crate::codec::{
+    zstd::{PreparedPrefix, ZstdParam, ZstdStrategy},
+    ZstdEncoder,
+};
+
+/// Configures a zstd encoder with advanced parameters, created by the `builder` function of each
+/// of the zstd encoders.
+///
+/// The options are only checked by [`build`](Self::build), which rejects combinations that can't
+/// work together rather than leaving the encoder to fail part way through the stream.
+pub struct ZstdEncoderBuilder<I, E> {
+    inner: I,
+    level: Option<i32>,
+    params: Vec<ZstdParam>,
+    dictionary: Option<Vec<u8>>,
+    prefix: Option<PreparedPrefix>,
+    content_size: bool,
+    build: fn(I, ZstdEncoder) -> E,
+}
+
+impl<I, E> ZstdEncoderBuilder<I, E> {
+    pub(crate) fn new(inner: I, build: fn(I, ZstdEncoder) -> E) -> Self {
+        Self {
+            inner,
+            level: None,
+            params: Vec::new(),
+            dictionary: None,
+            prefix: None,
+            content_size: false,
+            build,
+        }
+    }
+
+    /// Sets the compression level, which can range from 1-22. The default of `0` will use zstd's
+    /// default, which is `3`.
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Sets the log2 of the window size, see [`ZstdParam::WindowLog`].
+    pub fn window_log(self, log: u32) -> Self {
+        self.param(ZstdParam::WindowLog(log))
+    }
+
+    /// Sets the match finding strategy, see [`ZstdParam::Strategy`].
+    pub fn strategy(self, strategy: ZstdStrategy) -> Self {
+        self.param(ZstdParam::Strategy(strategy))
+    }
+
+    /// Sets whether to use long distance matching, see
+    /// [`ZstdParam::EnableLongDistanceMatching`].
+    pub fn enable_long_distance_matching(self, enable: bool) -> Self {
+        self.param(ZstdParam::EnableLongDistanceMatching(enable))
+    }
+
+    /// Sets the number of worker threads compressing in the background, see
+    /// [`ZstdParam::NbWorkers`]. Needs the `zstdmt` feature for any more than `0`.
+    ///
+    /// This trades latency for throughput on large inputs: output is produced in bursts as the
+    /// workers complete jobs of several megabytes, rather than steadily as input is written.
+    /// Closing the encoder still waits for every job and ends the frame as usual.
+    ///
+    /// The output doesn't depend on how the workers are scheduled, so for a given input, level,
+    /// parameters and number of workers it is byte-identical from run to run, e.g. for
+    /// reproducible builds.
+    pub fn workers(self, workers: u32) -> Self {
+        self.param(ZstdParam::NbWorkers(workers))
+    }
+
+    /// Sets the size of the input each worker compresses at once, see [`ZstdParam::JobSize`].
+    pub fn job_size(self, bytes: u32) -> Self {
+        self.param(ZstdParam::JobSize(bytes))
+    }
+
+    /// Sets any of the advanced parameters, overriding the value chosen by the level.
+    pub fn param(mut self, param: ZstdParam) -> Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Compresses with the given dictionary, which can be either a dictionary trained by
+    /// `zstd --train` or raw content. The same dictionary must be given to the decoder.
+    ///
+    /// This can't be combined with a [`prepared_prefix`](Self::prepared_prefix).
+    pub fn dictionary(mut self, dictionary: &[u8]) -> Self {
+        self.dictionary = Some(dictionary.to_vec());
+        self
+    }
+
+    /// Compresses referring back to the content of the given prepared prefix, at the level it
+    /// was prepared for. The same prefix must be given to the decoder.
+    ///
+    /// The level and parameters are fixed when the prefix is prepared, so this can't be
+    /// combined with setting them here, nor with a [`dictionary`](Self::dictionary).
+    pub fn prepared_prefix(mut self, prefix: &PreparedPrefix) -> Self {
+        self.prefix = Some(prefix.clone());
+        self
+    }
+
+    /// Sets whether to write the size of the content into the frame header.
+    ///
+    /// The content is streamed through the encoder, so its size isn't known when the header is
+    /// written, and enabling this is rejected.
+    pub fn include_content_size(mut self, include: bool) -> Self {
+        self.content_size = include;
+        self
+    }
+
+    /// Creates the encoder.
+    ///
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if a
+    /// parameter is out of the range zstd allows for it, or the options can't be combined.
+    pub fn build(self) -> Result<E> {
+        let invalid = |message| Err(Error::new(ErrorKind::InvalidInput, message));
+
+        if self.dictionary.is_some() && self.prefix.is_some() {
+            return invalid("a zstd encoder can't use both a dictionary and a prepared prefix");
+        }
+        if self.prefix.is_some() && (self.level.is_some() || !self.params.is_empty()) {
+            return invalid(
+                "the level and parameters of a zstd encoder using a prepared prefix are fixed when \
+                 the prefix is prepared",
+            );
+        }
+        if self.content_size {
+            return invalid(
+                "a zstd encoder can't include the content size as it isn't known up front",
+            );
+        }
+
+        let level = self.level.unwrap_or(0);
+        let mut encoder = match (&self.dictionary, &self.prefix) {
+            (Some(dictionary), _) => ZstdEncoder::with_dict(level, dictionary)?,
+            (_, Some(prefix)) => ZstdEncoder::with_prefix(prefix)?,
+            (None, None) => ZstdEncoder::new(level),
+        };
+        encoder.set_params(&self.params)?;
+
+        Ok((self.build)(self.inner, encoder))
+    }
+}
+
+impl<I, E> fmt::Debug for ZstdEncoderBuilder<I, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdEncoderBuilder")
+            .field("level", &self.level)
+            .field("params", &self.params)
+            .field("dictionary", &self.dictionary.as_ref().map(Vec::len))
+            .field("prefix", &self.prefix)
+            .field("content_size", &self.content_size)
+            .finish()
+    }
+}
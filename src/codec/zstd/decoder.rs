@@ -1,19 +1,216 @@
-use std::io::Result;
+use std::{
+    io::{Error, Result},
+    mem,
+};
 
-use crate::{codec::Decode, unshared::Unshared, util::PartialBuffer};
+use crate::{
+    codec::{
+        zstd::{params, scanner::FrameScanner, PreparedPrefix, ZstdDictionaries},
+        Decode,
+    },
+    error::CompressionError,
+    unshared::Unshared,
+    util::PartialBuffer,
+    BackendError, Format, Stats,
+};
 use libzstd::stream::raw::{Decoder, Operation};
 
+/// The zstd crate only keeps the name of an error, so find the code it came from.
+fn backend_error(err: Error) -> Error {
+    let message = err.to_string();
+    // All the codes are below `ZSTD_error_maxCode`
+    (1..120)
+        .find(|&code: &i32| zstd_safe::get_error_name((code as usize).wrapping_neg()) == message)
+        .map_or(err, |code| {
+            BackendError::new(Format::Zstd, Some(code), message).into()
+        })
+}
+
+/// The magic number starting each zstd frame that isn't a skippable one.
+const FRAME_MAGIC: u32 = 0xfd2f_b528;
+
+/// How much of the frame starting with `header` must be read to reach the end of its dictionary
+/// ID, or `None` if more of it is needed to tell.
+fn dictionary_id_end(header: &[u8]) -> Option<usize> {
+    if header.len() < 4 {
+        return None;
+    }
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if magic != FRAME_MAGIC {
+        // Skippable frames have no dictionary, and anything else is left for libzstd to reject
+        return Some(4);
+    }
+
+    let descriptor = *header.get(4)?;
+    let window_len = if descriptor & 0x20 == 0 { 1 } else { 0 };
+    let dictionary_id_len = [0, 1, 2, 4][usize::from(descriptor & 0x3)];
+    Some(5 + window_len + dictionary_id_len)
+}
+
+/// The ID of the dictionary the frame starting with `header` was compressed with, `0` if none.
+fn dictionary_id(header: &[u8]) -> u32 {
+    match dictionary_id_end(header) {
+        Some(end) if end > 4 => {
+            let descriptor = header[4];
+            let start = if descriptor & 0x20 == 0 { 6 } else { 5 };
+            header[start..end]
+                .iter()
+                .rev()
+                .fold(0, |id, &byte| id << 8 | u32::from(byte))
+        }
+        _ => 0,
+    }
+}
+
 #[derive(Debug)]
 pub struct ZstdDecoder {
     decoder: Unshared<Decoder>,
+    scanner: FrameScanner,
+    frames: u64,
+    in_frame: bool,
+    multiple_frames: bool,
+    // Keeps the digested prefix alive for as long as the decoder refers to it
+    _prefix: Option<PreparedPrefix>,
+    /// The dictionaries to pick from by the ID in each frame's header.
+    dictionaries: Option<ZstdDictionaries>,
+    /// The ID of the dictionary `decoder` was created with.
+    dictionary_id: u32,
+    /// The start of the next frame, collected until its dictionary ID has been read.
+    header: Vec<u8>,
+    /// Kept to set again on decoders created for another dictionary.
+    window_log_max: Option<u32>,
 }
 
 impl ZstdDecoder {
+    pub(crate) const MAGIC: &'static [u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
     pub(crate) fn new() -> Self {
         Self {
             decoder: Unshared::new(Decoder::new().unwrap()),
+            scanner: FrameScanner::new(),
+            frames: 0,
+            in_frame: false,
+            multiple_frames: true,
+            _prefix: None,
+            dictionaries: None,
+            dictionary_id: 0,
+            header: Vec::new(),
+            window_log_max: None,
+        }
+    }
+
+    pub(crate) fn with_dictionaries(dictionaries: ZstdDictionaries) -> Self {
+        Self {
+            dictionaries: Some(dictionaries),
+            ..Self::new()
         }
     }
+
+    pub(crate) fn with_dict(dictionary: &[u8]) -> Result<Self> {
+        Ok(Self {
+            decoder: Unshared::new(Decoder::with_dictionary(dictionary)?),
+            ..Self::new()
+        })
+    }
+
+    pub(crate) fn with_prefix(prefix: &PreparedPrefix) -> Result<Self> {
+        Ok(Self {
+            decoder: Unshared::new(Decoder::with_prepared_dictionary(
+                prefix.decoder_dictionary(),
+            )?),
+            _prefix: Some(prefix.clone()),
+            ..Self::new()
+        })
+    }
+
+    pub(crate) fn set_window_log_max(&mut self, log: u32) -> Result<()> {
+        self.decoder
+            .get_mut()
+            .set_parameter(params::window_log_max(log)?)?;
+        self.window_log_max = Some(log);
+        Ok(())
+    }
+
+    pub(crate) fn set_multiple_frames(&mut self, enabled: bool) {
+        self.multiple_frames = enabled;
+    }
+
+    pub(crate) fn stored(&self) -> Option<bool> {
+        self.scanner.stored()
+    }
+
+    pub(crate) fn content_size(&self) -> Option<u64> {
+        self.scanner.content_size()
+    }
+
+    /// Collects the start of the next frame from `input` until its dictionary ID has been read,
+    /// then switches to that dictionary and returns the collected bytes, still to be decoded.
+    fn read_header(&mut self, input: &mut PartialBuffer<&[u8]>) -> Result<Option<Vec<u8>>> {
+        loop {
+            match dictionary_id_end(&self.header) {
+                Some(end) if self.header.len() >= end => break,
+                _ => match input.unwritten().first() {
+                    Some(&byte) => {
+                        self.header.push(byte);
+                        input.advance(1);
+                    }
+                    None => return Ok(None),
+                },
+            }
+        }
+
+        let id = dictionary_id(&self.header);
+        if id != self.dictionary_id {
+            let dictionaries = self.dictionaries.as_ref().unwrap();
+            let dictionary = match id {
+                0 => &[][..],
+                _ => dictionaries.get_by_id(id).ok_or_else(|| {
+                    Error::from(CompressionError::invalid_header(
+                        Format::Zstd,
+                        format!("the frame needs dictionary {}, which wasn't given", id),
+                    ))
+                })?,
+            };
+            self.decoder = Unshared::new(Decoder::with_dictionary(dictionary)?);
+            self.dictionary_id = id;
+            if let Some(log) = self.window_log_max {
+                self.set_window_log_max(log)?;
+            }
+        }
+
+        Ok(Some(mem::take(&mut self.header)))
+    }
+
+    /// Decodes from `input`, returning how much of it was read and whether the stream has ended.
+    fn run(
+        &mut self,
+        input: &[u8],
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<(usize, bool)> {
+        let status = self
+            .decoder
+            .get_mut()
+            .run_on_buffers(input, output.unwritten_mut())
+            .map_err(backend_error)?;
+        self.scanner
+            .input(&mut PartialBuffer::new(&input[..status.bytes_read]));
+        output.advance(status.bytes_written);
+
+        // A hint of 0 means the current frame has been completely decoded and flushed
+        self.in_frame |= status.bytes_read > 0;
+        if status.remaining == 0 && self.in_frame {
+            self.frames += 1;
+            self.in_frame = false;
+            // libzstd stops at the end of the frame, leaving any input after it unread
+            if !self.multiple_frames {
+                return Ok((status.bytes_read, true));
+            }
+        }
+
+        // Otherwise libzstd moves on to the next frame by itself and discards skippable frames,
+        // so the stream only ends once the input does
+        Ok((status.bytes_read, false))
+    }
 }
 
 impl Decode for ZstdDecoder {
@@ -22,28 +219,64 @@ impl Decode for ZstdDecoder {
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<bool> {
-        let status = self
-            .decoder
-            .get_mut()
-            .run_on_buffers(input.unwritten(), output.unwritten_mut())?;
-        input.advance(status.bytes_read);
-        output.advance(status.bytes_written);
-        Ok(false)
+        // Picking the dictionary by ID, nothing of a frame is given to libzstd until it is known
+        while self.dictionaries.is_some() && !self.in_frame {
+            let header = match self.read_header(input)? {
+                Some(header) => header,
+                None => return Ok(false),
+            };
+            // The header needs no room in the output, so libzstd always reads all of it
+            let (read, done) = self.run(&header, output)?;
+            debug_assert_eq!(read, header.len());
+            if done {
+                return Ok(true);
+            }
+        }
+
+        let (read, done) = self.run(input.unwritten(), output)?;
+        input.advance(read);
+        Ok(done)
     }
 
     fn flush(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
         let mut out_buf = zstd_safe::OutBuffer::around(output.unwritten_mut());
-        let bytes_left = self.decoder.get_mut().flush(&mut out_buf)?;
+        let bytes_left = self
+            .decoder
+            .get_mut()
+            .flush(&mut out_buf)
+            .map_err(backend_error)?;
         let len = out_buf.as_slice().len();
         output.advance(len);
         Ok(bytes_left == 0)
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        if !self.header.is_empty() {
+            return Err(CompressionError::UnexpectedEof {
+                format: Format::Zstd,
+            }
+            .into());
+        }
+
         let mut out_buf = zstd_safe::OutBuffer::around(output.unwritten_mut());
-        let bytes_left = self.decoder.get_mut().finish(&mut out_buf, true)?;
+        let bytes_left = self
+            .decoder
+            .get_mut()
+            .finish(&mut out_buf, true)
+            .map_err(backend_error)?;
         let len = out_buf.as_slice().len();
         output.advance(len);
         Ok(bytes_left == 0)
     }
+
+    fn between_frames(&self) -> bool {
+        !self.in_frame && self.header.is_empty()
+    }
+
+    fn stats(&self, stats: &mut Stats) {
+        stats.frames = Some(self.frames);
+        stats.blocks = Some(self.scanner.blocks());
+        stats.window_size = self.scanner.window_size();
+        stats.checksum = self.scanner.checksum();
+    }
 }
@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+};
+
+/// A set of trained zstd dictionaries, each used for the content with a given key, such as a
+/// content type or a tenant, for stores compressing many small records of different kinds.
+///
+/// An encoder created with [`with_dictionaries`](crate::bufread::ZstdEncoder::with_dictionaries)
+/// and a key compresses with the dictionary for that key, and its frames record the dictionary's
+/// ID. A decoder created with
+/// [`with_dictionaries`](crate::bufread::ZstdDecoder::with_dictionaries) and the same set reads
+/// that ID from each frame and decodes it with the matching dictionary, without being told the key.
+///
+/// Cloning is cheap, the dictionaries themselves are reference counted.
+#[derive(Clone, Default)]
+pub struct ZstdDictionaries {
+    keys: HashMap<String, u32>,
+    dictionaries: HashMap<u32, Arc<[u8]>>,
+}
+
+impl ZstdDictionaries {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `dictionary`, trained by `zstd --train` or `ZDICT_trainFromBuffer`, for the content
+    /// with `key`, replacing any dictionary added for it before, and returns the dictionary's ID.
+    ///
+    /// Several keys may share a dictionary. Returns an error of kind
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) if `dictionary` isn't a trained
+    /// dictionary, as raw content has no ID to record in the frames, or if a different dictionary
+    /// with the same ID has already been added.
+    pub fn insert(&mut self, key: impl Into<String>, dictionary: &[u8]) -> Result<u32> {
+        let id = zstd_safe::get_dict_id(dictionary).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "only a trained zstd dictionary has an ID to pick it by",
+            )
+        })?;
+
+        match self.dictionaries.get(&id) {
+            Some(existing) if **existing != *dictionary => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "a different zstd dictionary with ID {} was already added",
+                        id
+                    ),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                self.dictionaries.insert(id, dictionary.into());
+            }
+        }
+
+        self.keys.insert(key.into(), id);
+        Ok(id)
+    }
+
+    /// Returns the dictionary used for the content with `key`.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.keys.get(key).and_then(|&id| self.get_by_id(id))
+    }
+
+    /// Returns the dictionary with the given ID.
+    pub fn get_by_id(&self, id: u32) -> Option<&[u8]> {
+        self.dictionaries.get(&id).map(|dictionary| &**dictionary)
+    }
+}
+
+impl fmt::Debug for ZstdDictionaries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdDictionaries")
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
@@ -1,18 +1,149 @@
-use crate::{codec::Encode, unshared::Unshared, util::PartialBuffer};
-use libzstd::stream::raw::{Encoder, Operation};
-use std::io::Result;
+use crate::{
+    codec::{
+        zstd::{PreparedPrefix, ZstdParam},
+        Encode,
+    },
+    unshared::Unshared,
+    util::PartialBuffer,
+};
+use libzstd::{
+    dict::EncoderDictionary,
+    stream::raw::{InBuffer, Operation, OutBuffer},
+};
+use std::io::{Error, ErrorKind, Result};
+use zstd_safe::{CParameter, CStream, ResetDirective, CONTENTSIZE_UNKNOWN};
+
+fn map_error_code(code: usize) -> Error {
+    Error::other(zstd_safe::get_error_name(code))
+}
+
+/// The zstd crate's raw `Encoder`, which doesn't give access to its context to pledge the
+/// content size.
+struct Encoder {
+    context: CStream<'static>,
+    /// The content size written into the header of each frame, `CONTENTSIZE_UNKNOWN` if none.
+    pledged: u64,
+}
+
+impl Encoder {
+    fn new(level: i32) -> Result<Self> {
+        Self::with_dictionary(level, &[])
+    }
+
+    fn with_dictionary(level: i32, dictionary: &[u8]) -> Result<Self> {
+        let mut context = zstd_safe::create_cstream();
+        zstd_safe::init_cstream_using_dict(&mut context, dictionary, level)
+            .map_err(map_error_code)?;
+        Ok(Self {
+            context,
+            pledged: CONTENTSIZE_UNKNOWN,
+        })
+    }
+
+    fn with_prepared_dictionary(dictionary: &EncoderDictionary<'_>) -> Result<Self> {
+        let mut context = zstd_safe::create_cstream();
+        zstd_safe::init_cstream_using_cdict(&mut context, dictionary.as_cdict())
+            .map_err(map_error_code)?;
+        Ok(Self {
+            context,
+            pledged: CONTENTSIZE_UNKNOWN,
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: CParameter) -> Result<()> {
+        zstd_safe::cctx_set_parameter(&mut self.context, parameter).map_err(map_error_code)?;
+        Ok(())
+    }
+
+    fn set_pledged_src_size(&mut self, size: u64) -> Result<()> {
+        zstd_safe::cctx_set_pledged_src_size(&mut self.context, size).map_err(map_error_code)?;
+        self.pledged = size;
+        Ok(())
+    }
+}
+
+impl Operation for Encoder {
+    fn run(&mut self, input: &mut InBuffer<'_>, output: &mut OutBuffer<'_>) -> Result<usize> {
+        zstd_safe::compress_stream(&mut self.context, output, input).map_err(map_error_code)
+    }
+
+    fn flush(&mut self, output: &mut OutBuffer<'_>) -> Result<usize> {
+        zstd_safe::flush_stream(&mut self.context, output).map_err(map_error_code)
+    }
+
+    fn finish(&mut self, output: &mut OutBuffer<'_>, _finished_frame: bool) -> Result<usize> {
+        zstd_safe::end_stream(&mut self.context, output).map_err(map_error_code)
+    }
+
+    fn reinit(&mut self) -> Result<()> {
+        // Unlike `ZSTD_resetCStream` this doesn't take a pledged size of 0 to mean unknown
+        zstd_safe::cctx_reset(&mut self.context, ResetDirective::ZSTD_reset_session_only)
+            .map_err(map_error_code)?;
+        self.set_pledged_src_size(self.pledged)
+    }
+}
 
 #[derive(Debug)]
 pub struct ZstdEncoder {
     encoder: Unshared<Encoder>,
+    // Keeps the digested prefix alive for as long as the encoder refers to it
+    _prefix: Option<PreparedPrefix>,
+    /// The content size pledged for each frame, and how much of it has been consumed.
+    content_size: Option<u64>,
+    consumed: u64,
 }
 
 impl ZstdEncoder {
     pub(crate) fn new(level: i32) -> Self {
         Self {
             encoder: Unshared::new(Encoder::new(level).unwrap()),
+            _prefix: None,
+            content_size: None,
+            consumed: 0,
         }
     }
+
+    pub(crate) fn with_content_size(level: i32, size: u64) -> Self {
+        let mut encoder = Self::new(level);
+        encoder
+            .encoder
+            .get_mut()
+            .set_pledged_src_size(size)
+            .unwrap();
+        encoder.content_size = Some(size);
+        encoder
+    }
+
+    pub(crate) fn with_params(level: i32, params: &[ZstdParam]) -> Result<Self> {
+        let mut encoder = Self::new(level);
+        encoder.set_params(params)?;
+        Ok(encoder)
+    }
+
+    pub(crate) fn set_params(&mut self, params: &[ZstdParam]) -> Result<()> {
+        for param in params {
+            self.encoder.get_mut().set_parameter(param.validate()?)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn with_dict(level: i32, dictionary: &[u8]) -> Result<Self> {
+        Ok(Self {
+            encoder: Unshared::new(Encoder::with_dictionary(level, dictionary)?),
+            ..Self::new(level)
+        })
+    }
+
+    pub(crate) fn with_prefix(prefix: &PreparedPrefix) -> Result<Self> {
+        Ok(Self {
+            encoder: Unshared::new(Encoder::with_prepared_dictionary(
+                prefix.encoder_dictionary(),
+            )?),
+            _prefix: Some(prefix.clone()),
+            content_size: None,
+            consumed: 0,
+        })
+    }
 }
 
 impl Encode for ZstdEncoder {
@@ -21,10 +152,23 @@ impl Encode for ZstdEncoder {
         input: &mut PartialBuffer<&[u8]>,
         output: &mut PartialBuffer<&mut [u8]>,
     ) -> Result<()> {
+        if let Some(size) = self.content_size {
+            if self.consumed + input.unwritten().len() as u64 > size {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "more input than the pledged zstd content size of {} bytes",
+                        size
+                    ),
+                ));
+            }
+        }
+
         let status = self
             .encoder
             .get_mut()
             .run_on_buffers(input.unwritten(), output.unwritten_mut())?;
+        self.consumed += status.bytes_read as u64;
         input.advance(status.bytes_read);
         output.advance(status.bytes_written);
         Ok(())
@@ -39,10 +183,28 @@ impl Encode for ZstdEncoder {
     }
 
     fn finish(&mut self, output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        match self.content_size {
+            Some(size) if self.consumed != size => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "zstd content size of {} bytes was pledged, but the input was {} bytes",
+                        size, self.consumed
+                    ),
+                ))
+            }
+            _ => {}
+        }
+
         let mut out_buf = zstd_safe::OutBuffer::around(output.unwritten_mut());
         let bytes_left = self.encoder.get_mut().finish(&mut out_buf, true)?;
         let len = out_buf.as_slice().len();
         output.advance(len);
         Ok(bytes_left == 0)
     }
+
+    fn reset(&mut self) -> Result<()> {
+        self.consumed = 0;
+        self.encoder.get_mut().reinit()
+    }
 }
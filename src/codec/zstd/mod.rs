@@ -1,4 +1,15 @@
+mod builder;
 mod decoder;
+mod dictionaries;
 mod encoder;
+mod params;
+mod prefix;
+mod scanner;
 
+pub use self::{
+    builder::ZstdEncoderBuilder,
+    dictionaries::ZstdDictionaries,
+    params::{ZstdParam, ZstdStrategy},
+    prefix::PreparedPrefix,
+};
 pub(crate) use self::{decoder::ZstdDecoder, encoder::ZstdEncoder};
@@ -0,0 +1,235 @@
+use std::io::{Error, ErrorKind, Result};
+
+use zstd_safe::{CParameter, DParameter, Strategy};
+
+/// The most worker threads zstd will start, `ZSTDMT_NBWORKERS_MAX`.
+#[cfg(target_pointer_width = "64")]
+const NBWORKERS_MAX: u32 = 200;
+#[cfg(not(target_pointer_width = "64"))]
+const NBWORKERS_MAX: u32 = 64;
+
+/// The bounds of the size of a worker's job, `ZSTDMT_JOBSIZE_MIN` and `ZSTDMT_JOBSIZE_MAX`.
+const JOBSIZE_MIN: u32 = 1 << 20;
+#[cfg(target_pointer_width = "64")]
+const JOBSIZE_MAX: u32 = 1 << 30;
+#[cfg(not(target_pointer_width = "64"))]
+const JOBSIZE_MAX: u32 = 1 << 29;
+
+/// The longest window (and so the largest `chainLog` and `searchLog` bounds) zstd supports
+/// depends on the pointer width.
+#[cfg(target_pointer_width = "64")]
+const WINDOWLOG_MAX: u32 = 31;
+#[cfg(not(target_pointer_width = "64"))]
+const WINDOWLOG_MAX: u32 = 30;
+
+/// The match finding strategy of a zstd encoder, from the fastest to the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZstdStrategy {
+    /// `ZSTD_fast`.
+    Fast,
+    /// `ZSTD_dfast`.
+    Dfast,
+    /// `ZSTD_greedy`.
+    Greedy,
+    /// `ZSTD_lazy`.
+    Lazy,
+    /// `ZSTD_lazy2`.
+    Lazy2,
+    /// `ZSTD_btlazy2`.
+    Btlazy2,
+    /// `ZSTD_btopt`.
+    Btopt,
+    /// `ZSTD_btultra`.
+    Btultra,
+    /// `ZSTD_btultra2`.
+    Btultra2,
+}
+
+impl From<ZstdStrategy> for Strategy {
+    fn from(strategy: ZstdStrategy) -> Self {
+        match strategy {
+            ZstdStrategy::Fast => Strategy::ZSTD_fast,
+            ZstdStrategy::Dfast => Strategy::ZSTD_dfast,
+            ZstdStrategy::Greedy => Strategy::ZSTD_greedy,
+            ZstdStrategy::Lazy => Strategy::ZSTD_lazy,
+            ZstdStrategy::Lazy2 => Strategy::ZSTD_lazy2,
+            ZstdStrategy::Btlazy2 => Strategy::ZSTD_btlazy2,
+            ZstdStrategy::Btopt => Strategy::ZSTD_btopt,
+            ZstdStrategy::Btultra => Strategy::ZSTD_btultra,
+            ZstdStrategy::Btultra2 => Strategy::ZSTD_btultra2,
+        }
+    }
+}
+
+/// An advanced compression parameter for zstd encoders, overriding the value chosen by the
+/// compression level.
+///
+/// These are mostly of interest when tuning for a specific dataset, see the zstd manual for what
+/// each of them does. Each is checked against the bounds zstd allows for it when the encoder is
+/// created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ZstdParam {
+    /// The log2 of the largest distance a match may refer back, `ZSTD_c_windowLog`, from 10 to 31
+    /// (30 on 32-bit platforms).
+    ///
+    /// Frames with a window log above 27 are rejected by decoders unless they raise their
+    /// `window_log_max` to match, including the `zstd` command line tool without `--long`.
+    WindowLog(u32),
+    /// The log2 of the size of the multi-probe search table, `ZSTD_c_chainLog`, from 6 to 30 (29
+    /// on 32-bit platforms).
+    ChainLog(u32),
+    /// The log2 of the number of searches to perform, `ZSTD_c_searchLog`, from 1 to 30 (29 on
+    /// 32-bit platforms).
+    SearchLog(u32),
+    /// The minimum length of a match searched for, `ZSTD_c_minMatch`, from 3 to 7.
+    MinMatch(u32),
+    /// The meaning depends on the strategy, for the optimal parsing strategies it is the length
+    /// of a match long enough to stop searching, `ZSTD_c_targetLength`, from 0 to 131072.
+    TargetLength(u32),
+    /// The match finding strategy, `ZSTD_c_strategy`.
+    Strategy(ZstdStrategy),
+    /// Whether to also search for long matches far back in the input,
+    /// `ZSTD_c_enableLongDistanceMatching`, which pays off for large inputs with a large window
+    /// log. It raises the window log to 27 unless that is set explicitly.
+    EnableLongDistanceMatching(bool),
+    /// The log2 of the size of the table long distance matching looks matches up in,
+    /// `ZSTD_c_ldmHashLog`, from 6 to 30. Larger tables find more of the distant repeats at the
+    /// cost of memory and speed, by default it is 7 less than the window log.
+    ///
+    /// This and the other `Ldm` parameters only have an effect with
+    /// [`EnableLongDistanceMatching`](Self::EnableLongDistanceMatching).
+    LdmHashLog(u32),
+    /// The minimum length of a match long distance matching looks for, `ZSTD_c_ldmMinMatch`,
+    /// from 4 to 4096, by default 64.
+    LdmMinMatch(u32),
+    /// The log2 of the number of entries in each bucket of the long distance matching table,
+    /// `ZSTD_c_ldmBucketSizeLog`, from 1 to 8, by default 3.
+    LdmBucketSizeLog(u32),
+    /// The log2 of how often positions are inserted into and looked up in the long distance
+    /// matching table, `ZSTD_c_ldmHashRateLog`, from 0 to 25 (24 on 32-bit platforms). Larger
+    /// values are faster but find fewer matches, by default it is the window log less the
+    /// `LdmHashLog`.
+    LdmHashRateLog(u32),
+    /// The number of worker threads compressing in the background, `ZSTD_c_nbWorkers`, from 0 to
+    /// 200 (64 on 32-bit platforms). With the default of `0` compression happens in the calls to
+    /// the encoder.
+    ///
+    /// Workers compress the input in jobs of several megabytes, so output comes in larger bursts:
+    /// writes can be accepted without producing any output until a job is complete, and flushing
+    /// waits for the jobs in progress. The output is an ordinary frame that any decoder can read.
+    ///
+    /// Values above `0` need the `zstdmt` feature, which builds zstd with threading support.
+    ///
+    /// The input is cut into jobs at fixed offsets, set by [`JobSize`](Self::JobSize), and their
+    /// output is written in order, so how the workers happen to be scheduled never changes it:
+    /// the same input compressed with the same level, parameters and number of workers is
+    /// byte-identical from run to run.
+    NbWorkers(u32),
+    /// The size of the input each worker compresses at once, `ZSTD_c_jobSize`, from 1 MiB to 1
+    /// GiB (512 MiB on 32-bit platforms). By default it is chosen from the window log, so fixing
+    /// it keeps the output the same when other parameters are tuned. Only has an effect with
+    /// [`NbWorkers`](Self::NbWorkers) above `0`.
+    JobSize(u32),
+}
+
+/// Checks the largest window log a decoder accepts is within zstd's bounds, returning the
+/// parameter to set.
+pub(crate) fn window_log_max(log: u32) -> Result<DParameter> {
+    if !(10..=WINDOWLOG_MAX).contains(&log) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "zstd windowLogMax of {} is out of range, it must be from 10 to {}",
+                log, WINDOWLOG_MAX
+            ),
+        ));
+    }
+
+    Ok(DParameter::WindowLogMax(log))
+}
+
+impl ZstdParam {
+    fn name(self) -> &'static str {
+        match self {
+            ZstdParam::WindowLog(_) => "windowLog",
+            ZstdParam::ChainLog(_) => "chainLog",
+            ZstdParam::SearchLog(_) => "searchLog",
+            ZstdParam::MinMatch(_) => "minMatch",
+            ZstdParam::TargetLength(_) => "targetLength",
+            ZstdParam::Strategy(_) => "strategy",
+            ZstdParam::EnableLongDistanceMatching(_) => "enableLongDistanceMatching",
+            ZstdParam::LdmHashLog(_) => "ldmHashLog",
+            ZstdParam::LdmMinMatch(_) => "ldmMinMatch",
+            ZstdParam::LdmBucketSizeLog(_) => "ldmBucketSizeLog",
+            ZstdParam::LdmHashRateLog(_) => "ldmHashRateLog",
+            ZstdParam::NbWorkers(_) => "nbWorkers",
+            ZstdParam::JobSize(_) => "jobSize",
+        }
+    }
+
+    /// The value and its bounds from `zstd.h` for the numeric parameters. zstd itself treats a
+    /// value of `0` as "use the default" rather than rejecting it, which would make a typo
+    /// silently ignored.
+    fn bounds(self) -> Option<(u32, u32, u32)> {
+        match self {
+            ZstdParam::WindowLog(value) => Some((value, 10, WINDOWLOG_MAX)),
+            ZstdParam::ChainLog(value) => Some((value, 6, WINDOWLOG_MAX - 1)),
+            ZstdParam::SearchLog(value) => Some((value, 1, WINDOWLOG_MAX - 1)),
+            ZstdParam::MinMatch(value) => Some((value, 3, 7)),
+            ZstdParam::TargetLength(value) => Some((value, 0, 128 * 1024)),
+            ZstdParam::LdmHashLog(value) => Some((value, 6, 30)),
+            ZstdParam::LdmMinMatch(value) => Some((value, 4, 4096)),
+            ZstdParam::LdmBucketSizeLog(value) => Some((value, 1, 8)),
+            ZstdParam::LdmHashRateLog(value) => Some((value, 0, WINDOWLOG_MAX - 6)),
+            ZstdParam::NbWorkers(value) => Some((value, 0, NBWORKERS_MAX)),
+            ZstdParam::JobSize(value) => Some((value, JOBSIZE_MIN, JOBSIZE_MAX)),
+            ZstdParam::Strategy(_) | ZstdParam::EnableLongDistanceMatching(_) => None,
+        }
+    }
+
+    /// Checks the value is within zstd's bounds, returning the parameter to set.
+    pub(crate) fn validate(self) -> Result<CParameter> {
+        if let ZstdParam::NbWorkers(workers) = self {
+            if workers > 0 && !cfg!(feature = "zstdmt") {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "zstd worker threads need the `zstdmt` feature of async-compression",
+                ));
+            }
+        }
+
+        if let Some((value, min, max)) = self.bounds() {
+            if value < min || value > max {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "zstd {} of {} is out of range, it must be from {} to {}",
+                        self.name(),
+                        value,
+                        min,
+                        max
+                    ),
+                ));
+            }
+        }
+
+        Ok(match self {
+            ZstdParam::WindowLog(value) => CParameter::WindowLog(value),
+            ZstdParam::ChainLog(value) => CParameter::ChainLog(value),
+            ZstdParam::SearchLog(value) => CParameter::SearchLog(value),
+            ZstdParam::MinMatch(value) => CParameter::MinMatch(value),
+            ZstdParam::TargetLength(value) => CParameter::TargetLength(value),
+            ZstdParam::Strategy(strategy) => CParameter::Strategy(strategy.into()),
+            ZstdParam::EnableLongDistanceMatching(enable) => {
+                CParameter::EnableLongDistanceMatching(enable)
+            }
+            ZstdParam::LdmHashLog(value) => CParameter::LdmHashLog(value),
+            ZstdParam::LdmMinMatch(value) => CParameter::LdmMinMatch(value),
+            ZstdParam::LdmBucketSizeLog(value) => CParameter::LdmBucketSizeLog(value),
+            ZstdParam::LdmHashRateLog(value) => CParameter::LdmHashRateLog(value),
+            ZstdParam::NbWorkers(value) => CParameter::NbWorkers(value),
+            ZstdParam::JobSize(value) => CParameter::JobSize(value),
+        })
+    }
+}
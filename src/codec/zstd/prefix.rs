@@ -0,0 +1,76 @@
+use std::{fmt, sync::Arc};
+
+use libzstd::dict::{DecoderDictionary, EncoderDictionary};
+
+/// A raw prefix that has been digested once, so that it can be shared between any number of zstd
+/// encoders and decoders without each of them loading it again.
+///
+/// Unlike a trained dictionary the prefix is plain content, such as a previous version of the
+/// data being compressed, that the compressed frames may refer back to. The frames do not record
+/// which prefix was used, they can only be decoded by a decoder given the same prefix. A prefix
+/// which happens to start with the zstd dictionary magic number is loaded as a dictionary instead.
+///
+/// Cloning a `PreparedPrefix` is cheap, the digested state is reference counted.
+#[derive(Clone)]
+pub struct PreparedPrefix {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    // The dictionaries borrow `prefix`, so they must be declared (and so dropped) before it
+    encoder: EncoderDictionary<'static>,
+    decoder: DecoderDictionary<'static>,
+    prefix: Vec<u8>,
+    level: i32,
+}
+
+impl PreparedPrefix {
+    /// Digests `prefix` for use by encoders compressing at `level`, and by decoders.
+    ///
+    /// The `level` argument here can range from 1-22. A level of `0` will use zstd's default,
+    /// which is `3`.
+    pub fn new(prefix: &[u8], level: i32) -> Self {
+        let prefix = prefix.to_vec();
+
+        // SAFETY: the slice points into the heap allocation owned by `prefix`, which is never
+        // modified and only freed after the dictionaries borrowing it have been dropped
+        let borrowed: &'static [u8] =
+            unsafe { std::slice::from_raw_parts(prefix.as_ptr(), prefix.len()) };
+
+        Self {
+            inner: Arc::new(Inner {
+                encoder: EncoderDictionary::new(borrowed, level),
+                decoder: DecoderDictionary::new(borrowed),
+                prefix,
+                level,
+            }),
+        }
+    }
+
+    /// The raw prefix content.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner.prefix
+    }
+
+    /// The compression level encoders using this prefix will compress at.
+    pub fn level(&self) -> i32 {
+        self.inner.level
+    }
+
+    pub(crate) fn encoder_dictionary(&self) -> &EncoderDictionary<'static> {
+        &self.inner.encoder
+    }
+
+    pub(crate) fn decoder_dictionary(&self) -> &DecoderDictionary<'static> {
+        &self.inner.decoder
+    }
+}
+
+impl fmt::Debug for PreparedPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedPrefix")
+            .field("len", &self.inner.prefix.len())
+            .field("level", &self.inner.level)
+            .finish()
+    }
+}
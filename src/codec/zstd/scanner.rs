@@ -0,0 +1,281 @@
+use crate::util::PartialBuffer;
+
+const MAGIC: u32 = 0xFD2F_B528;
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFF_FFF0;
+
+#[derive(Debug)]
+enum State {
+    Magic(PartialBuffer<[u8; 4]>),
+    SkippableLen(PartialBuffer<[u8; 4]>),
+    Descriptor,
+    /// The lengths of the dictionary ID and content size following the window descriptor.
+    Window(usize, usize),
+    /// The dictionary ID to skip, then the length of the content size.
+    Header(usize, usize),
+    ContentSize(PartialBuffer<[u8; 8]>, usize),
+    BlockHeader(PartialBuffer<[u8; 3]>),
+    Block(usize, bool),
+    Checksum(PartialBuffer<[u8; 4]>),
+    Skip(usize),
+    /// Not something we understand, the decoder will report the error.
+    Invalid,
+}
+
+/// Follows the frame and block headers in the input given to a decoder to track whether the
+/// stream consists only of raw (uncompressed) blocks, the number of blocks, and the content size,
+/// window size and checksum of each frame.
+///
+/// Every block header carries the size of its content, or its compressed size, so the scanner can
+/// skip over the blocks without decoding them.
+#[derive(Debug)]
+pub(crate) struct FrameScanner {
+    state: State,
+    has_checksum: bool,
+    single_segment: bool,
+    frame_seen: bool,
+    compressed_seen: bool,
+    blocks: u64,
+    content_size: Option<u64>,
+    window_size: Option<u64>,
+    checksum: Option<u32>,
+}
+
+impl FrameScanner {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: State::Magic(<_>::default()),
+            has_checksum: false,
+            single_segment: false,
+            frame_seen: false,
+            compressed_seen: false,
+            blocks: 0,
+            content_size: None,
+            window_size: None,
+            checksum: None,
+        }
+    }
+
+    /// Returns `Some(true)` once a whole frame has been seen and every block was raw,
+    /// `Some(false)` once any compressed block has been seen, and `None` before either is known.
+    pub(crate) fn stored(&self) -> Option<bool> {
+        if self.compressed_seen {
+            Some(false)
+        } else if self.frame_seen {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content size declared by the header of the last frame started, if it has one.
+    pub(crate) fn content_size(&self) -> Option<u64> {
+        self.content_size
+    }
+
+    /// Returns the number of complete blocks seen, across all frames.
+    pub(crate) fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the window size of the last frame started, once its header has been seen.
+    pub(crate) fn window_size(&self) -> Option<u64> {
+        self.window_size
+    }
+
+    /// Returns the content checksum stored at the end of the last frame which had one.
+    pub(crate) fn checksum(&self) -> Option<u32> {
+        self.checksum
+    }
+
+    pub(crate) fn input(&mut self, input: &mut PartialBuffer<&[u8]>) {
+        loop {
+            match &mut self.state {
+                State::Magic(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let magic = u32::from_le_bytes(data.take().into_inner());
+                    self.state = if magic == MAGIC {
+                        State::Descriptor
+                    } else if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+                        State::SkippableLen(<_>::default())
+                    } else {
+                        State::Invalid
+                    };
+                }
+
+                State::SkippableLen(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let len = u32::from_le_bytes(data.take().into_inner());
+                    self.state = State::Skip(len as usize);
+                }
+
+                State::Descriptor => {
+                    let descriptor = match input.unwritten().first() {
+                        Some(&descriptor) => descriptor,
+                        None => return,
+                    };
+                    input.advance(1);
+
+                    self.single_segment = descriptor & 0b0010_0000 != 0;
+                    self.has_checksum = descriptor & 0b0000_0100 != 0;
+                    self.window_size = None;
+
+                    let dict_id_len = [0, 1, 2, 4][usize::from(descriptor & 0b11)];
+                    let content_size_len = match descriptor >> 6 {
+                        0 if self.single_segment => 1,
+                        0 => 0,
+                        1 => 2,
+                        2 => 4,
+                        _ => 8,
+                    };
+
+                    // A single segment frame has no window descriptor, its window is the content
+                    self.state = if self.single_segment {
+                        State::Header(dict_id_len, content_size_len)
+                    } else {
+                        State::Window(dict_id_len, content_size_len)
+                    };
+                }
+
+                State::Window(dict_id_len, content_size_len) => {
+                    let descriptor = match input.unwritten().first() {
+                        Some(&descriptor) => descriptor,
+                        None => return,
+                    };
+                    input.advance(1);
+
+                    // The exponent is added to the smallest window log of 10, and the mantissa
+                    // adds eighths of that
+                    let base = 1u64 << (10 + (descriptor >> 3));
+                    self.window_size = Some(base + base / 8 * u64::from(descriptor & 0b111));
+
+                    self.state = State::Header(*dict_id_len, *content_size_len);
+                }
+
+                State::Header(remaining, content_size_len) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.state = if *content_size_len == 0 {
+                        self.content_size = None;
+                        State::BlockHeader(<_>::default())
+                    } else {
+                        // The field is read into the end of the buffer, so when taken as little
+                        // endian the rest can be shifted out
+                        let mut data = PartialBuffer::new([0; 8]);
+                        data.advance(8 - *content_size_len);
+                        State::ContentSize(data, *content_size_len)
+                    };
+                }
+
+                State::ContentSize(data, len) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let size = u64::from_le_bytes(data.take().into_inner()) >> (64 - 8 * *len);
+                    // The two byte field is offset to not overlap with the one byte field
+                    self.content_size = Some(if *len == 2 { size + 256 } else { size });
+                    if self.single_segment {
+                        self.window_size = self.content_size;
+                    }
+
+                    self.state = State::BlockHeader(<_>::default());
+                }
+
+                State::BlockHeader(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    let [a, b, c] = data.take().into_inner();
+                    let header = u32::from_le_bytes([a, b, c, 0]);
+                    let last = header & 0b1 != 0;
+                    let size = (header >> 3) as usize;
+
+                    self.state = match (header >> 1) & 0b11 {
+                        // Raw block
+                        0 => State::Block(size, last),
+                        // RLE block, whose content is a single byte repeated
+                        1 => {
+                            self.compressed_seen = true;
+                            State::Block(1, last)
+                        }
+                        // Compressed block, whose size is the compressed size
+                        2 => {
+                            self.compressed_seen = true;
+                            State::Block(size, last)
+                        }
+                        _ => State::Invalid,
+                    };
+                }
+
+                State::Block(remaining, last) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.blocks += 1;
+                    self.state = if *last {
+                        self.frame_seen = true;
+                        if self.has_checksum {
+                            State::Checksum(<_>::default())
+                        } else {
+                            State::Magic(<_>::default())
+                        }
+                    } else {
+                        State::BlockHeader(<_>::default())
+                    };
+                }
+
+                State::Checksum(data) => {
+                    data.copy_unwritten_from(input);
+
+                    if !data.unwritten().is_empty() {
+                        return;
+                    }
+
+                    self.checksum = Some(u32::from_le_bytes(data.take().into_inner()));
+                    self.state = State::Magic(<_>::default());
+                }
+
+                State::Skip(remaining) => {
+                    let len = std::cmp::min(*remaining, input.unwritten().len());
+                    *remaining -= len;
+                    input.advance(len);
+
+                    if *remaining > 0 {
+                        return;
+                    }
+
+                    self.state = State::Magic(<_>::default());
+                }
+
+                State::Invalid => return,
+            }
+        }
+    }
+}
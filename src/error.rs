@@ -0,0 +1,278 @@
+use std::{borrow::Cow, error::Error, fmt, io};
+
+use crate::Format;
+
+/// An error reported by the library implementing a format while decoding.
+///
+/// Decoders return these wrapped in an [`io::Error`] of kind
+/// [`InvalidData`](io::ErrorKind::InvalidData), they can be inspected with
+/// [`io::Error::get_ref`] and [`downcast_ref`](std::error::Error::downcast_ref) to tell apart
+/// failures such as zstd's "Corrupted block detected" and "Version not supported".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError {
+    format: Format,
+    code: Option<i32>,
+    message: Cow<'static, str>,
+}
+
+impl BackendError {
+    #[allow(dead_code)] // unused without any of the backends reporting errors
+    pub(crate) fn new(
+        format: Format,
+        code: Option<i32>,
+        message: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            format,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The format that was being decoded.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// The library's numeric error code, if it has them.
+    ///
+    /// These are the library's own codes, e.g. a `ZSTD_ErrorCode` for zstd or a `BZ_*` value for
+    /// bzip2, and so only meaningful together with [`BackendError::format`].
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// The library's description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} decoding failed: {}", self.format, self.message)?;
+        if let Some(code) = self.code {
+            write!(f, " (error code {})", code)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BackendError {}
+
+impl From<BackendError> for io::Error {
+    fn from(err: BackendError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// A problem with the compressed input found by this crate itself, rather than by the library
+/// implementing the format which reports a [`BackendError`].
+///
+/// Decoders return these wrapped in an [`io::Error`] of kind
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) for [`CompressionError::UnexpectedEof`] and
+/// [`InvalidData`](io::ErrorKind::InvalidData) otherwise, so checking the kind keeps working. The
+/// variant can be inspected with [`io::Error::get_ref`] and
+/// [`downcast_ref`](std::error::Error::downcast_ref). Going over a limit is reported by the
+/// separate [`LimitExceeded`], [`StepLimitExceeded`] and [`MemoryBudgetExceeded`] errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompressionError {
+    /// The input ended part way through the compressed stream.
+    UnexpectedEof {
+        /// The format that was being decoded.
+        format: Format,
+    },
+
+    /// The checksum recorded in the stream doesn't match the one computed over the decompressed
+    /// data.
+    ChecksumMismatch {
+        /// The format that was being decoded.
+        format: Format,
+        /// The checksum recorded in the stream.
+        expected: u32,
+        /// The checksum of the decompressed data.
+        found: u32,
+    },
+
+    /// A header or other framing of the stream is malformed, or asks for something the decoder
+    /// doesn't support, such as a larger window than it was configured with.
+    InvalidHeader {
+        /// The format that was being decoded.
+        format: Format,
+        /// What is wrong with the header.
+        reason: Cow<'static, str>,
+    },
+}
+
+impl CompressionError {
+    /// The format that was being decoded.
+    pub fn format(&self) -> Format {
+        match *self {
+            CompressionError::UnexpectedEof { format }
+            | CompressionError::ChecksumMismatch { format, .. }
+            | CompressionError::InvalidHeader { format, .. } => format,
+        }
+    }
+
+    #[cfg(any(feature = "flate2", feature = "snappy", feature = "zstd"))]
+    pub(crate) fn invalid_header(format: Format, reason: impl Into<Cow<'static, str>>) -> Self {
+        CompressionError::InvalidHeader {
+            format,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::UnexpectedEof { format } => {
+                write!(f, "{} stream ended unexpectedly", format)
+            }
+            CompressionError::ChecksumMismatch {
+                format,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} checksum mismatch, the stream records {:#010x} but the data has {:#010x}",
+                format, expected, found
+            ),
+            CompressionError::InvalidHeader { format, reason } => {
+                write!(f, "invalid {} header: {}", format, reason)
+            }
+        }
+    }
+}
+
+impl Error for CompressionError {}
+
+impl From<CompressionError> for io::Error {
+    fn from(err: CompressionError) -> Self {
+        let kind = match err {
+            CompressionError::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// The error returned once a decoder would produce more output than the limit it was given.
+///
+/// Decoders return this wrapped in an [`io::Error`] of kind [`Other`](io::ErrorKind::Other), none
+/// of the output past the limit is returned before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    limit: u64,
+}
+
+impl LimitExceeded {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+
+    /// The number of decompressed bytes the decoder was limited to.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "decompressed data exceeds the limit of {} bytes",
+            self.limit
+        )
+    }
+}
+
+impl Error for LimitExceeded {}
+
+impl From<LimitExceeded> for io::Error {
+    fn from(err: LimitExceeded) -> Self {
+        io::Error::other(err)
+    }
+}
+
+/// The error returned once a decoder would take more steps than the limit it was given.
+///
+/// Decoders return this wrapped in an [`io::Error`] of kind [`Other`](io::ErrorKind::Other).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepLimitExceeded {
+    limit: u64,
+}
+
+impl StepLimitExceeded {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+
+    /// The number of steps the decoder was limited to.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl fmt::Display for StepLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "decoding takes more than the limit of {} steps",
+            self.limit
+        )
+    }
+}
+
+impl Error for StepLimitExceeded {}
+
+impl From<StepLimitExceeded> for io::Error {
+    fn from(err: StepLimitExceeded) -> Self {
+        io::Error::other(err)
+    }
+}
+
+/// The error returned when a decoder cannot reserve the memory for its buffers from the
+/// [`MemoryBudget`](crate::MemoryBudget) it was given.
+///
+/// Decoders return this wrapped in an [`io::Error`] of kind [`Other`](io::ErrorKind::Other).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudgetExceeded {
+    limit: usize,
+    requested: usize,
+}
+
+impl MemoryBudgetExceeded {
+    pub(crate) fn new(limit: usize, requested: usize) -> Self {
+        Self { limit, requested }
+    }
+
+    /// The number of bytes the budget allows to be reserved at once.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The number of bytes the decoder tried to reserve on top of what it already held.
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+}
+
+impl fmt::Display for MemoryBudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "reserving {} bytes of buffers exceeds the memory budget of {} bytes",
+            self.requested, self.limit
+        )
+    }
+}
+
+impl Error for MemoryBudgetExceeded {}
+
+impl From<MemoryBudgetExceeded> for io::Error {
+    fn from(err: MemoryBudgetExceeded) -> Self {
+        io::Error::other(err)
+    }
+}
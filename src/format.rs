@@ -0,0 +1,182 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
+
+/// A compression format supported by this crate.
+///
+/// All variants are always present, whether or not the corresponding feature was enabled, so that
+/// a format chosen at runtime (e.g. from a configuration file or an HTTP header) can be checked
+/// with [`Format::is_available`] or [`Format::check_available`], and the `encoder`/`decoder`
+/// functions in each IO module return an error instead of failing to compile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// The `brotli` format.
+    Brotli,
+    /// The `bzip2` format, enabled by the `bzip` feature.
+    Bzip,
+    /// The raw `deflate` format.
+    Deflate,
+    /// The `gzip` format.
+    Gzip,
+    /// The `identity` format, which passes the data through unchanged.
+    Identity,
+    /// The `lz4` frame format.
+    Lz4,
+    /// The legacy `lzma` format, also known as "LZMA alone", which has no magic number so it is
+    /// never detected.
+    Lzma,
+    /// The `snappy` framing format.
+    Snappy,
+    /// The `xz` format.
+    Xz,
+    /// The `zlib` format.
+    Zlib,
+    /// The `zstd` format.
+    Zstd,
+}
+
+/// The magic numbers identifying formats, zlib is handled separately as its header has a check
+/// value rather than fixed bytes.
+const MAGIC: &[(Format, &[u8])] = &[
+    (Format::Bzip, b"BZh"),
+    (Format::Gzip, &[0x1f, 0x8b]),
+    (Format::Lz4, &[0x04, 0x22, 0x4d, 0x18]),
+    (Format::Snappy, b"\xff\x06\x00\x00sNaPpY"),
+    (Format::Xz, b"\xfd7zXZ\x00"),
+    (Format::Zstd, &[0x28, 0xb5, 0x2f, 0xfd]),
+];
+
+/// The longest prefix [`Format::detect`] needs to see to make a decision.
+pub(crate) const MAX_MAGIC_LEN: usize = 10;
+
+/// The result of looking for a magic number at the start of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Detection {
+    Format(Format),
+    /// The prefix is the start of a magic number, but too short to tell.
+    Incomplete,
+    Unknown,
+}
+
+/// Identifies the compression format of a buffer from the magic number at its start, without
+/// constructing a decoder, e.g. to decide how to route a request before reading the rest of it.
+///
+/// Returns `None` if the buffer is too short to tell or doesn't start with a known magic number.
+/// Only formats that have one can be recognised, so brotli, raw deflate and lzma are never
+/// returned, and nor is [`Format::Identity`]. A format is recognised whether or not the feature
+/// enabling it is, check [`Format::is_available`] before decoding it.
+///
+/// At most the first 10 bytes are looked at, and nothing is allocated.
+pub fn detect_format(bytes: &[u8]) -> Option<Format> {
+    match Format::detect(&bytes[..bytes.len().min(MAX_MAGIC_LEN)]) {
+        Detection::Format(format) => Some(format),
+        Detection::Incomplete | Detection::Unknown => None,
+    }
+}
+
+impl Format {
+    /// Identifies the format of a stream starting with `prefix` from its magic number. Only
+    /// formats that have one can be detected, which excludes brotli, raw deflate and lzma.
+    pub(crate) fn detect(prefix: &[u8]) -> Detection {
+        let mut incomplete = false;
+        for &(format, magic) in MAGIC {
+            if prefix.starts_with(magic) {
+                return Detection::Format(format);
+            }
+            incomplete |= magic.starts_with(prefix);
+        }
+
+        // A deflate compressed zlib stream with a 32K window, the header is a multiple of 31
+        match prefix {
+            [0x78, flags, ..] if u16::from_be_bytes([0x78, *flags]).is_multiple_of(31) => {
+                return Detection::Format(Format::Zlib)
+            }
+            [0x78] => incomplete = true,
+            _ => {}
+        }
+
+        if incomplete {
+            Detection::Incomplete
+        } else {
+            Detection::Unknown
+        }
+    }
+
+    /// All the formats, whether they are available or not.
+    pub const ALL: &'static [Format] = &[
+        Format::Brotli,
+        Format::Bzip,
+        Format::Deflate,
+        Format::Gzip,
+        Format::Identity,
+        Format::Lz4,
+        Format::Lzma,
+        Format::Snappy,
+        Format::Xz,
+        Format::Zlib,
+        Format::Zstd,
+    ];
+
+    /// The name of the crate feature that enables this format.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Format::Brotli => "brotli",
+            Format::Bzip => "bzip",
+            Format::Deflate => "deflate",
+            Format::Gzip => "gzip",
+            Format::Identity => "identity",
+            Format::Lz4 => "lz4",
+            Format::Lzma => "lzma",
+            Format::Snappy => "snappy",
+            Format::Xz => "xz",
+            Format::Zlib => "zlib",
+            Format::Zstd => "zstd",
+        }
+    }
+
+    /// Returns whether support for this format was compiled in.
+    pub fn is_available(self) -> bool {
+        match self {
+            Format::Brotli => cfg!(feature = "brotli"),
+            Format::Bzip => cfg!(feature = "bzip"),
+            Format::Deflate => cfg!(feature = "deflate"),
+            Format::Gzip => cfg!(feature = "gzip"),
+            Format::Identity => cfg!(feature = "identity"),
+            Format::Lz4 => cfg!(feature = "lz4"),
+            Format::Lzma => cfg!(feature = "lzma"),
+            Format::Snappy => cfg!(feature = "snappy"),
+            Format::Xz => cfg!(feature = "xz"),
+            Format::Zlib => cfg!(feature = "zlib"),
+            Format::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) naming the
+    /// missing feature if support for this format was not compiled in.
+    pub fn check_available(self) -> Result<()> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(self.unavailable())
+        }
+    }
+
+    pub(crate) fn unavailable(self) -> Error {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} support is not available, async-compression was built without the `{}` feature",
+                self,
+                self.feature()
+            ),
+        )
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.feature())
+    }
+}
@@ -0,0 +1,67 @@
+/// A compression level understood by every encoder, accepted by their `with_quality`
+/// constructors, for code that should work whichever format it is given.
+///
+/// Each format maps it onto its own range of levels, which differ in both their bounds and what
+/// they mean, so the same level is not comparable between formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Level {
+    /// The fastest level of the format.
+    Fastest,
+    /// The level of the format giving the smallest output.
+    Best,
+    /// The level the format's own tools use by default.
+    #[default]
+    Default,
+    /// A level in the format's native range, values outside of it are clamped to the nearest
+    /// end. bzip2 only has three levels, so others are rounded to the nearest of 1, 6 and 9.
+    Precise(i32),
+}
+
+impl Level {
+    /// Clamps the level into `min..=max`, with `fastest` and `default` for the named levels.
+    #[allow(dead_code)] // unused without any of the formats with levels
+    fn clamp(self, min: i32, max: i32, fastest: i32, default: i32) -> i32 {
+        match self {
+            Level::Fastest => fastest,
+            Level::Best => max,
+            Level::Default => default,
+            Level::Precise(level) => level.clamp(min, max),
+        }
+    }
+
+    #[cfg(feature = "brotli")]
+    pub(crate) fn into_brotli(self) -> u32 {
+        self.clamp(0, 11, 0, 11) as u32
+    }
+
+    #[cfg(feature = "bzip")]
+    pub(crate) fn into_bzip2(self) -> bzip2::Compression {
+        match self.clamp(1, 9, 1, 6) {
+            1..=3 => bzip2::Compression::Fastest,
+            4..=7 => bzip2::Compression::Default,
+            _ => bzip2::Compression::Best,
+        }
+    }
+
+    #[cfg(feature = "flate2")]
+    pub(crate) fn into_flate2(self) -> flate2::Compression {
+        flate2::Compression::new(self.clamp(0, 9, 1, 6) as u32)
+    }
+
+    #[cfg(feature = "lz4")]
+    pub(crate) fn into_lz4(self) -> u32 {
+        self.clamp(0, 12, 0, 0) as u32
+    }
+
+    /// The preset of the xz and lzma formats.
+    #[cfg(feature = "xz2")]
+    pub(crate) fn into_xz2(self) -> u32 {
+        self.clamp(0, 9, 0, 6) as u32
+    }
+
+    #[cfg(feature = "zstd")]
+    pub(crate) fn into_zstd(self) -> i32 {
+        self.clamp(1, zstd_safe::max_c_level(), 1, 3)
+    }
+}
@@ -37,6 +37,14 @@
     not(feature = "bufread"),
     doc = "`bufread` (*inactive*) | `futures::io::AsyncBufRead`"
 )]
+#![cfg_attr(
+    feature = "read",
+    doc = "[`read`] | [`futures::io::AsyncRead`](futures_io::AsyncRead)"
+)]
+#![cfg_attr(
+    not(feature = "read"),
+    doc = "`read` (*inactive*) | `futures::io::AsyncRead`"
+)]
 #![cfg_attr(
     feature = "write",
     doc = "[`write`](crate::write) | [`futures::io::AsyncWrite`](futures_io::AsyncWrite)"
@@ -53,6 +61,14 @@
     not(feature = "stream"),
     doc = "`stream` (*inactive*) | `futures::stream::Stream<Item = std::io::Result<bytes::Bytes>>`"
 )]
+#![cfg_attr(
+    feature = "tokio",
+    doc = "[`tokio`] | [`tokio::io::AsyncBufRead`](tokio_crate::io::AsyncBufRead), [`tokio::io::AsyncWrite`](tokio_crate::io::AsyncWrite)"
+)]
+#![cfg_attr(
+    not(feature = "tokio"),
+    doc = "`tokio` (*inactive*) | `tokio::io::AsyncBufRead`, `tokio::io::AsyncWrite`"
+)]
 //!
 
 //! ## Compression algorithm
@@ -87,6 +103,14 @@
     not(feature = "deflate"),
     doc = "`deflate` (*inactive*) | `DeflateEncoder`, `DeflateDecoder`"
 )]
+#![cfg_attr(
+    feature = "deflate64",
+    doc = "`deflate64` | [`Deflate64Decoder`](?search=Deflate64Decoder)"
+)]
+#![cfg_attr(
+    not(feature = "deflate64"),
+    doc = "`deflate64` (*inactive*) | `Deflate64Decoder`"
+)]
 #![cfg_attr(
     feature = "gzip",
     doc = "`gzip` | [`GzipEncoder`](?search=GzipEncoder), [`GzipDecoder`](?search=GzipDecoder)"
@@ -95,6 +119,46 @@
     not(feature = "gzip"),
     doc = "`gzip` (*inactive*) | `GzipEncoder`, `GzipDecoder`"
 )]
+#![cfg_attr(
+    feature = "identity",
+    doc = "`identity` | [`IdentityEncoder`](?search=IdentityEncoder), [`IdentityDecoder`](?search=IdentityDecoder)"
+)]
+#![cfg_attr(
+    not(feature = "identity"),
+    doc = "`identity` (*inactive*) | `IdentityEncoder`, `IdentityDecoder`"
+)]
+#![cfg_attr(
+    feature = "lz4",
+    doc = "`lz4` | [`Lz4Encoder`](?search=Lz4Encoder), [`Lz4Decoder`](?search=Lz4Decoder), [`Lz4BlockEncoder`](?search=Lz4BlockEncoder), [`Lz4BlockDecoder`](?search=Lz4BlockDecoder)"
+)]
+#![cfg_attr(
+    not(feature = "lz4"),
+    doc = "`lz4` (*inactive*) | `Lz4Encoder`, `Lz4Decoder`, `Lz4BlockEncoder`, `Lz4BlockDecoder`"
+)]
+#![cfg_attr(
+    feature = "lzma",
+    doc = "`lzma` | [`LzmaEncoder`](?search=LzmaEncoder), [`LzmaDecoder`](?search=LzmaDecoder)"
+)]
+#![cfg_attr(
+    not(feature = "lzma"),
+    doc = "`lzma` (*inactive*) | `LzmaEncoder`, `LzmaDecoder`"
+)]
+#![cfg_attr(
+    feature = "snappy",
+    doc = "`snappy` | [`SnappyEncoder`](?search=SnappyEncoder), [`SnappyDecoder`](?search=SnappyDecoder)"
+)]
+#![cfg_attr(
+    not(feature = "snappy"),
+    doc = "`snappy` (*inactive*) | `SnappyEncoder`, `SnappyDecoder`"
+)]
+#![cfg_attr(
+    feature = "xz",
+    doc = "`xz` | [`XzEncoder`](?search=XzEncoder), [`XzDecoder`](?search=XzDecoder)"
+)]
+#![cfg_attr(
+    not(feature = "xz"),
+    doc = "`xz` (*inactive*) | `XzEncoder`, `XzDecoder`"
+)]
 #![cfg_attr(
     feature = "zlib",
     doc = "`zlib` | [`ZlibEncoder`](?search=ZlibEncoder), [`ZlibDecoder`](?search=ZlibDecoder)"
@@ -113,6 +177,16 @@
 )]
 //!
 
+//! ## Default format
+//!
+//! Code that should not care which format it uses can refer to the `DefaultEncoder` and
+//! `DefaultDecoder` aliases in each of the above modules, these are enabled by one of the
+//! `default-brotli`, `default-bzip`, `default-deflate`, `default-gzip`, `default-identity`,
+//! `default-lz4`, `default-lzma`, `default-snappy`, `default-xz`, `default-zlib` or `default-zstd`
+//! features, which also enable the corresponding algorithm. If more than one is enabled the first in
+//! that list is used.
+//!
+
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(
     missing_docs,
@@ -123,18 +197,47 @@
 
 #[macro_use]
 mod macros;
-mod codec;
+mod budget;
+pub mod codec;
+mod error;
+mod format;
+mod level;
+mod stats;
+mod warning;
 
 #[cfg(feature = "bufread")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bufread")))]
 pub mod bufread;
+#[cfg(feature = "read")]
+#[cfg_attr(docsrs, doc(cfg(feature = "read")))]
+pub mod read;
 #[cfg(feature = "stream")]
 #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
 pub mod stream;
+#[cfg(any(feature = "bufread", feature = "read"))]
+mod tee;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod tokio;
 #[cfg(feature = "write")]
 #[cfg_attr(docsrs, doc(cfg(feature = "write")))]
 pub mod write;
 
+pub use crate::{
+    budget::MemoryBudget,
+    error::{
+        BackendError, CompressionError, LimitExceeded, MemoryBudgetExceeded, StepLimitExceeded,
+    },
+    format::{detect_format, Format},
+    level::Level,
+    stats::Stats,
+    warning::Warning,
+};
+
+#[cfg(any(feature = "bufread", feature = "read"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "bufread", feature = "read"))))]
+pub use crate::tee::TeeDecoder;
+
 /// Types to configure [`flate2`](::flate2) based encoders.
 #[cfg(feature = "flate2")]
 #[cfg_attr(
@@ -143,13 +246,44 @@ pub mod write;
 )]
 pub mod flate2 {
     pub use flate2::Compression;
+
+    #[cfg(feature = "deflate")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "deflate")))]
+    pub use crate::codec::Termination;
+
+    #[cfg(feature = "zlib")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zlib")))]
+    pub use crate::codec::Wrap;
+}
+
+/// Types describing gzip members.
+#[cfg(feature = "gzip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+pub mod gzip {
+    pub use crate::codec::GzipHeader;
+}
+
+/// Types describing legacy `.lzma` streams.
+#[cfg(feature = "lzma")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lzma")))]
+pub mod lzma {
+    pub use crate::codec::LzmaHeader;
 }
 
 /// Types to configure [`brotli2`](::brotli2) based encoders.
 #[cfg(feature = "brotli")]
 #[cfg_attr(docsrs, doc(cfg(feature = "brotli")))]
 pub mod brotli2 {
-    pub use brotli2::CompressParams;
+    pub use brotli2::{CompressMode, CompressParams};
+}
+
+/// Types to configure [`zstd`](::libzstd) based encoders and decoders.
+#[cfg(feature = "zstd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zstd")))]
+pub mod zstd {
+    pub use crate::codec::{
+        PreparedPrefix, ZstdDictionaries, ZstdEncoderBuilder, ZstdParam, ZstdStrategy,
+    };
 }
 
 /// Types to configure [`bzip2`](::bzip2) based encoders.
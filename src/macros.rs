@@ -1,12 +1,12 @@
 macro_rules! algos {
-    (@algo $algo:ident [$algo_s:expr] $decoder:ident $encoder:ident<$inner:ident> $({ $($constructor:tt)* })*) => {
+    (@algo $algo:ident [$algo_s:expr] $decoder:ident $encoder:ident<$inner:ident> $({ $($constructor:tt)* })* $(@enc { $($encoder_methods:tt)* })? $(@dec { $($decoder_methods:tt)* })?) => {
         #[cfg(feature = $algo_s)]
         decoder! {
             /// A
             #[doc = $algo_s]
             /// decoder, or decompressor.
             #[cfg_attr(docsrs, doc(cfg(feature = $algo_s)))]
-            $decoder
+            $decoder $({ $($decoder_methods)* })?
         }
 
         #[cfg(feature = $algo_s)]
@@ -15,11 +15,26 @@ macro_rules! algos {
             #[doc = $algo_s]
             /// encoder, or compressor.
             #[cfg_attr(docsrs, doc(cfg(feature = $algo_s)))]
-            $encoder<$inner> $({ $($constructor)* })*
+            $encoder<$inner> $({ $($constructor)* })* $(@ { $($encoder_methods)* })?
         }
     };
 
-    ($mod:ident<$inner:ident>) => {
+    // `was_stored` for the formats that can tell whether the content was compressed, with any
+    // format specific notes appended to the docs
+    (@was_stored $(#[$attr:meta])*) => {
+        /// Returns whether the stream decoded so far was made up of only stored blocks, meaning
+        /// the encoder did not actually compress the content.
+        ///
+        /// This is `None` until enough of the stream has been read to tell, `Some(false)` as soon
+        /// as a compressed block is seen and `Some(true)` once the end of the stream has been read
+        /// without seeing any compressed blocks.
+        $(#[$attr])*
+        pub fn was_stored(&self) -> Option<bool> {
+            self.inner.get_decoder_ref().stored()
+        }
+    };
+
+    ($($mod:ident)::+ <$inner:ident>) => {
         algos!(@algo brotli ["brotli"] BrotliDecoder BrotliEncoder<$inner> {
             /// The `level` argument here is typically 0-11.
             pub fn new(reader: $inner, level: u32) -> Self {
@@ -27,71 +42,1016 @@ macro_rules! algos {
                 params.quality(level);
                 Self::from_params(reader, &params)
             }
+
+            /// Compresses at the given [`Level`](crate::Level), a quality from 0-11.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                let mut params = brotli2::CompressParams::new();
+                params.quality(level.into_brotli());
+                Self::from_params(inner, &params)
+            }
         } {
+            /// Compresses at the given `quality`, ranging from 0-11, with a window of `2^lgwin - 16`
+            /// bytes, `lgwin` ranging from 10-24, tuned for the kind of input given by `mode`, e.g.
+            /// [`CompressMode::Text`](crate::brotli2::CompressMode::Text) for HTML.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `quality` or `lgwin` is out of range. The non-standard large window extension is not
+            /// supported.
+            pub fn with_params(
+                inner: $inner,
+                quality: u32,
+                lgwin: u32,
+                mode: crate::brotli2::CompressMode,
+            ) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::BrotliEncoder::with_params(quality, lgwin, mode)?,
+                    ),
+                })
+            }
+
+            /// Compresses with the given parameters, which unlike with
+            /// [`with_params`](Self::with_params) are passed on to brotli unchecked.
             pub fn from_params(inner: $inner, params: &brotli2::CompressParams) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::BrotliEncoder::new(params),
                     ),
                 }
             }
+
+            /// Compresses at the given `quality`, ranging from 0-11, with a custom dictionary of
+            /// data likely to occur in the input, which the decoder must also be given.
+            ///
+            /// This is brotli's original custom dictionary, distances reaching before the start of
+            /// the stream refer to the end of the dictionary. It is not the shared dictionary
+            /// format of later brotli versions, which the bundled brotli doesn't support.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `quality` is out of range.
+            pub fn with_dictionary(inner: $inner, quality: u32, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::BrotliEncoder::with_dictionary(quality, dictionary)?,
+                    ),
+                })
+            }
+        } @dec {
+            /// Creates a new decoder which will decompress data compressed with the given custom
+            /// dictionary, see `BrotliEncoder::with_dictionary`.
+            ///
+            /// A stream doesn't record whether it used a dictionary, decoding one without it
+            /// usually fails, as references into the dictionary are read as references into
+            /// brotli's built-in dictionary, but may instead produce the wrong output.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if the
+            /// dictionary is larger than 16 MiB.
+            pub fn with_dictionary(inner: $inner, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::BrotliDecoder::with_dictionary(dictionary)?,
+                    ),
+                })
+            }
         });
 
         algos!(@algo bzip ["bzip"] BzDecoder BzEncoder<$inner> {
             pub fn new(inner: $inner, level: bzip2::Compression) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::BzEncoder::new(level, 30),
                     ),
                 }
             }
+
+            /// Compresses at the given [`Level`](crate::Level), one of the levels 1, 6 and 9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::BzEncoder::new(level.into_bzip2(), 30),
+                    ),
+                }
+            }
+
+            /// Compresses with the given `work_factor`, which controls how much effort bzip2 spends
+            /// sorting repetitive input before switching to its slower but predictable fallback
+            /// algorithm.
+            ///
+            /// It ranges from 1 to 250, lower values fall back sooner which keeps the worst case
+            /// for highly repetitive input in check. `0` means the default of 30 which
+            /// [`new`](Self::new) uses.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `work_factor` is above 250.
+            pub fn with_work_factor(
+                inner: $inner,
+                level: bzip2::Compression,
+                work_factor: u32,
+            ) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::BzEncoder::with_work_factor(level, work_factor)?,
+                    ),
+                })
+            }
+        } @dec {
+            /// Creates a new decoder which, if `small` is true, uses bzip2's alternative
+            /// decompression algorithm. That needs at most around 2300 KiB rather than 3700 KiB
+            /// for streams compressed with the largest 900 KiB blocks, at the cost of decompressing
+            /// at about half the speed, for decoding many streams at once.
+            pub fn with_small(inner: $inner, small: bool) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::BzDecoder::with_small(small),
+                    ),
+                }
+            }
         });
 
         algos!(@algo deflate ["deflate"] DeflateDecoder DeflateEncoder<$inner> {
             pub fn new(inner: $inner, level: flate2::Compression) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::DeflateEncoder::new(level),
                     ),
                 }
             }
+
+            /// Compresses at the given [`Level`](crate::Level), from 0-9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::DeflateEncoder::new(level.into_flate2()),
+                    ),
+                }
+            }
+        } {
+            /// Compresses with the given preset dictionary, which the decoder must also be given.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dictionary(inner: $inner, level: flate2::Compression, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::DeflateEncoder::with_dictionary(level, dictionary)?,
+                    ),
+                })
+            }
+
+            /// Compresses with a window of `2^window_bits` bytes, `window_bits` ranging from 9-15,
+            /// for peers with little memory or to match a negotiated size. The default is 15.
+            ///
+            /// The decoder's window must be at least as large, see
+            /// `DeflateDecoder::with_window_bits`.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `window_bits` is out of range.
+            pub fn with_window_bits(inner: $inner, level: flate2::Compression, window_bits: u8) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::DeflateEncoder::with_window_bits(level, window_bits)?,
+                    ),
+                })
+            }
+        } @enc {
+            /// Sets how the end of the stream is marked, defaults to
+            /// [`Termination::Final`](crate::flate2::Termination::Final).
+            ///
+            /// Streams that don't end with a final block must be decoded by a decoder configured
+            /// with the same termination.
+            pub fn with_termination(mut self, termination: crate::flate2::Termination) -> Self {
+                self.inner.get_encoder_mut().set_termination(termination);
+                self
+            }
+        } @dec {
+            /// Creates a new decoder which will decompress data compressed with the given preset
+            /// dictionary. Raw deflate streams don't record whether a dictionary
+            /// was used, so giving the wrong one produces garbage or fails to decode.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dictionary(inner: $inner, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::DeflateDecoder::with_dictionary(dictionary)?,
+                    ),
+                })
+            }
+
+            /// Creates a new decoder for a stream compressed with a window of at most
+            /// `2^window_bits` bytes, `window_bits` ranging from 9-15, to match a negotiated size.
+            /// The default is 15.
+            ///
+            /// A raw deflate stream doesn't record its window size and the backend keeps a full
+            /// window regardless, so a stream compressed with a larger window is still decoded
+            /// rather than rejected. Use a zlib stream if that needs to be checked.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `window_bits` is out of range.
+            pub fn with_window_bits(inner: $inner, window_bits: u8) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::DeflateDecoder::with_window_bits(window_bits)?,
+                    ),
+                })
+            }
+
+            /// Sets how the end of the stream is expected to be marked, defaults to
+            /// [`Termination::Final`](crate::flate2::Termination::Final).
+            ///
+            /// With the sync flush terminations everything read before the end of the input is
+            /// decoded, a block with the `BFINAL` bit set still ends the stream.
+            pub fn with_termination(mut self, termination: crate::flate2::Termination) -> Self {
+                self.inner.get_decoder_mut().set_termination(termination);
+                self
+            }
+
+            algos!(@was_stored);
         });
 
         algos!(@algo gzip ["gzip"] GzipDecoder GzipEncoder<$inner> {
             pub fn new(inner: $inner, level: flate2::Compression) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::GzipEncoder::new(level),
                     ),
                 }
             }
+
+            /// Compresses at the given [`Level`](crate::Level), from 0-9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::GzipEncoder::new(level.into_flate2()),
+                    ),
+                }
+            }
+        } @enc {
+            /// Sets whether the member ends with the gzip trailer holding the CRC-32 and length of
+            /// the uncompressed data, defaults to `true`.
+            ///
+            /// Leaving it out saves 8 bytes when the output is embedded in a container that
+            /// records its own length and checksum. The output is then not valid gzip, it must be
+            /// decoded by a decoder configured with
+            /// [`without_trailer`](GzipDecoder::without_trailer).
+            pub fn with_trailer(mut self, trailer: bool) -> Self {
+                self.inner.get_encoder_mut().set_trailer(trailer);
+                self
+            }
+        } @dec {
+            algos!(@was_stored
+                ///
+                /// With several gzip members this is only about the first of them, the members
+                /// following it aren't looked at.
+            );
+
+            /// Returns the header of the gzip member being decoded, holding its original filename,
+            /// comment, modification time and so on.
+            ///
+            /// This is `None` until the whole header of the first member has been read, when the
+            /// input has several members it is replaced as each of their headers is read.
+            pub fn header(&self) -> Option<&crate::gzip::GzipHeader> {
+                self.inner.get_decoder_ref().header()
+            }
+
+            /// Returns the CRC-32 of the member decompressed so far, which once the member has
+            /// ended should equal [`declared_checksum`](Self::declared_checksum).
+            pub fn checksum(&self) -> u32 {
+                self.inner.get_decoder_ref().checksum()
+            }
+
+            /// Returns the CRC-32 recorded in the trailer of the member, `None` until the trailer
+            /// has been read.
+            ///
+            /// It is available even when it doesn't match [`checksum`](Self::checksum) and
+            /// decoding failed because of that, so both can be logged.
+            pub fn declared_checksum(&self) -> Option<u32> {
+                self.inner.get_decoder_ref().declared_checksum()
+            }
+
+            /// Expects a single member without the gzip trailer, as written by an encoder with
+            /// [`with_trailer(false)`](GzipEncoder::with_trailer), checking it decompresses to
+            /// `uncompressed_len` bytes given by the container it was embedded in rather than
+            /// against the trailer.
+            ///
+            /// Decoding ends after that member, any data following it is left unread. A member of
+            /// a different length fails with an error of kind
+            /// [`InvalidData`](std::io::ErrorKind::InvalidData).
+            pub fn without_trailer(mut self, uncompressed_len: u64) -> Self {
+                self.inner.get_decoder_mut().set_trailerless(uncompressed_len);
+                self
+            }
+
+            /// Sets whether the members following the first are decoded too, defaults to `true`
+            /// as a gzip file is a series of members.
+            ///
+            /// When disabled decoding ends after the first member and any data following it is
+            /// left unread, for a gzip member embedded in some other data.
+            pub fn with_multiple_members(mut self, enabled: bool) -> Self {
+                self.inner.get_decoder_mut().set_multiple_members(enabled);
+                self
+            }
+        });
+
+        algos!(@algo identity ["identity"] IdentityDecoder IdentityEncoder<$inner> {
+            /// The data is passed through unchanged, matching the HTTP `identity` content-coding.
+            pub fn new(inner: $inner) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::IdentityEncoder::new(),
+                    ),
+                }
+            }
+
+            /// The same as [`new`](Self::new), the format has no levels so `level` is ignored. For
+            /// code choosing the format at runtime.
+            pub fn with_quality(inner: $inner, _level: crate::Level) -> Self {
+                Self::new(inner)
+            }
+        });
+
+        algos!(@algo lz4 ["lz4"] Lz4Decoder Lz4Encoder<$inner> {
+            /// The `level` argument here can range from 0-12, levels above 2 use the slower
+            /// high-compression mode. A level of `0` will use lz4's default fast mode.
+            pub fn new(inner: $inner, level: u32) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::Lz4Encoder::new(level),
+                    ),
+                }
+            }
+
+            /// Compresses at the given [`Level`](crate::Level), from 0-12.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::Lz4Encoder::new(level.into_lz4()),
+                    ),
+                }
+            }
+        });
+
+        #[cfg(feature = "lz4")]
+        decoder! {
+            /// An lz4 block decoder, or decompressor, for the raw block format used inside many
+            /// protocols and databases, as opposed to the self-describing frame format of
+            /// [`Lz4Decoder`].
+            ///
+            /// A block has no magic number, checksum or end marker and doesn't record its
+            /// decompressed size, so the whole input up to the end of the underlying stream is
+            /// decoded as one block, and `decompressed_size` must be given to the constructor,
+            /// as known from the surrounding protocol. A block decompressing to more than that
+            /// fails with an error of kind [`InvalidData`](std::io::ErrorKind::InvalidData), one
+            /// decompressing to less is accepted, so an upper bound works too, but a buffer of the
+            /// whole `decompressed_size` is allocated for the output however little the block
+            /// decompresses to. Input longer than the most lz4 can compress that many bytes to is
+            /// refused with an [`InvalidData`](std::io::ErrorKind::InvalidData) error as soon as
+            /// it is read. The whole block and its output are held in memory, and nothing is
+            /// output until the input has ended.
+            #[cfg_attr(docsrs, doc(cfg(feature = "lz4")))]
+            Lz4BlockDecoder(decompressed_size: usize)
+        }
+
+        #[cfg(feature = "lz4")]
+        encoder! {
+            /// An lz4 block encoder, or compressor, writing the raw block format used inside many
+            /// protocols and databases, as opposed to the self-describing frame format of
+            /// [`Lz4Encoder`].
+            ///
+            /// The whole input is compressed into one block once it has ended, as a block can't
+            /// be written out part way through, so it is held in memory and flushing writes
+            /// nothing. The block doesn't record its decompressed size, which the decoder needs
+            /// to be given, so it must be recorded separately, e.g. from
+            /// [`total_in`](Self::total_in). The input must be less than 2 GiB.
+            #[cfg_attr(docsrs, doc(cfg(feature = "lz4")))]
+            Lz4BlockEncoder<$inner> {
+                /// The `level` argument here can range from 0-12, levels above 2 use the slower
+                /// high-compression mode and larger values are treated as 12. A level of `0` will
+                /// use lz4's default fast mode.
+                pub fn new(inner: $inner, level: u32) -> Self {
+                    Self {
+                        inner: crate::$($mod)::+::generic::Encoder::new(
+                            inner,
+                            crate::codec::Lz4BlockEncoder::new(level),
+                        ),
+                    }
+                }
+
+                /// Compresses at the given [`Level`](crate::Level), from 0-12.
+                pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                    Self::new(inner, level.into_lz4())
+                }
+            }
+        }
+
+        algos!(@algo lzma ["lzma"] LzmaDecoder LzmaEncoder<$inner> {
+            /// The `level` argument here is the preset, ranging from 0-9 like for xz, larger values
+            /// are treated as 9.
+            ///
+            /// The stream is always written with an unknown uncompressed size and an end marker,
+            /// like `xz --format=lzma` does when compressing a pipe. The format can't be flushed
+            /// part way through, so flushing only writes out the output that is already available
+            /// and the input may not be decodable until the encoder is closed.
+            pub fn new(inner: $inner, level: u32) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::LzmaEncoder::new(level),
+                    ),
+                }
+            }
+
+            /// Compresses at the given [`Level`](crate::Level), a preset from 0-9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::LzmaEncoder::new(level.into_xz2()),
+                    ),
+                }
+            }
+        } @dec {
+            /// Returns the header of the stream, holding its properties, dictionary size and, if
+            /// it was known to the encoder, its uncompressed size.
+            ///
+            /// This is `None` until the whole header has been read.
+            pub fn header(&self) -> Option<&crate::lzma::LzmaHeader> {
+                self.inner.get_decoder_ref().header()
+            }
+        });
+
+        algos!(@algo snappy ["snappy"] SnappyDecoder SnappyEncoder<$inner> {
+            /// The data is written in the snappy framing format, with each chunk checksummed.
+            pub fn new(inner: $inner) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::SnappyEncoder::new(),
+                    ),
+                }
+            }
+
+            /// The same as [`new`](Self::new), the format has no levels so `level` is ignored. For
+            /// code choosing the format at runtime.
+            pub fn with_quality(inner: $inner, _level: crate::Level) -> Self {
+                Self::new(inner)
+            }
+        });
+
+        algos!(@algo xz ["xz"] XzDecoder XzEncoder<$inner> {
+            /// The `level` argument here is the xz preset, ranging from 0-9, larger values are
+            /// treated as 9. The `xz` command line tool defaults to `6`.
+            pub fn new(inner: $inner, level: u32) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::XzEncoder::new(level, false),
+                    ),
+                }
+            }
+
+            /// Compresses at the given [`Level`](crate::Level), a preset from 0-9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::XzEncoder::new(level.into_xz2(), false),
+                    ),
+                }
+            }
+        } {
+            /// Uses the extreme variant of the preset `level`, ranging from 0-9, which spends
+            /// considerably more time compressing for a slightly smaller output, like `xz
+            /// --extreme`.
+            pub fn with_extreme(inner: $inner, level: u32) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::XzEncoder::new(level, true),
+                    ),
+                }
+            }
+
+            /// Compresses at the preset `level` with `threads` worker threads, like `xz --threads`.
+            /// The input is cut into blocks of `block_size` bytes, or if `0` three times the
+            /// preset's dictionary size and at least 1 MiB, each compressed on its own by a worker.
+            ///
+            /// The blocks are cut at fixed offsets of the input and written in order, so the
+            /// output only depends on the level and block size, not on how the threads are
+            /// scheduled or how many there are: it is byte-identical from run to run, e.g. for
+            /// reproducible builds. It differs from the output of [`new`](Self::new), which
+            /// doesn't split the input into blocks.
+            ///
+            /// Flushing ends the current block, the multithreaded encoder can't flush part way
+            /// through one.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `threads` is `0` or above 16384.
+            pub fn with_threads(
+                inner: $inner,
+                level: u32,
+                threads: u32,
+                block_size: u64,
+            ) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::XzEncoder::with_threads(level, threads, block_size)?,
+                    ),
+                })
+            }
         });
 
         algos!(@algo zlib ["zlib"] ZlibDecoder ZlibEncoder<$inner> {
             pub fn new(inner: $inner, level: flate2::Compression) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::ZlibEncoder::new(level),
                     ),
                 }
             }
+
+            /// Compresses at the given [`Level`](crate::Level), from 0-9.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZlibEncoder::new(level.into_flate2()),
+                    ),
+                }
+            }
+        } {
+            /// Compresses with the given preset dictionary, which the decoder must also be given.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dictionary(inner: $inner, level: flate2::Compression, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZlibEncoder::with_dictionary(level, dictionary)?,
+                    ),
+                })
+            }
+
+            /// Compresses with a window of `2^window_bits` bytes, `window_bits` ranging from 9-15,
+            /// for peers with little memory or to match a negotiated size. The default is 15.
+            ///
+            /// The decoder's window must be at least as large, see
+            /// `ZlibDecoder::with_window_bits`.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `window_bits` is out of range.
+            pub fn with_window_bits(inner: $inner, level: flate2::Compression, window_bits: u8) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZlibEncoder::with_window_bits(level, window_bits)?,
+                    ),
+                })
+            }
+
+            /// Compresses into a stream wrapped as given by `wrap`, so the same code can produce a
+            /// raw deflate, zlib or gzip stream, which `ZlibDecoder::with_wrap` given the same `wrap`
+            /// decodes.
+            pub fn with_wrap(inner: $inner, level: flate2::Compression, wrap: crate::flate2::Wrap) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZlibEncoder::with_wrap(level, wrap),
+                    ),
+                }
+            }
+        } @enc {
+            /// Returns the Adler-32 checksum of the preset dictionary this encoder was created with,
+            /// which is the dictionary id recorded in the zlib header, or `None` if it has none.
+            ///
+            /// A decoder side can use this to pick the right dictionary out of several.
+            pub fn dictionary_adler32(&self) -> Option<u32> {
+                self.inner.get_encoder_ref().dictionary_adler32()
+            }
+        } @dec {
+            /// Creates a new decoder which will decompress data compressed with the given preset
+            /// dictionary. The zlib header records which dictionary was used,
+            /// decoding fails if it is not the given one or if the stream was compressed without one.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dictionary(inner: $inner, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZlibDecoder::with_dictionary(dictionary)?,
+                    ),
+                })
+            }
+
+            /// Creates a new decoder for a stream compressed with a window of at most
+            /// `2^window_bits` bytes, `window_bits` ranging from 9-15, to match a negotiated size.
+            /// The default is 15.
+            ///
+            /// Decoding a stream compressed with a larger window fails with an error of kind
+            /// [`InvalidData`](std::io::ErrorKind::InvalidData). A zlib stream records its window
+            /// size in its header, so this fails as soon as the header is read.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `window_bits` is out of range.
+            pub fn with_window_bits(inner: $inner, window_bits: u8) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZlibDecoder::with_window_bits(window_bits)?,
+                    ),
+                })
+            }
+
+            /// Creates a new decoder for a stream wrapped as given by `wrap`, as produced by
+            /// `ZlibEncoder::with_wrap`.
+            pub fn with_wrap(inner: $inner, wrap: crate::flate2::Wrap) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZlibDecoder::with_wrap(wrap),
+                    ),
+                }
+            }
+
+            algos!(@was_stored);
+
+            /// Returns the Adler-32 of the data decompressed so far, which once the stream has
+            /// ended should equal [`declared_checksum`](Self::declared_checksum). When decoding a
+            /// gzip stream with [`Wrap::Gzip`](crate::flate2::Wrap) it is the CRC-32 of the member
+            /// instead.
+            pub fn checksum(&self) -> u32 {
+                self.inner.get_decoder_ref().checksum()
+            }
+
+            /// Returns the checksum recorded in the trailer of the stream, `None` until the
+            /// trailer has been read and always for a raw deflate stream, which has no trailer.
+            ///
+            /// It is available even when it doesn't match [`checksum`](Self::checksum) and
+            /// decoding failed because of that, so both can be logged.
+            pub fn declared_checksum(&self) -> Option<u32> {
+                self.inner.get_decoder_ref().declared_checksum()
+            }
         });
 
         algos!(@algo zstd ["zstd"] ZstdDecoder ZstdEncoder<$inner> {
-            /// The `level` argument here can range from 1-21. A level of `0` will use zstd's default, which is `3`.
+            /// The `level` argument here can range from 1-22, the range of zstd's levels. A level
+            /// of `0` will use zstd's default, which is `3`.
             pub fn new(inner: $inner, level: i32) -> Self {
                 Self {
-                    inner: crate::$mod::generic::Encoder::new(
+                    inner: crate::$($mod)::+::generic::Encoder::new(
                         inner,
                         crate::codec::ZstdEncoder::new(level),
                     ),
                 }
             }
+
+            /// Compresses at the given [`Level`](crate::Level), from 1-22, larger values are
+            /// treated as 22.
+            pub fn with_quality(inner: $inner, level: crate::Level) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZstdEncoder::new(level.into_zstd()),
+                    ),
+                }
+            }
+        } {
+            /// Creates a new encoder which will write `size` into the header of the frame as its
+            /// content size, for when the length of the input is known up front. Decoders can then
+            /// allocate for the content ahead of time, and `zstd -l` reports its size.
+            ///
+            /// The input must be exactly `size` bytes, otherwise writing more or finishing the
+            /// stream after fewer fails with an error of kind
+            /// [`InvalidInput`](std::io::ErrorKind::InvalidInput).
+            pub fn with_content_size(inner: $inner, level: i32, size: u64) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZstdEncoder::with_content_size(level, size),
+                    ),
+                }
+            }
+
+            /// Creates a builder to configure the encoder with advanced parameters, such as a large
+            /// window with long distance matching for large inputs.
+            pub fn builder(inner: $inner) -> crate::zstd::ZstdEncoderBuilder<$inner, Self> {
+                crate::zstd::ZstdEncoderBuilder::new(inner, |inner, encoder| Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(inner, encoder),
+                })
+            }
+
+            /// Compresses at the given level with the advanced parameters overriding the values
+            /// the level would choose, the later of any repeated parameters wins.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if a
+            /// parameter is out of the range zstd allows for it.
+            pub fn with_params(inner: $inner, level: i32, params: &[crate::zstd::ZstdParam]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZstdEncoder::with_params(level, params)?,
+                    ),
+                })
+            }
+
+            /// Compresses with the given dictionary, which can be either a dictionary trained by
+            /// `zstd --train` or raw content. The same dictionary must be given to the decoder.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dict(inner: $inner, level: i32, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZstdEncoder::with_dict(level, dictionary)?,
+                    ),
+                })
+            }
+
+            /// Compresses with the dictionary `dictionaries` has for `key`, such as the content
+            /// type of what is being compressed, recording its ID in the frame so a decoder
+            /// created with [`with_dictionaries`](Self::with_dictionaries) picks the same one.
+            ///
+            /// If there is no dictionary for `key` the content is compressed without one, which
+            /// such a decoder also reads back.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dictionaries(
+                inner: $inner,
+                level: i32,
+                dictionaries: &crate::zstd::ZstdDictionaries,
+                key: &str,
+            ) -> std::io::Result<Self> {
+                let encoder = match dictionaries.get(key) {
+                    Some(dictionary) => crate::codec::ZstdEncoder::with_dict(level, dictionary)?,
+                    None => crate::codec::ZstdEncoder::new(level),
+                };
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(inner, encoder),
+                })
+            }
+
+            /// Creates a new encoder which will compress at the level of the given prepared
+            /// prefix, referring back to its content. The same prefix must be given to the
+            /// decoder.
+            ///
+            /// Returns an error if the prefix could not be referenced.
+            pub fn with_prepared_prefix(inner: $inner, prefix: &crate::zstd::PreparedPrefix) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Encoder::new(
+                        inner,
+                        crate::codec::ZstdEncoder::with_prefix(prefix)?,
+                    ),
+                })
+            }
+        } @dec {
+            /// Returns the content size declared in the header of the frame being decoded, or
+            /// `None` if its header doesn't declare one or hasn't been read yet. When the input
+            /// has several frames it is replaced as each of their headers is read.
+            pub fn content_size(&self) -> Option<u64> {
+                self.inner.get_decoder_ref().content_size()
+            }
+
+            /// Creates a new decoder which will decompress data compressed with the given
+            /// dictionary. Data compressed with a different dictionary, or without one, fails to
+            /// decode.
+            ///
+            /// Returns an error if the dictionary could not be loaded.
+            pub fn with_dict(inner: $inner, dictionary: &[u8]) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZstdDecoder::with_dict(dictionary)?,
+                    ),
+                })
+            }
+
+            /// Creates a new decoder which decompresses each frame with the dictionary from
+            /// `dictionaries` whose ID is recorded in the frame's header, or without one if it
+            /// records none, so frames compressed for different keys can follow each other.
+            ///
+            /// A frame recording the ID of a dictionary missing from `dictionaries` fails to
+            /// decode with an [`InvalidHeader`](crate::CompressionError::InvalidHeader) error.
+            pub fn with_dictionaries(inner: $inner, dictionaries: &crate::zstd::ZstdDictionaries) -> Self {
+                Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZstdDecoder::with_dictionaries(dictionaries.clone()),
+                    ),
+                }
+            }
+
+            /// Creates a new decoder which will decompress data compressed with the given
+            /// prepared prefix. Data compressed with a different prefix, or without one, fails to
+            /// decode.
+            ///
+            /// Returns an error if the prefix could not be referenced.
+            pub fn with_prepared_prefix(inner: $inner, prefix: &crate::zstd::PreparedPrefix) -> std::io::Result<Self> {
+                Ok(Self {
+                    inner: crate::$($mod)::+::generic::Decoder::new(
+                        inner,
+                        crate::codec::ZstdDecoder::with_prefix(prefix)?,
+                    ),
+                })
+            }
+
+            /// Sets the log2 of the largest window this decoder will accept, from 10 to 31 (30 on
+            /// 32-bit platforms). Frames needing a larger window are rejected to bound the memory
+            /// used, by default those with a window log above 27.
+            ///
+            /// Streams compressed with a larger [`window_log`](crate::zstd::ZstdParam::WindowLog)
+            /// need this raised to match.
+            ///
+            /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if
+            /// `log` is out of range.
+            pub fn with_window_log_max(mut self, log: u32) -> std::io::Result<Self> {
+                self.inner.get_decoder_mut().set_window_log_max(log)?;
+                Ok(self)
+            }
+
+            /// Sets whether the frames following the first are decoded too, defaults to `true` as
+            /// a zstd stream can be a series of frames.
+            ///
+            /// When disabled decoding ends after the first frame, including a skippable one, and
+            /// any data following it is left unread, for a zstd frame embedded in some other data.
+            pub fn with_multiple_frames(mut self, enabled: bool) -> Self {
+                self.inner.get_decoder_mut().set_multiple_frames(enabled);
+                self
+            }
+
+            algos!(@was_stored
+                ///
+                /// In zstd the stored blocks are called raw blocks and an RLE block counts as
+                /// compressed. This is `Some(true)` as soon as one whole frame has been read, and
+                /// can still turn to `Some(false)` if a later frame has a compressed block.
+            );
         });
     }
 }
+
+macro_rules! formats {
+    ($inner:ident: [$($bound:tt)*] => [$($output:tt)*]) => {
+        decoder! {
+            /// A decoder which detects the format of its input from its magic number, for inputs
+            /// that may be compressed in any of several formats, or not at all.
+            ///
+            /// The bzip2, gzip, lz4, snappy, xz, zlib and zstd formats are recognised, other input
+            /// (including brotli, raw deflate and lzma, which have no magic number) is passed through
+            /// unchanged. Recognising a format whose feature was not enabled is an error of kind
+            /// [`InvalidInput`](std::io::ErrorKind::InvalidInput).
+            AutoDecoder {
+                /// Returns the format the input was detected as, once enough of it has been read
+                /// to tell. Input that wasn't recognised is [`Format::Identity`](crate::Format::Identity).
+                pub fn detected_format(&self) -> Option<crate::Format> {
+                    self.inner.get_decoder_ref().detected_format()
+                }
+
+                /// Only accepts input in the given format, for when the format is already known,
+                /// such as from a content type, and input claiming to be something else must not
+                /// be decoded. Input in any other format, including uncompressed input unless
+                /// `format` is [`Format::Identity`](crate::Format::Identity), fails with an error
+                /// of kind [`InvalidData`](std::io::ErrorKind::InvalidData) before any of it is
+                /// decoded.
+                ///
+                /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) for
+                /// brotli, raw deflate and lzma, which have no magic number to check.
+                pub fn with_expected_format(mut self, format: crate::Format) -> std::io::Result<Self> {
+                    self.inner.get_decoder_mut().set_expected_format(format)?;
+                    Ok(self)
+                }
+            }
+        }
+
+        /// Creates an encoder for a format chosen at runtime, using the format's default
+        /// compression level.
+        ///
+        /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if support
+        /// for `format` was not compiled in, see [`Format::check_available`](crate::Format::check_available).
+        #[allow(unreachable_patterns)]
+        pub fn encoder<'a, $inner: $($bound)* + Send + 'a>(
+            format: crate::Format,
+            inner: $inner,
+        ) -> std::io::Result<std::pin::Pin<Box<dyn $($output)* + Send + 'a>>> {
+            match format {
+                #[cfg(feature = "brotli")]
+                crate::Format::Brotli => Ok(Box::pin(BrotliEncoder::new(inner, 11))),
+                #[cfg(feature = "bzip")]
+                crate::Format::Bzip => Ok(Box::pin(BzEncoder::new(inner, bzip2::Compression::Default))),
+                #[cfg(feature = "deflate")]
+                crate::Format::Deflate => Ok(Box::pin(DeflateEncoder::new(inner, flate2::Compression::default()))),
+                #[cfg(feature = "gzip")]
+                crate::Format::Gzip => Ok(Box::pin(GzipEncoder::new(inner, flate2::Compression::default()))),
+                #[cfg(feature = "identity")]
+                crate::Format::Identity => Ok(Box::pin(IdentityEncoder::new(inner))),
+                #[cfg(feature = "lz4")]
+                crate::Format::Lz4 => Ok(Box::pin(Lz4Encoder::new(inner, 0))),
+                #[cfg(feature = "lzma")]
+                crate::Format::Lzma => Ok(Box::pin(LzmaEncoder::new(inner, 6))),
+                #[cfg(feature = "snappy")]
+                crate::Format::Snappy => Ok(Box::pin(SnappyEncoder::new(inner))),
+                #[cfg(feature = "xz")]
+                crate::Format::Xz => Ok(Box::pin(XzEncoder::new(inner, 6))),
+                #[cfg(feature = "zlib")]
+                crate::Format::Zlib => Ok(Box::pin(ZlibEncoder::new(inner, flate2::Compression::default()))),
+                #[cfg(feature = "zstd")]
+                crate::Format::Zstd => Ok(Box::pin(ZstdEncoder::new(inner, 0))),
+                _ => {
+                    drop(inner);
+                    Err(format.unavailable())
+                }
+            }
+        }
+
+        /// Creates a decoder for a format chosen at runtime.
+        ///
+        /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput) if support
+        /// for `format` was not compiled in, see [`Format::check_available`](crate::Format::check_available).
+        #[allow(unreachable_patterns)]
+        pub fn decoder<'a, $inner: $($bound)* + Send + 'a>(
+            format: crate::Format,
+            inner: $inner,
+        ) -> std::io::Result<std::pin::Pin<Box<dyn $($output)* + Send + 'a>>> {
+            match format {
+                #[cfg(feature = "brotli")]
+                crate::Format::Brotli => Ok(Box::pin(BrotliDecoder::new(inner))),
+                #[cfg(feature = "bzip")]
+                crate::Format::Bzip => Ok(Box::pin(BzDecoder::new(inner))),
+                #[cfg(feature = "deflate")]
+                crate::Format::Deflate => Ok(Box::pin(DeflateDecoder::new(inner))),
+                #[cfg(feature = "gzip")]
+                crate::Format::Gzip => Ok(Box::pin(GzipDecoder::new(inner))),
+                #[cfg(feature = "identity")]
+                crate::Format::Identity => Ok(Box::pin(IdentityDecoder::new(inner))),
+                #[cfg(feature = "lz4")]
+                crate::Format::Lz4 => Ok(Box::pin(Lz4Decoder::new(inner))),
+                #[cfg(feature = "lzma")]
+                crate::Format::Lzma => Ok(Box::pin(LzmaDecoder::new(inner))),
+                #[cfg(feature = "snappy")]
+                crate::Format::Snappy => Ok(Box::pin(SnappyDecoder::new(inner))),
+                #[cfg(feature = "xz")]
+                crate::Format::Xz => Ok(Box::pin(XzDecoder::new(inner))),
+                #[cfg(feature = "zlib")]
+                crate::Format::Zlib => Ok(Box::pin(ZlibDecoder::new(inner))),
+                #[cfg(feature = "zstd")]
+                crate::Format::Zstd => Ok(Box::pin(ZstdDecoder::new(inner))),
+                _ => {
+                    drop(inner);
+                    Err(format.unavailable())
+                }
+            }
+        }
+    };
+}
+
+macro_rules! default_algos {
+    ([$($prev:expr),*] $feature:expr => $encoder:ident $decoder:ident $(, $($rest:tt)*)?) => {
+        /// The encoder for the format chosen by the enabled `default-*` feature.
+        #[cfg(all(feature = $feature, not(any($(feature = $prev),*))))]
+        #[cfg_attr(docsrs, doc(cfg(feature = $feature)))]
+        pub type DefaultEncoder<I> = $encoder<I>;
+
+        /// The decoder for the format chosen by the enabled `default-*` feature.
+        #[cfg(all(feature = $feature, not(any($(feature = $prev),*))))]
+        #[cfg_attr(docsrs, doc(cfg(feature = $feature)))]
+        pub type DefaultDecoder<I> = $decoder<I>;
+
+        default_algos!([$($prev,)* $feature] $($($rest)*)?);
+    };
+
+    ([$($prev:expr),*]) => {};
+
+    () => {
+        default_algos!(
+            []
+            "default-brotli" => BrotliEncoder BrotliDecoder,
+            "default-bzip" => BzEncoder BzDecoder,
+            "default-deflate" => DeflateEncoder DeflateDecoder,
+            "default-gzip" => GzipEncoder GzipDecoder,
+            "default-identity" => IdentityEncoder IdentityDecoder,
+            "default-lz4" => Lz4Encoder Lz4Decoder,
+            "default-lzma" => LzmaEncoder LzmaDecoder,
+            "default-snappy" => SnappyEncoder SnappyDecoder,
+            "default-xz" => XzEncoder XzDecoder,
+            "default-zlib" => ZlibEncoder ZlibDecoder,
+            "default-zstd" => ZstdEncoder ZstdDecoder
+        );
+    };
+}
@@ -0,0 +1,151 @@
+// Originally sourced from `futures_util::io::buf_reader`, needs to be redefined locally as we only
+// depend on `futures-io`, and changed a bit to allow setting the capacity after construction.
+
+use futures_core::ready;
+use futures_io::{AsyncBufRead, AsyncRead};
+use pin_project::pin_project;
+use std::{
+    cmp::min,
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+const DEFAULT_BUF_SIZE: usize = 8192;
+
+#[pin_project]
+pub struct BufReader<R> {
+    #[pin]
+    inner: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+}
+
+impl<R: AsyncRead> BufReader<R> {
+    /// Creates a new `BufReader` with a default buffer capacity. The default is currently 8 KB,
+    /// but may change in the future.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; DEFAULT_BUF_SIZE].into(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+
+    /// Replaces the buffer with one of the specified capacity, any data still in the buffer is
+    /// kept so this must not shrink it below the amount of buffered data.
+    pub fn set_capacity(&mut self, cap: usize) {
+        let buffered = &self.buf[self.pos..self.cap];
+        assert!(cap >= buffered.len(), "BufReader capacity too small");
+
+        let mut buf = vec![0; cap];
+        buf[..buffered.len()].copy_from_slice(buffered);
+
+        self.cap -= self.pos;
+        self.pos = 0;
+        self.buf = buf.into();
+    }
+
+    /// Returns the size of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// It is inadvisable to directly read from the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Gets a pinned mutable reference to the underlying reader.
+    ///
+    /// It is inadvisable to directly read from the underlying reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().inner
+    }
+
+    /// Consumes this `BufReader`, returning the underlying reader.
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Replaces the underlying reader, returning the previous one.
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    pub fn reset(&mut self, inner: R) -> R {
+        self.pos = 0;
+        self.cap = 0;
+        std::mem::replace(&mut self.inner, inner)
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for BufReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // A zero length read doesn't need to pull anything from the underlying reader
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // If we don't have any buffered data and we're doing a massive read (larger than our
+        // internal buffer), bypass our internal buffer entirely.
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            let this = self.project();
+            let res = ready!(this.inner.poll_read(cx, buf));
+            *this.pos = 0;
+            *this.cap = 0;
+            return Poll::Ready(res);
+        }
+
+        let rem = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = min(rem.len(), buf.len());
+        buf[..len].copy_from_slice(&rem[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl<R: AsyncRead> AsyncBufRead for BufReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.project();
+
+        // If we've reached the end of our internal buffer then we need to fetch some more data
+        // from the underlying reader.
+        if *this.pos >= *this.cap {
+            *this.cap = ready!(this.inner.poll_read(cx, this.buf))?;
+            *this.pos = 0;
+        }
+
+        Poll::Ready(Ok(&this.buf[*this.pos..*this.cap]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        *this.pos = min(*this.pos + amt, *this.cap);
+    }
+}
+
+impl<R: AsyncRead + fmt::Debug> fmt::Debug for BufReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufReader")
+            .field("reader", &self.inner)
+            .field(
+                "buffer",
+                &format_args!("{}/{}", self.cap - self.pos, self.buf.len()),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,114 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{codec::Decode, read::BufReader, MemoryBudget};
+use futures_io::AsyncRead;
+use pin_project::pin_project;
+
+/// The `bufread` decoder over an internal buffer, so that any `AsyncRead` can be decoded.
+#[pin_project]
+#[derive(Debug)]
+pub struct Decoder<R: AsyncRead, D: Decode> {
+    #[pin]
+    inner: crate::bufread::Decoder<BufReader<R>, D>,
+}
+
+impl<R: AsyncRead, D: Decode> Decoder<R, D> {
+    /// Creates a new decoder wrapping the reader, using `decoder` for the format.
+    pub fn new(reader: R, decoder: D) -> Self {
+        let reader = BufReader::new(reader);
+        let capacity = reader.capacity();
+        let mut inner = crate::bufread::Decoder::new(reader, decoder);
+        inner.set_reader_memory(capacity);
+        Self { inner }
+    }
+
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.inner.get_mut().set_capacity(capacity);
+        self.inner.set_reader_memory(capacity);
+    }
+
+    /// Limits the total number of decompressed bytes produced, see the `with_limit` method of
+    /// the format decoders.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.inner.set_limit(limit);
+    }
+
+    /// Limits the number of calls into the format's decoder, see the `with_step_limit` method of
+    /// the format decoders.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.inner.set_step_limit(limit);
+    }
+
+    /// Bounds how many bytes are processed in one poll before yielding, see the
+    /// `with_poll_budget` method of the format decoders.
+    pub fn set_poll_budget(&mut self, bytes: usize) {
+        self.inner.set_poll_budget(bytes);
+    }
+
+    /// Reserves the memory of the internal buffer from `budget`, see the `with_memory_budget`
+    /// method of the format decoders.
+    pub fn set_memory_budget(&mut self, budget: &MemoryBudget) {
+        self.inner.set_memory_budget(budget);
+    }
+
+    /// Acquires a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    /// Acquires a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    /// Acquires a pinned mutable reference to the underlying reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    /// Consumes this decoder returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> crate::Stats {
+        self.inner.stats()
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    /// Acquires a reference to the format's decoder.
+    pub fn get_decoder_ref(&self) -> &D {
+        self.inner.get_decoder_ref()
+    }
+
+    /// Acquires a mutable reference to the format's decoder.
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.inner.get_decoder_mut()
+    }
+}
+
+impl<R: AsyncRead, D: Decode> AsyncRead for Decoder<R, D> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
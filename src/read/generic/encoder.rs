@@ -0,0 +1,106 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{codec::Encode, read::BufReader, Warning};
+use futures_io::AsyncRead;
+use pin_project::pin_project;
+
+/// The `bufread` encoder over an internal buffer, so that any `AsyncRead` can be encoded.
+#[pin_project]
+#[derive(Debug)]
+pub struct Encoder<R: AsyncRead, E: Encode> {
+    #[pin]
+    inner: crate::bufread::Encoder<BufReader<R>, E>,
+}
+
+impl<R: AsyncRead, E: Encode> Encoder<R, E> {
+    /// Creates a new encoder wrapping the reader, using `encoder` for the format.
+    pub fn new(reader: R, encoder: E) -> Self {
+        Self {
+            inner: crate::bufread::Encoder::new(BufReader::new(reader), encoder),
+        }
+    }
+
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.inner.get_mut().set_capacity(capacity);
+    }
+
+    /// Calls `hook` with warnings about the input, see the `with_warning_hook` method of the
+    /// format types.
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.inner.set_warning_hook(hook);
+    }
+
+    /// Acquires a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    /// Acquires a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    /// Acquires a pinned mutable reference to the underlying reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    /// Consumes this encoder returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+
+    /// Acquires a reference to the format's encoder.
+    pub fn get_encoder_ref(&self) -> &E {
+        self.inner.get_encoder_ref()
+    }
+
+    /// Acquires a mutable reference to the format's encoder.
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        self.inner.get_encoder_mut()
+    }
+
+    /// Resets the encoder to compress a new stream written to the given reader, returning the
+    /// previous one.
+    pub fn reset(&mut self, reader: R) -> Result<R> {
+        self.inner.reset()?;
+        Ok(self.inner.get_mut().reset(reader))
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> crate::Stats {
+        self.inner.stats()
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so far, or
+    /// `None` until there has been some of both.
+    pub fn current_ratio(&self) -> Option<f32> {
+        self.inner.current_ratio()
+    }
+}
+
+impl<R: AsyncRead, E: Encode> AsyncRead for Encoder<R, E> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
@@ -0,0 +1,169 @@
+macro_rules! decoder {
+    ($(#[$attr:meta])* $name:ident $(($($arg:ident: $ty:ty),*))? $({ $($methods:tt)* })?) => {
+        $(#[$attr])*
+        #[pin_project::pin_project]
+        ///
+        /// This structure implements an [`AsyncRead`](futures_io::AsyncRead) interface and will
+        /// read compressed data from an underlying stream and emit a stream of uncompressed data.
+        ///
+        /// The reader is buffered internally, data following the end of the compressed stream may
+        /// have been read into that buffer and is lost when the decoder is dropped. Only the
+        /// compressed input is buffered, data is decompressed straight into the buffer given to
+        /// [`poll_read`](futures_io::AsyncRead::poll_read).
+        pub struct $name<R: futures_io::AsyncRead> {
+            #[pin]
+            inner: crate::read::Decoder<R, crate::codec::$name>,
+        }
+
+        impl<R: futures_io::AsyncRead> core::fmt::Debug for $name<R> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The reader is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("reader", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
+        impl<R: futures_io::AsyncRead> $name<R> {
+            /// Creates a new decoder which will read compressed data from the given stream and
+            /// emit a uncompressed stream.
+            pub fn new(read: R $($(, $arg: $ty)*)?) -> $name<R> {
+                $name {
+                    inner: crate::read::Decoder::new(read, crate::codec::$name::new($($($arg),*)?)),
+                }
+            }
+
+            /// Sets the capacity of the buffer used to read from the underlying reader, defaults
+            /// to 8 KiB.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Limits the total number of decompressed bytes this decoder will produce, to guard
+            /// against small inputs that decompress to huge outputs.
+            ///
+            /// Once the data would exceed `limit` the decoder fails with an
+            /// [`io::Error`](std::io::Error) wrapping a [`LimitExceeded`](crate::LimitExceeded),
+            /// having produced exactly `limit` bytes before it.
+            pub fn with_limit(mut self, limit: u64) -> Self {
+                self.inner.set_limit(limit);
+                self
+            }
+
+            /// Limits the number of steps this decoder will take, to guard against inputs crafted
+            /// to be slow to decompress even if their output is small.
+            ///
+            /// A step is one call into the library implementing the format, the work done in each
+            /// is bounded by the size of the input chunk and output buffer it is given, so this
+            /// caps the CPU time spent on the stream. The number taken so far is reported by
+            /// [`stats`](Self::stats). Once `limit` steps have been
+            /// taken the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`StepLimitExceeded`](crate::StepLimitExceeded).
+            pub fn with_step_limit(mut self, limit: u64) -> Self {
+                self.inner.set_step_limit(limit);
+                self
+            }
+
+            /// Bounds how many bytes of input and output are processed in one poll, by default
+            /// 256 KiB.
+            ///
+            /// Highly compressed input can otherwise keep a single read busy for a long time,
+            /// starving the other tasks of the executor. Once the budget is used up the read
+            /// returns what has been decoded so far, or if that is nothing wakes the task and
+            /// returns [`Poll::Pending`](std::task::Poll::Pending) to be polled again later.
+            pub fn with_poll_budget(mut self, bytes: usize) -> Self {
+                self.inner.set_poll_budget(bytes);
+                self
+            }
+
+            /// Counts the memory of this decoder's buffers against `budget`, which bounds the memory
+            /// used by many decoders together.
+            ///
+            /// That is the buffer used to read from the underlying reader, see
+            /// [`with_buffer_capacity`](Self::with_buffer_capacity).
+            ///
+            /// It is reserved before the decoder first calls into the format, and again if the
+            /// buffers grow, and released when the decoder is dropped. If the budget does not have
+            /// room the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`MemoryBudgetExceeded`](crate::MemoryBudgetExceeded) without decoding anything,
+            /// it can be retried once other decoders have finished.
+            pub fn with_memory_budget(mut self, budget: &crate::MemoryBudget) -> Self {
+                self.inner.set_memory_budget(budget);
+                self
+            }
+
+            /// Acquires a reference to the underlying reader that this decoder is wrapping.
+            pub fn get_ref(&self) -> &R {
+                self.inner.get_ref()
+            }
+
+            /// Acquires a mutable reference to the underlying reader that this decoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the reader which
+            /// may otherwise confuse this decoder.
+            pub fn get_mut(&mut self) -> &mut R {
+                self.inner.get_mut()
+            }
+
+            /// Acquires a pinned mutable reference to the underlying reader that this decoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the reader which
+            /// may otherwise confuse this decoder.
+            pub fn get_pin_mut(self: std::pin::Pin<&mut Self>) -> std::pin::Pin<&mut R> {
+                self.project().inner.get_pin_mut()
+            }
+
+            /// Consumes this decoder returning the underlying reader.
+            ///
+            /// Note that this may discard internal state of this decoder, including data read from
+            /// the underlying reader into the internal buffer but not yet processed, so care
+            /// should be taken to avoid losing resources when this is called.
+            pub fn into_inner(self) -> R {
+                self.inner.into_inner()
+            }
+
+            /// Returns a snapshot of the statistics of this decoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of compressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of decompressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            $($($methods)*)?
+        }
+
+        impl<R: futures_io::AsyncRead> futures_io::AsyncRead for $name<R> {
+            fn poll_read(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &mut [u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.project().inner.poll_read(cx, buf)
+            }
+        }
+
+        const _: () = {
+            fn _assert() {
+                use crate::util::{_assert_send, _assert_sync};
+                use core::pin::Pin;
+                use futures_io::AsyncRead;
+
+                _assert_send::<$name<Pin<Box<dyn AsyncRead + Send>>>>();
+                _assert_sync::<$name<Pin<Box<dyn AsyncRead + Sync>>>>();
+            }
+        };
+    }
+}
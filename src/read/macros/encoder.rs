@@ -0,0 +1,141 @@
+macro_rules! encoder {
+    ($(#[$attr:meta])* $name:ident<$inner:ident> $({ $($constructor:tt)* })* $(@ { $($methods:tt)* })?) => {
+        $(#[$attr])*
+        #[pin_project::pin_project]
+        ///
+        /// This structure implements an [`AsyncRead`](futures_io::AsyncRead) interface and will
+        /// read uncompressed data from an underlying stream and emit a stream of compressed data.
+        pub struct $name<$inner: futures_io::AsyncRead> {
+            #[pin]
+            inner: crate::read::Encoder<$inner, crate::codec::$name>,
+        }
+
+        impl<$inner: futures_io::AsyncRead> core::fmt::Debug for $name<$inner> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The reader is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("reader", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
+        impl<$inner: futures_io::AsyncRead> $name<$inner> {
+            $(
+                /// Creates a new encoder which will read uncompressed data from the given stream
+                /// and emit a compressed stream.
+                ///
+                $($constructor)*
+            )*
+
+            /// Sets the capacity of the buffer used to read from the underlying reader, defaults
+            /// to 8 KiB.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Calls `hook` with each [`Warning`](crate::Warning) about the input, such as it
+            /// starting with the magic number of a compressed format, which usually means it is
+            /// being compressed twice by mistake. Warnings are only diagnostics, the output is the
+            /// same with or without the hook.
+            pub fn with_warning_hook(
+                mut self,
+                hook: impl Fn(crate::Warning) + Send + Sync + 'static,
+            ) -> Self {
+                self.inner.set_warning_hook(hook);
+                self
+            }
+
+            /// Acquires a reference to the underlying reader that this encoder is wrapping.
+            pub fn get_ref(&self) -> &$inner {
+                self.inner.get_ref()
+            }
+
+            /// Acquires a mutable reference to the underlying reader that this encoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the reader which
+            /// may otherwise confuse this encoder.
+            pub fn get_mut(&mut self) -> &mut $inner {
+                self.inner.get_mut()
+            }
+
+            /// Acquires a pinned mutable reference to the underlying reader that this encoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the reader which
+            /// may otherwise confuse this encoder.
+            pub fn get_pin_mut(self: std::pin::Pin<&mut Self>) -> std::pin::Pin<&mut $inner> {
+                self.project().inner.get_pin_mut()
+            }
+
+            /// Consumes this encoder returning the underlying reader.
+            ///
+            /// Note that this may discard internal state of this encoder, including data read from
+            /// the underlying reader into the internal buffer but not yet processed, so care
+            /// should be taken to avoid losing resources when this is called.
+            pub fn into_inner(self) -> $inner {
+                self.inner.into_inner()
+            }
+
+            /// Resets this encoder to compress a new stream read from `reader`, returning the
+            /// previous reader. The already allocated buffers and compression context are reused
+            /// where the backend supports it, saving the cost of creating a new encoder.
+            ///
+            /// Any of the previous stream that hadn't been compressed yet is discarded, including
+            /// data buffered from the previous reader, and the byte counters restart from zero.
+            pub fn reset(&mut self, reader: $inner) -> std::io::Result<$inner> {
+                self.inner.reset(reader)
+            }
+
+            /// Returns a snapshot of the statistics of this encoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of uncompressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of compressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so
+            /// far, or `None` until both some input has been consumed and some output produced.
+            ///
+            /// Encoders buffer input internally, so this lags behind the input and only settles
+            /// on the final ratio once the whole stream has been compressed.
+            pub fn current_ratio(&self) -> Option<f32> {
+                self.inner.current_ratio()
+            }
+
+            $($($methods)*)?
+        }
+
+        impl<$inner: futures_io::AsyncRead> futures_io::AsyncRead for $name<$inner> {
+            fn poll_read(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &mut [u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.project().inner.poll_read(cx, buf)
+            }
+        }
+
+        const _: () = {
+            fn _assert() {
+                use crate::util::{_assert_send, _assert_sync};
+                use core::pin::Pin;
+                use futures_io::AsyncRead;
+
+                _assert_send::<$name<Pin<Box<dyn AsyncRead + Send>>>>();
+                _assert_sync::<$name<Pin<Box<dyn AsyncRead + Sync>>>>();
+            }
+        };
+    }
+}
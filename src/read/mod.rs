@@ -0,0 +1,19 @@
+//! Types which operate over [`AsyncRead`](futures_io::AsyncRead) streams, both encoders and
+//! decoders for various formats.
+//!
+//! These buffer the reader internally, so there is no need to wrap it in a `BufReader` first. If
+//! the reader is already buffered the types in [`bufread`](crate::bufread) avoid the extra copy.
+
+#[macro_use]
+mod macros;
+mod generic;
+
+mod buf_reader;
+
+use self::buf_reader::BufReader;
+
+pub use self::generic::{Decoder, Encoder};
+
+algos!(read<R>);
+formats!(R: [futures_io::AsyncRead] => [futures_io::AsyncRead]);
+default_algos!();
@@ -0,0 +1,29 @@
+/// A snapshot of the statistics of an encoder or decoder, returned by their `stats` method.
+///
+/// The fields that only some formats track are `None` for the others.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of bytes consumed from the input so far.
+    pub total_in: u64,
+    /// The number of bytes produced so far.
+    pub total_out: u64,
+    /// The number of complete gzip members or zstd frames (including skippable frames) decoded so
+    /// far.
+    pub frames: Option<u64>,
+    /// The checksum the format keeps of the uncompressed data: the CRC-32 of the current gzip
+    /// member so far, or the Adler-32 of the zlib stream so far. For zstd and lz4 decoders it is
+    /// the content checksum stored at the end of the last frame read which has one, the low 32
+    /// bits of its XXH64 for zstd and its XXH32 for lz4.
+    pub checksum: Option<u32>,
+    /// The number of times a decoder has called into the library implementing the format so far,
+    /// a rough measure of the CPU time spent decoding. `None` for encoders.
+    pub steps: Option<u64>,
+    /// The number of complete blocks decoded so far, the deflate blocks of deflate, deflate64,
+    /// zlib and gzip streams, or the blocks of zstd and lz4 frames. `None` for encoders.
+    pub blocks: Option<u64>,
+    /// The size of the window the decoder keeps to refer back to, as declared by the zlib header
+    /// or the header of the current zstd frame, or as fixed by the format for deflate, deflate64,
+    /// gzip and lz4. `None` for encoders.
+    pub window_size: Option<u64>,
+}
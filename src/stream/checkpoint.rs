@@ -0,0 +1,94 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use bytes::Bytes;
+use futures_core::stream::Stream;
+use pin_project::pin_project;
+
+/// A point in a compressed stream from which decoding can be resumed, yielded by a
+/// [`CheckpointDecoder`].
+///
+/// To resume, create a new decoder over the compressed stream starting at `input_offset`, its
+/// output carries on from `output_offset` in the decompressed stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of compressed bytes before this point, counting any prefix the decoder was
+    /// created with.
+    pub input_offset: u64,
+    /// The number of decompressed bytes before this point.
+    pub output_offset: u64,
+}
+
+/// An item yielded by a [`CheckpointDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointItem {
+    /// A chunk of decompressed data.
+    Data(Bytes),
+    /// A point at which decoding can be resumed, all the data before it has already been yielded.
+    Checkpoint(Checkpoint),
+}
+
+mod private {
+    use super::*;
+
+    pub trait Source {
+        fn poll_next_item(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<CheckpointItem>>>;
+    }
+}
+
+pub(crate) use self::private::Source;
+
+/// Wraps a stream decoder so that, alongside the decompressed data, it yields a [`Checkpoint`]
+/// each time it reaches a point where decoding can be resumed, for consumers that persist their
+/// progress through a long stream.
+///
+/// These are the ends of gzip members and zstd frames, a new decoder needs no state from the
+/// earlier ones to carry on from there. So a stream written as a single member or frame has just
+/// one checkpoint, at its end, and decoders created with options such as a zstd dictionary must
+/// be created with the same options to resume.
+///
+/// Created by the `with_checkpoints` method of the gzip and zstd decoders.
+#[pin_project]
+#[derive(Debug)]
+pub struct CheckpointDecoder<D: Source> {
+    #[pin]
+    decoder: D,
+}
+
+impl<D: Source> CheckpointDecoder<D> {
+    pub(crate) fn new(decoder: D) -> Self {
+        Self { decoder }
+    }
+
+    /// Acquires a reference to the wrapped decoder.
+    pub fn get_ref(&self) -> &D {
+        &self.decoder
+    }
+
+    /// Acquires a mutable reference to the wrapped decoder.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// Consumes this `CheckpointDecoder` returning the wrapped decoder.
+    ///
+    /// The decoder keeps recording checkpoints, but yields only the data from its own
+    /// [`Stream`] implementation.
+    pub fn into_inner(self) -> D {
+        self.decoder
+    }
+}
+
+impl<D: Source> Stream for CheckpointDecoder<D> {
+    type Item = Result<CheckpointItem>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().decoder.poll_next_item(cx)
+    }
+}
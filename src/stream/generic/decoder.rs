@@ -5,7 +5,12 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::{codec::Decode, util::PartialBuffer};
+use crate::{
+    codec::{Decode, Limited},
+    stream::{Checkpoint, CheckpointItem},
+    util::PartialBuffer,
+    MemoryBudget, Stats,
+};
 use bytes::{Bytes, BytesMut};
 use futures_core::{ready, stream::Stream};
 use pin_project::pin_project;
@@ -21,52 +26,147 @@ enum State {
     Invalid,
 }
 
+/// The state machine behind the [`stream`](crate::stream) decoders, generic over the format's
+/// [`Decode`](crate::codec::Decode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Decoder<S: Stream<Item = Result<Bytes>>, D: Decode> {
     #[pin]
     stream: S,
-    decoder: D,
+    decoder: Limited<D>,
     state: State,
     input: Bytes,
     output: BytesMut,
+    output_capacity: usize,
+    total_in: u64,
+    total_out: u64,
+    checkpoints: bool,
+    /// A checkpoint to yield before anything else, once the output before it has been yielded.
+    checkpoint: Option<Checkpoint>,
 }
 
 impl<S: Stream<Item = Result<Bytes>>, D: Decode> Decoder<S, D> {
+    /// Creates a new decoder wrapping the stream, using `decoder` for the format.
     pub fn new(stream: S, decoder: D) -> Self {
+        let mut decoder = Limited::new(decoder);
+        decoder.set_memory(OUTPUT_BUFFER_SIZE);
         Self {
             stream,
             decoder,
             state: State::Reading,
             input: Bytes::new(),
             output: BytesMut::new(),
+            output_capacity: OUTPUT_BUFFER_SIZE,
+            total_in: 0,
+            total_out: 0,
+            checkpoints: false,
+            checkpoint: None,
         }
     }
 
+    /// Creates a new decoder which will first decode `prefix`, then continue with the stream.
+    pub fn with_prefix(prefix: Bytes, stream: S, decoder: D) -> Self {
+        Self {
+            state: State::Writing,
+            input: prefix,
+            ..Self::new(stream, decoder)
+        }
+    }
+
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "output buffer capacity must be non-zero");
+        self.output_capacity = capacity;
+        self.decoder.set_memory(capacity);
+    }
+
+    /// Acquires a reference to the underlying stream.
     pub fn get_ref(&self) -> &S {
         &self.stream
     }
 
+    /// Acquires a mutable reference to the underlying stream.
     pub fn get_mut(&mut self) -> &mut S {
         &mut self.stream
     }
 
+    /// Acquires a pinned mutable reference to the underlying stream.
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
         self.project().stream
     }
 
+    /// Consumes this decoder returning the underlying stream.
     pub fn into_inner(self) -> S {
         self.stream
     }
+
+    /// Acquires a reference to the format's decoder.
+    pub fn get_decoder_ref(&self) -> &D {
+        self.decoder.get_ref()
+    }
+
+    /// Acquires a mutable reference to the format's decoder.
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.decoder.get_mut()
+    }
+
+    /// Limits the total number of decompressed bytes produced, see the `with_limit` method of
+    /// the format decoders.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.decoder.set_limit(limit);
+    }
+
+    /// Limits the number of calls into the format's decoder, see the `with_step_limit` method of
+    /// the format decoders.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.decoder.set_step_limit(limit);
+    }
+
+    /// Reserves the memory of the internal buffer from `budget`, see the `with_memory_budget`
+    /// method of the format decoders.
+    pub fn set_memory_budget(&mut self, budget: &MemoryBudget) {
+        self.decoder.set_memory_budget(budget);
+    }
+
+    pub(crate) fn enable_checkpoints(&mut self) {
+        self.checkpoints = true;
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.decoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
 }
 
-impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
-    type Item = Result<Bytes>;
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+impl<S: Stream<Item = Result<Bytes>>, D: Decode> Decoder<S, D> {
+    fn do_poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<CheckpointItem>>> {
         let mut this = self.project();
 
-        #[allow(clippy::never_loop)] // https://github.com/rust-lang/rust-clippy/issues/4058
         loop {
+            if let Some(checkpoint) = this.checkpoint.take() {
+                return Poll::Ready(Some(Ok(CheckpointItem::Checkpoint(checkpoint))));
+            }
+
             break match mem::replace(this.state, State::Invalid) {
                 State::Reading => {
                     *this.state = State::Reading;
@@ -86,8 +186,8 @@ impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
                         continue;
                     }
 
-                    if this.output.len() < OUTPUT_BUFFER_SIZE {
-                        this.output.resize(OUTPUT_BUFFER_SIZE, 0);
+                    if this.output.len() < *this.output_capacity {
+                        this.output.resize(*this.output_capacity, 0);
                     }
 
                     let mut input = PartialBuffer::new(this.input.as_ref());
@@ -97,6 +197,7 @@ impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
 
                     let input_len = input.written().len();
                     this.input.advance(input_len);
+                    *this.total_in += input_len as u64;
 
                     *this.state = if done {
                         State::Flushing
@@ -105,12 +206,31 @@ impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
                     };
 
                     let output_len = output.written().len();
-                    Poll::Ready(Some(Ok(this.output.split_to(output_len).freeze())))
+                    *this.total_out += output_len as u64;
+
+                    // Decoders stop at the end of each member or frame, so the offsets now are
+                    // where the next one starts
+                    if *this.checkpoints && input_len > 0 && !done && this.decoder.between_frames()
+                    {
+                        *this.checkpoint = Some(Checkpoint {
+                            input_offset: *this.total_in,
+                            output_offset: *this.total_out,
+                        });
+                    }
+
+                    // Don't yield empty items while the decoder is just buffering input
+                    if output_len == 0 && (input_len > 0 || done) {
+                        continue;
+                    }
+
+                    Poll::Ready(Some(Ok(CheckpointItem::Data(
+                        this.output.split_to(output_len).freeze(),
+                    ))))
                 }
 
                 State::Flushing => {
-                    if this.output.len() < OUTPUT_BUFFER_SIZE {
-                        this.output.resize(OUTPUT_BUFFER_SIZE, 0);
+                    if this.output.len() < *this.output_capacity {
+                        this.output.resize(*this.output_capacity, 0);
                     }
 
                     let mut output = PartialBuffer::new(this.output.as_mut());
@@ -120,7 +240,15 @@ impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
                     *this.state = if done { State::Done } else { State::Reading };
 
                     let output_len = output.written().len();
-                    Poll::Ready(Some(Ok(this.output.split_to(output_len).freeze())))
+                    *this.total_out += output_len as u64;
+
+                    if output_len == 0 && done {
+                        continue;
+                    }
+
+                    Poll::Ready(Some(Ok(CheckpointItem::Data(
+                        this.output.split_to(output_len).freeze(),
+                    ))))
                 }
 
                 State::Done => Poll::Ready(None),
@@ -130,3 +258,34 @@ impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
         }
     }
 }
+
+impl<S: Stream<Item = Result<Bytes>>, D: Decode> Decoder<S, D> {
+    pub(crate) fn poll_next_item(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<CheckpointItem>>> {
+        let item = ready!(self.as_mut().do_poll_next(cx));
+
+        // Any error ends the stream, even if the source stream would carry on after it
+        if let Some(Err(_)) = item {
+            *self.project().state = State::Done;
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+impl<S: Stream<Item = Result<Bytes>>, D: Decode> Stream for Decoder<S, D> {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        loop {
+            return Poll::Ready(match ready!(self.as_mut().poll_next_item(cx)) {
+                Some(Ok(CheckpointItem::Data(chunk))) => Some(Ok(chunk)),
+                Some(Ok(CheckpointItem::Checkpoint(_))) => continue,
+                Some(Err(err)) => Some(Err(err)),
+                None => None,
+            });
+        }
+    }
+}
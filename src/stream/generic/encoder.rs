@@ -5,7 +5,7 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::{codec::Encode, util::PartialBuffer};
+use crate::{codec::Encode, util::PartialBuffer, warning::InputSniffer, Stats, Warning};
 use bytes::{Bytes, BytesMut};
 use futures_core::{ready, stream::Stream};
 use pin_project::pin_project;
@@ -16,11 +16,14 @@ const OUTPUT_BUFFER_SIZE: usize = 8_000;
 enum State {
     Reading,
     Writing,
+    Draining,
     Flushing,
     Done,
     Invalid,
 }
 
+/// The state machine behind the [`stream`](crate::stream) encoders, generic over the format's
+/// [`Encode`](crate::codec::Encode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Encoder<S: Stream<Item = Result<Bytes>>, E: Encode> {
@@ -30,40 +33,146 @@ pub struct Encoder<S: Stream<Item = Result<Bytes>>, E: Encode> {
     state: State,
     input: Bytes,
     output: BytesMut,
+    output_capacity: usize,
+    /// The expected size of the whole output, which the buffer is allocated for up front.
+    size_hint: usize,
+    coalesce: usize,
+    coalesced: usize,
+    total_in: u64,
+    total_out: u64,
+    sniffer: InputSniffer,
 }
 
 impl<S: Stream<Item = Result<Bytes>>, E: Encode> Encoder<S, E> {
-    pub(crate) fn new(stream: S, encoder: E) -> Self {
+    /// Creates a new encoder wrapping the stream, using `encoder` for the format.
+    pub fn new(stream: S, encoder: E) -> Self {
         Self {
             stream,
             encoder,
             state: State::Reading,
             input: Bytes::new(),
             output: BytesMut::new(),
+            output_capacity: OUTPUT_BUFFER_SIZE,
+            size_hint: 0,
+            coalesce: 1,
+            coalesced: 0,
+            total_in: 0,
+            total_out: 0,
+            sniffer: InputSniffer::default(),
         }
     }
 
-    pub(crate) fn get_ref(&self) -> &S {
+    /// Sets how many input items may be compressed into one output item, see the
+    /// `with_coalesce` method of the format encoders.
+    pub fn set_coalesce(&mut self, items: usize) {
+        self.coalesce = std::cmp::max(items, 1);
+    }
+
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "output buffer capacity must be non-zero");
+        self.output_capacity = capacity;
+    }
+
+    /// Sets the expected size of the whole output, see the `with_size_hint` method of the format
+    /// encoders.
+    pub fn set_size_hint(&mut self, hint: usize) {
+        self.size_hint = hint;
+    }
+
+    /// Calls `hook` with warnings about the input, see the `with_warning_hook` method of the
+    /// format types.
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.sniffer.set_hook(hook);
+    }
+
+    /// Acquires a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
         &self.stream
     }
 
-    pub(crate) fn get_mut(&mut self) -> &mut S {
+    /// Acquires a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
         &mut self.stream
     }
 
-    pub(crate) fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+    /// Acquires a pinned mutable reference to the underlying stream.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
         self.project().stream
     }
 
-    pub(crate) fn into_inner(self) -> S {
+    /// Consumes this encoder returning the underlying stream.
+    pub fn into_inner(self) -> S {
         self.stream
     }
+
+    /// Acquires a reference to the format's encoder.
+    pub fn get_encoder_ref(&self) -> &E {
+        &self.encoder
+    }
+
+    /// Acquires a mutable reference to the format's encoder.
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// Resets the encoder to compress a new stream read from the given stream, returning the
+    /// previous one.
+    pub fn reset(&mut self, stream: S) -> Result<S> {
+        self.encoder.reset()?;
+        self.state = State::Reading;
+        self.input = Bytes::new();
+        self.output.clear();
+        self.coalesced = 0;
+        self.total_in = 0;
+        self.total_out = 0;
+        self.sniffer.reset();
+        Ok(mem::replace(&mut self.stream, stream))
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.encoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so far, or
+    /// `None` until there has been some of both.
+    pub fn current_ratio(&self) -> Option<f32> {
+        if self.total_in == 0 || self.total_out == 0 {
+            None
+        } else {
+            Some(self.total_out as f32 / self.total_in as f32)
+        }
+    }
 }
 
-impl<S: Stream<Item = Result<Bytes>>, E: Encode> Stream for Encoder<S, E> {
-    type Item = Result<Bytes>;
+impl<S: Stream<Item = Result<Bytes>>, E: Encode> Encoder<S, E> {
+    /// The length to resize the output buffer to before a coding step. While the hinted size
+    /// hasn't been reached that is whatever remains of it, so each chunk is split off the same
+    /// allocation, and the remainder is already the right length without refilling it.
+    fn output_len(size_hint: usize, total_out: u64, capacity: usize) -> usize {
+        let remaining = (size_hint as u64).saturating_sub(total_out) as usize;
+        std::cmp::max(remaining, capacity)
+    }
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+    fn do_poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
         let mut this = self.project();
 
         #[allow(clippy::never_loop)] // https://github.com/rust-lang/rust-clippy/issues/4058
@@ -73,42 +182,71 @@ impl<S: Stream<Item = Result<Bytes>>, E: Encode> Stream for Encoder<S, E> {
                     *this.state = State::Reading;
                     *this.state = match ready!(this.stream.as_mut().poll_next(cx)) {
                         Some(chunk) => {
-                            if this.input.is_empty() {
-                                *this.input = chunk?;
+                            let chunk = chunk?;
+                            if chunk.is_empty() {
+                                State::Reading
                             } else {
-                                this.input.extend_from_slice(&chunk?);
+                                if this.input.is_empty() {
+                                    *this.input = chunk;
+                                } else {
+                                    this.input.extend_from_slice(&chunk);
+                                }
+
+                                *this.coalesced += 1;
+                                if *this.coalesced < *this.coalesce {
+                                    State::Reading
+                                } else {
+                                    *this.coalesced = 0;
+                                    State::Writing
+                                }
                             }
-                            State::Writing
                         }
-                        None => State::Flushing,
+                        None if this.input.is_empty() => State::Flushing,
+                        None => State::Draining,
                     };
                     continue;
                 }
 
-                State::Writing => {
+                state @ State::Writing | state @ State::Draining => {
                     if this.input.is_empty() {
-                        *this.state = State::Reading;
+                        *this.state = match state {
+                            State::Draining => State::Flushing,
+                            _ => State::Reading,
+                        };
                         continue;
                     }
 
-                    *this.state = State::Writing;
+                    *this.state = state;
 
-                    this.output.resize(OUTPUT_BUFFER_SIZE, 0);
+                    let len =
+                        Self::output_len(*this.size_hint, *this.total_out, *this.output_capacity);
+                    this.output.resize(len, 0);
 
                     let mut input = PartialBuffer::new(this.input.as_ref());
                     let mut output = PartialBuffer::new(this.output.as_mut());
 
                     this.encoder.encode(&mut input, &mut output)?;
+                    this.sniffer.sniff(input.written());
 
                     let input_len = input.written().len();
                     this.input.advance(input_len);
+                    *this.total_in += input_len as u64;
 
                     let output_len = output.written().len();
+                    *this.total_out += output_len as u64;
+
+                    // Don't yield empty items while the encoder is just buffering input
+                    if output_len == 0 && input_len > 0 {
+                        continue;
+                    }
+
                     Poll::Ready(Some(Ok(this.output.split_to(output_len).freeze())))
                 }
 
                 State::Flushing => {
-                    this.output.resize(OUTPUT_BUFFER_SIZE, 0);
+                    let len =
+                        Self::output_len(*this.size_hint, *this.total_out, *this.output_capacity);
+                    this.output.resize(len, 0);
 
                     let mut output = PartialBuffer::new(this.output.as_mut());
 
@@ -117,6 +255,12 @@ impl<S: Stream<Item = Result<Bytes>>, E: Encode> Stream for Encoder<S, E> {
                     *this.state = if done { State::Done } else { State::Flushing };
 
                     let output_len = output.written().len();
+                    *this.total_out += output_len as u64;
+
+                    if output_len == 0 && done {
+                        continue;
+                    }
+
                     Poll::Ready(Some(Ok(this.output.split_to(output_len).freeze())))
                 }
 
@@ -127,3 +271,18 @@ impl<S: Stream<Item = Result<Bytes>>, E: Encode> Stream for Encoder<S, E> {
         }
     }
 }
+
+impl<S: Stream<Item = Result<Bytes>>, E: Encode> Stream for Encoder<S, E> {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let item = ready!(self.as_mut().do_poll_next(cx));
+
+        // Any error ends the stream, even if the source stream would carry on after it
+        if let Some(Err(_)) = item {
+            *self.project().state = State::Done;
+        }
+
+        Poll::Ready(item)
+    }
+}
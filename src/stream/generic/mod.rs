@@ -1,4 +1,4 @@
 mod decoder;
 mod encoder;
 
-pub(crate) use self::{decoder::Decoder, encoder::Encoder};
+pub use self::{decoder::Decoder, encoder::Encoder};
@@ -0,0 +1,70 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::io::Result;
+
+use bytes::Bytes;
+use futures_core::stream::Stream;
+
+/// Adapts an [`Iterator`] of buffers already in memory into a [`Stream`], so that they can be
+/// given to the encoders and decoders in this module.
+///
+/// Each buffer is yielded as one chunk, without copying for `Vec<u8>` and [`Bytes`], and the
+/// stream is always ready. The `compress_all` functions of the encoders use one to compress the
+/// buffers without an executor.
+#[derive(Debug)]
+pub struct IterStream<I: Iterator> {
+    iter: I,
+}
+
+impl<I: Iterator> IterStream<I>
+where
+    I::Item: Into<Bytes>,
+{
+    /// Creates a new stream of the buffers from `iter`.
+    pub fn new(iter: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            iter: iter.into_iter(),
+        }
+    }
+
+    /// Consumes this stream returning the rest of the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<I: Iterator> Unpin for IterStream<I> {}
+
+impl<I: Iterator> Stream for IterStream<I>
+where
+    I::Item: Into<Bytes>,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        Poll::Ready(self.get_mut().iter.next().map(|chunk| Ok(chunk.into())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Polls `stream` to its end on the current thread, concatenating the chunks it yields.
+///
+/// There is nothing to wait for, as the streams given to this only depend on an [`IterStream`],
+/// so a `Pending` would only be a yield and is polled again straight away.
+pub(crate) fn collect_ready(stream: impl Stream<Item = Result<Bytes>>) -> Result<Vec<u8>> {
+    let mut stream = Box::pin(stream);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut output = Vec::new();
+    loop {
+        match stream.as_mut().poll_next(&mut cx) {
+            Poll::Ready(Some(chunk)) => output.extend_from_slice(&chunk?),
+            Poll::Ready(None) => return Ok(output),
+            Poll::Pending => {}
+        }
+    }
+}
@@ -0,0 +1,23 @@
+macro_rules! compress_all {
+    ($($algo_s:literal $encoder:ident($($arg:ident: $ty:ty),*);)*) => {$(
+        #[cfg(feature = $algo_s)]
+        impl<I: Iterator> $encoder<crate::stream::IterStream<I>>
+        where
+            I::Item: Into<bytes::Bytes>,
+        {
+            /// Compresses all of `chunks`, e.g. a `Vec<Vec<u8>>` already in memory, into one
+            /// buffer, without needing an executor.
+            ///
+            /// This drives an encoder like the one [`new`](Self::new) creates over an
+            /// [`IterStream`](crate::stream::IterStream) of the chunks to its end on the current
+            /// thread, so the output is exactly what that stream would yield.
+            pub fn compress_all(
+                chunks: impl IntoIterator<IntoIter = I> $(, $arg: $ty)*
+            ) -> std::io::Result<Vec<u8>> {
+                crate::stream::iter_stream::collect_ready(Self::new(
+                    crate::stream::IterStream::new(chunks) $(, $arg)*
+                ))
+            }
+        }
+    )*};
+}
@@ -1,28 +1,112 @@
 macro_rules! decoder {
-    ($(#[$attr:meta])* $name:ident) => {
+    ($(#[$attr:meta])* $name:ident $(($($arg:ident: $ty:ty),*))? $({ $($methods:tt)* })?) => {
         $(#[$attr])*
         #[pin_project::pin_project]
-        #[derive(Debug)]
         ///
         /// This structure implements a [`Stream`](futures_core::stream::Stream) interface and will read
         /// compressed data from an underlying stream and emit a stream of uncompressed data.
+        ///
+        /// The stream ends after yielding an error, whether it came from the underlying stream or
+        /// from decoding, even if the underlying stream would go on to yield more items.
         pub struct $name<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> {
             #[pin]
             inner: crate::stream::generic::Decoder<S, crate::codec::$name>,
         }
 
+        impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> core::fmt::Debug for $name<S> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The stream is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("stream", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
         impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> $name<S> {
             /// Creates a new decoder which will read compressed data from the given stream and
             /// emit an uncompressed stream.
-            pub fn new(stream: S) -> Self {
+            pub fn new(stream: S $($(, $arg: $ty)*)?) -> Self {
                 Self {
                     inner: crate::stream::Decoder::new(
                         stream,
-                        crate::codec::$name::new(),
+                        crate::codec::$name::new($($($arg),*)?),
+                    ),
+                }
+            }
+
+            /// Creates a new decoder which will first decode `prefix`, then continue reading
+            /// compressed data from the given stream.
+            ///
+            /// This is useful when the first bytes of the stream have already been read, e.g. to
+            /// sniff the content type, and can't be pushed back into the stream.
+            pub fn with_prefix(prefix: bytes::Bytes, stream: S $($(, $arg: $ty)*)?) -> Self {
+                Self {
+                    inner: crate::stream::Decoder::with_prefix(
+                        prefix,
+                        stream,
+                        crate::codec::$name::new($($($arg),*)?),
                     ),
                 }
             }
 
+            /// Sets the capacity of the buffer the output is produced into, which limits the size
+            /// of each yielded chunk, defaults to 8000 bytes.
+            ///
+            /// The output is decompressed straight into this buffer and each chunk is split off it
+            /// without copying, once a chunk has been dropped its space is reused for the next, so
+            /// a consumer that doesn't hold on to the chunks causes no further allocations.
+            ///
+            /// # Panics
+            ///
+            /// If `capacity` is zero.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Limits the total number of decompressed bytes this decoder will produce, to guard
+            /// against small inputs that decompress to huge outputs.
+            ///
+            /// Once the data would exceed `limit` the decoder fails with an
+            /// [`io::Error`](std::io::Error) wrapping a [`LimitExceeded`](crate::LimitExceeded),
+            /// having produced exactly `limit` bytes before it.
+            pub fn with_limit(mut self, limit: u64) -> Self {
+                self.inner.set_limit(limit);
+                self
+            }
+
+            /// Limits the number of steps this decoder will take, to guard against inputs crafted
+            /// to be slow to decompress even if their output is small.
+            ///
+            /// A step is one call into the library implementing the format, the work done in each
+            /// is bounded by the size of the input chunk and output buffer it is given, so this
+            /// caps the CPU time spent on the stream. The number taken so far is reported by
+            /// [`stats`](Self::stats). Once `limit` steps have been
+            /// taken the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`StepLimitExceeded`](crate::StepLimitExceeded).
+            pub fn with_step_limit(mut self, limit: u64) -> Self {
+                self.inner.set_step_limit(limit);
+                self
+            }
+
+            /// Counts the memory of this decoder's buffers against `budget`, which bounds the memory
+            /// used by many decoders together.
+            ///
+            /// That is the buffer the output is decompressed into, see
+            /// [`with_buffer_capacity`](Self::with_buffer_capacity).
+            ///
+            /// It is reserved before the decoder first calls into the format, and again if the
+            /// buffers grow, and released when the decoder is dropped. If the budget does not have
+            /// room the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`MemoryBudgetExceeded`](crate::MemoryBudgetExceeded) without decoding anything,
+            /// it can be retried once other decoders have finished.
+            pub fn with_memory_budget(mut self, budget: &crate::MemoryBudget) -> Self {
+                self.inner.set_memory_budget(budget);
+                self
+            }
+
             /// Acquires a reference to the underlying stream that this decoder is wrapping.
             pub fn get_ref(&self) -> &S {
                 self.inner.get_ref()
@@ -53,6 +137,29 @@ macro_rules! decoder {
             pub fn into_inner(self) -> S {
                 self.inner.into_inner()
             }
+
+            /// Splits the decompressed output into the records separated by `delimiter`, e.g.
+            /// `b'\n'` for the lines of newline delimited logs, see [`Split`](crate::stream::Split).
+            pub fn split(self, delimiter: u8) -> crate::stream::Split<Self> {
+                crate::stream::Split::new(self, delimiter)
+            }
+
+            /// Returns a snapshot of the statistics of this decoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of compressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of decompressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            $($($methods)*)?
         }
 
         impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>>
@@ -68,6 +175,17 @@ macro_rules! decoder {
             }
         }
 
+        impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>>
+            crate::stream::checkpoint::Source for $name<S>
+        {
+            fn poll_next_item(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Option<std::io::Result<crate::stream::CheckpointItem>>> {
+                self.project().inner.poll_next_item(cx)
+            }
+        }
+
         const _: () = {
             fn _assert() {
                 use std::{pin::Pin, io::Result};
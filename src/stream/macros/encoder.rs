@@ -1,16 +1,29 @@
 macro_rules! encoder {
-    ($(#[$attr:meta])* $name:ident<$inner:ident> $({ $($constructor:tt)* })*) => {
+    ($(#[$attr:meta])* $name:ident<$inner:ident> $({ $($constructor:tt)* })* $(@ { $($methods:tt)* })?) => {
         $(#[$attr])*
         #[pin_project::pin_project]
-        #[derive(Debug)]
         ///
         /// This structure implements a [`Stream`](futures_core::stream::Stream) interface and will read
         /// uncompressed data from an underlying stream and emit a stream of compressed data.
+        ///
+        /// The stream ends after yielding an error, whether it came from the underlying stream or
+        /// from encoding, even if the underlying stream would go on to yield more items.
         pub struct $name<$inner: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> {
             #[pin]
             inner: crate::stream::Encoder<$inner, crate::codec::$name>,
         }
 
+        impl<$inner: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> core::fmt::Debug for $name<$inner> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The stream is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("stream", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
         impl<$inner: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> $name<$inner> {
             $(
                 /// Creates a new encoder which will read uncompressed data from the given stream
@@ -19,6 +32,55 @@ macro_rules! encoder {
                 $($constructor)*
             )*
 
+            /// Sets how many (non-empty) items will be pulled from the underlying stream and
+            /// buffered together before they are passed to the compressor, defaults to `1`.
+            ///
+            /// Coalescing more items means fewer, larger compression steps at the cost of having to
+            /// wait for more of the underlying stream before any output is produced. If the
+            /// underlying stream ends before enough items are available then whatever has been
+            /// buffered so far is compressed.
+            pub fn with_coalesce(mut self, items: usize) -> Self {
+                self.inner.set_coalesce(items);
+                self
+            }
+
+            /// Sets the capacity of the buffer the output is produced into, which limits the size
+            /// of each yielded chunk, defaults to 8000 bytes.
+            ///
+            /// # Panics
+            ///
+            /// If `capacity` is zero.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Sets the expected size of the whole compressed output, e.g. estimated from the size
+            /// of the input, defaults to `0`.
+            ///
+            /// The output buffer is allocated with room for that much up front and the yielded
+            /// chunks are split off it, so while the estimate holds it is produced from a single
+            /// allocation in as few chunks as the format's encoder allows, rather than in chunks of
+            /// at most [`with_buffer_capacity`](Self::with_buffer_capacity) bytes each needing
+            /// their own. Once the output grows past the estimate it carries on in chunks of the
+            /// buffer capacity, so a wrong estimate only costs memory or allocations.
+            pub fn with_size_hint(mut self, hint: usize) -> Self {
+                self.inner.set_size_hint(hint);
+                self
+            }
+
+            /// Calls `hook` with each [`Warning`](crate::Warning) about the input, such as it
+            /// starting with the magic number of a compressed format, which usually means it is
+            /// being compressed twice by mistake. Warnings are only diagnostics, the output is the
+            /// same with or without the hook.
+            pub fn with_warning_hook(
+                mut self,
+                hook: impl Fn(crate::Warning) + Send + Sync + 'static,
+            ) -> Self {
+                self.inner.set_warning_hook(hook);
+                self
+            }
+
             /// Acquires a reference to the underlying stream that this encoder is wrapping.
             pub fn get_ref(&self) -> &$inner {
                 self.inner.get_ref()
@@ -49,6 +111,42 @@ macro_rules! encoder {
             pub fn into_inner(self) -> $inner {
                 self.inner.into_inner()
             }
+
+            /// Resets this encoder to compress a new stream read from `stream`, returning the
+            /// previous stream. The already allocated buffers and compression context are reused
+            /// where the backend supports it, saving the cost of creating a new encoder.
+            ///
+            /// Any of the previous stream that hadn't been compressed yet is discarded, and the byte
+            /// counters restart from zero.
+            pub fn reset(&mut self, stream: $inner) -> std::io::Result<$inner> {
+                self.inner.reset(stream)
+            }
+
+            /// Returns a snapshot of the statistics of this encoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of uncompressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of compressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so
+            /// far, or `None` until both some input has been consumed and some output produced.
+            ///
+            /// Encoders buffer input internally, so this lags behind the input and only settles
+            /// on the final ratio once the whole stream has been compressed.
+            pub fn current_ratio(&self) -> Option<f32> {
+                self.inner.current_ratio()
+            }
+
+            $($($methods)*)?
         }
 
         impl<$inner: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>>
@@ -0,0 +1,24 @@
+macro_rules! from_read {
+    ($($algo_s:literal $encoder:ident($($arg:ident: $ty:ty),*) $decoder:ident;)*) => {$(
+        #[cfg(feature = $algo_s)]
+        impl<R: futures_io::AsyncRead> $encoder<crate::stream::ReaderStream<R>> {
+            /// Creates a new encoder compressing the data read from `reader`, like [`new`](Self::new)
+            /// with the reader wrapped in a [`ReaderStream`](crate::stream::ReaderStream). Use that
+            /// directly to choose the size of the chunks read.
+            pub fn from_read(reader: R $(, $arg: $ty)*) -> Self {
+                Self::new(crate::stream::ReaderStream::new(reader) $(, $arg)*)
+            }
+        }
+
+        #[cfg(feature = $algo_s)]
+        impl<R: futures_io::AsyncRead> $decoder<crate::stream::ReaderStream<R>> {
+            /// Creates a new decoder decompressing the data read from `reader`, like
+            /// [`new`](Self::new) with the reader wrapped in a
+            /// [`ReaderStream`](crate::stream::ReaderStream). Use that directly to choose the size
+            /// of the chunks read.
+            pub fn from_read(reader: R) -> Self {
+                Self::new(crate::stream::ReaderStream::new(reader))
+            }
+        }
+    )*};
+}
@@ -3,3 +3,9 @@ mod encoder;
 
 #[macro_use]
 mod decoder;
+
+#[macro_use]
+mod from_read;
+
+#[macro_use]
+mod compress_all;
@@ -7,11 +7,83 @@
 //! between chunks of data from the underlying stream and the resulting compressed/decompressed
 //! stream, the encoders and decoders will buffer the incoming data and choose their own boundaries
 //! at which to yield a new item.
+//!
+//! Data from an [`AsyncRead`](futures_io::AsyncRead) can be encoded or decoded with the
+//! `from_read` constructors, which read it in chunks through a [`ReaderStream`]. Buffers already
+//! in memory can be compressed without an executor by the `compress_all` functions of the
+//! encoders.
 
 #[macro_use]
 mod macros;
+mod checkpoint;
 mod generic;
+mod iter_stream;
+mod reader_stream;
+mod split;
 
-pub(crate) use self::generic::{Decoder, Encoder};
+pub use self::generic::{Decoder, Encoder};
+pub use self::{
+    checkpoint::{Checkpoint, CheckpointDecoder, CheckpointItem},
+    iter_stream::IterStream,
+    reader_stream::ReaderStream,
+    split::Split,
+};
 
 algos!(stream<S>);
+formats!(S: [futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>] => [futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>]);
+default_algos!();
+
+#[cfg(feature = "deflate64")]
+decoder! {
+    /// A deflate64 decoder, or decompressor, for the "Enhanced Deflate" method 9 of ZIP archives.
+    /// There is no deflate64 encoder.
+    #[cfg_attr(docsrs, doc(cfg(feature = "deflate64")))]
+    Deflate64Decoder
+}
+from_read! {
+    "brotli" BrotliEncoder(level: u32) BrotliDecoder;
+    "bzip" BzEncoder(level: bzip2::Compression) BzDecoder;
+    "deflate" DeflateEncoder(level: flate2::Compression) DeflateDecoder;
+    "gzip" GzipEncoder(level: flate2::Compression) GzipDecoder;
+    "identity" IdentityEncoder() IdentityDecoder;
+    "lz4" Lz4Encoder(level: u32) Lz4Decoder;
+    "lzma" LzmaEncoder(level: u32) LzmaDecoder;
+    "snappy" SnappyEncoder() SnappyDecoder;
+    "xz" XzEncoder(level: u32) XzDecoder;
+    "zlib" ZlibEncoder(level: flate2::Compression) ZlibDecoder;
+    "zstd" ZstdEncoder(level: i32) ZstdDecoder;
+}
+
+compress_all! {
+    "brotli" BrotliEncoder(level: u32);
+    "bzip" BzEncoder(level: bzip2::Compression);
+    "deflate" DeflateEncoder(level: flate2::Compression);
+    "gzip" GzipEncoder(level: flate2::Compression);
+    "identity" IdentityEncoder();
+    "lz4" Lz4Encoder(level: u32);
+    "lzma" LzmaEncoder(level: u32);
+    "snappy" SnappyEncoder();
+    "xz" XzEncoder(level: u32);
+    "zlib" ZlibEncoder(level: flate2::Compression);
+    "zstd" ZstdEncoder(level: i32);
+}
+
+#[cfg(feature = "gzip")]
+impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> GzipDecoder<S> {
+    /// Wraps this decoder so that it also yields a [`Checkpoint`] at the end of each gzip
+    /// member, see [`CheckpointDecoder`].
+    pub fn with_checkpoints(mut self) -> CheckpointDecoder<Self> {
+        self.inner.enable_checkpoints();
+        CheckpointDecoder::new(self)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<S: futures_core::stream::Stream<Item = std::io::Result<bytes::Bytes>>> ZstdDecoder<S> {
+    /// Wraps this decoder so that it also yields a [`Checkpoint`] at the end of each zstd frame,
+    /// see [`CheckpointDecoder`].
+    pub fn with_checkpoints(mut self) -> CheckpointDecoder<Self> {
+        self.inner.enable_checkpoints();
+        CheckpointDecoder::new(self)
+    }
+}
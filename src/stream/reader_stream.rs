@@ -0,0 +1,90 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use bytes::{Bytes, BytesMut};
+use futures_core::{ready, stream::Stream};
+use futures_io::AsyncRead;
+use pin_project::pin_project;
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Adapts an [`AsyncRead`] into a [`Stream`] of the chunks read from it, so that it can be given
+/// to the encoders and decoders in this module.
+///
+/// The `from_read` constructors of the encoders and decoders wrap their reader in one of these.
+/// Each read is made into a buffer of up to the chunk size, and yielded without copying. The
+/// stream ends when the reader does.
+#[pin_project]
+#[derive(Debug)]
+pub struct ReaderStream<R: AsyncRead> {
+    #[pin]
+    reader: R,
+    buffer: BytesMut,
+    chunk_size: usize,
+    done: bool,
+}
+
+impl<R: AsyncRead> ReaderStream<R> {
+    /// Creates a new stream reading chunks of up to 8 KiB from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new stream reading chunks of up to `chunk_size` bytes from `reader`.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero.
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        Self {
+            reader,
+            buffer: BytesMut::new(),
+            chunk_size,
+            done: false,
+        }
+    }
+
+    /// Acquires a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Acquires a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consumes this stream returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead> Stream for ReaderStream<R> {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.buffer.len() < *this.chunk_size {
+            this.buffer.resize(*this.chunk_size, 0);
+        }
+
+        let len = ready!(this
+            .reader
+            .poll_read(cx, &mut this.buffer[..*this.chunk_size]))?;
+        if len == 0 {
+            *this.done = true;
+            return Poll::Ready(None);
+        }
+
+        Poll::Ready(Some(Ok(this.buffer.split_to(len).freeze())))
+    }
+}
@@ -0,0 +1,103 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use bytes::{Bytes, BytesMut};
+use futures_core::{ready, stream::Stream};
+use pin_project::pin_project;
+
+/// Splits a stream of bytes, such as the output of a decoder, into the records separated by a
+/// delimiter, e.g. the lines of newline delimited logs.
+///
+/// Each item is one record without its delimiter, records spanning several chunks of the
+/// underlying stream are joined together. A record after the last delimiter is yielded when the
+/// underlying stream ends, and no empty record follows a trailing delimiter. There is no limit on
+/// the length of a record, the whole of one is held in memory until its delimiter is found.
+///
+/// Like the decoders, this ends after yielding an error from the underlying stream, discarding
+/// any incomplete record.
+///
+/// Created by [`Split::new`] or the `split` method of the decoders in this module.
+#[pin_project]
+#[derive(Debug)]
+pub struct Split<S: Stream<Item = Result<Bytes>>> {
+    #[pin]
+    stream: S,
+    delimiter: u8,
+    buffer: BytesMut,
+    /// How much of the buffer is known not to contain the delimiter.
+    searched: usize,
+    done: bool,
+}
+
+impl<S: Stream<Item = Result<Bytes>>> Split<S> {
+    /// Creates a new stream of the records in `stream` separated by `delimiter`.
+    pub fn new(stream: S, delimiter: u8) -> Self {
+        Self {
+            stream,
+            delimiter,
+            buffer: BytesMut::new(),
+            searched: 0,
+            done: false,
+        }
+    }
+
+    /// Acquires a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Acquires a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Consumes this `Split` returning the underlying stream, any incomplete record is discarded.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream<Item = Result<Bytes>>> Stream for Split<S> {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        let mut this = self.project();
+
+        loop {
+            let delimiter = *this.delimiter;
+            if let Some(pos) = this.buffer[*this.searched..]
+                .iter()
+                .position(|&byte| byte == delimiter)
+            {
+                let mut record = this.buffer.split_to(*this.searched + pos + 1);
+                record.truncate(record.len() - 1);
+                *this.searched = 0;
+                return Poll::Ready(Some(Ok(record.freeze())));
+            }
+            *this.searched = this.buffer.len();
+
+            if *this.done {
+                *this.searched = 0;
+                return Poll::Ready(if this.buffer.is_empty() {
+                    None
+                } else {
+                    Some(Ok(this.buffer.take().freeze()))
+                });
+            }
+
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.buffer.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    *this.done = true;
+                    this.buffer.clear();
+                    *this.searched = 0;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => *this.done = true,
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::{Error, ErrorKind, Result};
+
+use futures_core::ready;
+use futures_io::{AsyncRead, AsyncWrite};
+use pin_project::pin_project;
+
+#[derive(Debug)]
+enum State {
+    Reading,
+    Closing,
+    Done,
+}
+
+/// Wraps a decoder so that the decompressed output it yields is also written to a side sink, for
+/// example to cache a decompressed file while serving it.
+///
+/// Any [`AsyncRead`] can be wrapped, so this works with the decoders from both the
+/// [`bufread`](crate::bufread) and [`read`](crate::read) modules.
+///
+/// Output is yielded as soon as it is decoded, the copy for the sink is kept until it has been
+/// written, and no more is decoded until then. So a slow sink applies backpressure to the reader,
+/// with at most one read's worth of output held here.
+///
+/// Once the decoder reaches the end of its stream the sink is closed, which also flushes it, and
+/// only then is the end returned to the caller. Errors from the sink are returned from
+/// [`poll_read`](AsyncRead::poll_read) like errors from the decoder. If this is dropped before
+/// the end is reached the sink is neither flushed nor closed.
+#[pin_project]
+#[derive(Debug)]
+pub struct TeeDecoder<R: AsyncRead, W: AsyncWrite> {
+    #[pin]
+    reader: R,
+    #[pin]
+    sink: W,
+    /// Output already yielded that the sink has not accepted yet.
+    pending: Vec<u8>,
+    written: usize,
+    state: State,
+}
+
+impl<R: AsyncRead, W: AsyncWrite> TeeDecoder<R, W> {
+    /// Creates a new `TeeDecoder` yielding the output of `reader` and writing a copy of it to
+    /// `sink`.
+    pub fn new(reader: R, sink: W) -> Self {
+        Self {
+            reader,
+            sink,
+            pending: Vec::new(),
+            written: 0,
+            state: State::Reading,
+        }
+    }
+
+    /// Acquires a reference to the wrapped decoder.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Acquires a mutable reference to the wrapped decoder.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Acquires a reference to the side sink.
+    pub fn get_sink_ref(&self) -> &W {
+        &self.sink
+    }
+
+    /// Acquires a mutable reference to the side sink.
+    ///
+    /// Note that writing to it directly interleaves with the output copied to it.
+    pub fn get_sink_mut(&mut self) -> &mut W {
+        &mut self.sink
+    }
+
+    /// Consumes this `TeeDecoder` returning the wrapped decoder and the side sink.
+    ///
+    /// Any output not yet written to the sink is discarded, it has all been written once the end
+    /// of the stream has been returned.
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.sink)
+    }
+
+    fn poll_drain(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut this = self.project();
+
+        while *this.written < this.pending.len() {
+            match ready!(this
+                .sink
+                .as_mut()
+                .poll_write(cx, &this.pending[*this.written..]))?
+            {
+                0 => {
+                    return Poll::Ready(Err(Error::new(
+                        ErrorKind::WriteZero,
+                        "failed to write the output to the sink",
+                    )))
+                }
+                n => *this.written += n,
+            }
+        }
+
+        this.pending.clear();
+        *this.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead, W: AsyncWrite> AsyncRead for TeeDecoder<R, W> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        ready!(self.as_mut().poll_drain(cx))?;
+
+        loop {
+            let this = self.as_mut().project();
+            match this.state {
+                State::Reading => {
+                    let len = ready!(this.reader.poll_read(cx, buf))?;
+                    if len == 0 {
+                        *this.state = State::Closing;
+                        continue;
+                    }
+
+                    this.pending.extend_from_slice(&buf[..len]);
+                    // The output is yielded whether or not the sink takes it straight away, if
+                    // writing it failed the copy is still pending so the next call sees the error
+                    let _ = self.as_mut().poll_drain(cx);
+                    return Poll::Ready(Ok(len));
+                }
+
+                State::Closing => {
+                    ready!(this.sink.poll_close(cx))?;
+                    *this.state = State::Done;
+                }
+
+                State::Done => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+}
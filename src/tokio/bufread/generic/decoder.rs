@@ -0,0 +1,110 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{
+    codec::Decode,
+    tokio::{poll_read_buf, Compat},
+    Stats,
+};
+use pin_project::pin_project;
+use tokio_crate::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// The `bufread` decoder driving a tokio reader.
+#[pin_project]
+#[derive(Debug)]
+pub struct Decoder<R: AsyncBufRead, D: Decode> {
+    #[pin]
+    inner: crate::bufread::Decoder<Compat<R>, D>,
+}
+
+impl<R: AsyncBufRead, D: Decode> Decoder<R, D> {
+    pub fn new(reader: R, decoder: D) -> Self {
+        Self {
+            inner: crate::bufread::Decoder::new(Compat::new(reader), decoder),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+
+    pub fn get_decoder_ref(&self) -> &D {
+        self.inner.get_decoder_ref()
+    }
+
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.inner.get_decoder_mut()
+    }
+
+    pub fn set_limit(&mut self, limit: u64) {
+        self.inner.set_limit(limit);
+    }
+
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.inner.set_step_limit(limit);
+    }
+
+    pub fn set_poll_budget(&mut self, bytes: usize) {
+        self.inner.set_poll_budget(bytes);
+    }
+
+    pub fn set_memory_budget(&mut self, budget: &crate::MemoryBudget) {
+        self.inner.set_memory_budget(budget);
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    pub fn trailing_len(&self) -> Option<usize> {
+        self.inner.trailing_len()
+    }
+
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    pub(crate) fn set_passthrough(&mut self, magic: Option<&'static [u8]>) {
+        self.inner.set_passthrough(magic);
+    }
+}
+
+impl<R: AsyncBufRead, D: Decode> AsyncRead for Decoder<R, D> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        let inner = self.project().inner;
+        poll_read_buf(buf, |buf| futures_io::AsyncRead::poll_read(inner, cx, buf))
+    }
+}
+
+impl<R: AsyncBufRead, D: Decode> AsyncBufRead for Decoder<R, D> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        futures_io::AsyncBufRead::poll_fill_buf(self.project().inner, cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        futures_io::AsyncBufRead::consume(self.project().inner, amt)
+    }
+}
@@ -0,0 +1,89 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{
+    codec::Encode,
+    tokio::{poll_read_buf, Compat},
+    Stats, Warning,
+};
+use pin_project::pin_project;
+use tokio_crate::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// The `bufread` encoder driving a tokio reader.
+#[pin_project]
+#[derive(Debug)]
+pub struct Encoder<R: AsyncBufRead, E: Encode> {
+    #[pin]
+    inner: crate::bufread::Encoder<Compat<R>, E>,
+}
+
+impl<R: AsyncBufRead, E: Encode> Encoder<R, E> {
+    pub fn new(reader: R, encoder: E) -> Self {
+        Self {
+            inner: crate::bufread::Encoder::new(Compat::new(reader), encoder),
+        }
+    }
+
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.inner.set_warning_hook(hook);
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+
+    pub fn get_encoder_ref(&self) -> &E {
+        self.inner.get_encoder_ref()
+    }
+
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        self.inner.get_encoder_mut()
+    }
+
+    /// Resets the encoder to start a new stream, the reader is left as is.
+    pub fn reset(&mut self) -> Result<()> {
+        self.inner.reset()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    pub fn current_ratio(&self) -> Option<f32> {
+        self.inner.current_ratio()
+    }
+}
+
+impl<R: AsyncBufRead, E: Encode> AsyncRead for Encoder<R, E> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        let inner = self.project().inner;
+        poll_read_buf(buf, |buf| futures_io::AsyncRead::poll_read(inner, cx, buf))
+    }
+}
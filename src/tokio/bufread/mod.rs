@@ -0,0 +1,46 @@
+//! Types which operate over tokio's [`AsyncBufRead`](tokio_crate::io::AsyncBufRead) streams, both
+//! encoders and decoders for various formats.
+
+#[macro_use]
+mod macros;
+mod generic;
+
+pub(crate) use generic::{Decoder, Encoder};
+
+algos!(tokio::bufread<R>);
+formats!(R: [tokio_crate::io::AsyncBufRead] => [tokio_crate::io::AsyncRead]);
+default_algos!();
+
+#[cfg(feature = "gzip")]
+impl<R: tokio_crate::io::AsyncBufRead> GzipDecoder<R> {
+    /// Sets whether input that does not start with the gzip magic bytes is passed through
+    /// unchanged instead of failing to decode, for sources such as HTTP servers that may label
+    /// uncompressed content as gzip.
+    ///
+    /// This must be set before anything has been read from the decoder.
+    pub fn with_passthrough(mut self, enabled: bool) -> Self {
+        self.inner.set_passthrough(if enabled {
+            Some(crate::codec::GzipDecoder::MAGIC)
+        } else {
+            None
+        });
+        self
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: tokio_crate::io::AsyncBufRead> ZstdDecoder<R> {
+    /// Sets whether input that does not start with the zstd magic number is passed through
+    /// unchanged instead of failing to decode, for sources that may label uncompressed content as
+    /// zstd.
+    ///
+    /// This must be set before anything has been read from the decoder.
+    pub fn with_passthrough(mut self, enabled: bool) -> Self {
+        self.inner.set_passthrough(if enabled {
+            Some(crate::codec::ZstdDecoder::MAGIC)
+        } else {
+            None
+        });
+        self
+    }
+}
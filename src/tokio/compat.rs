@@ -0,0 +1,84 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use futures_core::ready;
+use pin_project::pin_project;
+use tokio_crate::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+/// Presents a tokio reader or writer as the `futures-io` equivalent, so that the generic state
+/// machines can drive it. This is only a change of interface, nothing is buffered or allocated.
+#[pin_project]
+#[derive(Debug)]
+pub struct Compat<T>(#[pin] T);
+
+impl<T> Compat<T> {
+    pub fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
+        self.project().0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: AsyncRead> futures_io::AsyncRead for Compat<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let mut buf = ReadBuf::new(buf);
+        ready!(self.project().0.poll_read(cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+impl<T: AsyncBufRead> futures_io::AsyncBufRead for Compat<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        self.project().0.poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.project().0.consume(amt)
+    }
+}
+
+impl<T: AsyncWrite> futures_io::AsyncWrite for Compat<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        self.project().0.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.project().0.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.project().0.poll_shutdown(cx)
+    }
+}
+
+/// Reads into the unfilled part of a tokio [`ReadBuf`] with a `futures-io` style read, which
+/// writes directly into the buffer.
+pub fn poll_read_buf(
+    buf: &mut ReadBuf<'_>,
+    poll_read: impl FnOnce(&mut [u8]) -> Poll<Result<usize>>,
+) -> Poll<Result<()>> {
+    let len = ready!(poll_read(buf.initialize_unfilled()))?;
+    buf.advance(len);
+    Poll::Ready(Ok(()))
+}
@@ -0,0 +1,15 @@
+//! Implementations for tokio's [`AsyncBufRead`](tokio_crate::io::AsyncBufRead) and
+//! [`AsyncWrite`](tokio_crate::io::AsyncWrite) traits, with the same encoders and decoders as the
+//! [`bufread`](crate::bufread) and [`write`](crate::write) modules.
+//!
+//! They share the state machines of the `futures-io` implementations, reaching tokio's IO types
+//! through an internal adapter that only changes the interface, so unlike wrapping them with
+//! `tokio_util::compat` there is no extra copy or buffering, and they decode straight into the
+//! [`ReadBuf`](tokio_crate::io::ReadBuf) given to them.
+
+mod compat;
+
+pub mod bufread;
+pub mod write;
+
+use self::compat::{poll_read_buf, Compat};
@@ -0,0 +1,91 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{codec::Decode, tokio::Compat, Stats};
+use pin_project::pin_project;
+use tokio_crate::io::AsyncWrite;
+
+/// The `write` decoder driving a tokio writer.
+#[pin_project]
+#[derive(Debug)]
+pub struct Decoder<W: AsyncWrite, D: Decode> {
+    #[pin]
+    inner: crate::write::Decoder<Compat<W>, D>,
+}
+
+impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
+    pub fn new(writer: W, decoder: D) -> Self {
+        Self {
+            inner: crate::write::Decoder::new(Compat::new(writer), decoder),
+        }
+    }
+
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.inner.set_buffer_capacity(capacity);
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner.into_inner().into_inner()
+    }
+
+    pub fn get_decoder_ref(&self) -> &D {
+        self.inner.get_decoder_ref()
+    }
+
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.inner.get_decoder_mut()
+    }
+
+    pub fn set_limit(&mut self, limit: u64) {
+        self.inner.set_limit(limit);
+    }
+
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.inner.set_step_limit(limit);
+    }
+
+    pub fn set_memory_budget(&mut self, budget: &crate::MemoryBudget) {
+        self.inner.set_memory_budget(budget);
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+}
+
+impl<W: AsyncWrite, D: Decode> AsyncWrite for Decoder<W, D> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        futures_io::AsyncWrite::poll_write(self.project().inner, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_io::AsyncWrite::poll_flush(self.project().inner, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_io::AsyncWrite::poll_close(self.project().inner, cx)
+    }
+}
@@ -0,0 +1,118 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use crate::{codec::Encode, tokio::Compat, write::FlushMode, Stats, Warning};
+use pin_project::pin_project;
+use tokio_crate::io::AsyncWrite;
+
+/// The `write` encoder driving a tokio writer.
+#[pin_project]
+#[derive(Debug)]
+pub struct Encoder<W: AsyncWrite, E: Encode> {
+    #[pin]
+    inner: crate::write::Encoder<Compat<W>, E>,
+}
+
+impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
+    pub fn new(writer: W, encoder: E) -> Self {
+        Self {
+            inner: crate::write::Encoder::new(Compat::new(writer), encoder),
+        }
+    }
+
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.inner.set_buffer_capacity(capacity);
+    }
+
+    pub fn set_flush_mode(&mut self, mode: FlushMode) {
+        self.inner.set_flush_mode(mode);
+    }
+
+    pub fn set_flush_on_newline(&mut self, enabled: bool) {
+        self.inner.set_flush_on_newline(enabled);
+    }
+
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.inner.set_warning_hook(hook);
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().inner.get_pin_mut().get_pin_mut()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner.into_inner().into_inner()
+    }
+
+    pub fn get_encoder_ref(&self) -> &E {
+        self.inner.get_encoder_ref()
+    }
+
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        self.inner.get_encoder_mut()
+    }
+
+    pub fn reset(&mut self, writer: W) -> Result<W> {
+        Ok(self.inner.reset(Compat::new(writer))?.into_inner())
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    pub fn last_flush_output(&self) -> u64 {
+        self.inner.last_flush_output()
+    }
+
+    pub fn current_ratio(&self) -> Option<f32> {
+        self.inner.current_ratio()
+    }
+
+    pub fn poll_flush_at_input_offset(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<u64>> {
+        self.project().inner.poll_flush_at_input_offset(cx)
+    }
+
+    pub fn poll_finish(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.project().inner.poll_finish(cx)
+    }
+}
+
+impl<W: AsyncWrite, E: Encode> AsyncWrite for Encoder<W, E> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        futures_io::AsyncWrite::poll_write(self.project().inner, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_io::AsyncWrite::poll_flush(self.project().inner, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_io::AsyncWrite::poll_close(self.project().inner, cx)
+    }
+}
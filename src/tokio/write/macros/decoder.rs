@@ -0,0 +1,170 @@
+macro_rules! decoder {
+    ($(#[$attr:meta])* $name:ident $(($($arg:ident: $ty:ty),*))? $({ $($methods:tt)* })?) => {
+        $(#[$attr])*
+        #[pin_project::pin_project]
+        ///
+        /// This structure implements an [`AsyncWrite`](tokio_crate::io::AsyncWrite) interface and
+        /// will take in compressed data and write it uncompressed to an underlying stream.
+        pub struct $name<W: tokio_crate::io::AsyncWrite> {
+            #[pin]
+            inner: crate::tokio::write::Decoder<W, crate::codec::$name>,
+        }
+
+        impl<W: tokio_crate::io::AsyncWrite> core::fmt::Debug for $name<W> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The writer is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("writer", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
+        impl<W: tokio_crate::io::AsyncWrite> $name<W> {
+            /// Creates a new decoder which will take in compressed data and write it uncompressedd
+            /// to the given stream.
+            pub fn new(read: W $($(, $arg: $ty)*)?) -> $name<W> {
+                $name {
+                    inner: crate::tokio::write::Decoder::new(read, crate::codec::$name::new($($($arg),*)?)),
+                }
+            }
+
+            /// Sets the capacity of the buffer used to write to the underlying writer, defaults
+            /// to 8 KiB.
+            ///
+            /// # Panics
+            ///
+            /// If `capacity` is zero, or smaller than the amount of data currently buffered.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Limits the total number of decompressed bytes this decoder will produce, to guard
+            /// against small inputs that decompress to huge outputs.
+            ///
+            /// Once the data would exceed `limit` the decoder fails with an
+            /// [`io::Error`](std::io::Error) wrapping a [`LimitExceeded`](crate::LimitExceeded),
+            /// having produced exactly `limit` bytes before it.
+            pub fn with_limit(mut self, limit: u64) -> Self {
+                self.inner.set_limit(limit);
+                self
+            }
+
+            /// Limits the number of steps this decoder will take, to guard against inputs crafted
+            /// to be slow to decompress even if their output is small.
+            ///
+            /// A step is one call into the library implementing the format, the work done in each
+            /// is bounded by the size of the input chunk and output buffer it is given, so this
+            /// caps the CPU time spent on the stream. The number taken so far is reported by
+            /// [`stats`](Self::stats). Once `limit` steps have been
+            /// taken the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`StepLimitExceeded`](crate::StepLimitExceeded).
+            pub fn with_step_limit(mut self, limit: u64) -> Self {
+                self.inner.set_step_limit(limit);
+                self
+            }
+
+            /// Counts the memory of this decoder's buffers against `budget`, which bounds the memory
+            /// used by many decoders together.
+            ///
+            /// That is the buffer used to write to the underlying writer, see
+            /// [`with_buffer_capacity`](Self::with_buffer_capacity).
+            ///
+            /// It is reserved before the decoder first calls into the format, and again if the
+            /// buffers grow, and released when the decoder is dropped. If the budget does not have
+            /// room the decoder fails with an [`io::Error`](std::io::Error) wrapping a
+            /// [`MemoryBudgetExceeded`](crate::MemoryBudgetExceeded) without decoding anything,
+            /// it can be retried once other decoders have finished.
+            pub fn with_memory_budget(mut self, budget: &crate::MemoryBudget) -> Self {
+                self.inner.set_memory_budget(budget);
+                self
+            }
+
+            /// Acquires a reference to the underlying writer that this decoder is wrapping.
+            pub fn get_ref(&self) -> &W {
+                self.inner.get_ref()
+            }
+
+            /// Acquires a mutable reference to the underlying writer that this decoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the writer which
+            /// may otherwise confuse this decoder.
+            pub fn get_mut(&mut self) -> &mut W {
+                self.inner.get_mut()
+            }
+
+            /// Acquires a pinned mutable reference to the underlying writer that this decoder is
+            /// wrapping.
+            ///
+            /// Note that care must be taken to avoid tampering with the state of the writer which
+            /// may otherwise confuse this decoder.
+            pub fn get_pin_mut(self: std::pin::Pin<&mut Self>) -> std::pin::Pin<&mut W> {
+                self.project().inner.get_pin_mut()
+            }
+
+            /// Consumes this decoder returning the underlying writer.
+            ///
+            /// This does not flush, any decompressed data still buffered in this decoder is
+            /// discarded, so [`poll_shutdown`](tokio_crate::io::AsyncWrite::poll_shutdown) should
+            /// be called first to make sure everything has been written to the underlying writer.
+            pub fn into_inner(self) -> W {
+                self.inner.into_inner()
+            }
+
+            /// Returns a snapshot of the statistics of this decoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of compressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of decompressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            $($($methods)*)?
+        }
+
+        impl<W: tokio_crate::io::AsyncWrite> tokio_crate::io::AsyncWrite for $name<W> {
+            fn poll_write(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                self.project().inner.poll_write(cx, buf)
+            }
+
+            fn poll_flush(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                self.project().inner.poll_flush(cx)
+            }
+
+            fn poll_shutdown(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                self.project().inner.poll_shutdown(cx)
+            }
+        }
+
+        const _: () = {
+            fn _assert() {
+                use crate::util::{_assert_send, _assert_sync};
+                use core::pin::Pin;
+                use tokio_crate::io::AsyncWrite;
+
+                _assert_send::<$name<Pin<Box<dyn AsyncWrite + Send>>>>();
+                _assert_sync::<$name<Pin<Box<dyn AsyncWrite + Sync>>>>();
+            }
+        };
+    }
+}
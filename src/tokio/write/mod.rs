@@ -0,0 +1,46 @@
+//! Types which operate over tokio's [`AsyncWrite`](tokio_crate::io::AsyncWrite) streams, both
+//! encoders and decoders for various formats.
+
+#[macro_use]
+mod macros;
+mod generic;
+
+pub(crate) use generic::{Decoder, Encoder};
+
+pub use crate::write::FlushMode;
+
+algos!(tokio::write<W>);
+
+#[cfg(feature = "deflate")]
+impl<W: tokio_crate::io::AsyncWrite> DeflateEncoder<W> {
+    /// Sets whether a message can refer back to the data of earlier ones, defaults to `true`.
+    ///
+    /// When disabled, each flush with [`poll_flush`](tokio_crate::io::AsyncWrite::poll_flush) ends
+    /// a message and the next one starts with an empty window, as a fresh encoder would, but
+    /// keeping the allocated state. This is permessage-deflate's (RFC 7692) `no_context_takeover`,
+    /// a peer that negotiated it decodes each message on its own. The decoder may keep its window,
+    /// but can discard it with `DeflateDecoder::with_context_takeover`.
+    pub fn with_context_takeover(mut self, enabled: bool) -> Self {
+        self.inner.get_encoder_mut().set_context_takeover(enabled);
+        self
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl<W: tokio_crate::io::AsyncWrite> DeflateDecoder<W> {
+    /// Sets whether a message can refer back to the data of earlier ones, defaults to `true`.
+    ///
+    /// When disabled, each flush with [`poll_flush`](tokio_crate::io::AsyncWrite::poll_flush) ends
+    /// a message and the window is emptied before the next, keeping the allocated state, to decode
+    /// the messages of a permessage-deflate (RFC 7692) peer that negotiated `no_context_takeover`.
+    /// The input written before each flush must be a whole message ending in a sync flush,
+    /// including the `00 00 FF FF` that permessage-deflate strips, otherwise the rest of the
+    /// message is decoded without the start of its window and fails or is corrupted.
+    pub fn with_context_takeover(mut self, enabled: bool) -> Self {
+        self.inner.get_decoder_mut().set_context_takeover(enabled);
+        self
+    }
+}
+
+formats!(W: [tokio_crate::io::AsyncWrite] => [tokio_crate::io::AsyncWrite]);
+default_algos!();
@@ -1,6 +1,11 @@
 pub fn _assert_send<T: Send>() {}
 pub fn _assert_sync<T: Sync>() {}
 
+/// A buffer with a cursor, splitting it into the part that has been written (or read) so far and
+/// the part that hasn't.
+///
+/// The [`Encode`](crate::codec::Encode) and [`Decode`](crate::codec::Decode) methods take their
+/// input and output in these, and [`advance`](Self::advance) them past what they use.
 #[derive(Debug, Default)]
 pub struct PartialBuffer<B: AsRef<[u8]>> {
     buffer: B,
@@ -8,33 +13,42 @@ pub struct PartialBuffer<B: AsRef<[u8]>> {
 }
 
 impl<B: AsRef<[u8]>> PartialBuffer<B> {
-    pub(crate) fn new(buffer: B) -> Self {
+    /// Wraps `buffer` with nothing written yet.
+    pub fn new(buffer: B) -> Self {
         Self { buffer, index: 0 }
     }
 
-    pub(crate) fn written(&self) -> &[u8] {
+    /// The part of the buffer before the cursor.
+    pub fn written(&self) -> &[u8] {
         &self.buffer.as_ref()[..self.index]
     }
 
-    pub(crate) fn unwritten(&self) -> &[u8] {
+    /// The part of the buffer after the cursor.
+    pub fn unwritten(&self) -> &[u8] {
         &self.buffer.as_ref()[self.index..]
     }
 
-    pub(crate) fn advance(&mut self, amount: usize) {
+    /// Moves the cursor forward by `amount` bytes, which must be at most the length of
+    /// [`unwritten`](Self::unwritten).
+    pub fn advance(&mut self, amount: usize) {
         self.index += amount;
     }
 
-    pub(crate) fn into_inner(self) -> B {
+    /// Returns the wrapped buffer.
+    pub fn into_inner(self) -> B {
         self.buffer
     }
 }
 
 impl<B: AsRef<[u8]> + AsMut<[u8]>> PartialBuffer<B> {
-    pub(crate) fn unwritten_mut(&mut self) -> &mut [u8] {
+    /// The part of the buffer after the cursor, to write into before advancing past it.
+    pub fn unwritten_mut(&mut self) -> &mut [u8] {
         &mut self.buffer.as_mut()[self.index..]
     }
 
-    pub(crate) fn copy_unwritten_from<C: AsRef<[u8]>>(&mut self, other: &mut PartialBuffer<C>) {
+    /// Copies as much as fits from the unwritten part of `other` into the unwritten part of this
+    /// buffer, advancing both.
+    pub fn copy_unwritten_from<C: AsRef<[u8]>>(&mut self, other: &mut PartialBuffer<C>) {
         let len = std::cmp::min(self.unwritten().len(), other.unwritten().len());
 
         self.unwritten_mut()[..len].copy_from_slice(&other.unwritten()[..len]);
@@ -0,0 +1,73 @@
+use std::fmt;
+
+use crate::{
+    format::{Detection, MAX_MAGIC_LEN},
+    util::PartialBuffer,
+    Format,
+};
+
+/// A sign of a likely mistake noticed while compressing, passed to the hook set with an encoder's
+/// `with_warning_hook` method. Warnings are only diagnostics, they never change the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The input starts with the magic number of this format, so it is probably already
+    /// compressed and being compressed again by mistake.
+    ///
+    /// Compressing compressed data is valid, it just gains nothing. A magic number can also
+    /// occur by chance, particularly the two byte zlib header which text starting with e.g. `x^`
+    /// matches.
+    CompressedInput(Format),
+}
+
+type Hook = Box<dyn Fn(Warning) + Send + Sync>;
+
+/// Looks at the start of an encoder's input for the magic number of a compressed format, calling
+/// the warning hook if one is found.
+#[derive(Default)]
+pub(crate) struct InputSniffer {
+    hook: Option<Hook>,
+    header: PartialBuffer<[u8; MAX_MAGIC_LEN]>,
+    done: bool,
+}
+
+impl InputSniffer {
+    pub(crate) fn set_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Starts over for a new stream, keeping the hook.
+    pub(crate) fn reset(&mut self) {
+        self.header = PartialBuffer::default();
+        self.done = false;
+    }
+
+    /// Takes the next bytes of input consumed by the encoder.
+    pub(crate) fn sniff(&mut self, input: &[u8]) {
+        let hook = match &self.hook {
+            Some(hook) if !self.done && !input.is_empty() => hook,
+            _ => return,
+        };
+
+        self.header
+            .copy_unwritten_from(&mut PartialBuffer::new(input));
+        match Format::detect(self.header.written()) {
+            Detection::Incomplete if !self.header.unwritten().is_empty() => {}
+            Detection::Format(format) => {
+                self.done = true;
+                hook(Warning::CompressedInput(format));
+            }
+            Detection::Incomplete | Detection::Unknown => self.done = true,
+        }
+    }
+}
+
+impl fmt::Debug for InputSniffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputSniffer")
+            .field("hook", &self.hook.as_ref().map(|_| "..."))
+            .field("header", &self.header)
+            .field("done", &self.done)
+            .finish()
+    }
+}
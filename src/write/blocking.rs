@@ -0,0 +1,248 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    io::{self, Error, ErrorKind, Result},
+    mem,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use futures_core::ready;
+use futures_io::AsyncWrite;
+
+/// How much written data may be waiting for the task before writes block.
+const CAPACITY: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+struct State {
+    /// Data written but not yet taken by the task.
+    buf: Vec<u8>,
+    /// The number of flushes requested by the writer, and completed by the task.
+    flushes_requested: u64,
+    flushes_done: u64,
+    closing: bool,
+    /// Set once the task has finished, either by closing the writer or failing.
+    done: bool,
+    failed: bool,
+    /// The error the task failed with, until it has been returned to the writer.
+    error: Option<Error>,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Blocks until `ready` is true, or the task has finished.
+    fn wait_for<'a>(
+        &self,
+        mut state: MutexGuard<'a, State>,
+        ready: impl Fn(&State) -> bool,
+    ) -> Result<MutexGuard<'a, State>> {
+        loop {
+            if let Some(err) = state.error.take() {
+                return Err(err);
+            }
+            if ready(&state) {
+                return Ok(state);
+            }
+            if state.done {
+                return Err(Error::new(
+                    ErrorKind::BrokenPipe,
+                    if state.failed {
+                        "the writing task has failed"
+                    } else {
+                        "the writing task has already finished"
+                    },
+                ));
+            }
+            state = self
+                .changed
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+fn wake(state: &mut State) {
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
+
+/// A blocking [`std::io::Write`] that feeds the data written to it to an [`AsyncWrite`], such as
+/// one of the encoders in this module, for bridging code that can only write to a blocking
+/// writer.
+///
+/// Created by [`BlockingWriter::new`], which also returns the [`BlockingWriterTask`] future that
+/// does the asynchronous writing. The two are connected by an in-memory pipe holding up to 64 KiB
+/// of written data, once it is full writes block until the task has taken some of it.
+///
+/// # Executor requirements
+///
+/// The task must be spawned on an executor that runs it on a different thread from the one using
+/// this writer, and that thread must be allowed to block, e.g. a thread from
+/// `tokio::task::spawn_blocking` or a plain [`std::thread`]. Using the writer from the same
+/// thread as the task, or from within any asynchronous task on a single threaded executor,
+/// deadlocks.
+///
+/// # Closing
+///
+/// Call [`finish`](Self::finish) once everything has been written, to close the async writer
+/// (writing the end of a compressed stream) and wait for that to complete. Dropping this closes
+/// it too, but without waiting or reporting any error, which the task still returns.
+#[derive(Debug)]
+pub struct BlockingWriter {
+    shared: Arc<Shared>,
+}
+
+impl BlockingWriter {
+    /// Creates a new `BlockingWriter` feeding `writer`, and the task that must be spawned to
+    /// write to it.
+    pub fn new<W: AsyncWrite + Unpin>(writer: W) -> (Self, BlockingWriterTask<W>) {
+        let shared = Arc::new(Shared::default());
+        let task = BlockingWriterTask {
+            shared: shared.clone(),
+            writer: Some(writer),
+            pending: Vec::new(),
+            written: 0,
+        };
+        (Self { shared }, task)
+    }
+
+    /// Closes the async writer once everything written so far has been written to it, and blocks
+    /// until that has completed.
+    pub fn finish(self) -> Result<()> {
+        let mut state = self.shared.lock();
+        state.closing = true;
+        wake(&mut state);
+        self.shared
+            .wait_for(state, |state| state.done && !state.failed)
+            .map(drop)
+    }
+}
+
+impl io::Write for BlockingWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let state = self.shared.lock();
+        let mut state = self
+            .shared
+            .wait_for(state, |state| state.buf.len() < CAPACITY)?;
+        let len = std::cmp::min(buf.len(), CAPACITY - state.buf.len());
+        state.buf.extend_from_slice(&buf[..len]);
+        wake(&mut state);
+        Ok(len)
+    }
+
+    /// Blocks until everything written so far has been written to the async writer and it has
+    /// been flushed.
+    fn flush(&mut self) -> Result<()> {
+        let mut state = self.shared.lock();
+        state.flushes_requested += 1;
+        let target = state.flushes_requested;
+        wake(&mut state);
+        self.shared
+            .wait_for(state, |state| state.flushes_done >= target)
+            .map(drop)
+    }
+}
+
+impl Drop for BlockingWriter {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.closing = true;
+        wake(&mut state);
+    }
+}
+
+/// The future that writes the data given to a [`BlockingWriter`] to the async writer, see there
+/// for how it must be run.
+///
+/// It completes once the `BlockingWriter` has been finished or dropped and the async writer has
+/// been closed, returning the async writer, or with the first error the async writer returned.
+#[derive(Debug)]
+#[must_use = "the task must be spawned for the writes to make progress"]
+pub struct BlockingWriterTask<W> {
+    shared: Arc<Shared>,
+    writer: Option<W>,
+    /// Data taken from the pipe that the async writer has not accepted yet.
+    pending: Vec<u8>,
+    written: usize,
+}
+
+impl<W: AsyncWrite + Unpin> BlockingWriterTask<W> {
+    fn poll_step(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut writer = Pin::new(self.writer.as_mut().expect("polled after completion"));
+
+        loop {
+            while self.written < self.pending.len() {
+                match ready!(writer
+                    .as_mut()
+                    .poll_write(cx, &self.pending[self.written..]))?
+                {
+                    0 => {
+                        return Poll::Ready(Err(Error::new(
+                            ErrorKind::WriteZero,
+                            "failed to write the data to the async writer",
+                        )))
+                    }
+                    n => self.written += n,
+                }
+            }
+
+            let mut state = self.shared.lock();
+            if !state.buf.is_empty() {
+                // Swapping the buffers reuses their allocations
+                self.pending.clear();
+                mem::swap(&mut self.pending, &mut state.buf);
+                self.written = 0;
+                self.shared.changed.notify_all();
+            } else if state.flushes_done < state.flushes_requested {
+                let target = state.flushes_requested;
+                drop(state);
+                ready!(writer.as_mut().poll_flush(cx))?;
+                self.shared.lock().flushes_done = target;
+                self.shared.changed.notify_all();
+            } else if state.closing {
+                drop(state);
+                return writer.as_mut().poll_close(cx);
+            } else {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Future for BlockingWriterTask<W> {
+    type Output = Result<W>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<W>> {
+        let result = ready!(self.poll_step(cx));
+
+        let mut state = self.shared.lock();
+        state.done = true;
+        if let Err(err) = &result {
+            // The writer gets a copy, io errors can't be cloned
+            state.failed = true;
+            state.error = Some(Error::new(err.kind(), err.to_string()));
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+
+        Poll::Ready(result.map(|()| self.writer.take().unwrap()))
+    }
+}
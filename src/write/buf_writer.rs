@@ -41,6 +41,26 @@ impl<W: AsyncWrite> BufWriter<W> {
         }
     }
 
+    /// Replaces the buffer with one of the specified capacity, any data still in the buffer is
+    /// kept so this must not shrink it below the amount of buffered data.
+    pub fn set_capacity(&mut self, cap: usize) {
+        let buffered = &self.buf[self.written..self.buffered];
+        assert!(cap > 0, "BufWriter capacity must be non-zero");
+        assert!(cap >= buffered.len(), "BufWriter capacity too small");
+
+        let mut buf = vec![0; cap];
+        buf[..buffered.len()].copy_from_slice(buffered);
+
+        self.buffered -= self.written;
+        self.written = 0;
+        self.buf = buf.into();
+    }
+
+    /// Returns the size of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
     fn partial_flush_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         let mut this = self.project();
 
@@ -138,6 +158,15 @@ impl<W: AsyncWrite> BufWriter<W> {
     pub fn into_inner(self) -> W {
         self.inner
     }
+
+    /// Replaces the underlying writer, returning the previous one.
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    pub fn reset(&mut self, inner: W) -> W {
+        self.written = 0;
+        self.buffered = 0;
+        std::mem::replace(&mut self.inner, inner)
+    }
 }
 
 impl<W: AsyncWrite> AsyncWrite for BufWriter<W> {
@@ -0,0 +1,28 @@
+/// What [`poll_flush`](futures_io::AsyncWrite::poll_flush) on an encoder writes before flushing
+/// the underlying writer, set with `set_flush_mode` on each encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlushMode {
+    /// Only write out the output already produced, input the encoder is still holding on to is
+    /// not forced out so it may not be decodable yet.
+    None,
+    /// Make all input written so far decodable while continuing the same stream, such as zlib's
+    /// `Z_SYNC_FLUSH` or zstd's `ZSTD_e_flush`. This is the default.
+    Sync,
+    /// Like [`Sync`](Self::Sync), but also discard the history so decoding can restart from this
+    /// point, costing more ratio. Only deflate, zlib and gzip support this, it is the same as
+    /// [`Sync`](Self::Sync) for the other formats.
+    Full,
+    /// Like [`Sync`](Self::Sync), but without aligning the output to a byte boundary, zlib's
+    /// `Z_PARTIAL_FLUSH`. Instead of the empty stored block a sync flush ends with, it ends with
+    /// an empty fixed Huffman block of 10 bits, as some deflate based protocols require. Only
+    /// deflate, zlib and gzip support this, it is the same as [`Sync`](Self::Sync) for the other
+    /// formats.
+    ///
+    /// zlib's `Z_BLOCK` is not supported, as the flate2 backend doesn't offer it.
+    Partial,
+    /// End the current stream, writing its trailer such as a checksum, and start a new one with
+    /// the next input. For gzip this makes a file of several members and for zstd several
+    /// frames, decoders for other formats may stop at the end of the first stream.
+    Finish,
+}
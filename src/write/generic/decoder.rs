@@ -5,9 +5,10 @@ use core::{
 use std::io::{Error, ErrorKind, Result};
 
 use crate::{
-    codec::Decode,
+    codec::{Decode, Limited},
     util::PartialBuffer,
     write::{AsyncBufWrite, BufWriter},
+    MemoryBudget, Stats,
 };
 use futures_core::ready;
 use futures_io::AsyncWrite;
@@ -20,40 +21,110 @@ enum State {
     Done,
 }
 
+/// The state machine behind the [`write`](crate::write) decoders, generic over the format's
+/// [`Decode`](crate::codec::Decode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Decoder<W: AsyncWrite, D: Decode> {
     #[pin]
     writer: BufWriter<W>,
-    decoder: D,
+    decoder: Limited<D>,
     state: State,
+    total_in: u64,
+    total_out: u64,
 }
 
 impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
+    /// Creates a new decoder wrapping the writer, using `decoder` for the format.
     pub fn new(writer: W, decoder: D) -> Self {
+        let writer = BufWriter::new(writer);
+        let mut decoder = Limited::new(decoder);
+        decoder.set_memory(writer.capacity());
         Self {
-            writer: BufWriter::new(writer),
+            writer,
             decoder,
             state: State::Decoding,
+            total_in: 0,
+            total_out: 0,
         }
     }
 
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.writer.set_capacity(capacity);
+        self.decoder.set_memory(capacity);
+    }
+
+    /// Acquires a reference to the underlying writer.
     pub fn get_ref(&self) -> &W {
         self.writer.get_ref()
     }
 
+    /// Acquires a mutable reference to the underlying writer.
     pub fn get_mut(&mut self) -> &mut W {
         self.writer.get_mut()
     }
 
+    /// Acquires a pinned mutable reference to the underlying writer.
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
         self.project().writer.get_pin_mut()
     }
 
+    /// Consumes this decoder returning the underlying writer.
     pub fn into_inner(self) -> W {
         self.writer.into_inner()
     }
 
+    /// Acquires a reference to the format's decoder.
+    pub fn get_decoder_ref(&self) -> &D {
+        self.decoder.get_ref()
+    }
+
+    /// Acquires a mutable reference to the format's decoder.
+    pub fn get_decoder_mut(&mut self) -> &mut D {
+        self.decoder.get_mut()
+    }
+
+    /// Limits the total number of decompressed bytes produced, see the `with_limit` method of
+    /// the format decoders.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.decoder.set_limit(limit);
+    }
+
+    /// Limits the number of calls into the format's decoder, see the `with_step_limit` method of
+    /// the format decoders.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.decoder.set_step_limit(limit);
+    }
+
+    /// Reserves the memory of the internal buffer from `budget`, see the `with_memory_budget`
+    /// method of the format decoders.
+    pub fn set_memory_budget(&mut self, budget: &MemoryBudget) {
+        self.decoder.set_memory_budget(budget);
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.decoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
     fn do_poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -67,7 +138,10 @@ impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
 
             *this.state = match this.state {
                 State::Decoding => {
-                    if this.decoder.decode(input, &mut output)? {
+                    let consumed = input.written().len();
+                    let done = this.decoder.decode(input, &mut output)?;
+                    *this.total_in += (input.written().len() - consumed) as u64;
+                    if done {
                         State::Finishing
                     } else {
                         State::Decoding
@@ -87,6 +161,7 @@ impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
 
             let produced = output.written().len();
             this.writer.as_mut().produce(produced);
+            *this.total_out += produced as u64;
 
             if let State::Done = this.state {
                 return Poll::Ready(Ok(()));
@@ -126,6 +201,7 @@ impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
 
             let produced = output.written().len();
             this.writer.as_mut().produce(produced);
+            *this.total_out += produced as u64;
 
             if done {
                 return Poll::Ready(Ok(()));
@@ -136,7 +212,8 @@ impl<W: AsyncWrite, D: Decode> Decoder<W, D> {
 
 impl<W: AsyncWrite, D: Decode> AsyncWrite for Decoder<W, D> {
     fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
-        if buf.is_empty() {
+        // Nothing after the end of the stream is taken, a write of it reports no bytes written
+        if buf.is_empty() || matches!(self.state, State::Done) {
             return Poll::Ready(Ok(0));
         }
 
@@ -150,7 +227,7 @@ impl<W: AsyncWrite, D: Decode> AsyncWrite for Decoder<W, D> {
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
         ready!(self.as_mut().do_poll_flush(cx))?;
-        ready!(self.project().writer.as_mut().poll_close(cx))?;
+        ready!(self.project().writer.as_mut().poll_flush(cx))?;
         Poll::Ready(Ok(()))
     }
 
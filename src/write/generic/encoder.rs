@@ -2,15 +2,17 @@ use core::{
     pin::Pin,
     task::{Context, Poll},
 };
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result, SeekFrom};
 
 use crate::{
     codec::Encode,
     util::PartialBuffer,
-    write::{AsyncBufWrite, BufWriter},
+    warning::InputSniffer,
+    write::{AsyncBufWrite, BufWriter, FlushMode},
+    Stats, Warning,
 };
 use futures_core::ready;
-use futures_io::AsyncWrite;
+use futures_io::{AsyncSeek, AsyncWrite};
 use pin_project::pin_project;
 
 #[derive(Debug)]
@@ -20,6 +22,8 @@ enum State {
     Done,
 }
 
+/// The state machine behind the [`write`](crate::write) encoders, generic over the format's
+/// [`Encode`](crate::codec::Encode) implementation, for wrapping a custom format.
 #[pin_project]
 #[derive(Debug)]
 pub struct Encoder<W: AsyncWrite, E: Encode> {
@@ -27,33 +31,253 @@ pub struct Encoder<W: AsyncWrite, E: Encode> {
     writer: BufWriter<W>,
     encoder: E,
     state: State,
+    total_in: u64,
+    total_out: u64,
+    /// The value of `total_out` when the last flush completed.
+    flushed_out: u64,
+    /// The value of `total_in` when the last flush that wrote out all the input completed.
+    flushed_in: u64,
+    last_flush_output: u64,
+    flush_mode: FlushMode,
+    /// Whether the stream was ended by a [`FlushMode::Finish`] flush with no input since, so
+    /// there is nothing more to finish.
+    finished_stream: bool,
+    /// Whether the underlying writer has been closed, so closing again does nothing.
+    closed: bool,
+    flush_on_newline: bool,
+    /// Whether a write ended with a newline that hasn't been flushed yet.
+    line_flush_pending: bool,
+    sniffer: InputSniffer,
 }
 
 impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
+    /// Creates a new encoder wrapping the writer, using `encoder` for the format.
     pub fn new(writer: W, encoder: E) -> Self {
         Self {
             writer: BufWriter::new(writer),
             encoder,
             state: State::Encoding,
+            total_in: 0,
+            total_out: 0,
+            flushed_out: 0,
+            flushed_in: 0,
+            last_flush_output: 0,
+            flush_mode: FlushMode::Sync,
+            finished_stream: false,
+            closed: false,
+            flush_on_newline: false,
+            line_flush_pending: false,
+            sniffer: InputSniffer::default(),
         }
     }
 
+    /// Sets the capacity of the internal buffer, see the `with_buffer_capacity` method of the
+    /// format types.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.writer.set_capacity(capacity);
+    }
+
+    /// Sets what flushing writes, defaults to [`FlushMode::Sync`].
+    pub fn set_flush_mode(&mut self, mode: FlushMode) {
+        self.flush_mode = mode;
+    }
+
+    /// Sets whether input up to a newline is flushed as soon as it is written, see the
+    /// `with_flush_on_newline` method of the format encoders.
+    pub fn set_flush_on_newline(&mut self, enabled: bool) {
+        self.flush_on_newline = enabled;
+    }
+
+    /// Calls `hook` with warnings about the input, see the `with_warning_hook` method of the
+    /// format types.
+    pub fn set_warning_hook(&mut self, hook: impl Fn(Warning) + Send + Sync + 'static) {
+        self.sniffer.set_hook(hook);
+    }
+
+    /// Acquires a reference to the underlying writer.
     pub fn get_ref(&self) -> &W {
         self.writer.get_ref()
     }
 
+    /// Acquires a mutable reference to the underlying writer.
     pub fn get_mut(&mut self) -> &mut W {
         self.writer.get_mut()
     }
 
+    /// Acquires a pinned mutable reference to the underlying writer.
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
         self.project().writer.get_pin_mut()
     }
 
+    /// Consumes this encoder returning the underlying writer.
     pub fn into_inner(self) -> W {
         self.writer.into_inner()
     }
 
+    /// Acquires a reference to the format's encoder.
+    pub fn get_encoder_ref(&self) -> &E {
+        &self.encoder
+    }
+
+    /// Acquires a mutable reference to the format's encoder.
+    pub fn get_encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// Resets the encoder to compress a new stream written to the given writer, returning the
+    /// previous one.
+    pub fn reset(&mut self, writer: W) -> Result<W> {
+        self.encoder.reset()?;
+        self.state = State::Encoding;
+        self.total_in = 0;
+        self.total_out = 0;
+        self.flushed_out = 0;
+        self.flushed_in = 0;
+        self.last_flush_output = 0;
+        self.finished_stream = false;
+        self.closed = false;
+        self.line_flush_pending = false;
+        self.sniffer.reset();
+        Ok(self.writer.reset(writer))
+    }
+
+    /// Returns a snapshot of the statistics, see [`Stats`](crate::Stats).
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            total_in: self.total_in,
+            total_out: self.total_out,
+            ..Stats::default()
+        };
+        self.encoder.stats(&mut stats);
+        stats
+    }
+
+    /// Returns the number of bytes consumed from the input so far.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the number of bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Returns whether the stream has been ended by closing or finishing the encoder, see the
+    /// `is_finished` method of the format encoders.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Returns the number of compressed bytes produced between the previous flush and the last
+    /// one completing.
+    pub fn last_flush_output(&self) -> u64 {
+        self.last_flush_output
+    }
+
+    /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so far, or
+    /// `None` until there has been some of both.
+    pub fn current_ratio(&self) -> Option<f32> {
+        if self.total_in == 0 || self.total_out == 0 {
+            None
+        } else {
+            Some(self.total_out as f32 / self.total_in as f32)
+        }
+    }
+
+    /// Flushes the encoder and the underlying writer, returning the number of compressed bytes
+    /// written so far.
+    pub fn poll_flush_at_input_offset(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<u64>> {
+        if !self.encoder.can_sync_flush() {
+            return Poll::Ready(Err(Error::new(
+                ErrorKind::Unsupported,
+                "the format can't be flushed to a sync point, so there is no input offset to return",
+            )));
+        }
+
+        // The offset must be a sync point whatever the configured mode, so only a full flush is
+        // kept and anything else, even no flush at all, becomes a sync flush
+        let mode = match self.flush_mode {
+            FlushMode::Full => FlushMode::Full,
+            _ => FlushMode::Sync,
+        };
+        ready!(self.as_mut().do_poll_flush(cx, mode))?;
+        ready!(self.as_mut().project().writer.poll_flush(cx))?;
+        self.as_mut().flushed(mode);
+        Poll::Ready(Ok(self.total_out))
+    }
+
+    /// Ends the current stream and flushes the underlying writer, the next write starts a new
+    /// stream, whatever the flush mode is. See the `poll_finish_member` method of the gzip
+    /// encoder.
+    pub fn poll_finish_stream(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.as_mut().do_poll_flush(cx, FlushMode::Finish))?;
+        ready!(self.as_mut().project().writer.poll_flush(cx))?;
+        self.flushed(FlushMode::Finish);
+        Poll::Ready(Ok(()))
+    }
+
+    /// Ends the stream and flushes the underlying writer without closing it, see the `finish`
+    /// method of the format encoders.
+    pub fn poll_finish(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        *self.as_mut().project().line_flush_pending = false;
+        ready!(self.as_mut().do_poll_close(cx))?;
+        ready!(self.project().writer.poll_flush(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes out the buffered compressed output and seeks the underlying writer, see the
+    /// `poll_seek_inner` method of the format encoders.
+    pub fn poll_seek_inner(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>>
+    where
+        W: AsyncSeek,
+    {
+        let done = matches!(self.state, State::Done);
+        if self.total_in != self.flushed_in && !done {
+            return Poll::Ready(Err(Error::other(
+                "cannot seek the underlying writer while the encoder holds unflushed input",
+            )));
+        }
+        self.project().writer.poll_seek(cx, pos)
+    }
+
+    /// Records a completed flush, only once the writer has been flushed too as a flush that
+    /// returned `Pending` part way through starts over when polled again.
+    fn flushed(self: Pin<&mut Self>, mode: FlushMode) {
+        let this = self.project();
+        *this.last_flush_output = *this.total_out - *this.flushed_out;
+        *this.flushed_out = *this.total_out;
+        *this.line_flush_pending = false;
+        // Without flushing the encoder the input it holds is still not written out
+        if !matches!(mode, FlushMode::None) {
+            *this.flushed_in = *this.total_in;
+        }
+    }
+
+    /// Finishes flushing the lines of an earlier write, if that couldn't complete at the time.
+    fn poll_line_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.line_flush_pending {
+            // The lines must be decodable whatever the configured mode, so as for
+            // `poll_flush_at_input_offset` only a full flush is kept and anything else becomes a
+            // sync flush, or a full flush for the formats that can't sync flush
+            let mode = match self.flush_mode {
+                FlushMode::Full => FlushMode::Full,
+                _ if !self.encoder.can_sync_flush() => FlushMode::Full,
+                _ => FlushMode::Sync,
+            };
+            ready!(self.as_mut().do_poll_flush(cx, mode))?;
+            ready!(self.as_mut().project().writer.poll_flush(cx))?;
+            self.flushed(mode);
+        }
+        Poll::Ready(Ok(()))
+    }
+
     fn do_poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -67,7 +291,12 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
 
             *this.state = match this.state {
                 State::Encoding => {
+                    let consumed = input.written().len();
                     this.encoder.encode(input, &mut output)?;
+                    this.sniffer.sniff(&input.written()[consumed..]);
+                    let consumed = input.written().len() - consumed;
+                    *this.total_in += consumed as u64;
+                    *this.finished_stream &= consumed == 0;
                     State::Encoding
                 }
 
@@ -76,6 +305,7 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
 
             let produced = output.written().len();
             this.writer.as_mut().produce(produced);
+            *this.total_out += produced as u64;
 
             if input.unwritten().is_empty() {
                 return Poll::Ready(Ok(()));
@@ -83,7 +313,11 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
         }
     }
 
-    fn do_poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+    fn do_poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        mode: FlushMode,
+    ) -> Poll<Result<()>> {
         let mut this = self.project();
 
         loop {
@@ -91,13 +325,30 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
             let mut output = PartialBuffer::new(output);
 
             let done = match this.state {
-                State::Encoding => this.encoder.flush(&mut output)?,
+                State::Encoding => match mode {
+                    FlushMode::None => true,
+                    FlushMode::Sync => this.encoder.flush(&mut output)?,
+                    FlushMode::Full => this.encoder.full_flush(&mut output)?,
+                    FlushMode::Partial => this.encoder.partial_flush(&mut output)?,
+                    // A flush that returned `Pending` after finishing the stream must not finish
+                    // another, empty, one when polled again
+                    FlushMode::Finish if *this.finished_stream => true,
+                    FlushMode::Finish => {
+                        let done = this.encoder.finish(&mut output)?;
+                        if done {
+                            this.encoder.reset()?;
+                            *this.finished_stream = true;
+                        }
+                        done
+                    }
+                },
 
                 State::Finishing | State::Done => panic!("Flush after close"),
             };
 
             let produced = output.written().len();
             this.writer.as_mut().produce(produced);
+            *this.total_out += produced as u64;
 
             if done {
                 return Poll::Ready(Ok(()));
@@ -108,11 +359,17 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
     fn do_poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
         let mut this = self.project();
 
+        // The state is only moved on once the encoder has finished into the buffer, so when the
+        // writer applies backpressure part way through the trailer the next poll resumes it
         loop {
             let output = ready!(this.writer.as_mut().poll_partial_flush_buf(cx))?;
             let mut output = PartialBuffer::new(output);
 
             *this.state = match this.state {
+                // Nothing was written since a flush ended the last stream, so there's no need
+                // for another
+                State::Encoding if *this.finished_stream => State::Done,
+
                 State::Encoding | State::Finishing => {
                     if this.encoder.finish(&mut output)? {
                         State::Done
@@ -126,6 +383,7 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
 
             let produced = output.written().len();
             this.writer.as_mut().produce(produced);
+            *this.total_out += produced as u64;
 
             if let State::Done = this.state {
                 return Poll::Ready(Ok(()));
@@ -135,28 +393,64 @@ impl<W: AsyncWrite, E: Encode> Encoder<W, E> {
 }
 
 impl<W: AsyncWrite, E: Encode> AsyncWrite for Encoder<W, E> {
-    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
         if buf.is_empty() {
             return Poll::Ready(Ok(0));
         }
 
+        ready!(self.as_mut().poll_line_flush(cx))?;
+
+        // Only the input up to the last newline is taken, so there is at most one flush per write
+        // however many lines it has
+        let buf = match buf.iter().rposition(|&b| b == b'\n') {
+            Some(pos) if self.flush_on_newline => &buf[..=pos],
+            _ => buf,
+        };
         let mut input = PartialBuffer::new(buf);
 
-        match self.do_poll_write(cx, &mut input)? {
+        // Once any input has been given to the encoder it is part of the compressed stream, so we
+        // must report it as written even if the underlying writer failed afterwards, otherwise the
+        // caller would retry with the same bytes and they would be compressed twice. The error
+        // will be seen again on the next call as the compressed output is still buffered.
+        match self.as_mut().do_poll_write(cx, &mut input) {
             Poll::Pending if input.written().is_empty() => Poll::Pending,
-            _ => Poll::Ready(Ok(input.written().len())),
+            Poll::Ready(Err(err)) if input.written().is_empty() => Poll::Ready(Err(err)),
+            _ => {
+                // The lines are already written whether or not the flush completes now, one that
+                // doesn't is finished by the next write or flush, and an error is seen there too
+                if self.flush_on_newline && input.written().ends_with(b"\n") {
+                    *self.as_mut().project().line_flush_pending = true;
+                    let _ = self.as_mut().poll_line_flush(cx);
+                }
+                Poll::Ready(Ok(input.written().len()))
+            }
         }
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
-        ready!(self.as_mut().do_poll_flush(cx))?;
-        ready!(self.project().writer.as_mut().poll_close(cx))?;
+        ready!(self.as_mut().poll_line_flush(cx))?;
+        let mode = self.flush_mode;
+        ready!(self.as_mut().do_poll_flush(cx, mode))?;
+        ready!(self.as_mut().project().writer.as_mut().poll_flush(cx))?;
+        self.flushed(mode);
         Poll::Ready(Ok(()))
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        // Closing again, e.g. from overlapping cleanup paths, must not close the writer twice
+        if self.closed {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Closing writes out all the input, so there's no need to flush any lines first
+        *self.as_mut().project().line_flush_pending = false;
         ready!(self.as_mut().do_poll_close(cx))?;
-        ready!(self.project().writer.as_mut().poll_close(cx))?;
+        ready!(self.as_mut().project().writer.as_mut().poll_close(cx))?;
+        *self.project().closed = true;
         Poll::Ready(Ok(()))
     }
 }
@@ -1,16 +1,41 @@
 macro_rules! encoder {
-    ($(#[$attr:meta])* $name:ident<$inner:ident> $({ $($constructor:tt)* })*) => {
+    ($(#[$attr:meta])* $name:ident<$inner:ident> $({ $($constructor:tt)* })* $(@ { $($methods:tt)* })?) => {
         $(#[$attr])*
         #[pin_project::pin_project]
-        #[derive(Debug)]
         ///
         /// This structure implements an [`AsyncWrite`](futures_io::AsyncWrite) interface and will
         /// take in uncompressed data and write it compressed to an underlying stream.
+        ///
+        /// Closing writes the end of the compressed stream, such as its trailer. While the
+        /// underlying writer is not accepting data [`poll_close`](futures_io::AsyncWrite::poll_close)
+        /// returns `Pending` and continues from where it stopped when polled again, it only
+        /// completes once the whole stream has been written and the underlying writer closed.
+        ///
+        /// By default flushing with [`poll_flush`](futures_io::AsyncWrite::poll_flush) does not
+        /// end the stream, it makes all input written so far decodable from the output written so far and
+        /// then flushes the underlying writer, for example to deliver each message of a long lived
+        /// stream promptly. Each flush pads the output and discards state that later input could
+        /// have been matched against, so flushing often, such as after every small write, costs
+        /// compression ratio. bzip2 ends a block on each flush but may keep its last few bits until
+        /// more output follows, and the legacy `.lzma` format cannot flush part way through a
+        /// stream at all, flushing it only writes out what has already been compressed. Other
+        /// behaviours can be chosen with [`set_flush_mode`](Self::set_flush_mode).
         pub struct $name<$inner: futures_io::AsyncWrite> {
             #[pin]
             inner: crate::write::Encoder<$inner, crate::codec::$name>,
         }
 
+        impl<$inner: futures_io::AsyncWrite> core::fmt::Debug for $name<$inner> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                // The writer is left out, so this doesn't depend on it implementing `Debug`
+                f.debug_struct(stringify!($name))
+                    .field("writer", &format_args!(".."))
+                    .field("total_in", &self.total_in())
+                    .field("total_out", &self.total_out())
+                    .finish()
+            }
+        }
+
         impl<$inner: futures_io::AsyncWrite> $name<$inner> {
             $(
                 /// Creates a new encoder which will take in uncompressed data and write it
@@ -19,6 +44,53 @@ macro_rules! encoder {
                 $($constructor)*
             )*
 
+            /// Sets the capacity of the buffer used to write to the underlying writer, defaults
+            /// to 8 KiB.
+            ///
+            /// # Panics
+            ///
+            /// If `capacity` is zero, or smaller than the amount of data currently buffered.
+            pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+                self.inner.set_buffer_capacity(capacity);
+                self
+            }
+
+            /// Sets what [`poll_flush`](futures_io::AsyncWrite::poll_flush) writes, defaults to
+            /// [`FlushMode::Sync`](crate::write::FlushMode::Sync). It can be changed between
+            /// flushes, for example to end the stream with [`FlushMode::Finish`](crate::write::FlushMode::Finish)
+            /// only at some of them.
+            pub fn set_flush_mode(&mut self, mode: crate::write::FlushMode) {
+                self.inner.set_flush_mode(mode);
+            }
+
+            /// Flushes after each write that ends a line, so that a receiver decoding the output
+            /// gets every complete line as soon as it has been written, for example when shipping
+            /// logs or newline delimited JSON. Defaults to off.
+            ///
+            /// A write is cut short after its last newline, so however many lines it has there is
+            /// at most one flush, and input without newlines is never flushed. Each flush also
+            /// flushes the underlying writer, and is a [`FlushMode::Sync`](crate::write::FlushMode::Sync)
+            /// flush whatever mode is set with [`set_flush_mode`](Self::set_flush_mode), unless
+            /// that is [`FlushMode::Full`](crate::write::FlushMode::Full), which is kept. Formats
+            /// that can't sync flush, bzip2, lzma and lz4 blocks, do a full flush instead. One
+            /// that can't complete right away is finished by the next write, flush or close.
+            pub fn with_flush_on_newline(mut self, enabled: bool) -> Self {
+                self.inner.set_flush_on_newline(enabled);
+                self
+            }
+
+            /// Calls `hook` with each [`Warning`](crate::Warning) about the input, such as it
+            /// starting with the magic number of a compressed format, which usually means it is
+            /// being compressed twice by mistake. Warnings are only diagnostics, the output is the
+            /// same with or without the hook.
+            pub fn with_warning_hook(
+                mut self,
+                hook: impl Fn(crate::Warning) + Send + Sync + 'static,
+            ) -> Self {
+                self.inner.set_warning_hook(hook);
+                self
+            }
+
             /// Acquires a reference to the underlying writer that this encoder is wrapping.
             pub fn get_ref(&self) -> &$inner {
                 self.inner.get_ref()
@@ -44,11 +116,143 @@ macro_rules! encoder {
 
             /// Consumes this encoder returning the underlying writer.
             ///
-            /// Note that this may discard internal state of this encoder, so care should be taken
-            /// to avoid losing resources when this is called.
+            /// This does not flush, any compressed data still buffered in this encoder is
+            /// discarded, so [`poll_close`](futures_io::AsyncWrite::poll_close) should be called
+            /// first to finish the stream and write it all to the underlying writer.
             pub fn into_inner(self) -> $inner {
                 self.inner.into_inner()
             }
+
+            /// Resets this encoder to compress a new stream written to `writer`, returning the
+            /// previous writer. The already allocated buffers and compression context are reused
+            /// where the backend supports it, saving the cost of creating a new encoder.
+            ///
+            /// This does not flush, [`poll_close`](futures_io::AsyncWrite::poll_close) should be
+            /// called first to finish the previous stream and write it all to the previous
+            /// writer. The byte counters restart from zero.
+            pub fn reset(&mut self, writer: $inner) -> std::io::Result<$inner> {
+                self.inner.reset(writer)
+            }
+
+            /// Returns a snapshot of the statistics of this encoder, see [`Stats`](crate::Stats).
+            pub fn stats(&self) -> crate::Stats {
+                self.inner.stats()
+            }
+
+            /// Returns the number of uncompressed bytes consumed from the input so far.
+            pub fn total_in(&self) -> u64 {
+                self.inner.total_in()
+            }
+
+            /// Returns the number of compressed bytes produced so far.
+            pub fn total_out(&self) -> u64 {
+                self.inner.total_out()
+            }
+
+            /// Returns the ratio of compressed bytes produced to uncompressed bytes consumed so
+            /// far, or `None` until both some input has been consumed and some output produced.
+            ///
+            /// Encoders buffer input internally, so this lags behind the input and only settles
+            /// on the final ratio once the whole stream has been compressed.
+            pub fn current_ratio(&self) -> Option<f32> {
+                self.inner.current_ratio()
+            }
+
+            /// Returns the number of compressed bytes produced between the previous flush and the
+            /// last one completing, including those produced by the flush itself.
+            ///
+            /// This is `0` if the last flush emitted nothing new, for example because nothing was
+            /// written since the previous one, so there is nothing to pass on to the peer.
+            pub fn last_flush_output(&self) -> u64 {
+                self.inner.last_flush_output()
+            }
+
+            /// Returns whether the stream has been ended, by closing this encoder or by
+            /// [`finish`](Self::finish), after which nothing more can be written.
+            ///
+            /// Closing it again once the underlying writer has been closed does nothing and returns
+            /// `Ok`, so overlapping cleanup paths can each close it without writing a second
+            /// trailer.
+            pub fn is_finished(&self) -> bool {
+                self.inner.is_finished()
+            }
+
+            /// Flushes the encoder and the underlying writer, returning the number of compressed
+            /// bytes written so far.
+            ///
+            /// The returned offset is a sync point: all input accepted before this call can be
+            /// decoded from just that many bytes at the start of the output, without needing
+            /// anything written afterwards, so it can be recorded to resume a transfer from.
+            ///
+            /// This always makes a sync flush, or a full one if that is the flush mode, whatever
+            /// mode was set with [`set_flush_mode`](Self::set_flush_mode).
+            ///
+            /// Fails with an error of kind [`Unsupported`](std::io::ErrorKind::Unsupported) for
+            /// the bzip2, lzma and lz4 block formats, which can't write out all the input before
+            /// the stream ends.
+            pub fn poll_flush_at_input_offset(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<u64>> {
+                self.project().inner.poll_flush_at_input_offset(cx)
+            }
+
+            /// Writes the end of the compressed stream, such as its trailer, and flushes the
+            /// underlying writer, but unlike closing does not close it.
+            ///
+            /// This is for writing more after the compressed stream, e.g. a protocol trailer on
+            /// the same connection, see [`finish`](Self::finish). Nothing more may be written to
+            /// this encoder afterwards.
+            pub fn poll_finish(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                self.project().inner.poll_finish(cx)
+            }
+
+            /// Writes the end of the compressed stream and flushes the underlying writer without
+            /// closing it, see [`poll_finish`](Self::poll_finish), then returns the writer.
+            ///
+            /// Unlike [`into_inner`](Self::into_inner) the stream is complete and decodable once
+            /// this resolves.
+            pub fn finish(mut self) -> impl std::future::Future<Output = std::io::Result<$inner>>
+            where
+                $inner: Unpin,
+            {
+                async move {
+                    std::future::poll_fn(|cx| std::pin::Pin::new(&mut self).poll_finish(cx)).await?;
+                    Ok(self.into_inner())
+                }
+            }
+
+            /// Writes out the compressed output buffered in this encoder, then seeks the
+            /// underlying writer, returning the new position. This is for container formats that
+            /// need to go back and patch a header, such as a length prefix, once the compressed
+            /// data has been written.
+            ///
+            /// All the input written so far must have been flushed, with
+            /// [`poll_flush`](futures_io::AsyncWrite::poll_flush) in a mode other than
+            /// [`FlushMode::None`](crate::write::FlushMode::None), or the stream closed, otherwise
+            /// this returns an error and does not seek. Compressed output for that input would
+            /// still be held by the format's encoder and would be written at the new position.
+            ///
+            /// The encoder is unaware of the seek, anything written afterwards, including the end
+            /// of the stream written on close, is written at the new position. So the writer
+            /// should be seeked back to where it was, e.g. to the offset returned by
+            /// `SeekFrom::Current(0)`, before writing more, otherwise the stream is corrupted.
+            /// Counters such as [`total_out`](Self::total_out) are not affected by seeking.
+            pub fn poll_seek_inner(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                pos: std::io::SeekFrom,
+            ) -> std::task::Poll<std::io::Result<u64>>
+            where
+                $inner: futures_io::AsyncSeek,
+            {
+                self.project().inner.poll_seek_inner(cx, pos)
+            }
+
+            $($($methods)*)?
         }
 
         impl<$inner: futures_io::AsyncWrite> futures_io::AsyncWrite for $name<$inner> {
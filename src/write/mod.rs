@@ -5,13 +5,100 @@
 mod macros;
 mod generic;
 
+mod blocking;
 mod buf_write;
 mod buf_writer;
+mod flush_mode;
 
-use self::{
-    buf_write::AsyncBufWrite,
-    buf_writer::BufWriter,
-    generic::{Decoder, Encoder},
+use self::{buf_write::AsyncBufWrite, buf_writer::BufWriter};
+
+pub use self::generic::{Decoder, Encoder};
+
+pub use self::{
+    blocking::{BlockingWriter, BlockingWriterTask},
+    flush_mode::FlushMode,
 };
 
 algos!(write<W>);
+
+#[cfg(feature = "gzip")]
+impl<W: futures_io::AsyncWrite> GzipEncoder<W> {
+    /// Ends the current gzip member, writing its trailer, and flushes the underlying writer. The
+    /// next write starts a new member, so the output is a series of members which can each be
+    /// decoded on their own, such as the records of an append-only log.
+    ///
+    /// Nothing is written if the member was already ended with nothing written since. Unlike
+    /// closing, the underlying writer is left open.
+    pub fn poll_finish_member(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        self.project().inner.poll_finish_stream(cx)
+    }
+
+    /// Ends the current gzip member, see [`poll_finish_member`](Self::poll_finish_member).
+    pub fn finish_member(&mut self) -> impl core::future::Future<Output = std::io::Result<()>> + '_
+    where
+        W: Unpin,
+    {
+        core::future::poll_fn(move |cx| core::pin::Pin::new(&mut *self).poll_finish_member(cx))
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: futures_io::AsyncWrite> ZstdEncoder<W> {
+    /// Ends the current zstd frame, writing its epilogue, and flushes the underlying writer. The
+    /// next write starts a new frame, so the output is a series of frames which can each be
+    /// decoded on their own, such as the records of an append-only log.
+    ///
+    /// Nothing is written if the frame was already ended with nothing written since. Unlike
+    /// closing, the underlying writer is left open.
+    pub fn poll_finish_frame(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        self.project().inner.poll_finish_stream(cx)
+    }
+
+    /// Ends the current zstd frame, see [`poll_finish_frame`](Self::poll_finish_frame).
+    pub fn finish_frame(&mut self) -> impl core::future::Future<Output = std::io::Result<()>> + '_
+    where
+        W: Unpin,
+    {
+        core::future::poll_fn(move |cx| core::pin::Pin::new(&mut *self).poll_finish_frame(cx))
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl<W: futures_io::AsyncWrite> DeflateEncoder<W> {
+    /// Sets whether a message can refer back to the data of earlier ones, defaults to `true`.
+    ///
+    /// When disabled, each flush with [`poll_flush`](futures_io::AsyncWrite::poll_flush) ends a
+    /// message and the next one starts with an empty window, as a fresh encoder would, but keeping
+    /// the allocated state. This is permessage-deflate's (RFC 7692) `no_context_takeover`, a peer
+    /// that negotiated it decodes each message on its own. The decoder may keep its window, but can
+    /// discard it with `DeflateDecoder::with_context_takeover`.
+    pub fn with_context_takeover(mut self, enabled: bool) -> Self {
+        self.inner.get_encoder_mut().set_context_takeover(enabled);
+        self
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl<W: futures_io::AsyncWrite> DeflateDecoder<W> {
+    /// Sets whether a message can refer back to the data of earlier ones, defaults to `true`.
+    ///
+    /// When disabled, each flush with [`poll_flush`](futures_io::AsyncWrite::poll_flush) ends a
+    /// message and the window is emptied before the next, keeping the allocated state, to decode
+    /// the messages of a permessage-deflate (RFC 7692) peer that negotiated `no_context_takeover`.
+    /// The input written before each flush must be a whole message ending in a sync flush,
+    /// including the `00 00 FF FF` that permessage-deflate strips, otherwise the rest of the
+    /// message is decoded without the start of its window and fails or is corrupted.
+    pub fn with_context_takeover(mut self, enabled: bool) -> Self {
+        self.inner.get_decoder_mut().set_context_takeover(enabled);
+        self
+    }
+}
+
+formats!(W: [futures_io::AsyncWrite] => [futures_io::AsyncWrite]);
+default_algos!();
@@ -0,0 +1,114 @@
+//! Counts the allocations made while decoding, which needs its own global allocator and so its own
+//! test binary.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[macro_use]
+mod utils;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+#[ntest::timeout(5000)]
+fn stream_decompress_reuses_output_buffer() {
+    use async_compression::stream::GzipDecoder;
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::StreamExt};
+
+    let input = vec![7u8; 4 * 1024 * 1024];
+    let compressed = Bytes::from(utils::gzip::sync::compress(&input));
+
+    for capacity in &[1024, 8000, 64 * 1024] {
+        let mut decoder = GzipDecoder::new(futures::stream::iter(vec![Ok(compressed.clone())]))
+            .with_buffer_capacity(*capacity);
+
+        let before = ALLOCATIONS.load(Ordering::SeqCst);
+        let (mut chunks, mut len) = (0, 0);
+        while let Some(chunk) = block_on(decoder.next()) {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= *capacity);
+            len += chunk.len();
+            chunks += 1;
+        }
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst) - before;
+
+        assert_eq!(len, input.len());
+        // Once a chunk is dropped its space is reused for the next one, so the allocations don't
+        // grow with the number of chunks
+        assert!(
+            allocations < 20,
+            "{} allocations for {} chunks of {} bytes",
+            allocations,
+            chunks,
+            capacity
+        );
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn stream_compress_size_hint() {
+    use async_compression::{flate2::Compression, stream::GzipEncoder};
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    // Barely compressible, so the output is about as large as the input
+    let input: Vec<u8> = (0..1024 * 1024u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+        .collect();
+    let chunks: Vec<Bytes> = input.chunks(64 * 1024).map(Bytes::from).collect();
+
+    let compress = |hint: Option<usize>| {
+        let mut encoder = GzipEncoder::new(
+            futures::stream::iter(chunks.iter().cloned().map(Ok)),
+            Compression::default(),
+        );
+        if let Some(hint) = hint {
+            encoder = encoder.with_size_hint(hint);
+        }
+
+        let before = ALLOCATIONS.load(Ordering::SeqCst);
+        let output: Vec<Bytes> = block_on(encoder.try_collect()).unwrap();
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst) - before;
+
+        assert_eq!(utils::gzip::sync::decompress(&output.concat()), input);
+        allocations
+    };
+
+    let unhinted = compress(None);
+    let hinted = compress(Some(input.len() + 1024));
+    // An estimate that is too small still produces the same output
+    let underestimated = compress(Some(input.len() / 2));
+
+    assert!(
+        hinted * 4 < unhinted,
+        "{} allocations with a size hint, {} without",
+        hinted,
+        unhinted
+    );
+    assert!(underestimated < unhinted);
+}
@@ -2,3 +2,315 @@
 mod utils;
 
 test_cases!(brotli);
+
+/// Natural-language text which the encoder mostly represents as references into brotli's static
+/// dictionary with word transforms applied (capitalisation, suffixes, surrounding punctuation).
+const TEXT: &str = "\
+It was the best of times, it was the worst of times, it was the age of wisdom, it was the age of \
+foolishness, it was the epoch of belief, it was the epoch of incredulity, it was the season of \
+Light, it was the season of Darkness, it was the spring of hope, it was the winter of despair. \
+THE GOVERNMENT ANNOUNCED THAT EVERYTHING WAS PROCEEDING ACCORDING TO PLAN. Nevertheless, \
+several Independent Observers reported that the Committee's recommendations were being \
+systematically ignored; \"Unbelievable!\" exclaimed one. Meanwhile, the musicians continued \
+playing, dancing, singing and laughing until the morning, when everybody departed quietly.\n";
+
+fn compress_text() -> Vec<u8> {
+    use brotli2::{bufread::BrotliEncoder, CompressMode, CompressParams};
+    use std::io::Read;
+
+    let mut params = CompressParams::new();
+    params.quality(11).mode(CompressMode::Text);
+
+    let mut output = Vec::new();
+    BrotliEncoder::from_params(TEXT.as_bytes(), &params)
+        .read_to_end(&mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn brotli_decompress_static_dictionary_transforms() {
+    let compressed = compress_text();
+
+    // There's too little repetition in the text for this ratio without the static dictionary
+    assert!(compressed.len() < TEXT.len() / 2);
+
+    let chunks = compressed.chunks(1).map(Vec::from).collect::<Vec<_>>();
+    let stream = utils::InputStream::from(chunks);
+
+    assert_eq!(
+        utils::brotli::stream::decompress(stream.stream()),
+        TEXT.as_bytes()
+    );
+    assert_eq!(
+        utils::brotli::bufread::decompress(stream.reader()),
+        TEXT.as_bytes()
+    );
+    assert_eq!(
+        utils::brotli::write::decompress(stream.as_ref(), 1),
+        TEXT.as_bytes()
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn brotli_bufread_into_inner_after_stream_end() {
+    use async_compression::bufread::BrotliDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::brotli::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    input.extend_from_slice(b"trailer");
+
+    let stream = utils::InputStream::from(input.chunks(3).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = BrotliDecoder::new(stream.reader());
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+
+    // Nothing past the end of the compressed stream was consumed
+    let mut reader = decoder.into_inner();
+    let mut rest = Vec::new();
+    block_on(reader.read_to_end(&mut rest)).unwrap();
+    assert_eq!(rest, b"trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn brotli_decompress_backend_errors() {
+    use async_compression::{bufread::BrotliDecoder, BackendError, Format};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let decode_error = |input: &[u8]| {
+        let err = block_on(BrotliDecoder::new(input).read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<BackendError>())
+            .cloned()
+            .unwrap()
+    };
+
+    let mut corrupt = compress_text();
+    let half = corrupt.len() / 2;
+    for byte in &mut corrupt[half..] {
+        *byte ^= 0x55;
+    }
+    let corrupt = decode_error(&corrupt);
+    let padding = decode_error(&[0xff; 4]);
+
+    assert_eq!(corrupt.format(), Format::Brotli);
+    assert_eq!(padding.format(), Format::Brotli);
+    assert_eq!(padding.message(), "PADDING_2");
+    assert_ne!(corrupt.message(), padding.message());
+    assert_ne!(corrupt.code(), padding.code());
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn brotli_decompress_one_byte_output() {
+    use async_compression::{bufread, stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    // Long runs and dictionary references each expand to far more than a single byte of output
+    let input: Vec<u8> = std::iter::repeat_n(TEXT.as_bytes(), 20)
+        .flatten()
+        .copied()
+        .chain(std::iter::repeat_n(0, 100_000))
+        .chain((0..100_000u32).map(|i| (i * 7 % 251) as u8))
+        .collect();
+    let compressed = utils::brotli::sync::compress(&input);
+
+    let mut decoder = bufread::BrotliDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    let mut byte = [0];
+    while block_on(decoder.read(&mut byte)).unwrap() == 1 {
+        output.push(byte[0]);
+    }
+    assert_eq!(output, input);
+
+    let mut decoder = write::BrotliDecoder::new(Vec::new()).with_buffer_capacity(1);
+    block_on(decoder.write_all(&compressed)).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    let source = futures::stream::iter(vec![Ok(Bytes::from(compressed))]);
+    let chunks: Vec<Bytes> = block_on(
+        stream::BrotliDecoder::new(source)
+            .with_buffer_capacity(1)
+            .try_collect(),
+    )
+    .unwrap();
+    assert!(chunks.iter().all(|chunk| chunk.len() == 1));
+    assert_eq!(chunks.concat(), input);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn brotli_text_mode() {
+    use async_compression::{brotli2::CompressMode, bufread::BrotliEncoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = (0..200)
+        .map(|i| {
+            format!(
+                "<li class=\"item\"><a href=\"/items/{}\">Item number {}</a></li>\n",
+                i, i
+            )
+        })
+        .collect::<String>();
+    let input = format!(
+        "<!DOCTYPE html>\n<html><head><title>Items</title></head><body><ul>\n{}</ul></body></html>\n",
+        input
+    )
+    .into_bytes();
+
+    let mut compressed = Vec::new();
+    block_on(
+        BrotliEncoder::with_params(&input[..], 11, 22, CompressMode::Text)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    assert!(compressed.len() < input.len() / 10);
+    assert_eq!(utils::brotli::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn brotli_window_size() {
+    use async_compression::{brotli2::CompressMode, bufread::BrotliEncoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::iter::FromIterator;
+
+    // A random block repeated once, only a window larger than the block can refer back to it
+    let block = Vec::from_iter((0..16 * 1024).map(|_| rand::random::<u8>()));
+    let input = [&block[..], &block[..]].concat();
+
+    let compress = |lgwin| {
+        let mut compressed = Vec::new();
+        block_on(
+            BrotliEncoder::with_params(&input[..], 5, lgwin, CompressMode::Generic)
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+        assert_eq!(utils::brotli::sync::decompress(&compressed), input);
+        compressed.len()
+    };
+
+    assert!(compress(10) > input.len());
+    assert!(compress(24) < block.len() + block.len() / 10);
+}
+
+#[test]
+fn brotli_params_out_of_range() {
+    use async_compression::{brotli2::CompressMode, bufread::BrotliEncoder};
+
+    for &(quality, lgwin) in &[(12, 22), (11, 9), (11, 25), (11, 30)] {
+        let err = BrotliEncoder::with_params(&[][..], quality, lgwin, CompressMode::Generic)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
+
+/// A dictionary of the boilerplate shared by the JSON responses of some API, as would be served
+/// ahead of time to compress each response against.
+const DICTIONARY: &[u8] = br#"{"status":"ok","data":{"items":[{"id":0,"type":"article","attributes":{"title":"","author":{"name":"","email":"@example.com"},"published":"2024-01-01T00:00:00Z","tags":["news","sport","weather"]}}],"links":{"self":"https://api.example.com/v1/articles?page=","next":"https://api.example.com/v1/articles?page="}}}"#;
+
+/// A response which repeats much of the dictionary.
+const RESPONSE: &[u8] = br#"{"status":"ok","data":{"items":[{"id":1234,"type":"article","attributes":{"title":"Local team wins","author":{"name":"Jo","email":"jo@example.com"},"published":"2024-03-02T10:15:00Z","tags":["news","sport"]}}],"links":{"self":"https://api.example.com/v1/articles?page=7","next":"https://api.example.com/v1/articles?page=8"}}}"#;
+
+#[test]
+#[ntest::timeout(5000)]
+fn brotli_dictionary() {
+    use async_compression::{bufread, stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let input = RESPONSE.to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::BrotliEncoder::with_dictionary(&input[..], 11, DICTIONARY)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    // Most of the response is copied from the dictionary
+    assert!(compressed.len() < utils::brotli::sync::compress(&input).len() / 2);
+
+    let mut output = Vec::new();
+    block_on(
+        bufread::BrotliDecoder::with_dictionary(&compressed[..], DICTIONARY)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+
+    let mut encoder = write::BrotliEncoder::with_dictionary(Vec::new(), 5, DICTIONARY).unwrap();
+    block_on(encoder.write_all(&input)).unwrap();
+    block_on(encoder.close()).unwrap();
+    let mut decoder = write::BrotliDecoder::with_dictionary(Vec::new(), DICTIONARY).unwrap();
+    block_on(decoder.write_all(&encoder.into_inner())).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    let source = futures::stream::iter(vec![Ok(Bytes::from(input.clone()))]);
+    let chunks: Vec<Bytes> = block_on(
+        stream::BrotliEncoder::with_dictionary(source, 11, DICTIONARY)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    let source =
+        utils::InputStream::from(chunks.concat().chunks(1).map(Vec::from).collect::<Vec<_>>());
+    let chunks: Vec<Bytes> = block_on(
+        stream::BrotliDecoder::with_dictionary(source.stream(), DICTIONARY)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(chunks.concat(), input);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn brotli_dictionary_missing() {
+    use async_compression::bufread;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    let input = RESPONSE.to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::BrotliEncoder::with_dictionary(&input[..], 11, DICTIONARY)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    let err = block_on(bufread::BrotliDecoder::new(&compressed[..]).read_to_end(&mut Vec::new()))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let err = bufread::BrotliEncoder::with_dictionary(&[][..], 12, DICTIONARY)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = bufread::BrotliDecoder::with_dictionary(&[][..], &vec![0; (1 << 24) + 1])
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
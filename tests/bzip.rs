@@ -2,3 +2,115 @@
 mod utils;
 
 test_cases!(bzip);
+
+#[test]
+#[ntest::timeout(1000)]
+fn bzip_bufread_into_inner_after_stream_end() {
+    use async_compression::bufread::BzDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::bzip::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    input.extend_from_slice(b"trailer");
+
+    let stream = utils::InputStream::from(input.chunks(3).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = BzDecoder::new(stream.reader());
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+
+    // Nothing past the end of the compressed stream was consumed
+    let mut reader = decoder.into_inner();
+    let mut rest = Vec::new();
+    block_on(reader.read_to_end(&mut rest)).unwrap();
+    assert_eq!(rest, b"trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn bzip_decompress_backend_errors() {
+    use async_compression::{bufread::BzDecoder, BackendError, Format};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let decode_error = |input: &[u8]| {
+        let err = block_on(BzDecoder::new(input).read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<BackendError>())
+            .cloned()
+            .unwrap()
+    };
+
+    let not_bzip = decode_error(b"not bzip2 data");
+    let mut corrupt = utils::bzip::sync::compress(&[7; 10_000]);
+    let middle = corrupt.len() / 2;
+    corrupt[middle] ^= 0xff;
+    let corrupt = decode_error(&corrupt);
+
+    assert_eq!(not_bzip.format(), Format::Bzip);
+    assert_eq!(corrupt.format(), Format::Bzip);
+    assert_ne!(not_bzip.code(), corrupt.code());
+    assert_ne!(not_bzip.message(), corrupt.message());
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn bzip_work_factor_repetitive_input() {
+    use async_compression::{bufread::BzEncoder, bzip2::Compression};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    // A short period repeated many times is the worst case for bzip2's block sorting
+    let input = b"abababababababab".repeat(64 * 1024);
+
+    for work_factor in &[0, 1, 250] {
+        let mut encoder =
+            BzEncoder::with_work_factor(&input[..], Compression::Fastest, *work_factor).unwrap();
+        let mut compressed = Vec::new();
+        block_on(encoder.read_to_end(&mut compressed)).unwrap();
+        assert_eq!(utils::bzip::sync::decompress(&compressed), input);
+    }
+}
+
+#[test]
+fn bzip_work_factor_out_of_range() {
+    use async_compression::{bufread::BzEncoder, bzip2::Compression};
+
+    let err = BzEncoder::with_work_factor(&[][..], Compression::Default, 251).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn bzip_decompress_small() {
+    use async_compression::{bufread, stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+    use std::io::Read;
+
+    // Spans two of the largest 900 KiB blocks
+    let input: Vec<u8> = (0..1_000_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let mut compressed = Vec::new();
+    bzip2::read::BzEncoder::new(&input[..], bzip2::Compression::Best)
+        .read_to_end(&mut compressed)
+        .unwrap();
+    // The fourth byte of the header is the block size in units of 100 KiB
+    assert_eq!(compressed[3], b'9');
+
+    let mut output = Vec::new();
+    block_on(bufread::BzDecoder::with_small(&compressed[..], true).read_to_end(&mut output))
+        .unwrap();
+    assert_eq!(output, input);
+
+    let mut decoder = write::BzDecoder::with_small(Vec::new(), true);
+    block_on(decoder.write_all(&compressed)).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    let source = futures::stream::iter(vec![Ok(Bytes::from(compressed))]);
+    let chunks: Vec<Bytes> =
+        block_on(stream::BzDecoder::with_small(source, true).try_collect()).unwrap();
+    assert_eq!(chunks.concat(), input);
+}
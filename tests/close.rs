@@ -0,0 +1,88 @@
+mod utils;
+
+fn input() -> Vec<u8> {
+    (0..20_000u32).map(|i| (i % 13) as u8).collect()
+}
+
+macro_rules! close_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*);)*) => {
+        $(
+            #[test]
+            #[ntest::timeout(5000)]
+            fn $name() {
+                use async_compression::write::$encoder;
+                use futures::{executor::block_on, io::AsyncWriteExt};
+                use futures_test::{io::AsyncWriteTestExt, task::noop_context};
+                use std::{pin::Pin, task::Poll};
+
+                let input = input();
+
+                // Only one byte is accepted per write, and every other write is refused
+                let writer = Vec::new().limited_write(1).interleave_pending_write();
+                let mut encoder = $encoder::new(writer, $($arg),*);
+                block_on(encoder.write_all(&input)).unwrap();
+
+                let mut cx = noop_context();
+                let mut pending = 0;
+                loop {
+                    match futures::io::AsyncWrite::poll_close(Pin::new(&mut encoder), &mut cx) {
+                        Poll::Ready(result) => break result.unwrap(),
+                        Poll::Pending => pending += 1,
+                    }
+                }
+                assert!(pending > 0);
+
+                let output = encoder.into_inner().into_inner().into_inner();
+                assert_eq!(utils::$name::sync::decompress(&output), input);
+            }
+        )*
+    };
+}
+
+close_tests! {
+    brotli: BrotliEncoder(1);
+    bzip: BzEncoder(async_compression::bzip2::Compression::Fastest);
+    deflate: DeflateEncoder(async_compression::flate2::Compression::fast());
+    gzip: GzipEncoder(async_compression::flate2::Compression::fast());
+    identity: IdentityEncoder();
+    lz4: Lz4Encoder(0);
+    lzma: LzmaEncoder(1);
+    snappy: SnappyEncoder();
+    xz: XzEncoder(1);
+    zlib: ZlibEncoder(async_compression::flate2::Compression::fast());
+    zstd: ZstdEncoder(1);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn gzip_close_twice() {
+    use async_compression::write::GzipEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    let input = input();
+
+    // Fails if it is closed again
+    let mut encoder = GzipEncoder::new(
+        Vec::new().track_closed(),
+        async_compression::flate2::Compression::fast(),
+    );
+    block_on(encoder.write_all(&input)).unwrap();
+    assert!(!encoder.is_finished());
+
+    block_on(encoder.close()).unwrap();
+    assert!(encoder.is_finished());
+    let len = encoder.get_ref().get_ref().len();
+
+    block_on(encoder.close()).unwrap();
+    assert!(encoder.is_finished());
+    assert_eq!(encoder.total_out(), len as u64);
+
+    let output = encoder.into_inner().into_inner();
+    assert_eq!(output.len(), len);
+    // A second trailer would be read as the start of another member
+    assert_eq!(utils::gzip::sync::decompress(&output), input);
+    let mut decoder = flate2::bufread::GzDecoder::new(&output[..]);
+    std::io::copy(&mut decoder, &mut std::io::sink()).unwrap();
+    assert!(decoder.into_inner().is_empty());
+}
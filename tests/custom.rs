@@ -0,0 +1,125 @@
+//! A trivial custom format built on the public codec traits, wrapped in each of the generic
+//! encoders and decoders.
+
+use async_compression::codec::{Decode, Encode, PartialBuffer};
+use std::io::Result;
+
+#[macro_use]
+mod utils;
+
+/// XORs every byte with a key, so encoding and decoding are the same.
+#[derive(Debug)]
+struct Xor(u8);
+
+impl Xor {
+    fn apply(&self, input: &mut PartialBuffer<&[u8]>, output: &mut PartialBuffer<&mut [u8]>) {
+        let len = std::cmp::min(input.unwritten().len(), output.unwritten().len());
+        for (out, byte) in output.unwritten_mut()[..len]
+            .iter_mut()
+            .zip(&input.unwritten()[..len])
+        {
+            *out = byte ^ self.0;
+        }
+        input.advance(len);
+        output.advance(len);
+    }
+}
+
+impl Encode for Xor {
+    fn encode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<()> {
+        self.apply(input, output);
+        Ok(())
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn finish(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Decode for Xor {
+    fn decode(
+        &mut self,
+        input: &mut PartialBuffer<&[u8]>,
+        output: &mut PartialBuffer<&mut [u8]>,
+    ) -> Result<bool> {
+        self.apply(input, output);
+        Ok(false)
+    }
+
+    fn flush(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn finish(&mut self, _output: &mut PartialBuffer<&mut [u8]>) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+fn xor(data: &[u8]) -> Vec<u8> {
+    data.iter().map(|byte| byte ^ 0x5a).collect()
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn custom_codec_all_interfaces() {
+    use async_compression::{bufread, read, stream, write};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let input = utils::InputStream::from(vec![
+        b"hello ".to_vec(),
+        b"custom ".to_vec(),
+        b"format".to_vec(),
+    ]);
+    let encoded = xor(&input.bytes());
+
+    let mut output = Vec::new();
+    block_on(bufread::Encoder::new(input.reader(), Xor(0x5a)).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, encoded);
+
+    let mut output = Vec::new();
+    block_on(read::Decoder::new(&encoded[..], Xor(0x5a)).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input.bytes());
+
+    let mut encoder = write::Encoder::new(Vec::new(), Xor(0x5a));
+    for chunk in input.as_ref() {
+        block_on(encoder.write_all(chunk)).unwrap();
+    }
+    block_on(encoder.close()).unwrap();
+    assert_eq!(encoder.into_inner(), encoded);
+
+    let output: Vec<u8> = block_on(
+        stream::Decoder::new(
+            utils::InputStream::from(vec![encoded.clone()]).stream(),
+            Xor(0x5a),
+        )
+        .try_concat(),
+    )
+    .unwrap()
+    .to_vec();
+    assert_eq!(output, input.bytes());
+
+    // The generic types keep the features of the built in formats
+    let mut decoder = bufread::Decoder::new(&encoded[..], Xor(0x5a));
+    decoder.set_limit(5);
+    let err = block_on(decoder.read_to_end(&mut Vec::new())).unwrap_err();
+    assert!(err
+        .get_ref()
+        .unwrap()
+        .is::<async_compression::LimitExceeded>());
+}
@@ -0,0 +1,126 @@
+//! The encoders and decoders implement `Debug` whatever they wrap.
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::{fmt::Debug, io::Result};
+
+use async_compression::{bufread, flate2::Compression, read, stream, tokio, write};
+use bytes::Bytes;
+
+/// An empty reader, writer and stream that doesn't implement `Debug`.
+struct Opaque;
+
+impl futures::io::AsyncRead for Opaque {
+    fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<Result<usize>> {
+        Poll::Ready(Ok(0))
+    }
+}
+
+impl futures::io::AsyncBufRead for Opaque {
+    fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        Poll::Ready(Ok(&[]))
+    }
+
+    fn consume(self: Pin<&mut Self>, _: usize) {}
+}
+
+impl futures::io::AsyncWrite for Opaque {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl futures::stream::Stream for Opaque {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        Poll::Ready(None)
+    }
+}
+
+impl tokio_crate::io::AsyncRead for Opaque {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        _: &mut tokio_crate::io::ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl tokio_crate::io::AsyncBufRead for Opaque {
+    fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<&[u8]>> {
+        Poll::Ready(Ok(&[]))
+    }
+
+    fn consume(self: Pin<&mut Self>, _: usize) {}
+}
+
+impl tokio_crate::io::AsyncWrite for Opaque {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn assert_debug<T: Debug>() {}
+
+#[test]
+fn debug_without_debug_inner() {
+    assert_debug::<bufread::GzipEncoder<Opaque>>();
+    assert_debug::<bufread::GzipDecoder<Opaque>>();
+    assert_debug::<read::GzipEncoder<Opaque>>();
+    assert_debug::<read::GzipDecoder<Opaque>>();
+    assert_debug::<stream::GzipEncoder<Opaque>>();
+    assert_debug::<stream::GzipDecoder<Opaque>>();
+    assert_debug::<write::GzipEncoder<Opaque>>();
+    assert_debug::<write::GzipDecoder<Opaque>>();
+    assert_debug::<tokio::bufread::GzipEncoder<Opaque>>();
+    assert_debug::<tokio::bufread::GzipDecoder<Opaque>>();
+    assert_debug::<tokio::write::GzipEncoder<Opaque>>();
+    assert_debug::<tokio::write::GzipDecoder<Opaque>>();
+}
+
+#[test]
+fn debug_output() {
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut encoder = bufread::GzipEncoder::new(Opaque, Compression::default());
+    assert_eq!(
+        format!("{:?}", encoder),
+        "GzipEncoder { reader: .., total_in: 0, total_out: 0 }"
+    );
+
+    let mut output = Vec::new();
+    block_on(encoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(
+        format!("{:?}", encoder),
+        format!(
+            "GzipEncoder {{ reader: .., total_in: 0, total_out: {} }}",
+            output.len()
+        )
+    );
+
+    let decoder = write::GzipDecoder::new(Opaque);
+    assert_eq!(
+        format!("{:?}", decoder),
+        "GzipDecoder { writer: .., total_in: 0, total_out: 0 }"
+    );
+}
@@ -0,0 +1,54 @@
+use async_compression::bufread::{DefaultDecoder, DefaultEncoder};
+use futures::{executor::block_on, io::AsyncReadExt};
+
+/// Only applies when `default-zstd` is the sole `default-*` feature, with more enabled (e.g. by
+/// `--all-features`) the first one in alphabetical order is chosen instead.
+#[cfg(not(any(
+    feature = "default-brotli",
+    feature = "default-bzip",
+    feature = "default-deflate",
+    feature = "default-gzip",
+    feature = "default-identity",
+    feature = "default-lz4",
+    feature = "default-lzma",
+    feature = "default-snappy",
+    feature = "default-xz",
+    feature = "default-zlib"
+)))]
+#[test]
+fn default_is_zstd() {
+    use async_compression::write;
+    use futures::io::AsyncWriteExt;
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+
+    let mut compressed = Vec::new();
+    block_on(DefaultEncoder::new(&input[..], 0).read_to_end(&mut compressed)).unwrap();
+
+    // The aliases name exactly the zstd types, so the output is a zstd frame
+    assert_eq!(&compressed[..4], &[0x28, 0xb5, 0x2f, 0xfd]);
+    let _: async_compression::bufread::ZstdDecoder<&[u8]> = DefaultDecoder::new(&compressed[..]);
+
+    let mut output = Vec::new();
+    block_on(DefaultDecoder::new(&compressed[..]).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+
+    let mut decoder = write::DefaultDecoder::new(Vec::new());
+    block_on(decoder.write_all(&compressed)).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+}
+
+#[cfg(feature = "default-brotli")]
+#[test]
+fn first_default_wins() {
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+
+    let mut compressed = Vec::new();
+    block_on(DefaultEncoder::new(&input[..], 11).read_to_end(&mut compressed)).unwrap();
+    let _: async_compression::bufread::BrotliDecoder<&[u8]> = DefaultDecoder::new(&compressed[..]);
+
+    let mut output = Vec::new();
+    block_on(DefaultDecoder::new(&compressed[..]).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+}
@@ -2,3 +2,522 @@
 mod utils;
 
 test_cases!(deflate);
+
+fn compress(data: &[u8], level: flate2::Compression) -> Vec<u8> {
+    use flate2::bufread::DeflateEncoder;
+    use std::io::Read;
+
+    let mut output = Vec::new();
+    DeflateEncoder::new(data, level)
+        .read_to_end(&mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_bufread_decompress_was_stored() {
+    use async_compression::bufread::DeflateDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = compress(&[1, 2, 3, 4, 5, 6], flate2::Compression::none());
+
+    let mut decoder = DeflateDecoder::new(&compressed[..]);
+    assert_eq!(decoder.was_stored(), None);
+
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(decoder.was_stored(), Some(true));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_bufread_decompress_was_not_stored() {
+    use async_compression::bufread::DeflateDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = compress(&[1, 2, 3, 4, 5, 6], flate2::Compression::fast());
+
+    let mut decoder = DeflateDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(decoder.was_stored(), Some(false));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_bufread_decompress_stops_at_stream_end() {
+    use async_compression::bufread::DeflateDecoder;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, Cursor},
+    };
+
+    let mut input = compress(&[1, 2, 3, 4, 5, 6], flate2::Compression::fast());
+    let len = input.len();
+    input.extend_from_slice(b"not deflate");
+
+    let mut decoder = DeflateDecoder::new(Cursor::new(input));
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+
+    let mut reader = decoder.into_inner();
+    assert_eq!(reader.position(), len as u64);
+    let mut trailing = Vec::new();
+    block_on(reader.read_to_end(&mut trailing)).unwrap();
+    assert_eq!(trailing, b"not deflate");
+}
+
+fn compress_with_termination(
+    input: &[Vec<u8>],
+    termination: async_compression::flate2::Termination,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    use async_compression::{bufread, flate2::Compression, stream, write};
+    use futures::{
+        executor::{block_on, block_on_stream},
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let input = utils::InputStream::from(input.to_vec());
+
+    let mut bufread_output = Vec::new();
+    let mut encoder = bufread::DeflateEncoder::new(input.reader(), Compression::fast())
+        .with_termination(termination);
+    block_on(encoder.read_to_end(&mut bufread_output)).unwrap();
+
+    let encoder = stream::DeflateEncoder::new(Box::pin(input.stream()), Compression::fast())
+        .with_termination(termination);
+    let stream_output = block_on_stream(encoder).flat_map(Result::unwrap).collect();
+
+    let mut encoder =
+        write::DeflateEncoder::new(Vec::new(), Compression::fast()).with_termination(termination);
+    for chunk in input.as_ref() {
+        block_on(encoder.write_all(chunk)).unwrap();
+        block_on(encoder.flush()).unwrap();
+    }
+    block_on(encoder.close()).unwrap();
+    let write_output = encoder.into_inner();
+
+    (bufread_output, stream_output, write_output)
+}
+
+fn decompress_with_termination(
+    input: &[u8],
+    termination: async_compression::flate2::Termination,
+) -> Vec<u8> {
+    use async_compression::{bufread, stream, write};
+    use futures::{
+        executor::{block_on, block_on_stream},
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let input = utils::InputStream::from(input.chunks(1).map(Vec::from).collect::<Vec<_>>());
+
+    let mut bufread_output = Vec::new();
+    let mut decoder = bufread::DeflateDecoder::new(input.reader()).with_termination(termination);
+    block_on(decoder.read_to_end(&mut bufread_output)).unwrap();
+
+    let decoder =
+        stream::DeflateDecoder::new(Box::pin(input.stream())).with_termination(termination);
+    let stream_output: Vec<u8> = block_on_stream(decoder).flat_map(Result::unwrap).collect();
+
+    let mut decoder = write::DeflateDecoder::new(Vec::new()).with_termination(termination);
+    for chunk in input.as_ref() {
+        block_on(decoder.write_all(chunk)).unwrap();
+    }
+    block_on(decoder.close()).unwrap();
+    let write_output = decoder.into_inner();
+
+    assert_eq!(bufread_output, stream_output);
+    assert_eq!(bufread_output, write_output);
+    bufread_output
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_termination_final() {
+    use async_compression::flate2::Termination;
+
+    let input = vec![vec![1, 2, 3], vec![4, 5, 6]];
+    let (bufread, stream, write) = compress_with_termination(&input, Termination::Final);
+
+    for output in &[bufread, stream, write] {
+        assert!(!output.ends_with(&[0, 0, 0xff, 0xff]));
+        assert_eq!(
+            utils::deflate::sync::decompress(output),
+            &[1, 2, 3, 4, 5, 6][..]
+        );
+        assert_eq!(
+            decompress_with_termination(output, Termination::Final),
+            &[1, 2, 3, 4, 5, 6][..]
+        );
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_termination_sync_flush() {
+    use async_compression::flate2::Termination;
+
+    for input in &[vec![], vec![vec![1, 2, 3], vec![4, 5, 6]]] {
+        let expected: Vec<u8> = input.iter().flatten().cloned().collect();
+
+        let outputs = compress_with_termination(input, Termination::SyncFlush);
+        let stripped = compress_with_termination(input, Termination::StrippedSyncFlush);
+
+        for (output, stripped) in &[
+            (outputs.0, stripped.0),
+            (outputs.1, stripped.1),
+            (outputs.2, stripped.2),
+        ] {
+            assert!(output.ends_with(&[0, 0, 0xff, 0xff]));
+            assert_eq!(&output[..output.len() - 4], &stripped[..]);
+
+            assert_eq!(
+                decompress_with_termination(output, Termination::SyncFlush),
+                expected
+            );
+            assert_eq!(
+                decompress_with_termination(stripped, Termination::StrippedSyncFlush),
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_write_compress_flush_at_input_offset() {
+    use async_compression::{flate2::Compression, write::DeflateEncoder};
+    use flate2::{Decompress, FlushDecompress};
+    use futures::{executor::block_on, future::poll_fn, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+    use std::pin::Pin;
+
+    let mut encoder = DeflateEncoder::new(
+        Vec::new().interleave_pending_write(),
+        Compression::default(),
+    );
+
+    block_on(encoder.write_all(b"hello world, ")).unwrap();
+    block_on(encoder.write_all(b"hello again")).unwrap();
+    let offset = block_on(poll_fn(|cx| {
+        Pin::new(&mut encoder).poll_flush_at_input_offset(cx)
+    }))
+    .unwrap();
+
+    block_on(encoder.write_all(b", and goodbye")).unwrap();
+    block_on(encoder.close()).unwrap();
+    let compressed = encoder.into_inner().into_inner();
+    assert!((offset as usize) < compressed.len());
+
+    // Decoding just the bytes before the offset yields exactly the input written before it
+    let mut output = Vec::with_capacity(1024);
+    Decompress::new(false)
+        .decompress_vec(
+            &compressed[..offset as usize],
+            &mut output,
+            FlushDecompress::Sync,
+        )
+        .unwrap();
+    assert_eq!(output, b"hello world, hello again");
+
+    assert_eq!(
+        utils::deflate::sync::decompress(&compressed),
+        b"hello world, hello again, and goodbye"
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_write_compress_flush_at_input_offset_without_flush_mode() {
+    use async_compression::{
+        flate2::Compression,
+        write::{DeflateEncoder, FlushMode},
+    };
+    use flate2::{Decompress, FlushDecompress};
+    use futures::{executor::block_on, future::poll_fn, io::AsyncWriteExt};
+    use std::pin::Pin;
+
+    // Plain flushes write nothing out, but the offset must still be a sync point
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.set_flush_mode(FlushMode::None);
+
+    block_on(encoder.write_all(b"hello world, hello again")).unwrap();
+    let offset = block_on(poll_fn(|cx| {
+        Pin::new(&mut encoder).poll_flush_at_input_offset(cx)
+    }))
+    .unwrap();
+    assert!(offset > 0);
+
+    block_on(encoder.write_all(b", and goodbye")).unwrap();
+    block_on(encoder.close()).unwrap();
+    let compressed = encoder.into_inner();
+
+    let mut output = Vec::with_capacity(1024);
+    Decompress::new(false)
+        .decompress_vec(
+            &compressed[..offset as usize],
+            &mut output,
+            FlushDecompress::Sync,
+        )
+        .unwrap();
+    assert_eq!(output, b"hello world, hello again");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_write_compress_last_flush_output() {
+    use async_compression::{flate2::Compression, write::DeflateEncoder};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    let mut encoder = DeflateEncoder::new(
+        Vec::new().interleave_pending_write(),
+        Compression::default(),
+    );
+    assert_eq!(encoder.last_flush_output(), 0);
+
+    block_on(encoder.write_all(b"hello world")).unwrap();
+    block_on(encoder.flush()).unwrap();
+    let first = encoder.last_flush_output();
+    assert!(first > 0);
+    assert_eq!(first, encoder.total_out());
+
+    // Nothing was written since, so the flush has nothing new to emit
+    block_on(encoder.flush()).unwrap();
+    assert_eq!(encoder.last_flush_output(), 0);
+
+    block_on(encoder.write_all(b", hello again")).unwrap();
+    block_on(encoder.flush()).unwrap();
+    assert!(encoder.last_flush_output() > 0);
+    assert_eq!(first + encoder.last_flush_output(), encoder.total_out());
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_dictionary() {
+    use async_compression::{bufread, flate2::Compression, write};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let dictionary = b"the quick brown fox jumps over the lazy dog";
+    let input = b"the lazy dog jumps over the quick brown fox".to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::DeflateEncoder::with_dictionary(&input[..], Compression::default(), dictionary)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    assert!(compressed.len() < utils::deflate::sync::compress(&input).len());
+
+    let mut decoder = write::DeflateDecoder::with_dictionary(Vec::new(), dictionary).unwrap();
+    block_on(decoder.write_all(&compressed)).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    // Raw deflate doesn't identify the dictionary, so the wrong one can't decode the same data
+    let mut output = Vec::new();
+    let result = block_on(
+        bufread::DeflateDecoder::with_dictionary(
+            &compressed[..],
+            b"pack my box with five dozen liquor jugs",
+        )
+        .unwrap()
+        .read_to_end(&mut output),
+    );
+    assert!(result.is_err() || output != input);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate_decompress_large_and_tiny_reads() {
+    use async_compression::{bufread, read};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input: Vec<u8> = (0..256 * 1024u32).map(|i| (i % 251) as u8).collect();
+    let compressed = compress(&input, flate2::Compression::fast());
+
+    // With all the input available a single large read is filled with the whole output
+    let mut output = vec![0; 1024 * 1024];
+    let len = block_on(bufread::DeflateDecoder::new(&compressed[..]).read(&mut output)).unwrap();
+    assert_eq!(&output[..len], &input[..]);
+    let len = block_on(read::DeflateDecoder::new(&compressed[..]).read(&mut output)).unwrap();
+    assert_eq!(&output[..len], &input[..]);
+
+    let read_bytewise = |mut reader: std::pin::Pin<Box<dyn futures::io::AsyncRead>>| {
+        let mut output = Vec::new();
+        let mut byte = [0];
+        while block_on(reader.read(&mut byte)).unwrap() == 1 {
+            output.push(byte[0]);
+        }
+        output
+    };
+    assert_eq!(
+        read_bytewise(Box::pin(bufread::DeflateDecoder::new(&compressed[..]))),
+        input
+    );
+    assert_eq!(
+        read_bytewise(Box::pin(read::DeflateDecoder::new(&compressed[..]))),
+        input
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate_window_bits() {
+    use async_compression::{bufread, flate2::Compression};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    // Noise repeated 2000 bytes later, which only a window of more than 512 bytes can match
+    let mut x: u32 = 0x1234_5678;
+    let noise: Vec<u8> = (0..2000)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect();
+    let input = noise.repeat(2);
+
+    let compress = |window_bits: u8| {
+        let mut output = Vec::new();
+        block_on(
+            bufread::DeflateEncoder::with_window_bits(
+                &input[..],
+                Compression::default(),
+                window_bits,
+            )
+            .unwrap()
+            .read_to_end(&mut output),
+        )
+        .unwrap();
+        output
+    };
+    let decompress = |compressed: &[u8], window_bits: u8| {
+        let mut output = Vec::new();
+        block_on(
+            bufread::DeflateDecoder::with_window_bits(compressed, window_bits)
+                .unwrap()
+                .read_to_end(&mut output),
+        )
+        .unwrap();
+        output
+    };
+
+    let small = compress(9);
+    let large = compress(15);
+    assert!(large.len() < small.len());
+
+    assert_eq!(decompress(&small, 9), input);
+    assert_eq!(decompress(&small, 15), input);
+    assert_eq!(decompress(&large, 15), input);
+    assert_eq!(utils::deflate::sync::decompress(&small), input);
+
+    assert_eq!(
+        bufread::DeflateEncoder::with_window_bits(&input[..], Compression::default(), 8)
+            .err()
+            .unwrap()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        bufread::DeflateDecoder::with_window_bits(&large[..], 16)
+            .err()
+            .unwrap()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_write_no_context_takeover() {
+    use async_compression::{
+        bufread,
+        flate2::{Compression, Termination},
+        write,
+    };
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let messages: &[&[u8]] = &[
+        b"{\"type\":\"update\",\"id\":1,\"value\":\"the quick brown fox\"}",
+        b"{\"type\":\"update\",\"id\":2,\"value\":\"the quick brown fox\"}",
+    ];
+
+    // Each message is sync flushed and has the trailing `00 00 FF FF` stripped, as
+    // permessage-deflate (RFC 7692) frames them
+    let compress = |context_takeover| {
+        let mut encoder = write::DeflateEncoder::new(Vec::new(), Compression::default())
+            .with_context_takeover(context_takeover);
+        let mut frames = Vec::new();
+        for message in messages {
+            block_on(encoder.write_all(message)).unwrap();
+            block_on(encoder.flush()).unwrap();
+            let mut frame = std::mem::take(encoder.get_mut());
+            assert!(frame.ends_with(&[0x00, 0x00, 0xff, 0xff]));
+            frame.truncate(frame.len() - 4);
+            frames.push(frame);
+        }
+        frames
+    };
+    let taken_over = compress(true);
+    let frames = compress(false);
+
+    // Without the context the second message can't refer back to the first
+    assert_eq!(frames[0], taken_over[0]);
+    assert!(frames[1].len() > taken_over[1].len());
+    assert_eq!(frames[0].len(), frames[1].len());
+
+    // So each message decodes on its own, with a fresh decoder
+    for (frame, message) in frames.iter().zip(messages) {
+        let mut output = Vec::new();
+        let mut decoder = bufread::DeflateDecoder::new(&frame[..])
+            .with_termination(Termination::StrippedSyncFlush);
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, *message);
+    }
+
+    // Or with one decoder that discards its window after each message
+    let mut decoder = write::DeflateDecoder::new(Vec::new()).with_context_takeover(false);
+    for (frame, message) in frames.iter().zip(messages) {
+        block_on(decoder.write_all(frame)).unwrap();
+        block_on(decoder.write_all(&[0x00, 0x00, 0xff, 0xff])).unwrap();
+        block_on(decoder.flush()).unwrap();
+        assert_eq!(std::mem::take(decoder.get_mut()), *message);
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn deflate_write_flush_level_0_larger_than_buffer() {
+    use async_compression::{flate2::Compression, write};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    // Stored blocks are copied straight to the output, so the flush runs out of space part way
+    // through and has to be repeated to write out the rest of the input
+    let input: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+    let mut encoder = write::DeflateEncoder::new(Vec::new(), Compression::none());
+    block_on(encoder.write_all(&input)).unwrap();
+    block_on(encoder.flush()).unwrap();
+
+    let mut decoder = write::DeflateDecoder::new(Vec::new());
+    block_on(decoder.write_all(encoder.get_ref())).unwrap();
+    block_on(decoder.flush()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+}
@@ -0,0 +1,166 @@
+mod utils;
+
+use async_compression::{bufread::Deflate64Decoder, stream};
+use bytes::Bytes;
+use futures::{
+    executor::{block_on, block_on_stream},
+    io::AsyncReadExt,
+    stream::TryStreamExt,
+};
+use std::io::ErrorKind;
+use utils::InputStream;
+
+/// Bytes from a xorshift generator, which nothing can be matched against.
+fn noise(len: usize) -> Vec<u8> {
+    let mut x: u32 = 0x1234_5678;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect()
+}
+
+/// A deflate64 stream of a stored block of 40000 noise bytes, then a final fixed Huffman block of
+/// a match of length 300 at distance 40000, a match of length 50000 at distance 1 and the
+/// literals `end`. Both matches are out of reach of deflate, using the distance code for 32769
+/// and up and the length code taking 16 extra bits. Checked against Info-ZIP's unzip by storing
+/// it in a zip archive as method 9.
+fn sample() -> (Vec<u8>, Vec<u8>) {
+    const FIXED_BLOCK: [u8; 14] = [
+        0x1b, 0x4d, 0x09, 0x78, 0x3f, 0xdc, 0x68, 0xd3, 0x30, 0x48, 0xcd, 0x4b, 0x01, 0x00,
+    ];
+
+    let noise = noise(40000);
+
+    let mut compressed = vec![0];
+    compressed.extend_from_slice(&40000u16.to_le_bytes());
+    compressed.extend_from_slice(&(!40000u16).to_le_bytes());
+    compressed.extend_from_slice(&noise);
+    compressed.extend_from_slice(&FIXED_BLOCK);
+
+    let mut expected = noise.clone();
+    expected.extend_from_slice(&noise[..300]);
+    expected.extend(std::iter::repeat_n(noise[299], 50000));
+    expected.extend_from_slice(b"end");
+
+    (compressed, expected)
+}
+
+fn bufread_decompress(input: &InputStream) -> std::io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    block_on(Deflate64Decoder::new(input.reader()).read_to_end(&mut output))?;
+    Ok(output)
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_bufread_decompress() {
+    let (compressed, expected) = sample();
+
+    let output = bufread_decompress(&InputStream::from(vec![compressed])).unwrap();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_bufread_decompress_byte_at_a_time() {
+    let (compressed, expected) = sample();
+    // Only the contents of the stored block is passed in one go, so decoding stops and resumes at
+    // every point of the headers and Huffman codes
+    let (stored, fixed) = compressed.split_at(compressed.len() - 14);
+    let (header, contents) = stored.split_at(5);
+    let input: Vec<Vec<u8>> = header
+        .chunks(1)
+        .chain(Some(contents))
+        .chain(fixed.chunks(1))
+        .map(Vec::from)
+        .collect();
+
+    let output = bufread_decompress(&InputStream::from(input)).unwrap();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_stream_decompress() {
+    let (compressed, expected) = sample();
+    let input = InputStream::from(compressed.chunks(1000).map(Vec::from).collect::<Vec<_>>());
+
+    let decoder = stream::Deflate64Decoder::new(input.stream());
+    let output: Vec<Bytes> = block_on_stream(decoder).map(Result::unwrap).collect();
+
+    assert_eq!(output.concat(), expected);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_decompress_deflate() {
+    // A deflate stream that never uses the length code deflate64 redefines is also a deflate64
+    // stream, random letters have no repeats of 258 bytes or more and are compressed into dynamic
+    // Huffman blocks
+    let text: Vec<u8> = noise(20000)
+        .into_iter()
+        .map(|x| b"abcdefghijklmnopqrstuvwxyz "[usize::from(x) % 27])
+        .collect();
+    let compressed = utils::deflate::sync::compress(&text);
+
+    let output = bufread_decompress(&InputStream::from(vec![compressed])).unwrap();
+
+    assert_eq!(output, text);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_leaves_trailing_data() {
+    let (mut compressed, expected) = sample();
+    let len = compressed.len();
+    compressed.extend_from_slice(b"trailing");
+
+    let mut reader = &compressed[..];
+    let mut decoder = Deflate64Decoder::new(&mut reader);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, expected);
+    assert_eq!(reader, &compressed[len..]);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_truncated() {
+    let (compressed, _) = sample();
+
+    let err = bufread_decompress(&InputStream::from(vec![
+        compressed[..compressed.len() - 1].to_vec()
+    ]))
+    .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_distance_too_far_back() {
+    // A fixed Huffman block whose first symbol is a match, with nothing before it to copy
+    let compressed = vec![0x03, 0x02, 0x00];
+
+    let err = bufread_decompress(&InputStream::from(vec![compressed])).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn deflate64_stream_error() {
+    let input = InputStream::from(vec![vec![0x07]]);
+
+    let decoder = stream::Deflate64Decoder::new(input.stream());
+    let err = block_on(decoder.try_concat()).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,160 @@
+mod utils;
+
+use async_compression::{bufread, CompressionError, Format};
+use futures::{executor::block_on, io::AsyncReadExt};
+use std::{
+    convert::TryInto,
+    io::{self, ErrorKind},
+};
+
+fn compression_error(err: &io::Error) -> &CompressionError {
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<CompressionError>())
+        .unwrap()
+}
+
+fn decompress(decoder: impl futures::io::AsyncRead) -> io::Error {
+    let mut output = Vec::new();
+    block_on(Box::pin(decoder).read_to_end(&mut output)).unwrap_err()
+}
+
+fn assert_unexpected_eof(err: io::Error, format: Format) {
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(
+        compression_error(&err),
+        &CompressionError::UnexpectedEof { format }
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn unexpected_eof() {
+    let text = b"the quick brown fox jumps over the lazy dog".repeat(100);
+
+    macro_rules! truncated {
+        ($($name:ident: $decoder:ident, $format:expr;)*) => {
+            $(
+                let compressed = utils::$name::sync::compress(&text);
+                let truncated = &compressed[..compressed.len() / 2];
+                let err = decompress(bufread::$decoder::new(truncated));
+                assert_unexpected_eof(err, $format);
+            )*
+        };
+    }
+
+    truncated! {
+        brotli: BrotliDecoder, Format::Brotli;
+        deflate: DeflateDecoder, Format::Deflate;
+        gzip: GzipDecoder, Format::Gzip;
+        lz4: Lz4Decoder, Format::Lz4;
+        lzma: LzmaDecoder, Format::Lzma;
+        snappy: SnappyDecoder, Format::Snappy;
+        xz: XzDecoder, Format::Xz;
+        zlib: ZlibDecoder, Format::Zlib;
+    }
+
+    // Ending in the gzip header or footer rather than the deflate stream
+    let compressed = utils::gzip::sync::compress(&text);
+    for len in [4, compressed.len() - 4] {
+        let err = decompress(bufread::GzipDecoder::new(&compressed[..len]));
+        assert_unexpected_eof(err, Format::Gzip);
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn gzip_checksum_mismatch() {
+    let mut compressed = utils::gzip::sync::compress(b"hello world");
+    let crc = compressed.len() - 8;
+    let found = u32::from_le_bytes(compressed[crc..crc + 4].try_into().unwrap());
+    compressed[crc] ^= 0xff;
+    let expected = u32::from_le_bytes(compressed[crc..crc + 4].try_into().unwrap());
+
+    let err = decompress(bufread::GzipDecoder::new(&compressed[..]));
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        compression_error(&err),
+        &CompressionError::ChecksumMismatch {
+            format: Format::Gzip,
+            expected,
+            found,
+        }
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn zlib_checksum_mismatch() {
+    let mut compressed = utils::zlib::sync::compress(b"hello world");
+    let adler = compressed.len() - 4;
+    let found = u32::from_be_bytes(compressed[adler..].try_into().unwrap());
+    compressed[adler + 3] ^= 0xff;
+    let expected = u32::from_be_bytes(compressed[adler..].try_into().unwrap());
+
+    let err = decompress(bufread::ZlibDecoder::new(&compressed[..]));
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        compression_error(&err),
+        &CompressionError::ChecksumMismatch {
+            format: Format::Zlib,
+            expected,
+            found,
+        }
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn snappy_checksum_mismatch() {
+    let mut compressed = utils::snappy::sync::compress(b"hello world");
+    // The masked CRC32C of the first chunk follows the stream identifier and the chunk header
+    let crc = 10 + 4;
+    let expected = u32::from_le_bytes(compressed[crc..crc + 4].try_into().unwrap()) ^ 0xff;
+    let found = expected ^ 0xff;
+    compressed[crc] ^= 0xff;
+
+    let err = decompress(bufread::SnappyDecoder::new(&compressed[..]));
+
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        compression_error(&err),
+        &CompressionError::ChecksumMismatch {
+            format: Format::Snappy,
+            expected,
+            found,
+        }
+    );
+}
+
+fn assert_invalid_header(err: &io::Error, format: Format) {
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    match compression_error(err) {
+        CompressionError::InvalidHeader { format: actual, .. } => assert_eq!(*actual, format),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn invalid_header() {
+    let mut compressed = utils::gzip::sync::compress(b"hello world");
+    compressed[0] = 0;
+    let err = decompress(bufread::GzipDecoder::new(&compressed[..]));
+    assert_invalid_header(&err, Format::Gzip);
+
+    let compressed = utils::snappy::sync::compress(b"hello world");
+    let err = decompress(bufread::SnappyDecoder::new(&compressed[10..]));
+    assert_invalid_header(&err, Format::Snappy);
+
+    // A stream using the default 32 KiB window given to a decoder limited to 512 bytes
+    let compressed = utils::zlib::sync::compress(b"hello world");
+    let decoder = bufread::ZlibDecoder::with_window_bits(&compressed[..], 9).unwrap();
+    let err = decompress(decoder);
+    assert_invalid_header(&err, Format::Zlib);
+    assert_eq!(
+        err.to_string(),
+        "invalid zlib header: the stream uses a window of 15 bits, more than the decoder's 9"
+    );
+}
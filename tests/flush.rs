@@ -0,0 +1,503 @@
+mod utils;
+
+macro_rules! flush_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*) $decoder:ident;)*) => {
+        $(
+            #[test]
+            #[ntest::timeout(5000)]
+            fn $name() {
+                use async_compression::{bufread::$decoder, write::$encoder};
+                use futures::{executor::block_on, io::{AsyncReadExt, AsyncWriteExt}};
+                use futures_test::io::AsyncWriteTestExt;
+
+                // Decodes just the first `len` bytes of output, the stream hasn't ended yet so
+                // reading any further would fail
+                let decode_prefix = |compressed: &[u8], len: usize| {
+                    let mut output = vec![0; len];
+                    block_on($decoder::new(compressed).read_exact(&mut output)).unwrap();
+                    output
+                };
+
+                let mut encoder = $encoder::new(Vec::new().track_closed(), $($arg),*);
+
+                block_on(encoder.write_all(b"first message\n")).unwrap();
+                block_on(encoder.flush()).unwrap();
+                assert!(!encoder.get_ref().is_closed());
+                assert_eq!(
+                    decode_prefix(encoder.get_ref().get_ref(), 14),
+                    b"first message\n"
+                );
+
+                // Flushing doesn't end the stream, so it can be continued and flushed again
+                block_on(encoder.write_all(b"second message\n")).unwrap();
+                block_on(encoder.flush()).unwrap();
+                assert!(!encoder.get_ref().is_closed());
+                assert_eq!(
+                    decode_prefix(encoder.get_ref().get_ref(), 29),
+                    b"first message\nsecond message\n"
+                );
+
+                block_on(encoder.close()).unwrap();
+                assert!(encoder.get_ref().is_closed());
+                let output = encoder.into_inner().into_inner();
+                assert_eq!(
+                    utils::$name::sync::decompress(&output),
+                    b"first message\nsecond message\n"
+                );
+            }
+        )*
+    };
+}
+
+// bzip2 keeps the last bits of a flushed block until more output follows, and the legacy `.lzma`
+// format has no way to flush part way through a stream, so neither is included
+flush_tests! {
+    brotli: BrotliEncoder(1) BrotliDecoder;
+    deflate: DeflateEncoder(async_compression::flate2::Compression::fast()) DeflateDecoder;
+    gzip: GzipEncoder(async_compression::flate2::Compression::fast()) GzipDecoder;
+    identity: IdentityEncoder() IdentityDecoder;
+    lz4: Lz4Encoder(0) Lz4Decoder;
+    snappy: SnappyEncoder() SnappyDecoder;
+    xz: XzEncoder(1) XzDecoder;
+    zlib: ZlibEncoder(async_compression::flate2::Compression::fast()) ZlibDecoder;
+    zstd: ZstdEncoder(1) ZstdDecoder;
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn flush_mode_none() {
+    use async_compression::write::{FlushMode, GzipEncoder};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let mut encoder = GzipEncoder::new(Vec::new(), async_compression::flate2::Compression::fast());
+    encoder.set_flush_mode(FlushMode::None);
+    block_on(encoder.write_all(b"first message\n")).unwrap();
+    block_on(encoder.flush()).unwrap();
+
+    // The encoder is still holding on to the input, so only the gzip header has been written
+    assert_eq!(encoder.get_ref().len(), 10);
+
+    block_on(encoder.close()).unwrap();
+    assert_eq!(
+        utils::gzip::sync::decompress(encoder.get_ref()),
+        b"first message\n"
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn flush_mode_full() {
+    use async_compression::{
+        bufread::DeflateDecoder,
+        write::{DeflateEncoder, FlushMode},
+    };
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let message = b"the same message repeated\n".repeat(10);
+
+    let mut encoder =
+        DeflateEncoder::new(Vec::new(), async_compression::flate2::Compression::fast());
+    encoder.set_flush_mode(FlushMode::Full);
+    block_on(encoder.write_all(&message)).unwrap();
+    block_on(encoder.flush()).unwrap();
+    let offset = encoder.get_ref().len();
+    block_on(encoder.write_all(&message)).unwrap();
+    block_on(encoder.close()).unwrap();
+
+    let compressed = encoder.into_inner();
+    assert_eq!(
+        utils::deflate::sync::decompress(&compressed),
+        message.repeat(2)
+    );
+
+    // Nothing after a full flush refers back before it, so decoding can start from there
+    let mut output = Vec::new();
+    block_on(DeflateDecoder::new(&compressed[offset..]).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, message);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn flush_mode_partial() {
+    use async_compression::{
+        bufread::DeflateDecoder,
+        flate2::Compression,
+        write::{DeflateEncoder, FlushMode, ZlibEncoder},
+    };
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let message = b"first message\n";
+
+    let flushed = |mode| {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.set_flush_mode(mode);
+        block_on(encoder.write_all(message)).unwrap();
+        block_on(encoder.flush()).unwrap();
+        encoder.into_inner()
+    };
+    let sync = flushed(FlushMode::Sync);
+    let partial = flushed(FlushMode::Partial);
+
+    // A sync flush ends with an empty stored block, its length and complement on a byte boundary,
+    // a partial flush with just the 10 bits of an empty fixed Huffman block
+    assert!(sync.ends_with(&[0x00, 0x00, 0xff, 0xff]));
+    assert!(!partial.ends_with(&[0x00, 0x00, 0xff, 0xff]));
+    assert!(partial.len() < sync.len());
+
+    // Everything written before the flush can still be decoded
+    let mut output = vec![0; message.len()];
+    block_on(DeflateDecoder::new(&partial[..]).read_exact(&mut output)).unwrap();
+    assert_eq!(output, message);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.set_flush_mode(FlushMode::Partial);
+    block_on(encoder.write_all(message)).unwrap();
+    block_on(encoder.flush()).unwrap();
+    assert!(!encoder.get_ref().ends_with(&[0x00, 0x00, 0xff, 0xff]));
+    block_on(encoder.write_all(b"second message\n")).unwrap();
+    block_on(encoder.close()).unwrap();
+    assert_eq!(
+        utils::zlib::sync::decompress(encoder.get_ref()),
+        b"first message\nsecond message\n"
+    );
+}
+
+macro_rules! flush_mode_finish_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*) $decoder:ident $decompress:expr;)*) => {
+        $(
+            #[test]
+            #[ntest::timeout(5000)]
+            fn $name() {
+                use async_compression::{bufread::$decoder, write::{$encoder, FlushMode}};
+                use futures::{executor::block_on, io::{AsyncReadExt, AsyncWriteExt}};
+
+                let mut encoder = $encoder::new(Vec::new(), $($arg),*);
+                encoder.set_flush_mode(FlushMode::Finish);
+                block_on(encoder.write_all(b"first message\n")).unwrap();
+                block_on(encoder.flush()).unwrap();
+
+                // The first stream is complete, so decoding it checks its trailer
+                let mut output = Vec::new();
+                block_on($decoder::new(&encoder.get_ref()[..]).read_to_end(&mut output)).unwrap();
+                assert_eq!(output, b"first message\n");
+
+                block_on(encoder.write_all(b"second message\n")).unwrap();
+                block_on(encoder.flush()).unwrap();
+                let len = encoder.get_ref().len();
+
+                // Flushing or closing again without any new input doesn't add empty streams
+                block_on(encoder.flush()).unwrap();
+                block_on(encoder.close()).unwrap();
+                assert_eq!(encoder.get_ref().len(), len);
+
+                assert_eq!(
+                    $decompress(encoder.get_ref()),
+                    b"first message\nsecond message\n"
+                );
+            }
+        )*
+    };
+}
+
+mod flush_mode_finish {
+    use super::utils;
+
+    fn decompress_members(input: &[u8]) -> Vec<u8> {
+        use std::io::Read;
+
+        let mut output = Vec::new();
+        flate2::read::MultiGzDecoder::new(input)
+            .read_to_end(&mut output)
+            .unwrap();
+        output
+    }
+
+    flush_mode_finish_tests! {
+        gzip: GzipEncoder(async_compression::flate2::Compression::fast()) GzipDecoder decompress_members;
+        zstd: ZstdEncoder(1) ZstdDecoder utils::zstd::sync::decompress;
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn seek_inner() {
+    use async_compression::write::{FlushMode, ZstdEncoder};
+    use futures::{
+        executor::block_on,
+        future::poll_fn,
+        io::{AsyncWriteExt, Cursor},
+    };
+    use std::{io::SeekFrom, pin::Pin};
+
+    fn seek(encoder: &mut ZstdEncoder<Cursor<Vec<u8>>>, pos: SeekFrom) -> std::io::Result<u64> {
+        block_on(poll_fn(|cx| {
+            Pin::new(&mut *encoder).poll_seek_inner(cx, pos)
+        }))
+    }
+
+    // A container of a length prefix followed by a zstd frame, the length is only known once
+    // the frame has been written
+    let mut file = Cursor::new(Vec::new());
+    block_on(file.write_all(&[0; 4])).unwrap();
+
+    let mut encoder = ZstdEncoder::new(file, 1);
+    block_on(encoder.write_all(b"the first half, ")).unwrap();
+
+    // The encoder holds on to the input until it is flushed, so seeking now is refused
+    let err = seek(&mut encoder, SeekFrom::Start(0)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert_eq!(encoder.get_ref().position(), 4);
+
+    // As it does after a flush that leaves the input in the encoder
+    encoder.set_flush_mode(FlushMode::None);
+    block_on(encoder.flush()).unwrap();
+    assert!(seek(&mut encoder, SeekFrom::Start(0)).is_err());
+
+    // Once it is flushed the writer can be seeked and put back before writing more
+    encoder.set_flush_mode(FlushMode::Sync);
+    block_on(encoder.flush()).unwrap();
+    let end = seek(&mut encoder, SeekFrom::Current(0)).unwrap();
+    assert_eq!(end, 4 + encoder.total_out());
+    seek(&mut encoder, SeekFrom::Start(0)).unwrap();
+    seek(&mut encoder, SeekFrom::Start(end)).unwrap();
+    block_on(encoder.write_all(b"the second half")).unwrap();
+
+    // After closing the whole frame has been written, and the prefix can be patched
+    block_on(encoder.close()).unwrap();
+    let len = encoder.total_out() as u32;
+    seek(&mut encoder, SeekFrom::Start(0)).unwrap();
+    block_on(encoder.get_mut().write_all(&len.to_be_bytes())).unwrap();
+
+    let file = encoder.get_ref().get_ref();
+    assert_eq!(file[..4], len.to_be_bytes());
+    assert_eq!(file.len(), 4 + len as usize);
+    assert_eq!(
+        utils::zstd::sync::decompress(&file[4..]),
+        b"the first half, the second half"
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn flush_on_newline() {
+    use async_compression::{bufread::DeflateDecoder, write::DeflateEncoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    // Decodes just the first `len` bytes of output, without the encoder having been flushed or
+    // closed
+    let decode_prefix = |compressed: &[u8], len: usize| {
+        let mut output = vec![0; len];
+        block_on(DeflateDecoder::new(compressed).read_exact(&mut output)).unwrap();
+        output
+    };
+    let sync_flushes = |compressed: &[u8]| {
+        compressed
+            .windows(4)
+            .filter(|window| window == &[0x00, 0x00, 0xff, 0xff])
+            .count()
+    };
+
+    let mut encoder =
+        DeflateEncoder::new(Vec::new(), async_compression::flate2::Compression::fast())
+            .with_flush_on_newline(true);
+
+    block_on(encoder.write_all(b"first line\n")).unwrap();
+    assert_eq!(decode_prefix(encoder.get_ref(), 11), b"first line\n");
+    assert_eq!(sync_flushes(encoder.get_ref()), 1);
+
+    // Many lines in one write are flushed once
+    block_on(encoder.write_all(b"second line\nthird line\nfourth line\n")).unwrap();
+    assert_eq!(
+        decode_prefix(encoder.get_ref(), 46),
+        b"first line\nsecond line\nthird line\nfourth line\n"
+    );
+    assert_eq!(sync_flushes(encoder.get_ref()), 2);
+
+    // A write split after its last newline flushes just the complete lines
+    let len = encoder.get_ref().len();
+    block_on(encoder.write_all(b"fifth line\nsix")).unwrap();
+    assert_eq!(sync_flushes(encoder.get_ref()), 3);
+    let len_after_fifth = encoder.get_ref().len();
+    assert!(len_after_fifth > len);
+
+    // Without a newline nothing is flushed
+    block_on(encoder.write_all(b"th line")).unwrap();
+    assert_eq!(encoder.get_ref().len(), len_after_fifth);
+
+    block_on(encoder.close()).unwrap();
+    assert_eq!(
+        utils::deflate::sync::decompress(encoder.get_ref()),
+        b"first line\nsecond line\nthird line\nfourth line\nfifth line\nsixth line"
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn flush_on_newline_ignores_flush_mode() {
+    use async_compression::{
+        bufread::DeflateDecoder,
+        write::{DeflateEncoder, FlushMode},
+    };
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let decode_prefix = |compressed: &[u8], len: usize| {
+        let mut output = vec![0; len];
+        block_on(DeflateDecoder::new(compressed).read_exact(&mut output)).unwrap();
+        output
+    };
+
+    // Lines are still sync flushed when explicit flushes are turned off
+    let mut encoder =
+        DeflateEncoder::new(Vec::new(), async_compression::flate2::Compression::fast())
+            .with_flush_on_newline(true);
+    encoder.set_flush_mode(FlushMode::None);
+
+    block_on(encoder.write_all(b"first line\n")).unwrap();
+    assert_eq!(decode_prefix(encoder.get_ref(), 11), b"first line\n");
+
+    block_on(encoder.write_all(b"second line\nthi")).unwrap();
+    assert_eq!(
+        decode_prefix(encoder.get_ref(), 23),
+        b"first line\nsecond line\n"
+    );
+
+    // An explicit flush still follows the mode, and leaves the partial line buffered
+    let len = encoder.get_ref().len();
+    block_on(encoder.flush()).unwrap();
+    assert_eq!(encoder.get_ref().len(), len);
+
+    block_on(encoder.close()).unwrap();
+    assert_eq!(
+        utils::deflate::sync::decompress(encoder.get_ref()),
+        b"first line\nsecond line\nthi"
+    );
+}
+
+macro_rules! flush_at_input_offset_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*) $decoder:ident;)*) => {
+        $(
+            #[test]
+            #[ntest::timeout(5000)]
+            fn $name() {
+                use async_compression::{bufread::$decoder, write::{$encoder, FlushMode}};
+                use futures::{
+                    executor::block_on,
+                    future::poll_fn,
+                    io::{AsyncReadExt, AsyncWriteExt},
+                };
+                use std::pin::Pin;
+
+                let mut encoder = $encoder::new(Vec::new(), $($arg),*);
+                encoder.set_flush_mode(FlushMode::None);
+                block_on(encoder.write_all(b"first message\n")).unwrap();
+                let offset = block_on(poll_fn(|cx| {
+                    Pin::new(&mut encoder).poll_flush_at_input_offset(cx)
+                }))
+                .unwrap();
+                assert_eq!(offset as usize, encoder.get_ref().len());
+
+                block_on(encoder.write_all(b"second message\n")).unwrap();
+                block_on(encoder.close()).unwrap();
+                let compressed = encoder.into_inner();
+
+                // Just the bytes before the offset are enough to decode the input written before it
+                let mut output = vec![0; 14];
+                block_on($decoder::new(&compressed[..offset as usize]).read_exact(&mut output))
+                    .unwrap();
+                assert_eq!(output, b"first message\n");
+
+                assert_eq!(
+                    utils::$name::sync::decompress(&compressed),
+                    b"first message\nsecond message\n"
+                );
+            }
+        )*
+    };
+}
+
+mod flush_at_input_offset {
+    use crate::utils;
+
+    flush_at_input_offset_tests! {
+        brotli: BrotliEncoder(1) BrotliDecoder;
+        deflate: DeflateEncoder(async_compression::flate2::Compression::fast()) DeflateDecoder;
+        gzip: GzipEncoder(async_compression::flate2::Compression::fast()) GzipDecoder;
+        identity: IdentityEncoder() IdentityDecoder;
+        lz4: Lz4Encoder(0) Lz4Decoder;
+        snappy: SnappyEncoder() SnappyDecoder;
+        xz: XzEncoder(1) XzDecoder;
+        zlib: ZlibEncoder(async_compression::flate2::Compression::fast()) ZlibDecoder;
+        zstd: ZstdEncoder(1) ZstdDecoder;
+    }
+}
+
+macro_rules! flush_at_input_offset_unsupported_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*);)*) => {
+        $(
+            #[test]
+            #[ntest::timeout(5000)]
+            fn $name() {
+                use async_compression::write::$encoder;
+                use futures::{executor::block_on, future::poll_fn, io::AsyncWriteExt};
+                use std::{io::ErrorKind, pin::Pin};
+
+                let mut encoder = $encoder::new(Vec::new(), $($arg),*);
+                block_on(encoder.write_all(b"first message\n")).unwrap();
+                let err = block_on(poll_fn(|cx| {
+                    Pin::new(&mut encoder).poll_flush_at_input_offset(cx)
+                }))
+                .unwrap_err();
+                assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+                // The encoder is still usable afterwards
+                block_on(encoder.write_all(b"second message\n")).unwrap();
+                block_on(encoder.close()).unwrap();
+            }
+        )*
+    };
+}
+
+mod flush_at_input_offset_unsupported {
+    flush_at_input_offset_unsupported_tests! {
+        bzip: BzEncoder(async_compression::bzip2::Compression::Fastest);
+        lz4_block: Lz4BlockEncoder(0);
+        lzma: LzmaEncoder(1);
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn decoder_flush_then_write() {
+    use async_compression::write::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    let compressed = utils::gzip::sync::compress(b"first message\nsecond message\n");
+    let (first, second) = compressed.split_at(compressed.len() / 2);
+
+    let mut decoder = GzipDecoder::new(Vec::new().track_closed());
+    block_on(decoder.write_all(first)).unwrap();
+    block_on(decoder.flush()).unwrap();
+
+    // Flushing leaves the underlying writer open, so decoding can carry on after it
+    assert!(!decoder.get_ref().is_closed());
+    block_on(decoder.write_all(second)).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert!(decoder.get_ref().is_closed());
+    assert_eq!(
+        decoder.into_inner().into_inner(),
+        b"first message\nsecond message\n"
+    );
+}
@@ -0,0 +1,309 @@
+use async_compression::{bufread, detect_format, stream, write, Format};
+use bytes::Bytes;
+use futures::{
+    executor::block_on,
+    io::{AsyncReadExt, AsyncWriteExt},
+    stream::{self as futures_stream, TryStreamExt},
+};
+use std::io::ErrorKind;
+
+fn input() -> Vec<u8> {
+    (0..20_000u32)
+        .flat_map(|i| (i % 251).to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn format_availability_matches_features() {
+    for &format in Format::ALL {
+        let enabled = match format {
+            Format::Brotli => cfg!(feature = "brotli"),
+            Format::Bzip => cfg!(feature = "bzip"),
+            Format::Deflate => cfg!(feature = "deflate"),
+            Format::Gzip => cfg!(feature = "gzip"),
+            Format::Identity => cfg!(feature = "identity"),
+            Format::Lz4 => cfg!(feature = "lz4"),
+            Format::Lzma => cfg!(feature = "lzma"),
+            Format::Snappy => cfg!(feature = "snappy"),
+            Format::Xz => cfg!(feature = "xz"),
+            Format::Zlib => cfg!(feature = "zlib"),
+            Format::Zstd => cfg!(feature = "zstd"),
+            _ => unreachable!(),
+        };
+        assert_eq!(format.is_available(), enabled, "{}", format);
+        assert_eq!(format.check_available().is_ok(), enabled, "{}", format);
+    }
+}
+
+#[test]
+fn unavailable_formats_error() {
+    for &format in Format::ALL.iter().filter(|f| !f.is_available()) {
+        let err = format.check_available().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains(format.feature()), "{}", err);
+
+        let err = bufread::decoder(format, &[][..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = bufread::encoder(format, &[][..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = write::decoder(format, Vec::new()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = stream::encoder(format, futures_stream::empty())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
+
+#[cfg(not(feature = "lz4"))]
+#[test]
+fn lz4_unavailable() {
+    let err = bufread::decoder(Format::Lz4, &[][..]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("`lz4` feature"), "{}", err);
+}
+
+#[test]
+fn available_formats_roundtrip() {
+    let input = input();
+
+    for &format in Format::ALL.iter().filter(|f| f.is_available()) {
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::encoder(format, &input[..])
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        let mut output = Vec::new();
+        let mut writer = write::decoder(format, &mut output).unwrap();
+        block_on(writer.write_all(&compressed)).unwrap();
+        block_on(writer.close()).unwrap();
+        drop(writer);
+        assert_eq!(output, input, "{}", format);
+
+        let chunks: Vec<Bytes> = block_on(
+            stream::decoder(
+                format,
+                futures_stream::iter(vec![Ok(Bytes::from(compressed))]),
+            )
+            .unwrap()
+            .try_collect(),
+        )
+        .unwrap();
+
+        assert_eq!(chunks.concat(), input, "{}", format);
+    }
+}
+
+/// Decodes `input` with the auto-detecting decoder of each interface, checking they agree and
+/// returning the output and detected format.
+fn auto_decode(input: &[u8]) -> std::io::Result<(Vec<u8>, Option<Format>)> {
+    let mut decoder = bufread::AutoDecoder::new(input);
+    assert_eq!(decoder.detected_format(), None);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output))?;
+    let format = decoder.detected_format();
+
+    // Feed the stream a byte at a time so the magic straddles chunks
+    let source = futures_stream::iter(input.iter().map(|&b| Ok(Bytes::from(vec![b]))));
+    let mut decoder = stream::AutoDecoder::new(source);
+    let chunks: Vec<Bytes> = block_on((&mut decoder).try_collect())?;
+    assert_eq!(chunks.concat(), output);
+    assert_eq!(decoder.detected_format(), format);
+
+    let mut decoder = write::AutoDecoder::new(Vec::new());
+    block_on(decoder.write_all(input))?;
+    block_on(decoder.close())?;
+    assert_eq!(decoder.detected_format(), format);
+    assert_eq!(decoder.into_inner(), output);
+
+    Ok((output, format))
+}
+
+#[test]
+fn auto_decoder_detects_formats() {
+    let input = input();
+
+    for &format in Format::ALL.iter().filter(|f| f.is_available()) {
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::encoder(format, &input[..])
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        let (output, detected) =
+            auto_decode(&compressed).unwrap_or_else(|err| panic!("{}: {}", format, err));
+        match format {
+            // Without a magic number these can't be told apart from uncompressed input
+            Format::Brotli | Format::Deflate | Format::Lzma => {
+                assert_eq!(output, compressed, "{}", format);
+                assert_eq!(detected, Some(Format::Identity), "{}", format);
+            }
+            _ => {
+                assert_eq!(output, input, "{}", format);
+                assert_eq!(detected, Some(format), "{}", format);
+            }
+        }
+    }
+}
+
+#[test]
+fn auto_decoder_passes_through_unknown_input() {
+    for input in &[&b""[..], b"x", b"BZ", b"plain text that is not compressed"] {
+        let (output, detected) = auto_decode(input).unwrap();
+        assert_eq!(output, *input);
+        assert_eq!(detected, Some(Format::Identity));
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn auto_decoder_detects_unavailable_format() {
+    let mut output = Vec::new();
+    let err = block_on(
+        bufread::AutoDecoder::new(&[0x28, 0xb5, 0x2f, 0xfd, 0, 0][..]).read_to_end(&mut output),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("`zstd` feature"), "{}", err);
+}
+
+#[test]
+fn auto_decoder_accepts_expected_format() {
+    let input = input();
+
+    for &format in Format::ALL.iter().filter(|f| f.is_available()) {
+        if let Format::Brotli | Format::Deflate | Format::Lzma = format {
+            continue;
+        }
+
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::encoder(format, &input[..])
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        let mut decoder = bufread::AutoDecoder::new(&compressed[..])
+            .with_expected_format(format)
+            .unwrap();
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output))
+            .unwrap_or_else(|err| panic!("{}: {}", format, err));
+        assert_eq!(output, input, "{}", format);
+        assert_eq!(decoder.detected_format(), Some(format), "{}", format);
+    }
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+#[test]
+fn auto_decoder_rejects_unexpected_format() {
+    let input = input();
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::encoder(Format::Zstd, &input[..])
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    let mut output = Vec::new();
+    let err = block_on(
+        bufread::AutoDecoder::new(&compressed[..])
+            .with_expected_format(Format::Gzip)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(output.is_empty());
+
+    // Uncompressed input is rejected too, unless that is what's expected
+    let mut decoder = write::AutoDecoder::new(Vec::new())
+        .with_expected_format(Format::Gzip)
+        .unwrap();
+    let err = block_on(async {
+        decoder.write_all(b"plain text").await?;
+        decoder.close().await
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut decoder = write::AutoDecoder::new(Vec::new())
+        .with_expected_format(Format::Identity)
+        .unwrap();
+    block_on(decoder.write_all(b"plain text")).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), b"plain text");
+}
+
+#[test]
+fn auto_decoder_expected_format_needs_magic() {
+    for &format in &[Format::Brotli, Format::Deflate, Format::Lzma] {
+        let err = bufread::AutoDecoder::new(&[][..])
+            .with_expected_format(format)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{}", format);
+    }
+}
+
+#[test]
+fn detect_format_magic() {
+    let cases: &[(&[u8], Format)] = &[
+        (b"BZh91AY&SY", Format::Bzip),
+        (&[0x1f, 0x8b, 0x08, 0x00], Format::Gzip),
+        (&[0x04, 0x22, 0x4d, 0x18, 0x64], Format::Lz4),
+        (b"\xff\x06\x00\x00sNaPpY\x00", Format::Snappy),
+        (b"\xfd7zXZ\x00\x00\x04", Format::Xz),
+        (&[0x78, 0x9c, 0x01], Format::Zlib),
+        (&[0x78, 0x01], Format::Zlib),
+        (&[0x28, 0xb5, 0x2f, 0xfd, 0x00], Format::Zstd),
+    ];
+
+    for &(bytes, format) in cases {
+        assert_eq!(detect_format(bytes), Some(format), "{}", format);
+        // Too short to see the whole magic number
+        assert_eq!(detect_format(&bytes[..1]), None, "{}", format);
+    }
+}
+
+#[test]
+fn detect_format_unknown() {
+    for bytes in &[
+        &b""[..],
+        b"B",
+        b"BZ",
+        b"\xfd7zX",
+        // A zlib header failing its check
+        &[0x78, 0x9d],
+        b"plain text that is not compressed",
+    ] {
+        assert_eq!(detect_format(bytes), None, "{:?}", bytes);
+    }
+}
+
+#[test]
+fn detect_format_compressed() {
+    let input = input();
+
+    for &format in Format::ALL.iter().filter(|f| f.is_available()) {
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::encoder(format, &input[..])
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        let expected = match format {
+            Format::Brotli | Format::Deflate | Format::Identity | Format::Lzma => None,
+            _ => Some(format),
+        };
+        assert_eq!(detect_format(&compressed), expected, "{}", format);
+    }
+}
@@ -149,3 +149,1418 @@ fn gzip_bufread_chunks_decompress_with_extra_header() {
 
     assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
 }
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_compress_coalesce() {
+    use async_compression::{flate2::Compression, stream::GzipEncoder};
+    use bytes::Bytes;
+    use futures::{channel::mpsc, executor::block_on, stream::StreamExt};
+    use std::task::Poll;
+
+    let (sender, receiver) = mpsc::unbounded();
+    let mut encoder = GzipEncoder::new(receiver, Compression::fast()).with_coalesce(3);
+    let mut cx = futures_test::task::noop_context();
+
+    sender
+        .unbounded_send(Ok(Bytes::from(&[1, 2, 3][..])))
+        .unwrap();
+    sender.unbounded_send(Ok(Bytes::new())).unwrap();
+    sender
+        .unbounded_send(Ok(Bytes::from(&[4, 5, 6][..])))
+        .unwrap();
+
+    // Only two non-empty items are available, so nothing is compressed yet
+    assert!(encoder.poll_next_unpin(&mut cx).is_pending());
+
+    sender
+        .unbounded_send(Ok(Bytes::from(&[7, 8, 9][..])))
+        .unwrap();
+    let mut compressed = match encoder.poll_next_unpin(&mut cx) {
+        Poll::Ready(Some(Ok(bytes))) => bytes.to_vec(),
+        other => panic!("unexpected poll result {:?}", other),
+    };
+    assert!(!compressed.is_empty());
+
+    // Ending the stream with a partially coalesced buffer still compresses it
+    sender.unbounded_send(Ok(Bytes::from(&[10][..]))).unwrap();
+    drop(sender);
+
+    while let Some(chunk) = block_on(encoder.next()) {
+        compressed.extend_from_slice(&chunk.unwrap());
+    }
+
+    let output = utils::gzip::sync::decompress(&compressed);
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10][..]);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_compress_current_ratio() {
+    use async_compression::{flate2::Compression, write::GzipEncoder};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(2000);
+    let mut encoder = GzipEncoder::new(Vec::new(), Compression::default());
+    assert_eq!(encoder.current_ratio(), None);
+
+    // Flushing after each chunk makes the output keep up with the input, as the content repeats
+    // later chunks compress far better than the first so the ratio keeps dropping
+    let mut ratios = Vec::new();
+    for chunk in input.chunks(4400) {
+        block_on(encoder.write_all(chunk)).unwrap();
+        block_on(encoder.flush()).unwrap();
+        ratios.push(encoder.current_ratio().unwrap());
+    }
+    block_on(encoder.close()).unwrap();
+
+    assert!(ratios.windows(2).all(|pair| pair[1] <= pair[0]));
+    assert!(ratios[ratios.len() - 1] < 1.0);
+
+    let ratio = encoder.current_ratio().unwrap();
+    let compressed = encoder.into_inner();
+    assert_eq!(ratio, compressed.len() as f32 / input.len() as f32);
+    assert_eq!(utils::gzip::sync::decompress(&compressed), input);
+}
+
+/// Accepts at most `limit` bytes per write and fails every `fail_every`th call, as a flaky
+/// connection might.
+struct FlakyWriter {
+    output: Vec<u8>,
+    limit: usize,
+    fail_every: usize,
+    calls: usize,
+}
+
+impl futures::io::AsyncWrite for FlakyWriter {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.calls += 1;
+        if self.calls == self.fail_every {
+            self.calls = 0;
+            return std::task::Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "flaky",
+            )));
+        }
+        let len = std::cmp::min(self.limit, buf.len());
+        self.output.extend_from_slice(&buf[..len]);
+        std::task::Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn gzip_write_compress_consumes_input_once() {
+    use async_compression::{flate2::Compression, write::GzipEncoder};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let input = Vec::from_iter((0..65_536).map(|_| rand::random::<u8>()));
+
+    let writer = FlakyWriter {
+        output: Vec::new(),
+        limit: 100,
+        fail_every: 7,
+        calls: 0,
+    };
+    let mut encoder = GzipEncoder::new(writer, Compression::fast());
+
+    let mut remaining = &input[..];
+    let mut consumed = 0;
+    let mut errors = 0;
+    while !remaining.is_empty() {
+        match block_on(encoder.write(remaining)) {
+            Ok(len) => {
+                consumed += len;
+                remaining = &remaining[len..];
+            }
+            Err(_) => errors += 1,
+        }
+    }
+    while block_on(encoder.close()).is_err() {
+        errors += 1;
+    }
+
+    assert!(errors > 0);
+    assert_eq!(consumed, input.len());
+
+    let output = utils::gzip::sync::decompress(&encoder.into_inner().output);
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_passthrough() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::gzip::sync::compress(b"hello compressed world");
+    let plain = b"hello plain world";
+    // Starts like the magic but diverges, the matched byte must still be output
+    let almost = [0x1f, 0x00, 0x01];
+
+    for (input, expected) in &[
+        (&compressed[..], &b"hello compressed world"[..]),
+        (&plain[..], &plain[..]),
+        (&almost[..], &almost[..]),
+        (&[0x1f][..], &[0x1f][..]),
+        (&[][..], &[][..]),
+    ] {
+        let stream = utils::InputStream::from(input.chunks(1).map(Vec::from).collect::<Vec<_>>());
+        let mut decoder = GzipDecoder::new(stream.reader()).with_passthrough(true);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(&output[..], *expected);
+    }
+}
+
+/// Runs `gzip -t` on the data, then decompresses it with `gzip -dc`, both must succeed without any
+/// warnings (which `gzip` reports via a non-zero exit status).
+#[cfg(feature = "test-gzip-cli")]
+fn gzip_cli_decompress(data: &[u8]) -> Vec<u8> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let run = |arg| {
+        let mut child = Command::new("gzip")
+            .arg(arg)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("the gzip command line tool must be installed for this test");
+        child.stdin.take().unwrap().write_all(data).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(
+            output.status.success(),
+            "gzip {} failed: {}",
+            arg,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    };
+
+    run("-t");
+    run("-dc")
+}
+
+#[test]
+#[cfg(feature = "test-gzip-cli")]
+fn gzip_cli_accepts_output() {
+    use async_compression::{bufread, flate2::Compression, stream};
+    use futures::{
+        executor::{block_on, block_on_stream},
+        io::AsyncReadExt,
+    };
+    use std::iter::FromIterator;
+
+    let random = Vec::from_iter((0..100_000).map(|_| rand::random::<u8>()));
+    let inputs = vec![vec![], vec![1, 2, 3, 4, 5, 6], vec![0; 100_000], random];
+
+    for input in &inputs {
+        let input_stream =
+            utils::InputStream::from(input.chunks(1024).map(Vec::from).collect::<Vec<_>>());
+
+        for &level in &[
+            Compression::none(),
+            Compression::fast(),
+            Compression::best(),
+        ] {
+            let mut compressed = Vec::new();
+            let mut encoder = bufread::GzipEncoder::new(input_stream.reader(), level);
+            block_on(encoder.read_to_end(&mut compressed)).unwrap();
+            assert_eq!(&gzip_cli_decompress(&compressed), input);
+
+            let encoder = stream::GzipEncoder::new(Box::pin(input_stream.stream()), level);
+            let compressed: Vec<u8> = block_on_stream(encoder).flat_map(Result::unwrap).collect();
+            assert_eq!(&gzip_cli_decompress(&compressed), input);
+        }
+
+        let compressed = utils::gzip::write::compress(input_stream.as_ref(), 65_536);
+        assert_eq!(&gzip_cli_decompress(&compressed), input);
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_decompress_multiple_members() {
+    let mut compressed = utils::gzip::sync::compress(b"hello ");
+    compressed.extend(utils::gzip::sync::compress(b"world"));
+    let input = utils::InputStream::from(compressed.chunks(3).map(Vec::from).collect::<Vec<_>>());
+
+    assert_eq!(
+        utils::gzip::bufread::decompress(input.reader()),
+        b"hello world"
+    );
+    assert_eq!(
+        utils::gzip::stream::decompress(input.stream()),
+        b"hello world"
+    );
+    assert_eq!(
+        utils::gzip::write::decompress(input.as_ref(), 65_536),
+        b"hello world"
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_trailing_garbage() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    for (garbage, kind) in &[
+        (&b"not a gzip member"[..], ErrorKind::InvalidData),
+        (&b"\x1f\x8b"[..], ErrorKind::UnexpectedEof),
+    ] {
+        let mut compressed = utils::gzip::sync::compress(b"hello");
+        compressed.extend_from_slice(garbage);
+
+        let mut decoder = GzipDecoder::new(&compressed[..]);
+        let mut output = Vec::new();
+        let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+        assert_eq!(err.kind(), *kind);
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_no_empty_items() {
+    use async_compression::{
+        flate2::Compression,
+        stream::{GzipDecoder, GzipEncoder},
+    };
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    // Deflate buffers small writes, so most steps produce no output
+    let input = utils::InputStream::from(vec![vec![1; 10]; 20]);
+
+    let encoded: Vec<Bytes> =
+        block_on(GzipEncoder::new(input.stream(), Compression::default()).try_collect()).unwrap();
+    assert!(encoded.iter().all(|item| !item.is_empty()));
+
+    let compressed = encoded.concat();
+    let chunks = utils::InputStream::from(compressed.chunks(2).map(Vec::from).collect::<Vec<_>>());
+
+    let decoded: Vec<Bytes> = block_on(GzipDecoder::new(chunks.stream()).try_collect()).unwrap();
+    assert!(decoded.iter().all(|item| !item.is_empty()));
+    assert_eq!(decoded.concat(), input.bytes());
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_decompress_with_prefix() {
+    use async_compression::stream::GzipDecoder;
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let compressed = utils::gzip::sync::compress(&[1, 2, 3, 4, 5, 6]);
+
+    // The magic bytes and more have already been read to sniff the format
+    let (prefix, rest) = compressed.split_at(4);
+    let rest = utils::InputStream::from(vec![rest.to_vec()]);
+
+    let decoder = GzipDecoder::with_prefix(Bytes::from(prefix), rest.stream());
+    let output: Vec<Bytes> = block_on(decoder.try_collect()).unwrap();
+
+    assert_eq!(output.concat(), &[1, 2, 3, 4, 5, 6][..]);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_read_buffer_capacity_and_into_inner() {
+    use async_compression::{
+        flate2::Compression,
+        read::{GzipDecoder, GzipEncoder},
+    };
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, Cursor},
+    };
+
+    let input = b"hello world, hello world, hello world".to_vec();
+
+    let mut encoder = GzipEncoder::new(Cursor::new(input.clone()), Compression::default())
+        .with_buffer_capacity(3);
+    let mut compressed = Vec::new();
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+
+    // The original reader is returned, not the internal buffered wrapper
+    let reader: Cursor<Vec<u8>> = encoder.into_inner();
+    assert_eq!(reader.position(), input.len() as u64);
+
+    let mut decoder = GzipDecoder::new(Cursor::new(compressed)).with_buffer_capacity(5);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, input);
+    assert_eq!(
+        decoder.get_ref().get_ref().len(),
+        decoder.get_ref().position() as usize
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_into_inner_after_close() {
+    use async_compression::{
+        flate2::Compression,
+        write::{GzipDecoder, GzipEncoder},
+    };
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let mut encoder = GzipEncoder::new(Vec::new(), Compression::default());
+    block_on(encoder.write_all(&[1, 2, 3, 4, 5, 6])).unwrap();
+    block_on(encoder.close()).unwrap();
+    assert_eq!(encoder.get_ref().len(), encoder.get_mut().len());
+
+    // The whole stream has been written out by close, so the writer can be reused for a trailer
+    let mut output = encoder.into_inner();
+    let compressed_len = output.len();
+    output.extend_from_slice(b"trailer");
+
+    assert_eq!(&output[compressed_len..], b"trailer");
+    assert_eq!(
+        utils::gzip::sync::decompress(&output[..compressed_len]),
+        &[1, 2, 3, 4, 5, 6][..]
+    );
+
+    let mut decoder = GzipDecoder::new(Vec::new());
+    block_on(decoder.write_all(&output[..compressed_len])).unwrap();
+    block_on(decoder.close()).unwrap();
+    let mut output = decoder.into_inner();
+    output.extend_from_slice(b"trailer");
+
+    assert_eq!(output, b"\x01\x02\x03\x04\x05\x06trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_total_in_out() {
+    use async_compression::{
+        bufread::{GzipDecoder, GzipEncoder},
+        flate2::Compression,
+    };
+    use futures::{io::AsyncRead, task::Poll};
+    use futures_test::{io::AsyncReadTestExt, task::noop_context};
+    use std::pin::Pin;
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(200);
+
+    // The source returns `Pending` between every chunk, the counters must only ever go up and
+    // account for exactly what has been read so far
+    let mut encoder = GzipEncoder::new(
+        input.as_slice().interleave_pending().limited(100),
+        Compression::default(),
+    );
+    let mut compressed = Vec::new();
+    let mut last_in = 0;
+    let mut buf = [0; 7];
+    loop {
+        match Pin::new(&mut encoder).poll_read(&mut noop_context(), &mut buf) {
+            Poll::Ready(Ok(0)) => break,
+            Poll::Ready(Ok(len)) => compressed.extend_from_slice(&buf[..len]),
+            Poll::Ready(Err(err)) => panic!("{}", err),
+            Poll::Pending => {}
+        }
+        assert!(encoder.total_in() >= last_in);
+        last_in = encoder.total_in();
+        assert_eq!(encoder.total_out(), compressed.len() as u64);
+    }
+    assert_eq!(encoder.total_in(), input.len() as u64);
+    assert_eq!(encoder.total_out(), compressed.len() as u64);
+
+    let mut decoder = GzipDecoder::new(compressed.as_slice().interleave_pending().limited(10));
+    let mut output = Vec::new();
+    let mut last_in = 0;
+    loop {
+        match Pin::new(&mut decoder).poll_read(&mut noop_context(), &mut buf) {
+            Poll::Ready(Ok(0)) => break,
+            Poll::Ready(Ok(len)) => output.extend_from_slice(&buf[..len]),
+            Poll::Ready(Err(err)) => panic!("{}", err),
+            Poll::Pending => {}
+        }
+        assert!(decoder.total_in() >= last_in);
+        last_in = decoder.total_in();
+        assert_eq!(decoder.total_out(), output.len() as u64);
+    }
+    assert_eq!(output, input);
+    assert_eq!(decoder.total_in(), compressed.len() as u64);
+    assert_eq!(decoder.total_out(), input.len() as u64);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_total_in_out() {
+    use async_compression::{
+        flate2::Compression,
+        write::{GzipDecoder, GzipEncoder},
+    };
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(200);
+
+    let mut encoder = GzipEncoder::new(
+        Vec::new().interleave_pending_write().limited_write(10),
+        Compression::default(),
+    );
+    for chunk in input.chunks(300) {
+        block_on(encoder.write_all(chunk)).unwrap();
+    }
+    block_on(encoder.close()).unwrap();
+    assert_eq!(encoder.total_in(), input.len() as u64);
+    let total_out = encoder.total_out();
+    let compressed = encoder.into_inner().into_inner().into_inner();
+    assert_eq!(total_out, compressed.len() as u64);
+
+    let mut decoder = GzipDecoder::new(Vec::new().interleave_pending_write().limited_write(10));
+    let mut last_in = 0;
+    for chunk in compressed.chunks(50) {
+        block_on(decoder.write_all(chunk)).unwrap();
+        assert!(decoder.total_in() > last_in);
+        last_in = decoder.total_in();
+    }
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.total_in(), compressed.len() as u64);
+    assert_eq!(decoder.total_out(), input.len() as u64);
+    assert_eq!(decoder.into_inner().into_inner().into_inner(), input);
+}
+
+/// Compresses `data` into a BGZF block, a gzip member recording its own size in the extra field.
+fn bgzf_block(data: &[u8]) -> Vec<u8> {
+    use flate2::{write::GzEncoder, Compression, GzBuilder};
+    use std::io::Write;
+
+    let mut encoder: GzEncoder<Vec<u8>> = GzBuilder::new()
+        .extra(&b"BC\x02\x00\x00\x00"[..])
+        .operating_system(255)
+        .write(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    let mut block = encoder.finish().unwrap();
+
+    // There's no header CRC, so the size can be patched in afterwards
+    let bsize = (block.len() - 1) as u16;
+    block[16..18].copy_from_slice(&bsize.to_le_bytes());
+    block
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_members_index() {
+    use async_compression::{
+        bufread::{GzipMember, GzipMembers},
+        gzip::GzipHeader,
+    };
+    use flate2::{write::GzEncoder, Compression, GzBuilder};
+    use futures::{
+        executor::block_on,
+        io::{BufReader, Cursor},
+        stream::TryStreamExt,
+    };
+    use std::io::Write;
+
+    let first = bgzf_block(&[1; 1000]);
+
+    let mut encoder: GzEncoder<Vec<u8>> = GzBuilder::new()
+        .filename(&b"second.txt"[..])
+        .comment(&b"not bgzf"[..])
+        .mtime(1_234_567)
+        .operating_system(3)
+        .write(Vec::new(), Compression::default());
+    encoder.write_all(&[2; 1000]).unwrap();
+    let second = encoder.finish().unwrap();
+
+    let third = bgzf_block(b"");
+
+    let file = [&first[..], &second[..], &third[..]].concat();
+
+    let bgzf_header = |len: usize| GzipHeader {
+        extra: Some([&b"BC\x02\x00"[..], &((len - 1) as u16).to_le_bytes()[..]].concat()),
+        operating_system: 255,
+        ..GzipHeader::default()
+    };
+    let expected = vec![
+        GzipMember {
+            offset: 0,
+            len: first.len() as u64,
+            header: bgzf_header(first.len()),
+        },
+        GzipMember {
+            offset: first.len() as u64,
+            len: second.len() as u64,
+            header: GzipHeader {
+                filename: Some(b"second.txt".to_vec()),
+                comment: Some(b"not bgzf".to_vec()),
+                mtime: 1_234_567,
+                extra: None,
+                operating_system: 3,
+            },
+        },
+        GzipMember {
+            offset: (first.len() + second.len()) as u64,
+            len: third.len() as u64,
+            header: bgzf_header(third.len()),
+        },
+    ];
+
+    let members: Vec<GzipMember> =
+        block_on(GzipMembers::new(Cursor::new(file.clone())).try_collect()).unwrap();
+    assert_eq!(members, expected);
+
+    // Seeking past a block must discard whatever the reader had buffered
+    let reader = BufReader::with_capacity(7, Cursor::new(file.clone()));
+    let members: Vec<GzipMember> = block_on(GzipMembers::new(reader).try_collect()).unwrap();
+    assert_eq!(members, expected);
+
+    // A file cut off part way through a member that has to be inflated is an error
+    let truncated = Cursor::new(file[..first.len() + 20].to_vec());
+    let err = block_on(GzipMembers::new(truncated).try_collect::<Vec<_>>()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_extra_field() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = [bgzf_block(&[1, 2, 3]), bgzf_block(&[4, 5, 6])].concat();
+
+    let mut output = Vec::new();
+    block_on(GzipDecoder::new(&compressed[..]).read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_stats() {
+    use async_compression::{
+        bufread::{GzipDecoder, GzipEncoder},
+        flate2::Compression,
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+    let mut crc = flate2::Crc::new();
+    crc.update(&input);
+
+    let mut encoder = GzipEncoder::new(&input[..], Compression::default());
+    let mut compressed = Vec::new();
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+
+    let stats = encoder.stats();
+    assert_eq!(stats.total_in, input.len() as u64);
+    assert_eq!(stats.total_out, compressed.len() as u64);
+    assert_eq!(stats.frames, None);
+    assert_eq!(stats.checksum, Some(crc.sum()));
+    assert_eq!(stats.blocks, None);
+    assert_eq!(stats.window_size, None);
+
+    let mut decoder = GzipDecoder::new(&compressed[..]);
+    assert_eq!(decoder.stats().frames, Some(0));
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    let stats = decoder.stats();
+    assert_eq!(output, input);
+    assert_eq!(stats.total_in, compressed.len() as u64);
+    assert_eq!(stats.total_out, input.len() as u64);
+    assert_eq!(stats.frames, Some(1));
+    assert_eq!(stats.checksum, Some(crc.sum()));
+    assert_eq!(stats.blocks, Some(1));
+    assert_eq!(stats.window_size, Some(32 * 1024));
+
+    // The blocks of every member are counted
+    let members = compressed.repeat(2);
+    let mut decoder = GzipDecoder::new(&members[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    assert_eq!(decoder.stats().frames, Some(2));
+    assert_eq!(decoder.stats().blocks, Some(2));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_error_ends_stream() {
+    use async_compression::{
+        flate2::Compression,
+        stream::{GzipDecoder, GzipEncoder},
+    };
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::StreamExt};
+    use std::io::{Error, ErrorKind, Result};
+
+    let compressed = utils::gzip::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    let (first, second) = compressed.split_at(compressed.len() / 2);
+
+    // The source recovers after its error, but the decoder must not carry on from it
+    let source = || {
+        futures::stream::iter(vec![
+            Ok(Bytes::from(first)),
+            Err(Error::new(ErrorKind::ConnectionReset, "source failed")),
+            Ok(Bytes::from(second)),
+        ])
+    };
+
+    let items: Vec<Result<Bytes>> = block_on(GzipDecoder::new(source()).collect());
+    let err = items.last().unwrap().as_ref().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    assert!(items[..items.len() - 1].iter().all(Result::is_ok));
+
+    let items: Vec<Result<Bytes>> =
+        block_on(GzipEncoder::new(source(), Compression::default()).collect());
+    let err = items.last().unwrap().as_ref().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+
+    // Errors from decoding end the stream the same way
+    let garbage = futures::stream::iter(vec![
+        Ok(Bytes::from(&b"not gzip data"[..])),
+        Ok(Bytes::new()),
+    ]);
+    let items: Vec<Result<Bytes>> = block_on(GzipDecoder::new(garbage).collect());
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].as_ref().unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_empty_poll_read_mid_stream() {
+    use async_compression::{bufread, read};
+    use futures::{
+        executor::block_on,
+        io::{AsyncRead, AsyncReadExt},
+        task::Poll,
+    };
+    use futures_test::task::noop_context;
+    use std::pin::Pin;
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(200);
+    let compressed = utils::gzip::sync::compress(&input);
+
+    // A zero length read returns immediately, without consuming any input or losing any output
+    let mut decoder = bufread::GzipDecoder::new(compressed.as_slice());
+    let mut output = vec![0; 100];
+    block_on(decoder.read_exact(&mut output)).unwrap();
+    let (total_in, total_out) = (decoder.total_in(), decoder.total_out());
+    assert!(matches!(
+        Pin::new(&mut decoder).poll_read(&mut noop_context(), &mut []),
+        Poll::Ready(Ok(0))
+    ));
+    assert_eq!(
+        (decoder.total_in(), decoder.total_out()),
+        (total_in, total_out)
+    );
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+
+    let mut decoder = read::GzipDecoder::new(compressed.as_slice());
+    let mut output = vec![0; 100];
+    block_on(decoder.read_exact(&mut output)).unwrap();
+    let remaining = decoder.get_ref().len();
+    assert!(matches!(
+        Pin::new(&mut decoder).poll_read(&mut noop_context(), &mut []),
+        Poll::Ready(Ok(0))
+    ));
+    assert_eq!(decoder.get_ref().len(), remaining);
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+
+    let mut encoder = bufread::GzipEncoder::new(input.as_slice(), Default::default());
+    let mut compressed = vec![0; 10];
+    block_on(encoder.read_exact(&mut compressed)).unwrap();
+    let total_in = encoder.total_in();
+    assert!(matches!(
+        Pin::new(&mut encoder).poll_read(&mut noop_context(), &mut []),
+        Poll::Ready(Ok(0))
+    ));
+    assert_eq!(encoder.total_in(), total_in);
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+    assert_eq!(utils::gzip::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_decompress_header() {
+    use async_compression::{bufread, gzip::GzipHeader, read, stream, write};
+    use bytes::Bytes;
+    use flate2::{Compression, GzBuilder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+    use std::io::Write;
+
+    let builders: Vec<(GzBuilder, GzipHeader)> = vec![
+        (
+            GzBuilder::new().filename("hello_world.txt"),
+            GzipHeader {
+                filename: Some(b"hello_world.txt".to_vec()),
+                ..GzipHeader::default()
+            },
+        ),
+        (
+            GzBuilder::new().comment("test file, please delete"),
+            GzipHeader {
+                comment: Some(b"test file, please delete".to_vec()),
+                ..GzipHeader::default()
+            },
+        ),
+        (
+            GzBuilder::new().mtime(1_234_567_890).operating_system(3),
+            GzipHeader {
+                mtime: 1_234_567_890,
+                operating_system: 3,
+                ..GzipHeader::default()
+            },
+        ),
+        (
+            GzBuilder::new().extra(&b"AB\x02\x00xy"[..]),
+            GzipHeader {
+                extra: Some(b"AB\x02\x00xy".to_vec()),
+                ..GzipHeader::default()
+            },
+        ),
+        (
+            GzBuilder::new()
+                .filename("notes.txt")
+                .comment("all of them")
+                .mtime(42)
+                .extra(&b"CD\x00\x00"[..])
+                .operating_system(11),
+            GzipHeader {
+                filename: Some(b"notes.txt".to_vec()),
+                comment: Some(b"all of them".to_vec()),
+                mtime: 42,
+                extra: Some(b"CD\x00\x00".to_vec()),
+                operating_system: 11,
+            },
+        ),
+    ];
+
+    for (builder, mut expected) in builders {
+        let mut compressed = Vec::new();
+        let mut encoder = builder.write(&mut compressed, Compression::fast());
+        encoder.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        encoder.finish().unwrap();
+        // flate2 records an unknown OS unless told otherwise
+        if expected.operating_system == 0 {
+            expected.operating_system = 255;
+        }
+
+        let mut decoder = bufread::GzipDecoder::new(&compressed[..]);
+        assert_eq!(decoder.header(), None);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+        assert_eq!(decoder.header(), Some(&expected));
+
+        let mut decoder = read::GzipDecoder::new(&compressed[..]);
+        block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+        assert_eq!(decoder.header(), Some(&expected));
+
+        let mut decoder = write::GzipDecoder::new(Vec::new());
+        block_on(decoder.write_all(&compressed)).unwrap();
+        block_on(decoder.close()).unwrap();
+        assert_eq!(decoder.header(), Some(&expected));
+
+        let input = futures::stream::iter(vec![Ok(Bytes::from(compressed.clone()))]);
+        let mut decoder = stream::GzipDecoder::new(input);
+        while block_on(decoder.try_next()).unwrap().is_some() {}
+        assert_eq!(decoder.header(), Some(&expected));
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_decompress_header_crc() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    // flate2 can't write the optional header CRC16, so add it to a header with a filename
+    let compressed = compress_with_header(&[1, 2, 3, 4, 5, 6]);
+    let header_len = 10 + b"hello_world.txt\0".len() + b"test file, please delete\0".len();
+    let mut header = compressed[..header_len].to_vec();
+    header[3] |= 0b0000_0010;
+    let mut crc = flate2::Crc::new();
+    crc.update(&header);
+    let crc16 = (crc.sum() as u16).to_le_bytes();
+
+    let with_crc = [&header[..], &crc16, &compressed[header_len..]].concat();
+    let mut decoder = GzipDecoder::new(&with_crc[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(
+        decoder.header().unwrap().filename.as_deref(),
+        Some(&b"hello_world.txt"[..])
+    );
+
+    let bad_crc = [
+        &header[..],
+        &[crc16[0] ^ 1, crc16[1]],
+        &compressed[header_len..],
+    ]
+    .concat();
+    let err = block_on(GzipDecoder::new(&bad_crc[..]).read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_trailerless_roundtrip() {
+    use async_compression::{
+        bufread::{GzipDecoder, GzipEncoder},
+        flate2::Compression,
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+
+    let mut with_trailer = Vec::new();
+    block_on(GzipEncoder::new(&input[..], Compression::fast()).read_to_end(&mut with_trailer))
+        .unwrap();
+    let mut compressed = Vec::new();
+    block_on(
+        GzipEncoder::new(&input[..], Compression::fast())
+            .with_trailer(false)
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    assert_eq!(compressed.len() + 8, with_trailer.len());
+    assert_eq!(compressed[..], with_trailer[..compressed.len()]);
+
+    // The container supplies the length, and its own framing follows the payload
+    let mut container = compressed.clone();
+    container.extend_from_slice(b"next record");
+
+    let mut decoder = GzipDecoder::new(&container[..]).without_trailer(input.len() as u64);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+    assert_eq!(decoder.into_inner(), b"next record");
+
+    let err = block_on(
+        GzipDecoder::new(&compressed[..])
+            .without_trailer(input.len() as u64 + 1)
+            .read_to_end(&mut Vec::new()),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // A normal decoder expects the trailer that isn't there
+    let err = block_on(GzipDecoder::new(&compressed[..]).read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_decompress_checkpoints() {
+    use async_compression::stream::{Checkpoint, CheckpointItem, GzipDecoder};
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let members: &[&[u8]] = &[b"hello ", b"", b"gzip ", b"world"];
+    let compressed: Vec<u8> = members
+        .iter()
+        .flat_map(|member| utils::gzip::sync::compress(member))
+        .collect();
+    let input = utils::InputStream::from(compressed.chunks(3).map(Vec::from).collect::<Vec<_>>());
+
+    let items: Vec<CheckpointItem> = block_on(
+        GzipDecoder::new(input.stream())
+            .with_checkpoints()
+            .try_collect(),
+    )
+    .unwrap();
+
+    let mut output = Vec::new();
+    let mut checkpoints = Vec::new();
+    for item in items {
+        match item {
+            CheckpointItem::Data(chunk) => output.extend_from_slice(&chunk),
+            CheckpointItem::Checkpoint(checkpoint) => {
+                // Everything before the checkpoint has already been yielded
+                assert_eq!(checkpoint.output_offset, output.len() as u64);
+                checkpoints.push(checkpoint);
+            }
+        }
+    }
+    assert_eq!(output, b"hello gzip world");
+
+    let mut expected = Vec::new();
+    let (mut input_offset, mut output_offset) = (0, 0);
+    for member in members {
+        input_offset += utils::gzip::sync::compress(member).len() as u64;
+        output_offset += member.len() as u64;
+        expected.push(Checkpoint {
+            input_offset,
+            output_offset,
+        });
+    }
+    assert_eq!(checkpoints, expected);
+
+    // A fresh decoder picks up from each of them, the last is the end of the stream
+    let (last, rest) = checkpoints.split_last().unwrap();
+    assert_eq!(last.input_offset, compressed.len() as u64);
+    for checkpoint in rest {
+        let rest =
+            utils::InputStream::from(vec![compressed[checkpoint.input_offset as usize..].to_vec()]);
+        assert_eq!(
+            utils::gzip::stream::decompress(rest.stream()),
+            &output[checkpoint.output_offset as usize..]
+        );
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_lines() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{
+        executor::block_on,
+        io::{AsyncBufReadExt, AsyncReadExt},
+        stream::TryStreamExt,
+    };
+
+    let text: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+    let compressed = utils::gzip::sync::compress(text.as_bytes());
+    let input = utils::InputStream::from(compressed.chunks(5).map(Vec::from).collect::<Vec<_>>());
+
+    let lines: Vec<String> =
+        block_on(GzipDecoder::new(input.reader()).lines().try_collect()).unwrap();
+    assert_eq!(lines, text.lines().collect::<Vec<_>>());
+
+    // Reads take what is left in the buffer before decoding any more
+    let mut decoder = GzipDecoder::new(input.reader());
+    let mut line = Vec::new();
+    block_on(decoder.read_until(b'\n', &mut line)).unwrap();
+    assert_eq!(line, b"line 0\n");
+    let mut rest = Vec::new();
+    block_on(decoder.read_to_end(&mut rest)).unwrap();
+    assert_eq!(rest, &text.as_bytes()[line.len()..]);
+    assert_eq!(decoder.total_out(), text.len() as u64);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_decompress_split_records() {
+    use async_compression::stream::{GzipDecoder, Split};
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let text: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+    let compressed = utils::gzip::sync::compress(text.as_bytes());
+    let input = utils::InputStream::from(compressed.chunks(5).map(Vec::from).collect::<Vec<_>>());
+
+    // Decompressed chunks of 7 bytes, so most lines straddle a chunk boundary
+    let lines: Vec<Bytes> = block_on(
+        GzipDecoder::new(input.stream())
+            .with_buffer_capacity(7)
+            .split(b'\n')
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(lines, text.lines().map(Bytes::from).collect::<Vec<_>>(),);
+
+    // The last record is yielded without a trailing delimiter, empty records are kept
+    let compressed = utils::gzip::sync::compress(b"first\n\nlast");
+    let input = utils::InputStream::from(compressed.chunks(1).map(Vec::from).collect::<Vec<_>>());
+    let records: Vec<Bytes> = block_on(
+        GzipDecoder::new(input.stream())
+            .with_buffer_capacity(1)
+            .split(b'\n')
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(records, ["first", "", "last"]);
+
+    let chunks = vec![b"a,b".to_vec(), b"c,".to_vec(), b",d,".to_vec()];
+    let input = utils::InputStream::from(chunks);
+    let records: Vec<Bytes> = block_on(Split::new(input.stream(), b',').try_collect()).unwrap();
+    assert_eq!(records, ["a", "bc", "", "d"]);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn gzip_write_compress_blocking_writer() {
+    use async_compression::{
+        flate2::Compression,
+        write::{BlockingWriter, GzipEncoder},
+    };
+    use futures::executor::block_on;
+    use std::io::Write;
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(10_000);
+
+    let (mut writer, task) = BlockingWriter::new(GzipEncoder::new(Vec::new(), Compression::fast()));
+    let thread = {
+        let input = input.clone();
+        std::thread::spawn(move || {
+            for chunk in input.chunks(1000) {
+                writer.write_all(chunk)?;
+            }
+            writer.flush()?;
+            writer.finish()
+        })
+    };
+
+    let encoder = block_on(task).unwrap();
+    thread.join().unwrap().unwrap();
+
+    let compressed = encoder.into_inner();
+    let stream = utils::InputStream::from(vec![compressed]);
+    assert_eq!(utils::gzip::bufread::decompress(stream.reader()), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_compress_from_read() {
+    use async_compression::{
+        flate2::Compression,
+        stream::{GzipDecoder, GzipEncoder, ReaderStream},
+    };
+    use futures::{executor::block_on, io::Cursor, stream::TryStreamExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(1000);
+
+    let compressed =
+        block_on(GzipEncoder::from_read(Cursor::new(&input), Compression::default()).try_concat())
+            .unwrap();
+    assert_eq!(utils::gzip::sync::decompress(&compressed), input);
+
+    // The chunk size is chosen by creating the stream directly
+    let chunks: Vec<_> =
+        block_on(ReaderStream::with_chunk_size(Cursor::new(&compressed), 10).try_collect())
+            .unwrap();
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 10));
+
+    let stream = ReaderStream::with_chunk_size(Cursor::new(&compressed), 10);
+    let output = block_on(GzipDecoder::new(stream).try_concat()).unwrap();
+    assert_eq!(&output[..], &input[..]);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_stream_compress_all() {
+    use async_compression::{flate2::Compression, stream::GzipEncoder};
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream, stream::TryStreamExt};
+
+    let chunks: Vec<Vec<u8>> = (0..100)
+        .map(|i| {
+            format!("record {} of the batch\n", i)
+                .repeat(i)
+                .into_bytes()
+        })
+        .collect();
+
+    let streamed = block_on(
+        GzipEncoder::new(
+            stream::iter(
+                chunks
+                    .clone()
+                    .into_iter()
+                    .map(|chunk| Ok(Bytes::from(chunk))),
+            ),
+            Compression::default(),
+        )
+        .try_concat(),
+    )
+    .unwrap();
+
+    let compressed = GzipEncoder::compress_all(chunks.clone(), Compression::default()).unwrap();
+    assert_eq!(compressed, &streamed[..]);
+    assert_eq!(utils::gzip::sync::decompress(&compressed), chunks.concat());
+
+    let compressed = GzipEncoder::compress_all(Vec::<Vec<u8>>::new(), Compression::default());
+    assert_eq!(utils::gzip::sync::decompress(&compressed.unwrap()), b"");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_compress_warns_about_compressed_input() {
+    use async_compression::{flate2::Compression, write::GzipEncoder, Format, Warning};
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use std::sync::{Arc, Mutex};
+
+    fn compress(input: &[u8]) -> Vec<Warning> {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let warnings = warnings.clone();
+            move |warning| warnings.lock().unwrap().push(warning)
+        };
+
+        let mut encoder =
+            GzipEncoder::new(Vec::new(), Compression::default()).with_warning_hook(hook);
+        // A byte at a time, so the magic number is split across writes
+        for byte in input {
+            block_on(encoder.write_all(std::slice::from_ref(byte))).unwrap();
+        }
+        block_on(encoder.close()).unwrap();
+
+        // The output is compressed as usual
+        assert_eq!(utils::gzip::sync::decompress(encoder.get_ref()), input);
+
+        let warnings = warnings.lock().unwrap();
+        warnings.clone()
+    }
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(10);
+    assert_eq!(compress(&input), []);
+
+    let compressed = utils::gzip::sync::compress(&input);
+    assert_eq!(
+        compress(&compressed),
+        [Warning::CompressedInput(Format::Gzip)]
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_finish_member() {
+    use async_compression::{bufread::GzipDecoder, write::GzipEncoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let mut encoder = GzipEncoder::new(Vec::new(), flate2::Compression::default());
+    block_on(encoder.write_all(b"first record\n")).unwrap();
+    block_on(encoder.finish_member()).unwrap();
+    let first_len = encoder.get_ref().len();
+    // Finishing again with nothing written since doesn't add an empty member
+    block_on(encoder.finish_member()).unwrap();
+    assert_eq!(encoder.get_ref().len(), first_len);
+
+    block_on(encoder.write_all(b"second record\n")).unwrap();
+    block_on(encoder.finish_member()).unwrap();
+    let output = encoder.get_ref().clone();
+
+    let (first, second) = output.split_at(first_len);
+    assert_eq!(utils::gzip::sync::decompress(first), b"first record\n");
+    assert_eq!(utils::gzip::sync::decompress(second), b"second record\n");
+
+    let mut decoder = GzipDecoder::new(&output[..]);
+    let mut decoded = Vec::new();
+    block_on(decoder.read_to_end(&mut decoded)).unwrap();
+    assert_eq!(decoded, b"first record\nsecond record\n");
+    assert_eq!(decoder.stats().frames, Some(2));
+
+    // Closing after finishing a member doesn't add an empty one either
+    block_on(encoder.close()).unwrap();
+    assert_eq!(encoder.get_ref(), &output);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_finish() {
+    use async_compression::write::GzipEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    // A socket which returns `Pending` between operations and fails writes once closed
+    let socket = Vec::new().track_closed().interleave_pending_write();
+
+    let mut encoder = GzipEncoder::new(socket, flate2::Compression::default());
+    block_on(encoder.write_all(b"compressed body")).unwrap();
+    let mut socket = block_on(encoder.finish()).unwrap();
+    assert!(!socket.get_ref().is_closed());
+    let body_len = socket.get_ref().get_ref().len();
+
+    block_on(socket.write_all(b"trailer")).unwrap();
+    block_on(socket.close()).unwrap();
+
+    let output = socket.into_inner().into_inner();
+    let (body, trailer) = output.split_at(body_len);
+    assert_eq!(utils::gzip::sync::decompress(body), b"compressed body");
+    assert_eq!(trailer, b"trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_write_decompress_short_write() {
+    use async_compression::write::GzipDecoder;
+    use futures::{
+        executor::block_on,
+        io::{AsyncWriteExt, ErrorKind},
+    };
+
+    let compressed = utils::gzip::sync::compress(b"hello world");
+    let mut input = compressed.clone();
+    input.extend_from_slice(b"trailing");
+
+    let mut decoder = GzipDecoder::new(Vec::new()).with_multiple_members(false);
+    let written = block_on(decoder.write(&input)).unwrap();
+    assert_eq!(written, compressed.len());
+    // Once the member has ended nothing more is taken
+    assert_eq!(block_on(decoder.write(&input[written..])).unwrap(), 0);
+    assert_eq!(
+        block_on(decoder.write_all(b"more")).unwrap_err().kind(),
+        ErrorKind::WriteZero
+    );
+
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), b"hello world");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_then_read_rest() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::gzip::sync::compress(b"hello world");
+    input.extend_from_slice(b"hello");
+
+    for chunk_size in &[1, input.len()] {
+        let stream =
+            utils::InputStream::from(input.chunks(*chunk_size).map(Vec::from).collect::<Vec<_>>());
+        let mut decoder = GzipDecoder::new(stream.reader()).with_multiple_members(false);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, b"hello world");
+
+        let mut rest = Vec::new();
+        block_on(decoder.into_inner().read_to_end(&mut rest)).unwrap();
+        assert_eq!(rest, b"hello");
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_trailing_len() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{
+        executor::block_on,
+        io::{AsyncBufReadExt, AsyncReadExt},
+    };
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(10);
+    let mut compressed = utils::gzip::sync::compress(&input);
+    compressed.extend_from_slice(b"extra");
+
+    let mut decoder = GzipDecoder::new(&compressed[..]).with_multiple_members(false);
+    assert_eq!(decoder.trailing_len(), None);
+
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+    assert_eq!(decoder.trailing_len(), Some(5));
+
+    let mut reader = decoder.into_inner();
+    assert_eq!(block_on(reader.fill_buf()).unwrap(), b"extra");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_trailing_member() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{
+        executor::block_on,
+        io::{AsyncBufReadExt, AsyncReadExt},
+    };
+
+    let first = utils::gzip::sync::compress(b"the first record");
+    let second = utils::gzip::sync::compress(b"the second record");
+    let compressed = [&first[..], &second[..]].concat();
+
+    // Decode just the first member, the bytes of the second already buffered are left unconsumed
+    let mut decoder = GzipDecoder::new(&compressed[..]).with_multiple_members(false);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"the first record");
+    assert_eq!(decoder.trailing_len(), Some(second.len()));
+    assert_eq!(decoder.total_in(), first.len() as u64);
+
+    let mut reader = decoder.into_inner();
+    assert_eq!(block_on(reader.fill_buf()).unwrap(), &second[..]);
+
+    let mut decoder = GzipDecoder::new(reader).with_multiple_members(false);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"the second record");
+    assert_eq!(decoder.trailing_len(), Some(0));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_checksum() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    let compressed = utils::gzip::sync::compress(&[1, 2, 3, 4, 5, 6]);
+
+    let mut decoder = GzipDecoder::new(&compressed[..]);
+    assert_eq!(decoder.declared_checksum(), None);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(decoder.declared_checksum(), Some(decoder.checksum()));
+
+    // The last byte of the CRC, which is followed by the length
+    let mut corrupted = compressed.clone();
+    let len = corrupted.len();
+    corrupted[len - 5] ^= 0xff;
+
+    let mut decoder = GzipDecoder::new(&corrupted[..]);
+    let mut output = Vec::new();
+    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(
+        decoder.declared_checksum(),
+        Some(decoder.checksum() ^ 0xff00_0000)
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn gzip_bufread_decompress_was_stored_multiple_members() {
+    use async_compression::bufread::GzipDecoder;
+    use flate2::{write::GzEncoder, Compression};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::Write;
+
+    let compress = |input: &[u8], level| {
+        let mut encoder = GzEncoder::new(Vec::new(), level);
+        encoder.write_all(input).unwrap();
+        encoder.finish().unwrap()
+    };
+    let stored = compress(b"stored member", Compression::none());
+    let compressed = compress(b"compressed member", Compression::fast());
+
+    // Only the first member is looked at, whatever follows it
+    for (first, second, expected) in &[(&stored, &compressed, true), (&compressed, &stored, false)]
+    {
+        let input = [&first[..], &second[..]].concat();
+        let mut decoder = GzipDecoder::new(&input[..]);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output.len(), 30);
+        assert_eq!(decoder.was_stored(), Some(*expected));
+    }
+}
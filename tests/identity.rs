@@ -0,0 +1,94 @@
+#[macro_use]
+mod utils;
+
+test_cases!(identity);
+
+#[test]
+#[ntest::timeout(1000)]
+fn identity_write_compress_flush_passes_through() {
+    use async_compression::write::IdentityEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let mut encoder = IdentityEncoder::new(Vec::new());
+
+    block_on(encoder.write_all(&[1, 2, 3])).unwrap();
+    block_on(encoder.flush()).unwrap();
+    assert_eq!(encoder.get_ref(), &[1, 2, 3]);
+
+    block_on(encoder.write_all(&[4, 5, 6])).unwrap();
+    block_on(encoder.close()).unwrap();
+    assert_eq!(encoder.into_inner(), &[1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn identity_stream_empty_chunks_skip_codec() {
+    use async_compression::stream::{IdentityDecoder, IdentityEncoder};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        stream::{self, TryStreamExt},
+    };
+
+    // The identity codec produces exactly one output item for each step it is run, so the output
+    // items show which input chunks reached the codec
+    let chunks = || {
+        stream::iter(
+            vec![
+                Bytes::from(&[1, 2, 3][..]),
+                Bytes::new(),
+                Bytes::from(&[4, 5][..]),
+                Bytes::new(),
+                Bytes::new(),
+                Bytes::from(&[6][..]),
+                Bytes::new(),
+            ]
+            .into_iter()
+            .map(Ok),
+        )
+    };
+
+    let expected = vec![
+        Bytes::from(&[1, 2, 3][..]),
+        Bytes::from(&[4, 5][..]),
+        Bytes::from(&[6][..]),
+    ];
+
+    let encoded: Vec<Bytes> = block_on(IdentityEncoder::new(chunks()).try_collect()).unwrap();
+    assert_eq!(encoded, expected);
+
+    let decoded: Vec<Bytes> = block_on(IdentityDecoder::new(chunks()).try_collect()).unwrap();
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+fn identity_content_encoding_dispatch() {
+    use async_compression::bufread::{GzipDecoder, IdentityDecoder, ZstdDecoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncRead, AsyncReadExt},
+    };
+    use std::pin::Pin;
+
+    // A dispatcher on the `Content-Encoding` header needs no special case for `identity`
+    fn decoder<'a>(encoding: &str, body: &'a [u8]) -> Pin<Box<dyn AsyncRead + Send + 'a>> {
+        match encoding {
+            "gzip" => Box::pin(GzipDecoder::new(body)),
+            "zstd" => Box::pin(ZstdDecoder::new(body)),
+            "identity" => Box::pin(IdentityDecoder::new(body)),
+            _ => panic!("unsupported encoding {}", encoding),
+        }
+    }
+
+    for (encoding, body) in &[
+        ("gzip", utils::gzip::sync::compress(b"hello world")),
+        ("zstd", utils::zstd::sync::compress(b"hello world")),
+        ("identity", b"hello world".to_vec()),
+    ] {
+        let mut output = Vec::new();
+        block_on(decoder(encoding, body).read_to_end(&mut output)).unwrap();
+        assert_eq!(output, b"hello world", "{}", encoding);
+    }
+}
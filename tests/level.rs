@@ -0,0 +1,101 @@
+mod utils;
+
+use async_compression::Level;
+
+/// Compresses the input with the `bufread` encoder at the level, returning the output.
+macro_rules! compress {
+    ($encoder:ident, $input:expr, $level:expr) => {{
+        use futures::{executor::block_on, io::AsyncReadExt};
+
+        let mut output = Vec::new();
+        block_on(
+            async_compression::bufread::$encoder::with_quality(&$input[..], $level)
+                .read_to_end(&mut output),
+        )
+        .unwrap();
+        output
+    }};
+}
+
+macro_rules! level_tests {
+    ($($name:ident: $encoder:ident;)*) => {
+        #[test]
+        #[ntest::timeout(20000)]
+        fn every_level_round_trips() {
+            let input = b"the quick brown fox jumps over the lazy dog\n".repeat(1000);
+
+            for level in &[
+                Level::Fastest,
+                Level::Best,
+                Level::Default,
+                Level::Precise(-100),
+                Level::Precise(5),
+                Level::Precise(100),
+            ] {
+                $(
+                    let compressed = compress!($encoder, input, *level);
+                    assert_eq!(
+                        utils::$name::sync::decompress(&compressed),
+                        input,
+                        "{} at {:?}",
+                        stringify!($name),
+                        level
+                    );
+                )*
+            }
+        }
+    };
+}
+
+level_tests! {
+    brotli: BrotliEncoder;
+    bzip: BzEncoder;
+    deflate: DeflateEncoder;
+    gzip: GzipEncoder;
+    identity: IdentityEncoder;
+    lz4: Lz4Encoder;
+    lzma: LzmaEncoder;
+    snappy: SnappyEncoder;
+    xz: XzEncoder;
+    zlib: ZlibEncoder;
+    zstd: ZstdEncoder;
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn best_is_smaller_than_fastest() {
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(1000);
+
+    assert!(
+        compress!(ZstdEncoder, input, Level::Best).len()
+            < compress!(ZstdEncoder, input, Level::Fastest).len()
+    );
+    assert!(
+        compress!(XzEncoder, input, Level::Best).len()
+            <= compress!(XzEncoder, input, Level::Fastest).len()
+    );
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn precise_levels_are_clamped() {
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(1000);
+
+    // Level 0 stores the input uncompressed, below it is the same
+    assert_eq!(
+        compress!(DeflateEncoder, input, Level::Precise(-1)),
+        compress!(DeflateEncoder, input, Level::Precise(0))
+    );
+    assert_eq!(
+        compress!(DeflateEncoder, input, Level::Precise(100)),
+        compress!(DeflateEncoder, input, Level::Best)
+    );
+    assert_eq!(
+        compress!(ZstdEncoder, input, Level::Precise(100)),
+        compress!(ZstdEncoder, input, Level::Best)
+    );
+    assert_eq!(
+        compress!(ZstdEncoder, input, Level::Precise(i32::MIN)),
+        compress!(ZstdEncoder, input, Level::Fastest)
+    );
+}
@@ -0,0 +1,338 @@
+mod utils;
+
+/// A megabyte of zeros, which every format compresses down to almost nothing.
+fn zeros() -> Vec<u8> {
+    vec![0; 1 << 20]
+}
+
+const LIMIT: u64 = 64 * 1024;
+
+fn assert_limit_exceeded(err: std::io::Error) {
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<async_compression::LimitExceeded>())
+        .unwrap();
+    assert_eq!(err.limit(), LIMIT);
+}
+
+macro_rules! limit_tests {
+    ($($name:ident: $decoder:ident;)*) => {
+        $(
+            mod $name {
+                use crate::{assert_limit_exceeded, utils, zeros, LIMIT};
+                use bytes::Bytes;
+                use futures::{
+                    executor::block_on,
+                    io::{AsyncReadExt, AsyncWriteExt},
+                    stream::StreamExt,
+                };
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn bufread() {
+                    use async_compression::bufread::$decoder;
+
+                    let compressed = utils::$name::sync::compress(&zeros());
+
+                    let mut decoder = $decoder::new(&compressed[..]).with_limit(LIMIT);
+                    let mut output = Vec::new();
+                    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+                    assert_limit_exceeded(err);
+                    // Everything up to the limit was produced, nothing past it
+                    assert_eq!(output.len() as u64, LIMIT);
+
+                    // A limit the data fits in is not an error
+                    let mut decoder = $decoder::new(&compressed[..]).with_limit(1 << 20);
+                    let mut output = Vec::new();
+                    block_on(decoder.read_to_end(&mut output)).unwrap();
+                    assert_eq!(output, zeros());
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn read() {
+                    use async_compression::read::$decoder;
+
+                    let compressed = utils::$name::sync::compress(&zeros());
+
+                    let mut decoder = $decoder::new(&compressed[..]).with_limit(LIMIT);
+                    let mut output = Vec::new();
+                    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+                    assert_limit_exceeded(err);
+                    assert_eq!(output.len() as u64, LIMIT);
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn write() {
+                    use async_compression::write::$decoder;
+
+                    let compressed = utils::$name::sync::compress(&zeros());
+
+                    let mut decoder = $decoder::new(Vec::new()).with_limit(LIMIT);
+                    let err = match block_on(decoder.write_all(&compressed)) {
+                        Ok(()) => block_on(decoder.close()).unwrap_err(),
+                        Err(err) => err,
+                    };
+                    assert_limit_exceeded(err);
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn stream() {
+                    use async_compression::stream::$decoder;
+
+                    let compressed = utils::$name::sync::compress(&zeros());
+
+                    let input = futures::stream::iter(vec![Ok(Bytes::from(compressed))]);
+                    let mut decoder = $decoder::new(input).with_limit(LIMIT);
+                    let mut len = 0;
+                    let err = loop {
+                        match block_on(decoder.next()).unwrap() {
+                            Ok(chunk) => len += chunk.len() as u64,
+                            Err(err) => break err,
+                        }
+                    };
+                    assert_limit_exceeded(err);
+                    assert_eq!(len, LIMIT);
+                }
+            }
+        )*
+    };
+}
+
+limit_tests! {
+    brotli: BrotliDecoder;
+    bzip: BzDecoder;
+    deflate: DeflateDecoder;
+    gzip: GzipDecoder;
+    identity: IdentityDecoder;
+    lz4: Lz4Decoder;
+    lzma: LzmaDecoder;
+    snappy: SnappyDecoder;
+    xz: XzDecoder;
+    zlib: ZlibDecoder;
+    zstd: ZstdDecoder;
+}
+
+/// Thousands of empty gzip members, which decompress to nothing, arriving in small chunks as from
+/// a network connection, so that each takes steps to decode.
+fn empty_members() -> utils::InputStream {
+    let input = utils::gzip::sync::compress(&[]).repeat(10_000);
+    utils::InputStream::from(input.chunks(64).map(Vec::from).collect::<Vec<_>>())
+}
+
+fn assert_step_limit_exceeded(err: std::io::Error, limit: u64) {
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<async_compression::StepLimitExceeded>())
+        .unwrap();
+    assert_eq!(err.limit(), limit);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn bufread_step_limit() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = empty_members();
+
+    let mut decoder = GzipDecoder::new(input.reader()).with_step_limit(100);
+    let mut output = Vec::new();
+    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+    assert_step_limit_exceeded(err, 100);
+    assert!(output.is_empty());
+    assert_eq!(decoder.stats().steps, Some(100));
+
+    // Without a limit the same input decodes, taking far more steps
+    let mut decoder = GzipDecoder::new(input.reader());
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert!(output.is_empty());
+    assert!(decoder.stats().steps.unwrap() > 1000);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn stream_step_limit() {
+    use async_compression::stream::GzipDecoder;
+    use futures::{executor::block_on, stream::StreamExt};
+
+    let mut decoder = GzipDecoder::new(empty_members().stream()).with_step_limit(100);
+    let err = block_on(decoder.next()).unwrap().unwrap_err();
+    assert_step_limit_exceeded(err, 100);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn bufread_poll_budget_caps_output() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::io::AsyncRead;
+    use futures_test::task::noop_context;
+    use std::{pin::Pin, task::Poll};
+
+    let compressed = utils::gzip::sync::compress(&zeros());
+    let mut buf = vec![0; 1 << 20];
+
+    let mut decoder = GzipDecoder::new(&compressed[..]);
+    match Pin::new(&mut decoder).poll_read(&mut noop_context(), &mut buf) {
+        Poll::Ready(Ok(len)) => assert_eq!(len, 256 * 1024),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut decoder = GzipDecoder::new(&compressed[..]).with_poll_budget(LIMIT as usize);
+    match Pin::new(&mut decoder).poll_read(&mut noop_context(), &mut buf) {
+        Poll::Ready(Ok(len)) => assert_eq!(len as u64, LIMIT),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn bufread_poll_budget_yields() {
+    use async_compression::bufread::GzipDecoder;
+    use futures::io::AsyncRead;
+    use futures_test::task::new_count_waker;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    // Polls until the end of the stream, which produces no output, returning how many polls
+    // that took, checking the task was woken to poll it again each time it yielded
+    fn polls(mut decoder: GzipDecoder<impl futures::io::AsyncBufRead + Unpin>) -> usize {
+        let (waker, count) = new_count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut buf = [0; 1024];
+        for polls in 1.. {
+            match Pin::new(&mut decoder).poll_read(&mut cx, &mut buf) {
+                Poll::Ready(Ok(0)) => return polls,
+                Poll::Pending => assert_eq!(count.get(), polls),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        unreachable!()
+    }
+
+    let input = utils::gzip::sync::compress(&[]).repeat(10_000);
+    assert!(input.len() < 256 * 1024);
+
+    assert_eq!(polls(GzipDecoder::new(&input[..])), 1);
+
+    let polls = polls(GzipDecoder::new(&input[..]).with_poll_budget(16 * 1024));
+    assert!(polls >= input.len() / (16 * 1024), "{}", polls);
+}
+
+fn assert_memory_budget_exceeded(err: std::io::Error, limit: usize, requested: usize) {
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<async_compression::MemoryBudgetExceeded>())
+        .unwrap();
+    assert_eq!(err.limit(), limit);
+    assert_eq!(err.requested(), requested);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn memory_budget() {
+    use async_compression::{read, stream, write, MemoryBudget};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+
+    let compressed = utils::gzip::sync::compress(&zeros());
+    let input = || futures::stream::iter(vec![Ok(Bytes::from(compressed.clone()))]);
+
+    // Room for two decoders with 8 KiB buffers, not three
+    let budget = MemoryBudget::new(20 * 1024);
+
+    let mut first = read::GzipDecoder::new(&compressed[..])
+        .with_buffer_capacity(8 * 1024)
+        .with_memory_budget(&budget);
+    let mut second = stream::GzipDecoder::new(input())
+        .with_buffer_capacity(8 * 1024)
+        .with_memory_budget(&budget);
+    let mut third = write::GzipDecoder::new(Vec::new())
+        .with_buffer_capacity(8 * 1024)
+        .with_memory_budget(&budget);
+
+    // Nothing is reserved until the decoders start
+    assert_eq!(budget.used(), 0);
+
+    let mut chunk = [0; 1024];
+    assert_eq!(block_on(first.read(&mut chunk)).unwrap(), 1024);
+    assert_eq!(block_on(second.next()).unwrap().unwrap().len(), 8 * 1024);
+    assert_eq!(budget.used(), 16 * 1024);
+
+    let err = block_on(third.write_all(&compressed)).unwrap_err();
+    assert_memory_budget_exceeded(err, 20 * 1024, 8 * 1024);
+    assert!(third.get_ref().is_empty());
+    assert_eq!(budget.used(), 16 * 1024);
+
+    // Once one finishes and is dropped there is room for another
+    let mut output = Vec::new();
+    block_on(first.read_to_end(&mut output)).unwrap();
+    drop(first);
+    assert_eq!(budget.used(), 8 * 1024);
+
+    let mut third = write::GzipDecoder::new(Vec::new())
+        .with_buffer_capacity(8 * 1024)
+        .with_memory_budget(&budget);
+    block_on(third.write_all(&compressed)).unwrap();
+    block_on(third.close()).unwrap();
+    assert_eq!(third.into_inner(), zeros());
+
+    // As does one whose buffer alone does not fit in what is left
+    let mut bigger = stream::GzipDecoder::new(input())
+        .with_buffer_capacity(32 * 1024)
+        .with_memory_budget(&budget);
+    let err = block_on(bigger.next()).unwrap().unwrap_err();
+    assert_memory_budget_exceeded(err, 20 * 1024, 32 * 1024);
+
+    drop(second);
+    assert_eq!(budget.used(), 0);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn bufread_memory_budget() {
+    use async_compression::{bufread::GzipDecoder, MemoryBudget};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::gzip::sync::compress(&zeros());
+
+    // Room for two decoders with 8 KiB buffers, not three
+    let budget = MemoryBudget::new(20 * 1024);
+
+    // Only reading them through `poll_read` their buffers are never allocated, but they are
+    // still reserved once decoding starts
+    let mut first = GzipDecoder::new(&compressed[..]).with_memory_budget(&budget);
+    let mut second = GzipDecoder::new(&compressed[..]).with_memory_budget(&budget);
+    let mut third = GzipDecoder::new(&compressed[..]).with_memory_budget(&budget);
+    assert_eq!(budget.used(), 0);
+
+    let mut chunk = [0; 1024];
+    assert_eq!(block_on(first.read(&mut chunk)).unwrap(), 1024);
+    assert_eq!(block_on(second.read(&mut chunk)).unwrap(), 1024);
+    assert_eq!(budget.used(), 16 * 1024);
+
+    let err = block_on(third.read(&mut chunk)).unwrap_err();
+    assert_memory_budget_exceeded(err, 20 * 1024, 8 * 1024);
+    assert_eq!(third.total_out(), 0);
+
+    drop(first);
+    let mut output = Vec::new();
+    block_on(third.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, zeros());
+    assert_eq!(budget.used(), 16 * 1024);
+
+    drop(second);
+    drop(third);
+    assert_eq!(budget.used(), 0);
+}
@@ -0,0 +1,196 @@
+#[macro_use]
+mod utils;
+
+test_cases!(lz4);
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_stream_compress_empty_is_valid_frame() {
+    let compressed = utils::lz4::stream::compress(utils::InputStream::from(vec![]).stream());
+
+    // Magic number, frame descriptor and the end mark are always present
+    assert_eq!(&compressed[..4], &[0x04, 0x22, 0x4d, 0x18]);
+    assert!(utils::lz4::sync::decompress(&compressed).is_empty());
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_bufread_decompress_block_boundary_mid_chunk() {
+    use futures::io::BufReader;
+    use std::iter::FromIterator;
+
+    // Larger than the 64 KiB default block size so the frame contains multiple blocks, read back
+    // through an odd-sized buffer so block boundaries land inside a read
+    let input = Vec::from_iter((0..200_000u32).map(|i| ((i % 251) ^ (i / 1000)) as u8));
+    let compressed = utils::lz4::sync::compress(&input);
+
+    let output = utils::lz4::bufread::decompress(BufReader::with_capacity(7, &compressed[..]));
+
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_stream_compress_block_boundary_mid_chunk() {
+    use std::iter::FromIterator;
+
+    let input = Vec::from_iter((0..200_000u32).map(|i| ((i % 251) ^ (i / 1000)) as u8));
+    let chunks = Vec::from_iter(input.chunks(50_001).map(Vec::from));
+
+    let compressed = utils::lz4::stream::compress(utils::InputStream::from(chunks).stream());
+
+    assert_eq!(utils::lz4::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_bufread_decompress_stats() {
+    use async_compression::bufread::Lz4Decoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::iter::FromIterator;
+
+    // Four blocks of at most 64 KiB
+    let input = Vec::from_iter((0..200_000u32).map(|i| ((i % 251) ^ (i / 1000)) as u8));
+    let compressed = utils::lz4::sync::compress(&input);
+
+    let mut decoder = Lz4Decoder::new(&compressed[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    let stats = decoder.stats();
+    // The content checksum is stored at the end of the frame
+    let trailer = &compressed[compressed.len() - 4..];
+    assert_eq!(
+        stats.checksum,
+        Some(u32::from_le_bytes([
+            trailer[0], trailer[1], trailer[2], trailer[3]
+        ]))
+    );
+    assert_eq!(stats.blocks, Some(4));
+    assert_eq!(stats.window_size, Some(64 * 1024));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_bufread_into_inner_after_stream_end() {
+    use async_compression::bufread::Lz4Decoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::lz4::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    input.extend_from_slice(b"trailer");
+
+    let stream = utils::InputStream::from(input.chunks(3).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = Lz4Decoder::new(stream.reader());
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+
+    // Nothing past the end of the compressed stream was consumed
+    let mut reader = decoder.into_inner();
+    let mut rest = Vec::new();
+    block_on(reader.read_to_end(&mut rest)).unwrap();
+    assert_eq!(rest, b"trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_block_roundtrip() {
+    use async_compression::{bufread, write};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+    use std::iter::FromIterator;
+
+    let input = Vec::from_iter((0..200_000u32).map(|i| ((i % 251) ^ (i / 1000)) as u8));
+
+    for &level in &[0, 9] {
+        let mut encoder = write::Lz4BlockEncoder::new(Vec::new(), level);
+        for chunk in input.chunks(50_001) {
+            block_on(encoder.write_all(chunk)).unwrap();
+        }
+        block_on(encoder.close()).unwrap();
+        assert_eq!(encoder.total_in(), input.len() as u64);
+        let compressed = encoder.into_inner();
+
+        // A raw block, without the frame's magic number, readable by lz4 itself
+        assert_ne!(&compressed[..4], &[0x04, 0x22, 0x4d, 0x18]);
+        let decoded = liblz4::block::decompress(&compressed, Some(input.len() as i32)).unwrap();
+        assert_eq!(decoded, input);
+
+        let mut decoder = bufread::Lz4BlockDecoder::new(&compressed[..], input.len());
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, input);
+
+        // The size may be an upper bound
+        let mut decoder = write::Lz4BlockDecoder::new(Vec::new(), input.len() + 100);
+        for chunk in compressed.chunks(1000) {
+            block_on(decoder.write_all(chunk)).unwrap();
+        }
+        block_on(decoder.close()).unwrap();
+        assert_eq!(decoder.into_inner(), input);
+    }
+
+    // A block compressed by lz4 itself
+    let compressed = liblz4::block::compress(&input, None, false).unwrap();
+    let mut decoder = bufread::Lz4BlockDecoder::new(&compressed[..], input.len());
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_block_decompressed_size_too_small() {
+    use async_compression::bufread::{Lz4BlockDecoder, Lz4BlockEncoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(100);
+    let mut compressed = Vec::new();
+    block_on(Lz4BlockEncoder::new(&input[..], 0).read_to_end(&mut compressed)).unwrap();
+
+    let mut decoder = Lz4BlockDecoder::new(&compressed[..], input.len() - 1);
+    let mut output = Vec::new();
+    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(output.is_empty());
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_block_level_above_max() {
+    use async_compression::bufread::{Lz4BlockDecoder, Lz4BlockEncoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(100);
+    let compress = |level| {
+        let mut compressed = Vec::new();
+        block_on(Lz4BlockEncoder::new(&input[..], level).read_to_end(&mut compressed)).unwrap();
+        compressed
+    };
+
+    // Levels past the top one, even those not fitting an `i32`, are the top one
+    let compressed = compress(u32::MAX);
+    assert_eq!(compressed, compress(12));
+    assert_eq!(compress(13), compress(12));
+
+    let mut output = Vec::new();
+    block_on(Lz4BlockDecoder::new(&compressed[..], input.len()).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lz4_block_input_too_long() {
+    use async_compression::write::Lz4BlockDecoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use std::io::ErrorKind;
+
+    // No block decompressing to 1000 bytes is longer than 1000 + 1000 / 255 + 16 bytes, so the
+    // input is refused once it goes past that, without waiting for it to end
+    let mut decoder = Lz4BlockDecoder::new(Vec::new(), 1000);
+    block_on(decoder.write_all(&[0; 1019])).unwrap();
+    let err = block_on(decoder.write_all(&[0; 1])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(decoder.get_ref().is_empty());
+}
@@ -0,0 +1,137 @@
+#[macro_use]
+mod utils;
+
+test_cases!(lzma);
+
+/// `printf 'hello, lzma alone format\n' | xz --format=lzma`
+const CLI_OUTPUT: &[u8] = &[
+    0x5d, 0x00, 0x00, 0x80, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x34, 0x19,
+    0x49, 0xee, 0x8d, 0xef, 0x8c, 0x6a, 0x57, 0x93, 0xf0, 0xc0, 0xda, 0xb2, 0x81, 0x27, 0xed, 0x49,
+    0x53, 0x84, 0xdb, 0x20, 0xe4, 0x77, 0x66, 0x8a, 0xdb, 0x01, 0xe8, 0xdf, 0xfd, 0x50, 0x08, 0x00,
+];
+
+#[test]
+#[ntest::timeout(1000)]
+fn lzma_bufread_decompress_cli_output() {
+    use async_compression::{bufread::LzmaDecoder, lzma::LzmaHeader};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let stream = utils::InputStream::from(CLI_OUTPUT.chunks(5).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = LzmaDecoder::new(stream.reader());
+    assert_eq!(decoder.header(), None);
+
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"hello, lzma alone format\n");
+
+    // Preset 6 has lc=3, lp=0, pb=2 and an 8 MiB dictionary, and the size of a pipe isn't known
+    assert_eq!(
+        decoder.header(),
+        Some(&LzmaHeader {
+            properties: 0x5d,
+            dictionary_size: 8 * 1024 * 1024,
+            uncompressed_size: None,
+        })
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lzma_bufread_decompress_known_size() {
+    use async_compression::bufread::LzmaDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+
+    // Filling in the size turns the stream into the known-size variant, an end marker is still
+    // allowed after the last byte
+    let mut compressed = utils::lzma::sync::compress(&input);
+    compressed[5..13].copy_from_slice(&(input.len() as u64).to_le_bytes());
+
+    let mut decoder = LzmaDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+    assert_eq!(
+        decoder.header().unwrap().uncompressed_size,
+        Some(input.len() as u64)
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lzma_bufread_compress_unknown_size() {
+    use async_compression::bufread::LzmaEncoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+
+    let mut compressed = Vec::new();
+    block_on(LzmaEncoder::new(&input[..], 6).read_to_end(&mut compressed)).unwrap();
+    assert_eq!(&compressed[..13], &CLI_OUTPUT[..13]);
+    assert_eq!(utils::lzma::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn lzma_decompress_truncated() {
+    use async_compression::bufread::LzmaDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    for len in &[5, CLI_OUTPUT.len() / 2, CLI_OUTPUT.len() - 1] {
+        let err = block_on(LzmaDecoder::new(&CLI_OUTPUT[..*len]).read_to_end(&mut Vec::new()))
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof, "{}", len);
+    }
+}
+
+/// Runs the `xz` command line tool on the data, in the legacy `.lzma` format.
+#[cfg(feature = "test-xz-cli")]
+fn xz_cli(args: &[&str], data: &[u8]) -> Vec<u8> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let mut child = Command::new("xz")
+        .args(["-c", "--format=lzma"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the xz command line tool must be installed for this test");
+    let mut stdin = child.stdin.take().unwrap();
+    let data = data.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&data).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(
+        output.status.success(),
+        "xz failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+#[test]
+#[cfg(feature = "test-xz-cli")]
+fn lzma_cli_roundtrip() {
+    let inputs = vec![
+        vec![],
+        vec![1, 2, 3, 4, 5, 6],
+        b"the quick brown fox jumps over the lazy dog ".repeat(5000),
+    ];
+
+    for input in &inputs {
+        let compressed = xz_cli(&[], input);
+        let stream =
+            utils::InputStream::from(compressed.chunks(1024).map(Vec::from).collect::<Vec<_>>());
+        assert_eq!(&utils::lzma::bufread::decompress(stream.reader()), input);
+        assert_eq!(&utils::lzma::stream::decompress(stream.stream()), input);
+
+        let stream = utils::InputStream::from(vec![input.clone()]);
+        let compressed = utils::lzma::bufread::compress(stream.reader());
+        assert_eq!(&xz_cli(&["-d"], &compressed), input);
+    }
+}
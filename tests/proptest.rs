@@ -74,4 +74,4 @@ macro_rules! tests {
     }
 }
 
-tests!(brotli, bzip, deflate, gzip, zlib, zstd);
+tests!(brotli, bzip, deflate, gzip, identity, lz4, lzma, snappy, xz, zlib, zstd);
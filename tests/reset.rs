@@ -0,0 +1,92 @@
+use std::iter::FromIterator;
+
+mod utils;
+
+fn inputs() -> (Vec<u8>, Vec<u8>) {
+    let first = Vec::from_iter((0..20_000u32).map(|i| (i % 11) as u8));
+    let second = Vec::from_iter((0..30_000).map(|_| rand::random()));
+    (first, second)
+}
+
+macro_rules! reset_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*);)*) => {
+        $(
+            mod $name {
+                use crate::{inputs, utils};
+                use futures::{
+                    executor::block_on,
+                    io::{AsyncReadExt, AsyncWriteExt},
+                };
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn bufread() {
+                    use async_compression::bufread::$encoder;
+
+                    let (first, second) = inputs();
+
+                    let mut encoder = $encoder::new(&first[..], $($arg),*);
+                    let mut compressed = Vec::new();
+                    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), first);
+
+                    let previous = encoder.reset(&second[..]).unwrap();
+                    assert!(previous.is_empty());
+                    assert_eq!(encoder.total_in(), 0);
+                    let mut compressed = Vec::new();
+                    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), second);
+
+                    // Resetting part way through a stream abandons it
+                    encoder.reset(&first[..]).unwrap();
+                    block_on(encoder.read_exact(&mut [0; 10])).unwrap();
+                    encoder.reset(&second[..]).unwrap();
+                    let mut compressed = Vec::new();
+                    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), second);
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn write() {
+                    use async_compression::write::$encoder;
+
+                    let (first, second) = inputs();
+
+                    let mut encoder = $encoder::new(Vec::new(), $($arg),*);
+                    block_on(encoder.write_all(&first)).unwrap();
+                    block_on(encoder.close()).unwrap();
+                    let compressed = encoder.reset(Vec::new()).unwrap();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), first);
+
+                    block_on(encoder.write_all(&second)).unwrap();
+                    block_on(encoder.close()).unwrap();
+                    let compressed = encoder.reset(Vec::new()).unwrap();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), second);
+
+                    // Resetting part way through a stream abandons it
+                    block_on(encoder.write_all(&second)).unwrap();
+                    encoder.reset(Vec::new()).unwrap();
+                    block_on(encoder.write_all(&first)).unwrap();
+                    block_on(encoder.close()).unwrap();
+                    let compressed = encoder.into_inner();
+                    assert_eq!(utils::$name::sync::decompress(&compressed), first);
+                }
+            }
+        )*
+    };
+}
+
+reset_tests! {
+    brotli: BrotliEncoder(1);
+    bzip: BzEncoder(async_compression::bzip2::Compression::Fastest);
+    deflate: DeflateEncoder(async_compression::flate2::Compression::fast());
+    gzip: GzipEncoder(async_compression::flate2::Compression::fast());
+    identity: IdentityEncoder();
+    lz4: Lz4Encoder(0);
+    lzma: LzmaEncoder(1);
+    snappy: SnappyEncoder();
+    xz: XzEncoder(1);
+    zlib: ZlibEncoder(async_compression::flate2::Compression::fast());
+    zstd: ZstdEncoder(1);
+}
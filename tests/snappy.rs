@@ -0,0 +1,51 @@
+#[macro_use]
+mod utils;
+
+test_cases!(snappy);
+
+#[test]
+#[ntest::timeout(1000)]
+fn snappy_bufread_decompress_checksum_mismatch() {
+    use async_compression::bufread::SnappyDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut compressed = utils::snappy::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    // Flip a bit in the checksum of the first chunk after the stream identifier
+    compressed[14] ^= 1;
+
+    let mut decoder = SnappyDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    let err = block_on(decoder.read_to_end(&mut output)).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(output.is_empty());
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn snappy_bufread_decompress_uncompressed_chunk() {
+    use std::iter::FromIterator;
+
+    // Random data is incompressible so it is stored in an uncompressed chunk
+    let input = Vec::from_iter((0..1024).map(|_| rand::random()));
+    let compressed = utils::snappy::sync::compress(&input);
+    assert_eq!(compressed[10], 0x01);
+
+    let output =
+        utils::snappy::bufread::decompress(utils::InputStream::from(vec![compressed]).reader());
+
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn snappy_stream_compress_uncompressed_chunk() {
+    use std::iter::FromIterator;
+
+    let input = Vec::from_iter((0..1024).map(|_| rand::random()));
+    let compressed =
+        utils::snappy::stream::compress(utils::InputStream::from(vec![input.clone()]).stream());
+    assert_eq!(compressed[10], 0x01);
+
+    assert_eq!(utils::snappy::sync::decompress(&compressed), input);
+}
@@ -0,0 +1,44 @@
+mod utils;
+
+#[test]
+#[ntest::timeout(5000)]
+fn tee_decoder() {
+    use async_compression::{bufread::GzipDecoder, TeeDecoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use futures_test::io::AsyncWriteTestExt;
+
+    let input: Vec<u8> = (0..100_000u32).map(|i| (i % 17) as u8).collect();
+    let compressed = utils::gzip::sync::compress(&input);
+
+    // A sink slower than the reader, to check nothing is lost waiting on it
+    let sink = Vec::new()
+        .limited_write(1000)
+        .interleave_pending_write()
+        .track_closed();
+    let mut tee = TeeDecoder::new(GzipDecoder::new(&compressed[..]), sink);
+    let mut output = Vec::new();
+    block_on(tee.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+
+    let (_, sink) = tee.into_inner();
+    assert!(sink.is_closed());
+    assert_eq!(sink.into_inner().into_inner().into_inner(), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn tee_decoder_sink_error() {
+    use async_compression::{read::GzipDecoder, TeeDecoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::gzip::sync::compress(&[1, 2, 3, 4, 5, 6]);
+
+    // A sink that accepts nothing
+    let mut sink = [0; 0];
+    let mut tee = TeeDecoder::new(
+        GzipDecoder::new(&compressed[..]),
+        futures::io::Cursor::new(&mut sink[..]),
+    );
+    let err = block_on(tee.read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+}
@@ -0,0 +1,239 @@
+mod utils;
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::Result;
+
+use tokio_crate::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Runs `future` on a single threaded tokio runtime.
+fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+    tokio_crate::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// Yields at most `limit` bytes per read, and returns `Pending` before every read, so each chunk
+/// of the input needs a separate poll.
+struct Trickle<'a> {
+    data: &'a [u8],
+    limit: usize,
+    pending: bool,
+}
+
+impl<'a> Trickle<'a> {
+    fn new(data: &'a [u8], limit: usize) -> Self {
+        Self {
+            data,
+            limit,
+            pending: false,
+        }
+    }
+}
+
+impl AsyncRead for Trickle<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let len = self.limit.min(buf.remaining()).min(self.data.len());
+        buf.put_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Accepts at most `limit` bytes per write, and returns `Pending` before every write.
+struct TrickleWriter {
+    data: Vec<u8>,
+    limit: usize,
+    pending: bool,
+    shutdown: bool,
+}
+
+impl TrickleWriter {
+    fn new(limit: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            limit,
+            pending: false,
+            shutdown: false,
+        }
+    }
+}
+
+impl AsyncWrite for TrickleWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        assert!(!self.shutdown, "write after shutdown");
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let len = self.limit.min(buf.len());
+        self.data.extend_from_slice(&buf[..len]);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.shutdown = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn inputs() -> Vec<Vec<u8>> {
+    vec![
+        vec![],
+        vec![1, 2, 3, 4, 5, 6],
+        (0..65_536).map(|_| rand::random()).collect(),
+    ]
+}
+
+macro_rules! tokio_tests {
+    ($($name:ident: $encoder:ident($($arg:expr),*) $decoder:ident;)*) => {
+        $(
+            mod $name {
+                use super::{block_on, inputs, utils, Trickle, TrickleWriter};
+                use async_compression::tokio::{bufread, write};
+                use tokio_crate::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn bufread_compress() {
+                    for input in inputs() {
+                        let reader = BufReader::with_capacity(1024, Trickle::new(&input, 1000));
+                        let mut encoder = bufread::$encoder::new(reader, $($arg),*);
+                        let mut compressed = Vec::new();
+                        block_on(encoder.read_to_end(&mut compressed)).unwrap();
+                        assert_eq!(utils::$name::sync::decompress(&compressed), input);
+                        assert_eq!(encoder.total_in(), input.len() as u64);
+                    }
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn bufread_decompress() {
+                    for input in inputs() {
+                        let compressed = utils::$name::sync::compress(&input);
+                        let reader = BufReader::with_capacity(1024, Trickle::new(&compressed, 1000));
+                        let mut decoder = bufread::$decoder::new(reader);
+
+                        // Read with a tiny buffer to exercise the `ReadBuf` handling
+                        let mut output = Vec::new();
+                        let mut buf = [0; 3];
+                        loop {
+                            match block_on(decoder.read(&mut buf)).unwrap() {
+                                0 => break,
+                                len => output.extend_from_slice(&buf[..len]),
+                            }
+                        }
+                        assert_eq!(output, input);
+                        assert_eq!(decoder.total_out(), input.len() as u64);
+                    }
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn write_compress() {
+                    for input in inputs() {
+                        let mut encoder = write::$encoder::new(TrickleWriter::new(10), $($arg),*);
+                        block_on(async {
+                            for chunk in input.chunks(1000) {
+                                encoder.write_all(chunk).await?;
+                            }
+                            encoder.shutdown().await
+                        })
+                        .unwrap();
+                        let writer = encoder.into_inner();
+                        assert!(writer.shutdown);
+                        assert_eq!(utils::$name::sync::decompress(&writer.data), input);
+                    }
+                }
+
+                #[test]
+                #[ntest::timeout(5000)]
+                fn write_decompress() {
+                    for input in inputs() {
+                        let compressed = utils::$name::sync::compress(&input);
+                        let mut decoder = write::$decoder::new(TrickleWriter::new(10));
+                        block_on(async {
+                            for chunk in compressed.chunks(1000) {
+                                decoder.write_all(chunk).await?;
+                            }
+                            decoder.shutdown().await
+                        })
+                        .unwrap();
+                        let writer = decoder.into_inner();
+                        assert!(writer.shutdown);
+                        assert_eq!(writer.data, input);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+tokio_tests! {
+    brotli: BrotliEncoder(1) BrotliDecoder;
+    bzip: BzEncoder(async_compression::bzip2::Compression::Fastest) BzDecoder;
+    deflate: DeflateEncoder(async_compression::flate2::Compression::fast()) DeflateDecoder;
+    gzip: GzipEncoder(async_compression::flate2::Compression::fast()) GzipDecoder;
+    identity: IdentityEncoder() IdentityDecoder;
+    lz4: Lz4Encoder(0) Lz4Decoder;
+    lzma: LzmaEncoder(1) LzmaDecoder;
+    snappy: SnappyEncoder() SnappyDecoder;
+    xz: XzEncoder(1) XzDecoder;
+    zlib: ZlibEncoder(async_compression::flate2::Compression::fast()) ZlibDecoder;
+    zstd: ZstdEncoder(1) ZstdDecoder;
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn auto_decoder() {
+    use async_compression::{tokio::bufread, Format};
+    use tokio_crate::io::AsyncReadExt;
+
+    let compressed = utils::zstd::sync::compress(b"hello tokio");
+    let mut decoder = bufread::AutoDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"hello tokio");
+    assert_eq!(decoder.detected_format(), Some(Format::Zstd));
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn bufread_decoder_lines() {
+    use async_compression::tokio::bufread::GzipDecoder;
+    use tokio_crate::io::{AsyncBufReadExt, BufReader};
+
+    let text: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+    let compressed = utils::gzip::sync::compress(text.as_bytes());
+
+    let reader = BufReader::with_capacity(7, Trickle::new(&compressed, 5));
+    let mut lines = GzipDecoder::new(reader).lines();
+    let mut output = Vec::new();
+    while let Some(line) = block_on(lines.next_line()).unwrap() {
+        output.push(line);
+    }
+    assert_eq!(output, text.lines().collect::<Vec<_>>());
+}
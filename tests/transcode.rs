@@ -0,0 +1,79 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Tracks the current and peak number of allocated bytes, this test is alone in its binary so
+/// nothing else allocates concurrently.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn pattern(len: usize) -> impl Iterator<Item = u8> {
+    (0..len).map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+}
+
+#[test]
+fn gzip_to_zstd_bounded_memory() {
+    use async_compression::bufread::{GzipDecoder, ZstdEncoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, BufReader, Cursor},
+    };
+    use std::io::{Read, Write};
+
+    const LEN: usize = 16 * 1024 * 1024;
+
+    let compressed = {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let chunk: Vec<u8> = pattern(LEN).collect();
+        encoder.write_all(&chunk).unwrap();
+        encoder.finish().unwrap()
+    };
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+
+    let mut transcoder = ZstdEncoder::new(
+        BufReader::new(GzipDecoder::new(Cursor::new(&compressed[..]))),
+        3,
+    );
+    let mut output = Vec::new();
+    block_on(transcoder.read_to_end(&mut output)).unwrap();
+    drop(transcoder);
+
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    // The transcode must only need its fixed size buffers (plus the small compressed output), not
+    // anything proportional to the 16 MiB of content passing through it. libzstd allocates its
+    // fixed size contexts through the C allocator so they are not counted here.
+    assert!(peak < 1024 * 1024, "peak memory {} bytes", peak);
+
+    let mut decoded = Vec::new();
+    libzstd::stream::read::Decoder::new(&output[..])
+        .unwrap()
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded.len(), LEN);
+    assert!(decoded.into_iter().eq(pattern(LEN)));
+}
@@ -175,6 +175,22 @@ pub mod brotli {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::BrotliEncoder;
+            pin_mut!(input);
+            async_read_to_vec(BrotliEncoder::new(input, 1))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::BrotliDecoder;
+            pin_mut!(input);
+            async_read_to_vec(BrotliDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -237,6 +253,22 @@ pub mod bzip {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::{bzip2::Compression, read::BzEncoder};
+            pin_mut!(input);
+            async_read_to_vec(BzEncoder::new(input, Compression::Fastest))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::BzDecoder;
+            pin_mut!(input);
+            async_read_to_vec(BzDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -303,6 +335,22 @@ pub mod deflate {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::{flate2::Compression, read::DeflateEncoder};
+            pin_mut!(input);
+            async_read_to_vec(DeflateEncoder::new(input, Compression::fast()))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::DeflateDecoder;
+            pin_mut!(input);
+            async_read_to_vec(DeflateDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -322,6 +370,403 @@ pub mod deflate {
     }
 }
 
+pub mod identity {
+    pub mod sync {
+        pub fn compress(bytes: &[u8]) -> Vec<u8> {
+            bytes.to_vec()
+        }
+
+        pub fn decompress(bytes: &[u8]) -> Vec<u8> {
+            bytes.to_vec()
+        }
+    }
+
+    pub mod stream {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::IdentityEncoder;
+            pin_mut!(input);
+            stream_to_vec(IdentityEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::IdentityDecoder;
+            pin_mut!(input);
+            stream_to_vec(IdentityDecoder::new(input))
+        }
+    }
+
+    pub mod bufread {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::IdentityEncoder;
+            pin_mut!(input);
+            async_read_to_vec(IdentityEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::IdentityDecoder;
+            pin_mut!(input);
+            async_read_to_vec(IdentityDecoder::new(input))
+        }
+    }
+
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::IdentityEncoder;
+            pin_mut!(input);
+            async_read_to_vec(IdentityEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::IdentityDecoder;
+            pin_mut!(input);
+            async_read_to_vec(IdentityDecoder::new(input))
+        }
+    }
+
+    pub mod write {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::IdentityEncoder;
+            async_write_to_vec(input, |input| Box::pin(IdentityEncoder::new(input)), limit)
+        }
+
+        pub fn decompress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::IdentityDecoder;
+            async_write_to_vec(input, |input| Box::pin(IdentityDecoder::new(input)), limit)
+        }
+    }
+}
+
+pub mod lz4 {
+    pub mod sync {
+        use crate::utils::prelude::*;
+
+        pub fn compress(bytes: &[u8]) -> Vec<u8> {
+            use liblz4::EncoderBuilder;
+            use std::io::Write;
+            let mut encoder = EncoderBuilder::new().build(Vec::new()).unwrap();
+            encoder.write_all(bytes).unwrap();
+            let (output, result) = encoder.finish();
+            result.unwrap();
+            output
+        }
+
+        pub fn decompress(bytes: &[u8]) -> Vec<u8> {
+            use liblz4::Decoder;
+            read_to_vec(Decoder::new(bytes).unwrap())
+        }
+    }
+
+    pub mod stream {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::Lz4Encoder;
+            pin_mut!(input);
+            stream_to_vec(Lz4Encoder::new(input, 0))
+        }
+
+        pub fn decompress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::Lz4Decoder;
+            pin_mut!(input);
+            stream_to_vec(Lz4Decoder::new(input))
+        }
+    }
+
+    pub mod bufread {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::Lz4Encoder;
+            pin_mut!(input);
+            async_read_to_vec(Lz4Encoder::new(input, 0))
+        }
+
+        pub fn decompress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::Lz4Decoder;
+            pin_mut!(input);
+            async_read_to_vec(Lz4Decoder::new(input))
+        }
+    }
+
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::Lz4Encoder;
+            pin_mut!(input);
+            async_read_to_vec(Lz4Encoder::new(input, 0))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::Lz4Decoder;
+            pin_mut!(input);
+            async_read_to_vec(Lz4Decoder::new(input))
+        }
+    }
+
+    pub mod write {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::Lz4Encoder;
+            async_write_to_vec(input, |input| Box::pin(Lz4Encoder::new(input, 0)), limit)
+        }
+
+        pub fn decompress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::Lz4Decoder;
+            async_write_to_vec(input, |input| Box::pin(Lz4Decoder::new(input)), limit)
+        }
+    }
+}
+
+pub mod lzma {
+    pub mod sync {
+        use crate::utils::prelude::*;
+
+        pub fn compress(bytes: &[u8]) -> Vec<u8> {
+            use xz2::{
+                bufread::XzEncoder,
+                stream::{LzmaOptions, Stream},
+            };
+            let options = LzmaOptions::new_preset(6).unwrap();
+            let stream = Stream::new_lzma_encoder(&options).unwrap();
+            read_to_vec(XzEncoder::new_stream(bytes, stream))
+        }
+
+        pub fn decompress(bytes: &[u8]) -> Vec<u8> {
+            use xz2::{bufread::XzDecoder, stream::Stream};
+            let stream = Stream::new_lzma_decoder(u64::MAX).unwrap();
+            read_to_vec(XzDecoder::new_stream(bytes, stream))
+        }
+    }
+
+    pub mod stream {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::LzmaEncoder;
+            pin_mut!(input);
+            stream_to_vec(LzmaEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::LzmaDecoder;
+            pin_mut!(input);
+            stream_to_vec(LzmaDecoder::new(input))
+        }
+    }
+
+    pub mod bufread {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::LzmaEncoder;
+            pin_mut!(input);
+            async_read_to_vec(LzmaEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::LzmaDecoder;
+            pin_mut!(input);
+            async_read_to_vec(LzmaDecoder::new(input))
+        }
+    }
+
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::LzmaEncoder;
+            pin_mut!(input);
+            async_read_to_vec(LzmaEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::LzmaDecoder;
+            pin_mut!(input);
+            async_read_to_vec(LzmaDecoder::new(input))
+        }
+    }
+
+    pub mod write {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::LzmaEncoder;
+            async_write_to_vec(input, |input| Box::pin(LzmaEncoder::new(input, 6)), limit)
+        }
+
+        pub fn decompress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::LzmaDecoder;
+            async_write_to_vec(input, |input| Box::pin(LzmaDecoder::new(input)), limit)
+        }
+    }
+}
+
+pub mod snappy {
+    pub mod sync {
+        use crate::utils::prelude::*;
+
+        pub fn compress(bytes: &[u8]) -> Vec<u8> {
+            use snap::read::FrameEncoder;
+            read_to_vec(FrameEncoder::new(bytes))
+        }
+
+        pub fn decompress(bytes: &[u8]) -> Vec<u8> {
+            use snap::read::FrameDecoder;
+            read_to_vec(FrameDecoder::new(bytes))
+        }
+    }
+
+    pub mod stream {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::SnappyEncoder;
+            pin_mut!(input);
+            stream_to_vec(SnappyEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::SnappyDecoder;
+            pin_mut!(input);
+            stream_to_vec(SnappyDecoder::new(input))
+        }
+    }
+
+    pub mod bufread {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::SnappyEncoder;
+            pin_mut!(input);
+            async_read_to_vec(SnappyEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::SnappyDecoder;
+            pin_mut!(input);
+            async_read_to_vec(SnappyDecoder::new(input))
+        }
+    }
+
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::SnappyEncoder;
+            pin_mut!(input);
+            async_read_to_vec(SnappyEncoder::new(input))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::SnappyDecoder;
+            pin_mut!(input);
+            async_read_to_vec(SnappyDecoder::new(input))
+        }
+    }
+
+    pub mod write {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::SnappyEncoder;
+            async_write_to_vec(input, |input| Box::pin(SnappyEncoder::new(input)), limit)
+        }
+
+        pub fn decompress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::SnappyDecoder;
+            async_write_to_vec(input, |input| Box::pin(SnappyDecoder::new(input)), limit)
+        }
+    }
+}
+
+pub mod xz {
+    pub mod sync {
+        use crate::utils::prelude::*;
+
+        pub fn compress(bytes: &[u8]) -> Vec<u8> {
+            use xz2::bufread::XzEncoder;
+            read_to_vec(XzEncoder::new(bytes, 6))
+        }
+
+        pub fn decompress(bytes: &[u8]) -> Vec<u8> {
+            use xz2::bufread::XzDecoder;
+            read_to_vec(XzDecoder::new(bytes))
+        }
+    }
+
+    pub mod stream {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::XzEncoder;
+            pin_mut!(input);
+            stream_to_vec(XzEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+            use async_compression::stream::XzDecoder;
+            pin_mut!(input);
+            stream_to_vec(XzDecoder::new(input))
+        }
+    }
+
+    pub mod bufread {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::XzEncoder;
+            pin_mut!(input);
+            async_read_to_vec(XzEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl AsyncBufRead) -> Vec<u8> {
+            use async_compression::bufread::XzDecoder;
+            pin_mut!(input);
+            async_read_to_vec(XzDecoder::new(input))
+        }
+    }
+
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::XzEncoder;
+            pin_mut!(input);
+            async_read_to_vec(XzEncoder::new(input, 6))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::XzDecoder;
+            pin_mut!(input);
+            async_read_to_vec(XzDecoder::new(input))
+        }
+    }
+
+    pub mod write {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::XzEncoder;
+            async_write_to_vec(input, |input| Box::pin(XzEncoder::new(input, 6)), limit)
+        }
+
+        pub fn decompress(input: &[Vec<u8>], limit: usize) -> Vec<u8> {
+            use async_compression::write::XzDecoder;
+            async_write_to_vec(input, |input| Box::pin(XzDecoder::new(input)), limit)
+        }
+    }
+}
+
 pub mod zlib {
     pub mod sync {
         use crate::utils::prelude::*;
@@ -369,6 +814,22 @@ pub mod zlib {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::{flate2::Compression, read::ZlibEncoder};
+            pin_mut!(input);
+            async_read_to_vec(ZlibEncoder::new(input, Compression::fast()))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::ZlibDecoder;
+            pin_mut!(input);
+            async_read_to_vec(ZlibDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -435,6 +896,22 @@ pub mod gzip {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::{flate2::Compression, read::GzipEncoder};
+            pin_mut!(input);
+            async_read_to_vec(GzipEncoder::new(input, Compression::fast()))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::GzipDecoder;
+            pin_mut!(input);
+            async_read_to_vec(GzipDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -502,6 +979,22 @@ pub mod zstd {
         }
     }
 
+    pub mod read {
+        use crate::utils::prelude::*;
+
+        pub fn compress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::ZstdEncoder;
+            pin_mut!(input);
+            async_read_to_vec(ZstdEncoder::new(input, 0))
+        }
+
+        pub fn decompress(input: impl AsyncRead) -> Vec<u8> {
+            use async_compression::read::ZstdDecoder;
+            pin_mut!(input);
+            async_read_to_vec(ZstdDecoder::new(input))
+        }
+    }
+
     pub mod write {
         use crate::utils::prelude::*;
 
@@ -754,6 +1247,135 @@ macro_rules! test_cases {
         }
     };
 
+    (@ [ $variant:ident :: read :: compress ]) => {
+        mod compress {
+            use crate::utils;
+            use std::iter::FromIterator;
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn empty() {
+                let mut input: &[u8] = &[];
+                let compressed = utils::$variant::read::compress(&mut input);
+                let output = utils::$variant::sync::decompress(&compressed);
+
+                assert_eq!(output, &[][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn empty_chunk() {
+                let input = utils::InputStream::from(vec![vec![]]);
+
+                let compressed = utils::$variant::read::compress(input.reader());
+                let output = utils::$variant::sync::decompress(&compressed);
+
+                assert_eq!(output, input.bytes());
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn short() {
+                let input = utils::InputStream::from([[1, 2, 3], [4, 5, 6]]);
+
+                let compressed = utils::$variant::read::compress(input.reader());
+                let output = utils::$variant::sync::decompress(&compressed);
+
+                assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn long() {
+                let input = vec![
+                    Vec::from_iter((0..32_768).map(|_| rand::random())),
+                    Vec::from_iter((0..32_768).map(|_| rand::random())),
+                ];
+                let input = utils::InputStream::from(input);
+
+                let compressed = utils::$variant::read::compress(input.reader());
+                let output = utils::$variant::sync::decompress(&compressed);
+
+                assert_eq!(output, input.bytes());
+            }
+        }
+    };
+
+    (@ [ $variant:ident :: read :: decompress ]) => {
+        mod decompress {
+            use crate::utils;
+            use std::iter::FromIterator;
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn empty() {
+                let compressed = utils::$variant::sync::compress(&[]);
+
+                let stream = utils::InputStream::from(vec![compressed]);
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, &[][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn zeros() {
+                let compressed = utils::$variant::sync::compress(&[0; 10]);
+
+                let stream = utils::InputStream::from(vec![compressed]);
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, &[0; 10][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn short() {
+                let compressed = utils::$variant::sync::compress(&[1, 2, 3, 4, 5, 6]);
+
+                let stream = utils::InputStream::from(vec![compressed]);
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn short_chunks() {
+                let compressed = utils::$variant::sync::compress(&[1, 2, 3, 4, 5, 6]);
+
+                let stream = utils::InputStream::from(compressed.chunks(2).map(Vec::from).collect::<Vec<_>>());
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn long() {
+                let input = Vec::from_iter((0..65_536).map(|_| rand::random()));
+                let compressed = utils::$variant::sync::compress(&input);
+
+                let stream = utils::InputStream::from(vec![compressed]);
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, input);
+            }
+
+            #[test]
+            #[ntest::timeout(1000)]
+            fn long_chunks() {
+                let input = Vec::from_iter((0..65_536).map(|_| rand::random()));
+                let compressed = utils::$variant::sync::compress(&input);
+
+                let stream = utils::InputStream::from(compressed.chunks(1024).map(Vec::from).collect::<Vec<_>>());
+                let output = utils::$variant::read::decompress(stream.reader());
+
+                assert_eq!(output, input);
+            }
+        }
+    };
+
     (@ [ $variant:ident :: write :: compress ]) => {
         mod compress {
             use crate::utils;
@@ -940,6 +1562,7 @@ macro_rules! test_cases {
     ($variant:ident) => {
         test_cases!($variant::{
             bufread::{compress, decompress},
+            read::{compress, decompress},
             stream::{compress, decompress},
             write::{compress, decompress},
         });
@@ -0,0 +1,193 @@
+#[macro_use]
+mod utils;
+
+test_cases!(xz);
+
+/// Input that is not too repetitive, so each block compresses to a fair amount of output.
+fn text(len: usize) -> Vec<u8> {
+    (0..len as u32)
+        .map(|i| ((i % 251) ^ (i / 1000)) as u8)
+        .collect()
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn xz_bufread_decompress_multiple_blocks() {
+    use async_compression::bufread::XzDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::Read;
+
+    let input = text(200_000);
+
+    // Splitting into blocks of 16 KiB gives a container with many blocks in its index
+    let stream = xz2::stream::MtStreamBuilder::new()
+        .threads(1)
+        .block_size(16 * 1024)
+        .preset(6)
+        .encoder()
+        .unwrap();
+    let mut compressed = Vec::new();
+    xz2::read::XzEncoder::new_stream(&input[..], stream)
+        .read_to_end(&mut compressed)
+        .unwrap();
+
+    let mut output = Vec::new();
+    block_on(XzDecoder::new(&compressed[..]).read_to_end(&mut output)).unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn xz_decompress_truncated() {
+    use async_compression::bufread::XzDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::xz::sync::compress(&text(10_000));
+
+    // Cut off in the data, and in the index and stream footer that follow it
+    for len in &[
+        compressed.len() / 2,
+        compressed.len() - 10,
+        compressed.len() - 1,
+    ] {
+        let err =
+            block_on(XzDecoder::new(&compressed[..*len]).read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof, "{}", len);
+    }
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn xz_extreme_preset() {
+    use async_compression::bufread::XzEncoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = text(100_000);
+
+    let mut compressed = Vec::new();
+    block_on(XzEncoder::with_extreme(&input[..], 9).read_to_end(&mut compressed)).unwrap();
+    assert_eq!(utils::xz::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn xz_decompress_corrupt() {
+    use async_compression::{bufread::XzDecoder, BackendError, Format};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut compressed = utils::xz::sync::compress(&text(10_000));
+    let middle = compressed.len() / 2;
+    compressed[middle] ^= 0xff;
+
+    let err = block_on(XzDecoder::new(&compressed[..]).read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<BackendError>()
+        .unwrap();
+    assert_eq!(err.format(), Format::Xz);
+}
+
+/// Compresses the data with the `xz` command line tool, in blocks so the output has an index.
+#[cfg(feature = "test-xz-cli")]
+fn xz_cli_compress(data: &[u8]) -> Vec<u8> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let mut child = Command::new("xz")
+        .args(["-c", "--block-size=32KiB"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the xz command line tool must be installed for this test");
+    let mut stdin = child.stdin.take().unwrap();
+    let data = data.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&data).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(
+        output.status.success(),
+        "xz failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+#[test]
+#[cfg(feature = "test-xz-cli")]
+fn xz_cli_output_decompresses() {
+    let inputs = vec![vec![], vec![1, 2, 3, 4, 5, 6], text(200_000)];
+
+    for input in &inputs {
+        let compressed = xz_cli_compress(input);
+        let stream =
+            utils::InputStream::from(compressed.chunks(1024).map(Vec::from).collect::<Vec<_>>());
+        assert_eq!(&utils::xz::bufread::decompress(stream.reader()), input);
+        assert_eq!(&utils::xz::stream::decompress(stream.stream()), input);
+    }
+}
+
+#[test]
+#[ntest::timeout(20000)]
+fn xz_threads_deterministic() {
+    use async_compression::write::XzEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let input = text(3 << 20);
+
+    // The blocks are cut at the same offsets and written in order whatever the threads do
+    let compress = |threads| {
+        let mut encoder = XzEncoder::with_threads(Vec::new(), 1, threads, 1 << 20).unwrap();
+        for chunk in input.chunks(100_000) {
+            block_on(encoder.write_all(chunk)).unwrap();
+        }
+        block_on(encoder.close()).unwrap();
+        encoder.into_inner()
+    };
+
+    let compressed = compress(4);
+    assert_eq!(compressed, compress(4));
+    assert_eq!(compressed, compress(2));
+    assert_eq!(utils::xz::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn xz_threads_flush() {
+    use async_compression::{bufread::XzDecoder, write::XzEncoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    // A flush ends the block, so everything before it can be decoded
+    let mut encoder = XzEncoder::with_threads(Vec::new(), 1, 2, 0).unwrap();
+    block_on(encoder.write_all(b"first message\n")).unwrap();
+    block_on(encoder.flush()).unwrap();
+    let mut output = vec![0; 14];
+    block_on(XzDecoder::new(&encoder.get_ref()[..]).read_exact(&mut output)).unwrap();
+    assert_eq!(output, b"first message\n");
+
+    block_on(encoder.write_all(b"second message\n")).unwrap();
+    block_on(encoder.close()).unwrap();
+    assert_eq!(
+        utils::xz::sync::decompress(encoder.get_ref()),
+        b"first message\nsecond message\n"
+    );
+}
+
+#[test]
+fn xz_threads_out_of_range() {
+    use async_compression::write::XzEncoder;
+
+    for &threads in &[0, 16385] {
+        let err = XzEncoder::with_threads(Vec::new(), 6, threads, 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -2,3 +2,460 @@
 mod utils;
 
 test_cases!(zlib);
+
+fn compress(data: &[u8], level: flate2::Compression) -> Vec<u8> {
+    use flate2::bufread::ZlibEncoder;
+    use std::io::Read;
+
+    let mut output = Vec::new();
+    ZlibEncoder::new(data, level)
+        .read_to_end(&mut output)
+        .unwrap();
+    output
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_stream_decompress_was_stored() {
+    use async_compression::stream::ZlibDecoder;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let compressed = compress(&[1, 2, 3, 4, 5, 6], flate2::Compression::none());
+
+    // Split the input so that the block header straddles chunks
+    let input = utils::InputStream::from(compressed.chunks(2).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = ZlibDecoder::new(input.stream());
+    let output = block_on((&mut decoder).try_concat()).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(decoder.was_stored(), Some(true));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_stream_decompress_was_not_stored() {
+    use async_compression::stream::ZlibDecoder;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let compressed = compress(&[1, 2, 3, 4, 5, 6], flate2::Compression::fast());
+
+    let input = utils::InputStream::from(vec![compressed]);
+    let mut decoder = ZlibDecoder::new(input.stream());
+    let output = block_on((&mut decoder).try_concat()).unwrap();
+
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    assert_eq!(decoder.was_stored(), Some(false));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_bufread_into_inner_after_stream_end() {
+    use async_compression::bufread::ZlibDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::zlib::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    input.extend_from_slice(b"trailer");
+
+    let stream = utils::InputStream::from(input.chunks(3).map(Vec::from).collect::<Vec<_>>());
+    let mut decoder = ZlibDecoder::new(stream.reader());
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+
+    // Nothing past the end of the compressed stream was consumed
+    let mut reader = decoder.into_inner();
+    let mut rest = Vec::new();
+    block_on(reader.read_to_end(&mut rest)).unwrap();
+    assert_eq!(rest, b"trailer");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_dictionary() {
+    use async_compression::{bufread, flate2::Compression, stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let dictionary = b"the quick brown fox jumps over the lazy dog";
+    let input = b"the lazy dog jumps over the quick brown fox".to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::ZlibEncoder::with_dictionary(&input[..], Compression::default(), dictionary)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    // The header sets FDICT and is followed by the dictionary's Adler-32
+    assert_ne!(compressed[1] & 0b0010_0000, 0);
+    assert!(compressed.len() < compress(&input, Compression::default()).len());
+
+    let mut output = Vec::new();
+    block_on(
+        bufread::ZlibDecoder::with_dictionary(&compressed[..], dictionary)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+
+    let mut encoder =
+        write::ZlibEncoder::with_dictionary(Vec::new(), Compression::default(), dictionary)
+            .unwrap();
+    block_on(encoder.write_all(&input)).unwrap();
+    block_on(encoder.close()).unwrap();
+    let mut decoder = write::ZlibDecoder::with_dictionary(Vec::new(), dictionary).unwrap();
+    block_on(decoder.write_all(&encoder.into_inner())).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    let source = futures::stream::iter(vec![Ok(Bytes::from(input.clone()))]);
+    let chunks: Vec<Bytes> = block_on(
+        stream::ZlibEncoder::with_dictionary(source, Compression::default(), dictionary)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    // Split the input so that the header and dictionary id straddle chunks
+    let source =
+        utils::InputStream::from(chunks.concat().chunks(1).map(Vec::from).collect::<Vec<_>>());
+    let chunks: Vec<Bytes> = block_on(
+        stream::ZlibDecoder::with_dictionary(source.stream(), dictionary)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(chunks.concat(), input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_dictionary_mismatch() {
+    use async_compression::{bufread, flate2::Compression};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    let dictionary = b"the quick brown fox jumps over the lazy dog";
+    let input = b"the lazy dog jumps over the quick brown fox".to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::ZlibEncoder::with_dictionary(&input[..], Compression::default(), dictionary)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    let decode = |compressed: &[u8], dictionary: Option<&[u8]>| {
+        let mut output = Vec::new();
+        let result = match dictionary {
+            Some(dictionary) => block_on(
+                bufread::ZlibDecoder::with_dictionary(compressed, dictionary)
+                    .unwrap()
+                    .read_to_end(&mut output),
+            ),
+            None => block_on(bufread::ZlibDecoder::new(compressed).read_to_end(&mut output)),
+        };
+        result.unwrap_err().kind()
+    };
+
+    // The wrong dictionary
+    assert_eq!(
+        decode(
+            &compressed,
+            Some(b"pack my box with five dozen liquor jugs")
+        ),
+        ErrorKind::InvalidData
+    );
+    // No dictionary
+    assert_eq!(decode(&compressed, None), ErrorKind::InvalidData);
+    // A dictionary for a stream compressed without one
+    let plain = compress(&input, Compression::default());
+    assert_eq!(decode(&plain, Some(dictionary)), ErrorKind::InvalidData);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_dictionary_adler32() {
+    use async_compression::{bufread, flate2::Compression, stream, write};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    fn adler32(data: &[u8]) -> u32 {
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in data {
+            a = (a + u32::from(byte)) % 65521;
+            b = (b + a) % 65521;
+        }
+        (b << 16) | a
+    }
+
+    let dictionary = b"the quick brown fox jumps over the lazy dog";
+    let input = b"the lazy dog jumps over the quick brown fox".to_vec();
+
+    let mut encoder =
+        bufread::ZlibEncoder::with_dictionary(&input[..], Compression::default(), dictionary)
+            .unwrap();
+    assert_eq!(encoder.dictionary_adler32(), Some(adler32(dictionary)));
+
+    // It is the dictionary id written to the header
+    let mut compressed = Vec::new();
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+    assert_eq!(
+        u32::from_be_bytes([compressed[2], compressed[3], compressed[4], compressed[5]]),
+        adler32(dictionary)
+    );
+
+    let encoder =
+        write::ZlibEncoder::with_dictionary(Vec::new(), Compression::default(), dictionary)
+            .unwrap();
+    assert_eq!(encoder.dictionary_adler32(), Some(adler32(dictionary)));
+
+    let source = futures::stream::iter(Vec::<std::io::Result<bytes::Bytes>>::new());
+    let encoder =
+        stream::ZlibEncoder::with_dictionary(source, Compression::default(), dictionary).unwrap();
+    assert_eq!(encoder.dictionary_adler32(), Some(adler32(dictionary)));
+
+    assert_eq!(
+        bufread::ZlibEncoder::new(&input[..], Compression::default()).dictionary_adler32(),
+        None
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_wrap_round_trip() {
+    use async_compression::{
+        bufread,
+        flate2::{Compression, Wrap},
+        stream, write,
+    };
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let input = b"the quick brown fox jumps over the lazy dog".repeat(10);
+
+    #[allow(unused_mut)]
+    let mut wraps = vec![(Wrap::Raw, None), (Wrap::Zlib, Some(&[0x78][..]))];
+    #[cfg(feature = "gzip")]
+    wraps.push((Wrap::Gzip, Some(&[0x1f, 0x8b][..])));
+
+    for (wrap, header) in wraps {
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::ZlibEncoder::with_wrap(&input[..], Compression::default(), wrap)
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        match header {
+            Some(header) => assert!(compressed.starts_with(header), "{:?}", wrap),
+            None => assert_eq!(utils::deflate::sync::decompress(&compressed), input),
+        }
+
+        let mut output = Vec::new();
+        block_on(bufread::ZlibDecoder::with_wrap(&compressed[..], wrap).read_to_end(&mut output))
+            .unwrap();
+        assert_eq!(output, input, "{:?}", wrap);
+
+        let mut encoder = write::ZlibEncoder::with_wrap(Vec::new(), Compression::default(), wrap);
+        block_on(encoder.write_all(&input)).unwrap();
+        block_on(encoder.close()).unwrap();
+        let mut decoder = write::ZlibDecoder::with_wrap(Vec::new(), wrap);
+        block_on(decoder.write_all(&encoder.into_inner())).unwrap();
+        block_on(decoder.close()).unwrap();
+        assert_eq!(decoder.into_inner(), input, "{:?}", wrap);
+
+        let source = futures::stream::iter(vec![Ok(Bytes::from(input.clone()))]);
+        let chunks: Vec<Bytes> = block_on(
+            stream::ZlibEncoder::with_wrap(source, Compression::default(), wrap).try_collect(),
+        )
+        .unwrap();
+        let source =
+            utils::InputStream::from(chunks.concat().chunks(1).map(Vec::from).collect::<Vec<_>>());
+        let chunks: Vec<Bytes> =
+            block_on(stream::ZlibDecoder::with_wrap(source.stream(), wrap).try_collect()).unwrap();
+        assert_eq!(chunks.concat(), input, "{:?}", wrap);
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+#[cfg(feature = "gzip")]
+fn zlib_wrap_mismatch() {
+    use async_compression::{
+        bufread,
+        flate2::{Compression, Wrap},
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = b"the quick brown fox jumps over the lazy dog".to_vec();
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::ZlibEncoder::with_wrap(&input[..], Compression::default(), Wrap::Gzip)
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    let mut output = Vec::new();
+    // A gzip header is not a valid zlib header
+    assert!(block_on(
+        bufread::ZlibDecoder::with_wrap(&compressed[..], Wrap::Zlib).read_to_end(&mut output)
+    )
+    .is_err());
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn zlib_window_bits() {
+    use async_compression::{bufread, flate2::Compression};
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::io::ErrorKind;
+
+    // Noise repeated 2000 bytes later, which only a window of more than 512 bytes can match
+    let mut x: u32 = 0x1234_5678;
+    let noise: Vec<u8> = (0..2000)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect();
+    let input = noise.repeat(2);
+
+    let compress = |window_bits: u8| {
+        let mut output = Vec::new();
+        block_on(
+            bufread::ZlibEncoder::with_window_bits(&input[..], Compression::default(), window_bits)
+                .unwrap()
+                .read_to_end(&mut output),
+        )
+        .unwrap();
+        output
+    };
+    let decompress = |compressed: &[u8], window_bits: u8| {
+        let mut output = Vec::new();
+        block_on(
+            bufread::ZlibDecoder::with_window_bits(compressed, window_bits)
+                .unwrap()
+                .read_to_end(&mut output),
+        )
+        .map(|_| output)
+    };
+
+    let small = compress(9);
+    let large = compress(15);
+    assert!(large.len() < small.len());
+
+    assert_eq!(decompress(&small, 9).unwrap(), input);
+    assert_eq!(decompress(&small, 15).unwrap(), input);
+    assert_eq!(decompress(&large, 15).unwrap(), input);
+    assert_eq!(
+        decompress(&large, 9).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+
+    assert_eq!(
+        bufread::ZlibEncoder::with_window_bits(&input[..], Compression::default(), 8)
+            .err()
+            .unwrap()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        bufread::ZlibDecoder::with_window_bits(&large[..], 16)
+            .err()
+            .unwrap()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_bufread_stats() {
+    use async_compression::bufread::{ZlibDecoder, ZlibEncoder};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut encoder = ZlibEncoder::new(&b"the quick brown fox"[..], flate2::Compression::default());
+    let mut compressed = Vec::new();
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+    // Adler-32 of the input, as given by zlib's own implementation
+    assert_eq!(encoder.stats().checksum, Some(0x478e_0734));
+    assert_eq!(encoder.stats().blocks, None);
+
+    let mut decoder = ZlibDecoder::new(&compressed[..]);
+    assert_eq!(decoder.stats().window_size, None);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    let stats = decoder.stats();
+    assert_eq!(stats.checksum, Some(0x478e_0734));
+    assert_eq!(stats.blocks, Some(1));
+    assert_eq!(stats.window_size, Some(32 * 1024));
+
+    // The window comes from the header
+    let mut encoder = ZlibEncoder::with_window_bits(
+        &b"the quick brown fox"[..],
+        flate2::Compression::default(),
+        12,
+    )
+    .unwrap();
+    let mut compressed = Vec::new();
+    block_on(encoder.read_to_end(&mut compressed)).unwrap();
+    let mut decoder = ZlibDecoder::new(&compressed[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    assert_eq!(decoder.stats().window_size, Some(4 * 1024));
+
+    // Each sync flush ends the block, and adds an empty stored block, then finishing adds the
+    // final block
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    for _ in 0..3 {
+        std::io::Write::write_all(&mut encoder, b"the quick brown fox").unwrap();
+        std::io::Write::flush(&mut encoder).unwrap();
+    }
+    let compressed = encoder.finish().unwrap();
+    let mut decoder = ZlibDecoder::new(&compressed[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    assert_eq!(decoder.stats().blocks, Some(7));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zlib_bufread_decompress_checksum() {
+    use async_compression::bufread::ZlibDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::zlib::sync::compress(b"the quick brown fox");
+
+    let mut decoder = ZlibDecoder::new(&compressed[..]);
+    assert_eq!(decoder.declared_checksum(), None);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(output, b"the quick brown fox");
+    // Adler-32 of the output, as given by zlib's own implementation
+    assert_eq!(decoder.checksum(), 0x478e_0734);
+    assert_eq!(decoder.declared_checksum(), Some(decoder.checksum()));
+
+    // Enough output of large bytes to need the sums reduced several times
+    let large = utils::zlib::sync::compress(&[0xff; 100_000]);
+    let mut decoder = ZlibDecoder::new(&large[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    assert_eq!(decoder.declared_checksum(), Some(decoder.checksum()));
+
+    let mut corrupted = compressed.clone();
+    *corrupted.last_mut().unwrap() ^= 0xff;
+
+    let mut decoder = ZlibDecoder::new(&corrupted[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap_err();
+    assert_eq!(decoder.declared_checksum(), Some(decoder.checksum() ^ 0xff));
+}
@@ -2,3 +2,1087 @@
 mod utils;
 
 test_cases!(zstd);
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_bufread_decompress_was_stored() {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::iter::FromIterator;
+
+    // Random data is incompressible so zstd will fall back to raw blocks
+    let input = Vec::from_iter((0..16_384).map(|_| rand::random()));
+    let compressed = utils::zstd::sync::compress(&input);
+
+    let mut decoder = ZstdDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, input);
+    assert_eq!(decoder.was_stored(), Some(true));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_bufread_decompress_was_not_stored() {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::zstd::sync::compress(&[0; 16_384]);
+
+    let mut decoder = ZstdDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    assert_eq!(output, &[0; 16_384][..]);
+    assert_eq!(decoder.was_stored(), Some(false));
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_bufread_decompress_passthrough() {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = utils::zstd::sync::compress(&[1, 2, 3, 4, 5, 6]);
+    for input in [vec![compressed], vec![vec![1, 2, 3], vec![4, 5, 6]]]
+        .iter()
+        .cloned()
+    {
+        let stream = utils::InputStream::from(input);
+        let mut decoder = ZstdDecoder::new(stream.reader()).with_passthrough(true);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, &[1, 2, 3, 4, 5, 6][..]);
+    }
+}
+
+/// Two independently compressed frames with skippable frames before, between and after them, as
+/// produced by e.g. concatenating the output of separate `zstd -c` runs.
+fn multi_frame() -> Vec<u8> {
+    let skippable = |magic: u8, content: &[u8]| {
+        let mut frame = vec![magic, 0x2a, 0x4d, 0x18];
+        frame.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        frame.extend_from_slice(content);
+        frame
+    };
+
+    let mut compressed = skippable(0x50, b"metadata");
+    compressed.extend(utils::zstd::sync::compress(b"hello "));
+    compressed.extend(skippable(0x5f, b""));
+    compressed.extend(utils::zstd::sync::compress(b"world"));
+    compressed.extend(skippable(0x53, &[0; 100]));
+    compressed
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_decompress_multiple_frames() {
+    let compressed = multi_frame();
+
+    for chunk_size in &[1, 7, compressed.len()] {
+        let input = utils::InputStream::from(
+            compressed
+                .chunks(*chunk_size)
+                .map(Vec::from)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            utils::zstd::stream::decompress(input.stream()),
+            b"hello world"
+        );
+        assert_eq!(
+            utils::zstd::bufread::decompress(input.reader()),
+            b"hello world"
+        );
+        assert_eq!(
+            utils::zstd::write::decompress(input.as_ref(), 65_536),
+            b"hello world"
+        );
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_write_decompress_short_write() {
+    use async_compression::write::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let compressed = utils::zstd::sync::compress(b"hello world");
+    let mut input = compressed.clone();
+    input.extend_from_slice(b"trailing");
+
+    let mut decoder = ZstdDecoder::new(Vec::new()).with_multiple_frames(false);
+    assert_eq!(block_on(decoder.write(&input)).unwrap(), compressed.len());
+    assert_eq!(block_on(decoder.write(b"trailing")).unwrap(), 0);
+
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), b"hello world");
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_write_finish_frame() {
+    use async_compression::write::ZstdEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let mut encoder = ZstdEncoder::new(Vec::new(), 3);
+    block_on(encoder.write_all(b"first record\n")).unwrap();
+    block_on(encoder.finish_frame()).unwrap();
+    let first_len = encoder.get_ref().len();
+    block_on(encoder.write_all(b"second record\n")).unwrap();
+    block_on(encoder.close()).unwrap();
+    let output = encoder.into_inner();
+
+    let (first, second) = output.split_at(first_len);
+    assert_eq!(utils::zstd::sync::decompress(first), b"first record\n");
+    assert_eq!(utils::zstd::sync::decompress(second), b"second record\n");
+    assert_eq!(
+        utils::zstd::sync::decompress(&output),
+        b"first record\nsecond record\n"
+    );
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_bufread_decompress_single_frame() {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let mut input = utils::zstd::sync::compress(b"hello world");
+    input.extend_from_slice(b"hello");
+
+    // Fed a byte at a time the decoder can't have read ahead of the frame either
+    for chunk_size in &[1, input.len()] {
+        let stream =
+            utils::InputStream::from(input.chunks(*chunk_size).map(Vec::from).collect::<Vec<_>>());
+        let mut decoder = ZstdDecoder::new(stream.reader()).with_multiple_frames(false);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, b"hello world");
+
+        let mut rest = Vec::new();
+        block_on(decoder.get_mut().read_to_end(&mut rest)).unwrap();
+        assert_eq!(rest, b"hello");
+    }
+
+    // By default the trailing bytes are taken for the start of another frame
+    let mut decoder = ZstdDecoder::new(&input[..]);
+    let mut output = Vec::new();
+    assert!(block_on(decoder.read_to_end(&mut output)).is_err());
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn zstd_buffer_capacity() {
+    use async_compression::{stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::AsyncWriteExt,
+        stream::{self as futures_stream, TryStreamExt},
+    };
+    use std::iter::FromIterator;
+
+    let input = Vec::from_iter((0..300_000u32).map(|i| (i % 7 * i % 13) as u8));
+
+    for &capacity in &[1, 100, 256 * 1024] {
+        let mut encoder = write::ZstdEncoder::new(Vec::new(), 0).with_buffer_capacity(capacity);
+        for chunk in input.chunks(5000) {
+            block_on(encoder.write_all(chunk)).unwrap();
+        }
+        block_on(encoder.close()).unwrap();
+        let compressed = encoder.into_inner();
+        assert_eq!(utils::zstd::sync::decompress(&compressed), input);
+
+        let mut decoder = write::ZstdDecoder::new(Vec::new()).with_buffer_capacity(capacity);
+        for chunk in compressed.chunks(333) {
+            block_on(decoder.write_all(chunk)).unwrap();
+        }
+        block_on(decoder.close()).unwrap();
+        assert_eq!(decoder.into_inner(), input);
+
+        let chunks = || {
+            futures_stream::iter(
+                input
+                    .chunks(5000)
+                    .map(|chunk| Ok(Bytes::from(chunk)))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let compressed: Vec<Bytes> = block_on(
+            stream::ZstdEncoder::new(chunks(), 0)
+                .with_buffer_capacity(capacity)
+                .try_collect(),
+        )
+        .unwrap();
+        assert!(compressed.iter().all(|chunk| chunk.len() <= capacity));
+        let compressed = compressed.concat();
+        assert_eq!(utils::zstd::sync::decompress(&compressed), input);
+
+        let output: Vec<Bytes> = block_on(
+            stream::ZstdDecoder::new(futures_stream::iter(vec![Ok(Bytes::from(compressed))]))
+                .with_buffer_capacity(capacity)
+                .try_collect(),
+        )
+        .unwrap();
+        assert!(output.iter().all(|chunk| chunk.len() <= capacity));
+        assert_eq!(output.concat(), input);
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_bufread_decompress_stats() {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let compressed = multi_frame();
+
+    let mut decoder = ZstdDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+
+    let stats = decoder.stats();
+    assert_eq!(stats.total_in, compressed.len() as u64);
+    assert_eq!(stats.total_out, output.len() as u64);
+    // Skippable frames are counted too
+    assert_eq!(stats.frames, Some(5));
+    assert_eq!(stats.checksum, None);
+    assert_eq!(stats.blocks, Some(2));
+    // Streamed without a content size, the last frame declares the 2 MiB window of level 3
+    assert_eq!(stats.window_size, Some(2 * 1024 * 1024));
+
+    // With a content checksum, it is the one stored at the end of the frame
+    let input = b"the quick brown fox jumps over the lazy dog ".repeat(100);
+    let mut context = zstd_safe::create_cctx();
+    for param in [
+        zstd_safe::CParameter::ChecksumFlag(true),
+        zstd_safe::CParameter::ContentSizeFlag(false),
+    ] {
+        zstd_safe::cctx_set_parameter(&mut context, param).unwrap();
+    }
+    let mut compressed = vec![0; zstd_safe::compress_bound(input.len())];
+    let len = zstd_safe::compress2(&mut context, &mut compressed, &input).unwrap();
+    compressed.truncate(len);
+
+    let mut decoder = ZstdDecoder::new(&compressed[..]);
+    block_on(decoder.read_to_end(&mut Vec::new())).unwrap();
+    let stats = decoder.stats();
+    let trailer = &compressed[compressed.len() - 4..];
+    assert_eq!(
+        stats.checksum,
+        Some(u32::from_le_bytes([
+            trailer[0], trailer[1], trailer[2], trailer[3]
+        ]))
+    );
+    assert_eq!(stats.blocks, Some(1));
+    // Without a content size the frame has a window descriptor, libzstd shrinks the window to
+    // the next power of two above the input
+    assert_eq!(stats.window_size, Some(8 * 1024));
+}
+
+/// Small JSON documents sharing their structure, as dictionaries are meant for.
+fn json_document(i: u32) -> Vec<u8> {
+    format!(
+        r#"{{"id":{},"name":"user-{}","email":"user{}@example.com","active":{},"roles":["reader","{}"]}}"#,
+        i,
+        i * 7919 % 10_007,
+        i,
+        i % 3 != 1,
+        ["writer", "admin"][(i % 2) as usize],
+    )
+    .into_bytes()
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn zstd_dictionary() {
+    use async_compression::{bufread, read, stream, write};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let samples: Vec<Vec<u8>> = (0..2000).map(json_document).collect();
+    let dictionary = libzstd::dict::from_samples(&samples, 4096).unwrap();
+    let input = json_document(123_456);
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::ZstdEncoder::with_dict(&input[..], 3, &dictionary)
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+
+    // The dictionary is what makes tiny documents compress at all
+    assert!(compressed.len() < utils::zstd::sync::compress(&input).len());
+
+    let mut output = Vec::new();
+    block_on(
+        bufread::ZstdDecoder::with_dict(&compressed[..], &dictionary)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+
+    let mut output = Vec::new();
+    block_on(
+        read::ZstdDecoder::with_dict(&compressed[..], &dictionary)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+
+    let mut encoder = write::ZstdEncoder::with_dict(Vec::new(), 3, &dictionary).unwrap();
+    block_on(encoder.write_all(&input)).unwrap();
+    block_on(encoder.close()).unwrap();
+    let mut decoder = write::ZstdDecoder::with_dict(Vec::new(), &dictionary).unwrap();
+    block_on(decoder.write_all(&encoder.into_inner())).unwrap();
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), input);
+
+    let source = futures::stream::iter(vec![Ok(Bytes::from(input.clone()))]);
+    let chunks: Vec<Bytes> = block_on(
+        stream::ZstdEncoder::with_dict(source, 3, &dictionary)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    let source = futures::stream::iter(vec![Ok(chunks.concat().into())]);
+    let chunks: Vec<Bytes> = block_on(
+        stream::ZstdDecoder::with_dict(source, &dictionary)
+            .unwrap()
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(chunks.concat(), input);
+
+    // Without the dictionary decoding fails instead of producing garbage
+    let mut output = Vec::new();
+    let result = block_on(bufread::ZstdDecoder::new(&compressed[..]).read_to_end(&mut output));
+    assert!(result.is_err());
+}
+
+/// Lines of an access log, a second kind of content with a structure of its own.
+fn log_line(i: u32) -> Vec<u8> {
+    format!(
+        "10.0.{}.{} - - [17/Oct/2020:13:{:02}:{:02} +0000] \"{} /api/v1/items/{} HTTP/1.1\" {} {}\n",
+        i % 256,
+        i * 31 % 256,
+        i / 60 % 60,
+        i % 60,
+        ["GET", "POST", "DELETE"][(i % 3) as usize],
+        i * 7919 % 10_007,
+        [200, 201, 404][(i % 3) as usize],
+        i * 131 % 4096,
+    )
+    .into_bytes()
+}
+
+#[test]
+#[ntest::timeout(20000)]
+fn zstd_dictionaries() {
+    use async_compression::{bufread, write, zstd::ZstdDictionaries, CompressionError, Format};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    let compress = |dictionaries: &ZstdDictionaries, key: &str, input: &[u8]| {
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::ZstdEncoder::with_dictionaries(input, 3, dictionaries, key)
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+        compressed
+    };
+
+    let json_samples: Vec<Vec<u8>> = (0..2000).map(json_document).collect();
+    let log_samples: Vec<Vec<u8>> = (0..2000).map(log_line).collect();
+    let mut dictionaries = ZstdDictionaries::new();
+    let json_id = dictionaries
+        .insert(
+            "application/json",
+            &libzstd::dict::from_samples(&json_samples, 4096).unwrap(),
+        )
+        .unwrap();
+    let log_id = dictionaries
+        .insert(
+            "text/x-log",
+            &libzstd::dict::from_samples(&log_samples, 4096).unwrap(),
+        )
+        .unwrap();
+    assert_ne!(json_id, log_id);
+
+    // Each record is compressed with the dictionary for its content type, and one of an unknown
+    // type without any
+    let records = vec![
+        ("application/json", json_document(123_456)),
+        ("text/x-log", log_line(123_456)),
+        ("text/plain", b"no dictionary for this one".to_vec()),
+        ("application/json", json_document(54_321)),
+    ];
+    let frames: Vec<Vec<u8>> = records
+        .iter()
+        .map(|(key, input)| compress(&dictionaries, key, input))
+        .collect();
+    assert_eq!(zstd_safe::get_dict_id_from_frame(&frames[0]), json_id);
+    assert_eq!(zstd_safe::get_dict_id_from_frame(&frames[1]), log_id);
+    assert_eq!(zstd_safe::get_dict_id_from_frame(&frames[2]), 0);
+    assert!(frames[1].len() < utils::zstd::sync::compress(&records[1].1).len());
+
+    // The decoder picks each frame's dictionary by the ID it records, without being told the key
+    let compressed = frames.concat();
+    let expected: Vec<u8> = records
+        .iter()
+        .flat_map(|(_, input)| input.clone())
+        .collect();
+    let mut output = Vec::new();
+    block_on(
+        bufread::ZstdDecoder::with_dictionaries(&compressed[..], &dictionaries)
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+
+    // Including when the headers are split across writes
+    let mut decoder = write::ZstdDecoder::with_dictionaries(Vec::new(), &dictionaries);
+    for byte in compressed.chunks(1) {
+        block_on(decoder.write_all(byte)).unwrap();
+    }
+    block_on(decoder.close()).unwrap();
+    assert_eq!(decoder.into_inner(), expected);
+
+    // A frame needing a dictionary the decoder wasn't given is rejected
+    let mut output = Vec::new();
+    let err = block_on(
+        bufread::ZstdDecoder::with_dictionaries(&frames[1][..], &ZstdDictionaries::new())
+            .read_to_end(&mut output),
+    )
+    .unwrap_err();
+    match err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<CompressionError>())
+    {
+        Some(CompressionError::InvalidHeader { format, .. }) => assert_eq!(*format, Format::Zstd),
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    // Only trained dictionaries have an ID to pick them by
+    assert_eq!(
+        dictionaries
+            .insert("text/plain", b"raw content")
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn zstd_prepared_prefix() {
+    use async_compression::{bufread, stream, write, zstd::PreparedPrefix};
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::TryStreamExt,
+    };
+
+    let prefix: Vec<u8> = (0..200).flat_map(json_document).collect();
+    let prefix = PreparedPrefix::new(&prefix, 3);
+
+    for i in 0..4 {
+        let input = json_document(1_000 + i);
+
+        let mut compressed = Vec::new();
+        block_on(
+            bufread::ZstdEncoder::with_prepared_prefix(&input[..], &prefix)
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+
+        // Referring back to the prefix is what makes tiny documents compress at all
+        assert!(compressed.len() < utils::zstd::sync::compress(&input).len());
+
+        let mut output = Vec::new();
+        block_on(
+            bufread::ZstdDecoder::with_prepared_prefix(&compressed[..], &prefix)
+                .unwrap()
+                .read_to_end(&mut output),
+        )
+        .unwrap();
+        assert_eq!(output, input);
+
+        let mut encoder = write::ZstdEncoder::with_prepared_prefix(Vec::new(), &prefix).unwrap();
+        block_on(encoder.write_all(&input)).unwrap();
+        block_on(encoder.close()).unwrap();
+        let mut decoder = write::ZstdDecoder::with_prepared_prefix(Vec::new(), &prefix).unwrap();
+        block_on(decoder.write_all(&encoder.into_inner())).unwrap();
+        block_on(decoder.close()).unwrap();
+        assert_eq!(decoder.into_inner(), input);
+
+        let source = futures::stream::iter(vec![Ok(Bytes::from(input.clone()))]);
+        let chunks: Vec<Bytes> = block_on(
+            stream::ZstdEncoder::with_prepared_prefix(source, &prefix)
+                .unwrap()
+                .try_collect(),
+        )
+        .unwrap();
+        let source = futures::stream::iter(vec![Ok(chunks.concat().into())]);
+        let chunks: Vec<Bytes> = block_on(
+            stream::ZstdDecoder::with_prepared_prefix(source, &prefix.clone())
+                .unwrap()
+                .try_collect(),
+        )
+        .unwrap();
+        assert_eq!(chunks.concat(), input);
+
+        // Without the prefix decoding fails instead of producing garbage
+        let mut output = Vec::new();
+        let result = block_on(bufread::ZstdDecoder::new(&compressed[..]).read_to_end(&mut output));
+        assert!(result.is_err());
+    }
+}
+
+fn decode_error(input: &[u8]) -> async_compression::BackendError {
+    use async_compression::bufread::ZstdDecoder;
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let err = block_on(ZstdDecoder::new(input).read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<async_compression::BackendError>())
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_decompress_backend_errors() {
+    use async_compression::Format;
+
+    let mut corrupt =
+        utils::zstd::sync::compress(&(0..10_000u32).flat_map(json_document).collect::<Vec<_>>());
+    for byte in &mut corrupt[6..10] {
+        *byte ^= 0xff;
+    }
+    // A frame header asking for a window far larger than the decoder allows
+    let huge_window = [0x28, 0xb5, 0x2f, 0xfd, 0x00, 0xff, 0, 0, 0, 0];
+    let bad_magic = [0x27, 0xb5, 0x2f, 0xfd, 0x00, 0x58, 0, 0, 0, 0];
+
+    let errors = [
+        decode_error(&corrupt),
+        decode_error(&huge_window),
+        decode_error(&bad_magic),
+    ];
+    for err in &errors {
+        assert_eq!(err.format(), Format::Zstd);
+        assert!(err.code().is_some());
+    }
+    assert_eq!(errors[0].message(), "Corrupted block detected");
+    assert_eq!(
+        errors[1].message(),
+        "Frame requires too much memory for decoding"
+    );
+    assert_eq!(errors[2].message(), "Unknown frame descriptor");
+    assert_ne!(errors[0].code(), errors[1].code());
+    assert_ne!(errors[1].code(), errors[2].code());
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn zstd_advanced_params() {
+    use async_compression::{bufread::ZstdEncoder, zstd::ZstdParam};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let input = (0..100).flat_map(json_document).collect::<Vec<_>>();
+
+    for param in &[
+        ZstdParam::ChainLog(12),
+        ZstdParam::SearchLog(4),
+        ZstdParam::MinMatch(6),
+        ZstdParam::TargetLength(64),
+    ] {
+        let mut compressed = Vec::new();
+        block_on(
+            ZstdEncoder::with_params(&input[..], 19, &[*param])
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+        assert_eq!(
+            utils::zstd::sync::decompress(&compressed),
+            input,
+            "{:?}",
+            param
+        );
+    }
+}
+
+#[test]
+fn zstd_advanced_params_out_of_range() {
+    use async_compression::{bufread::ZstdEncoder, zstd::ZstdParam};
+
+    for param in &[
+        ZstdParam::ChainLog(5),
+        ZstdParam::ChainLog(31),
+        ZstdParam::SearchLog(0),
+        ZstdParam::SearchLog(31),
+        ZstdParam::MinMatch(2),
+        ZstdParam::MinMatch(8),
+        ZstdParam::TargetLength(128 * 1024 + 1),
+        ZstdParam::LdmHashLog(5),
+        ZstdParam::LdmHashLog(31),
+        ZstdParam::LdmMinMatch(3),
+        ZstdParam::LdmMinMatch(4097),
+        ZstdParam::LdmBucketSizeLog(0),
+        ZstdParam::LdmBucketSizeLog(9),
+        ZstdParam::LdmHashRateLog(32),
+        ZstdParam::NbWorkers(201),
+        ZstdParam::JobSize((1 << 20) - 1),
+    ] {
+        let err = ZstdEncoder::with_params(&[][..], 3, &[*param])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{:?}", param);
+    }
+}
+
+#[test]
+#[ntest::timeout(20000)]
+#[cfg(feature = "zstdmt")]
+fn zstd_workers() {
+    use async_compression::{bufread, write};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    // Several of zstd's jobs worth of words, compressible but not trivially
+    let words: Vec<&[u8]> = vec![b"alpha ", b"bravo ", b"charlie ", b"delta ", b"echo\n"];
+    let input: Vec<u8> = (0..2_000_000u32)
+        .flat_map(|i| words[(i.wrapping_mul(2_654_435_761) >> 29) as usize % words.len()])
+        .copied()
+        .collect();
+    assert!(input.len() > 8 << 20);
+
+    let mut compressed = Vec::new();
+    block_on(
+        bufread::ZstdEncoder::builder(&input[..])
+            .workers(4)
+            .build()
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    assert_eq!(utils::zstd::sync::decompress(&compressed), input);
+
+    // Written in small pieces, closing has to wait for the outstanding jobs to end the frame
+    let mut encoder = write::ZstdEncoder::builder(Vec::new())
+        .workers(4)
+        .build()
+        .unwrap();
+    for chunk in input.chunks(100_000) {
+        block_on(encoder.write_all(chunk)).unwrap();
+    }
+    block_on(encoder.close()).unwrap();
+    assert_eq!(utils::zstd::sync::decompress(encoder.get_ref()), input);
+}
+
+#[test]
+#[ntest::timeout(20000)]
+#[cfg(feature = "zstdmt")]
+fn zstd_workers_deterministic() {
+    use async_compression::write::ZstdEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+
+    let words: Vec<&[u8]> = vec![b"alpha ", b"bravo ", b"charlie ", b"delta ", b"echo\n"];
+    let input: Vec<u8> = (0..1_000_000u32)
+        .flat_map(|i| words[(i.wrapping_mul(2_654_435_761) >> 29) as usize % words.len()])
+        .copied()
+        .collect();
+
+    // However the workers are scheduled, and however the input is split into writes, the same
+    // jobs are compressed and written in the same order
+    let compress = |chunk_size| {
+        let mut encoder = ZstdEncoder::builder(Vec::new())
+            .workers(4)
+            .job_size(1 << 20)
+            .build()
+            .unwrap();
+        for chunk in input.chunks(chunk_size) {
+            block_on(encoder.write_all(chunk)).unwrap();
+        }
+        block_on(encoder.close()).unwrap();
+        encoder.into_inner()
+    };
+
+    let compressed = compress(100_000);
+    assert_eq!(compressed, compress(100_000));
+    assert_eq!(compressed, compress(7_777));
+    assert_eq!(utils::zstd::sync::decompress(&compressed), input);
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn zstd_long_distance_matching() {
+    use async_compression::{
+        bufread::{ZstdDecoder, ZstdEncoder},
+        zstd::ZstdStrategy,
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::iter::FromIterator;
+
+    // A random block repeated far enough apart that only a large window can refer back to it
+    let block = Vec::from_iter((0..256 * 1024).map(|_| rand::random::<u8>()));
+    let mut input = block.clone();
+    input.extend(std::iter::repeat_n(0, 4 << 20));
+    input.extend(&block);
+
+    let mut compressed = Vec::new();
+    block_on(
+        ZstdEncoder::builder(&input[..])
+            .level(3)
+            .window_log(28)
+            .strategy(ZstdStrategy::Dfast)
+            .enable_long_distance_matching(true)
+            .build()
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    // The repeated block was matched rather than stored again
+    assert!(compressed.len() < block.len() + block.len() / 2);
+
+    // By default a window this large is rejected
+    let err = block_on(ZstdDecoder::new(&compressed[..]).read_to_end(&mut Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut output = Vec::new();
+    block_on(
+        ZstdDecoder::new(&compressed[..])
+            .with_window_log_max(28)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(10000)]
+fn zstd_long_distance_matching_params() {
+    use async_compression::{
+        bufread::ZstdEncoder,
+        zstd::{ZstdParam, ZstdStrategy},
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+    use std::iter::FromIterator;
+
+    let block = Vec::from_iter((0..256 * 1024).map(|_| rand::random::<u8>()));
+    let mut input = block.clone();
+    input.extend(std::iter::repeat_n(0, 4 << 20));
+    input.extend(&block);
+
+    let compress = |params: &[ZstdParam]| {
+        let mut compressed = Vec::new();
+        block_on(
+            ZstdEncoder::with_params(&input[..], 3, params)
+                .unwrap()
+                .read_to_end(&mut compressed),
+        )
+        .unwrap();
+        compressed
+    };
+
+    let plain = compress(&[ZstdParam::Strategy(ZstdStrategy::Dfast)]);
+    // A window small enough for decoders to accept by default, still reaching back past the
+    // zeros
+    let tuned = compress(&[
+        ZstdParam::Strategy(ZstdStrategy::Dfast),
+        ZstdParam::WindowLog(23),
+        ZstdParam::EnableLongDistanceMatching(true),
+        ZstdParam::LdmHashLog(16),
+        ZstdParam::LdmMinMatch(32),
+        ZstdParam::LdmBucketSizeLog(4),
+        ZstdParam::LdmHashRateLog(4),
+    ]);
+
+    assert!(plain.len() > block.len() * 2 - block.len() / 10);
+    assert!(tuned.len() < block.len() + block.len() / 2);
+    assert_eq!(utils::zstd::sync::decompress(&tuned), input);
+}
+
+#[test]
+fn zstd_window_log_out_of_range() {
+    use async_compression::bufread::{ZstdDecoder, ZstdEncoder};
+
+    let err = ZstdEncoder::builder(&[][..])
+        .window_log(9)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = ZstdDecoder::new(&[][..])
+        .with_window_log_max(40)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn zstd_builder_rejects_incompatible_options() {
+    use async_compression::{
+        bufread::ZstdEncoder,
+        zstd::{PreparedPrefix, ZstdParam},
+    };
+
+    let prefix = PreparedPrefix::new(&json_document(1), 3);
+
+    let builders = vec![
+        ZstdEncoder::builder(&[][..])
+            .dictionary(b"dictionary")
+            .prepared_prefix(&prefix),
+        ZstdEncoder::builder(&[][..])
+            .prepared_prefix(&prefix)
+            .level(19),
+        ZstdEncoder::builder(&[][..])
+            .prepared_prefix(&prefix)
+            .param(ZstdParam::MinMatch(4)),
+        ZstdEncoder::builder(&[][..]).include_content_size(true),
+    ];
+    for builder in builders {
+        let description = format!("{:?}", builder);
+        let err = builder.build().err().unwrap();
+        assert_eq!(
+            err.kind(),
+            std::io::ErrorKind::InvalidInput,
+            "{}",
+            description
+        );
+        assert!(err.to_string().contains("zstd"), "{}: {}", description, err);
+    }
+}
+
+#[test]
+fn zstd_builder_combines_compatible_options() {
+    use async_compression::{
+        bufread::{ZstdDecoder, ZstdEncoder},
+        zstd::{PreparedPrefix, ZstdParam},
+    };
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    let samples: Vec<Vec<u8>> = (0..2000).map(json_document).collect();
+    let dictionary = libzstd::dict::from_samples(&samples, 4096).unwrap();
+    let prefix: Vec<u8> = (0..200).flat_map(json_document).collect();
+    let prefix = PreparedPrefix::new(&prefix, 3);
+    let input = json_document(123_456);
+
+    let mut compressed = Vec::new();
+    block_on(
+        ZstdEncoder::builder(&input[..])
+            .level(19)
+            .dictionary(&dictionary)
+            .param(ZstdParam::MinMatch(4))
+            .include_content_size(false)
+            .build()
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    let mut output = Vec::new();
+    block_on(
+        ZstdDecoder::with_dict(&compressed[..], &dictionary)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+
+    let mut compressed = Vec::new();
+    block_on(
+        ZstdEncoder::builder(&input[..])
+            .prepared_prefix(&prefix)
+            .build()
+            .unwrap()
+            .read_to_end(&mut compressed),
+    )
+    .unwrap();
+    let mut output = Vec::new();
+    block_on(
+        ZstdDecoder::with_prepared_prefix(&compressed[..], &prefix)
+            .unwrap()
+            .read_to_end(&mut output),
+    )
+    .unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_stream_decompress_checkpoints() {
+    use async_compression::stream::{CheckpointItem, ZstdDecoder};
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let compressed = multi_frame();
+
+    for chunk_size in &[1, 7, compressed.len()] {
+        let input = utils::InputStream::from(
+            compressed
+                .chunks(*chunk_size)
+                .map(Vec::from)
+                .collect::<Vec<_>>(),
+        );
+
+        let items: Vec<CheckpointItem> = block_on(
+            ZstdDecoder::new(input.stream())
+                .with_checkpoints()
+                .try_collect(),
+        )
+        .unwrap();
+
+        let mut output = Vec::new();
+        let mut checkpoints = Vec::new();
+        for item in items {
+            match item {
+                CheckpointItem::Data(chunk) => output.extend_from_slice(&chunk),
+                CheckpointItem::Checkpoint(checkpoint) => {
+                    assert_eq!(checkpoint.output_offset, output.len() as u64);
+                    checkpoints.push(checkpoint);
+                }
+            }
+        }
+        assert_eq!(output, b"hello world");
+
+        // One at the end of each of the five frames, skippable or not
+        assert_eq!(checkpoints.len(), 5, "{}", chunk_size);
+        assert_eq!(
+            checkpoints.last().unwrap().input_offset,
+            compressed.len() as u64
+        );
+
+        for checkpoint in &checkpoints[..checkpoints.len() - 1] {
+            let rest = &compressed[checkpoint.input_offset as usize..];
+            assert_eq!(
+                utils::zstd::sync::decompress(rest),
+                &output[checkpoint.output_offset as usize..]
+            );
+        }
+    }
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_content_size() {
+    use async_compression::{bufread::ZstdDecoder, write::ZstdEncoder};
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    // Sizes taking each width of the field in the frame header
+    for &size in &[0, 10, 1000, 100_000] {
+        let input = b"the quick brown fox jumps over the lazy dog\n"
+            .iter()
+            .cycle()
+            .take(size)
+            .copied()
+            .collect::<Vec<u8>>();
+
+        let mut encoder = ZstdEncoder::with_content_size(Vec::new(), 0, size as u64);
+        block_on(encoder.write_all(&input)).unwrap();
+        block_on(encoder.close()).unwrap();
+        let compressed = encoder.into_inner();
+
+        let mut decoder = ZstdDecoder::new(&compressed[..]);
+        assert_eq!(decoder.content_size(), None);
+        let mut output = Vec::new();
+        block_on(decoder.read_to_end(&mut output)).unwrap();
+        assert_eq!(output, input);
+        assert_eq!(decoder.content_size(), Some(size as u64));
+    }
+
+    // A frame without the field
+    let compressed = utils::zstd::sync::compress(&[1, 2, 3]);
+    let mut decoder = ZstdDecoder::new(&compressed[..]);
+    let mut output = Vec::new();
+    block_on(decoder.read_to_end(&mut output)).unwrap();
+    assert_eq!(decoder.content_size(), None);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_content_size_mismatch() {
+    use async_compression::write::ZstdEncoder;
+    use futures::{executor::block_on, io::AsyncWriteExt};
+    use std::io::ErrorKind;
+
+    // Finishing short of the pledged size
+    let mut encoder = ZstdEncoder::with_content_size(Vec::new(), 0, 10);
+    block_on(encoder.write_all(&[0; 9])).unwrap();
+    let err = block_on(encoder.close()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    // Writing past it
+    let mut encoder = ZstdEncoder::with_content_size(Vec::new(), 0, 10);
+    block_on(encoder.write_all(&[0; 10])).unwrap();
+    let err = block_on(encoder.write_all(&[0])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+#[ntest::timeout(1000)]
+fn zstd_stream_compress_all() {
+    use async_compression::stream::{IterStream, ZstdEncoder};
+    use bytes::Bytes;
+    use futures::{executor::block_on, stream::TryStreamExt};
+
+    let chunks: Vec<Bytes> = (0..50u8)
+        .map(|i| Bytes::from(vec![i; 1000 * i as usize]))
+        .collect();
+
+    let streamed =
+        block_on(ZstdEncoder::new(IterStream::new(chunks.clone()), 3).try_concat()).unwrap();
+    let compressed = ZstdEncoder::compress_all(chunks.clone(), 3).unwrap();
+    assert_eq!(compressed, &streamed[..]);
+    assert_eq!(utils::zstd::sync::decompress(&compressed), chunks.concat());
+}
+
+#[test]
+#[ntest::timeout(5000)]
+fn zstd_bufread_compress_level_range() {
+    use async_compression::{bufread::ZstdEncoder, Level};
+    use futures::{executor::block_on, io::AsyncReadExt};
+
+    // The documented range of levels is zstd's own
+    assert_eq!(zstd_safe::max_c_level(), 22);
+
+    let input = b"the quick brown fox jumps over the lazy dog\n".repeat(100);
+    let compress = |mut encoder: ZstdEncoder<&[u8]>| {
+        let mut compressed = Vec::new();
+        block_on(encoder.read_to_end(&mut compressed)).unwrap();
+        compressed
+    };
+
+    let best = compress(ZstdEncoder::with_quality(&input[..], Level::Best));
+    assert_eq!(best, compress(ZstdEncoder::new(&input[..], 22)));
+    assert_eq!(
+        best,
+        compress(ZstdEncoder::with_quality(&input[..], Level::Precise(22)))
+    );
+    assert_eq!(
+        best,
+        compress(ZstdEncoder::with_quality(&input[..], Level::Precise(100)))
+    );
+    assert_eq!(utils::zstd::sync::decompress(&best), input);
+}